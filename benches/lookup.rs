@@ -0,0 +1,49 @@
+//! Times repeated lookups by path in a compound file of 5,000 entries, with and without the name
+//! index. Run with `cargo bench --bench lookup`. Without the index, each lookup follows the chain
+//! of directory sectors from its start, so only a sample of the lookups is timed.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::CfbBuilder;
+use ole_kit::cfb::{Cfb, CfbOpenOptions};
+use std::io::Cursor;
+use std::time::Instant;
+
+const STORAGES: usize = 50;
+const STREAMS_PER_STORAGE: usize = 99;
+const LOOKUPS: usize = 10_000;
+const UNINDEXED_LOOKUPS: usize = 100;
+
+fn main() {
+    let mut builder = CfbBuilder::new();
+    let mut paths = Vec::new();
+    for i in 0..STORAGES {
+        let storage = builder.storage(0, &format!("Storage{}", i));
+        for j in 0..STREAMS_PER_STORAGE {
+            builder.stream(storage, &format!("Stream{}", j), &[]);
+            paths.push(format!("Storage{}/Stream{}", i, j));
+        }
+    }
+    let bytes = builder.build();
+
+    let open = |name_index| {
+        CfbOpenOptions::new().name_index(name_index).open_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap()
+    };
+    for (label, cfb, lookups) in [("without index", open(false), UNINDEXED_LOOKUPS), ("with index", open(true), LOOKUPS)] {
+        let elapsed = time(&cfb, &paths, lookups);
+        print!("{:>13}: {} lookups in {:?}, {:?} each", label, lookups, elapsed, elapsed / lookups as u32);
+        match cfb.index_stats() {
+            Some(stats) => println!(", index of {} paths in {} bytes", stats.paths, stats.bytes),
+            None => println!(),
+        }
+    }
+}
+
+fn time(cfb: &Cfb, paths: &[String], lookups: usize) -> std::time::Duration {
+    let start = Instant::now();
+    for path in paths.iter().cycle().step_by(7).take(lookups) {
+        assert!(cfb.entry_by_path(path).unwrap().is_some());
+    }
+    start.elapsed()
+}
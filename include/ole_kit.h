@@ -0,0 +1,152 @@
+#ifndef OLE_KIT_H
+#define OLE_KIT_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A pointer or a string argument is null, or a string isn't UTF-8.
+ */
+#define OLEKIT_ERR_INVALID_ARGUMENT -1
+
+/**
+ * The file can't be opened or read.
+ */
+#define OLEKIT_ERR_IO -2
+
+/**
+ * The file isn't a compound file.
+ */
+#define OLEKIT_ERR_NOT_CFB -3
+
+/**
+ * The compound file is corrupted or cut short.
+ */
+#define OLEKIT_ERR_CORRUPT -4
+
+/**
+ * Reading needs more than the limits of the compound file allow.
+ */
+#define OLEKIT_ERR_LIMIT_EXCEEDED -5
+
+/**
+ * No entry has the name looked up.
+ */
+#define OLEKIT_ERR_NOT_FOUND -6
+
+/**
+ * The entry of the name looked up is a storage rather than a stream.
+ */
+#define OLEKIT_ERR_NOT_A_STREAM -7
+
+/**
+ * The library panicked, which is a bug.
+ */
+#define OLEKIT_ERR_PANIC -8
+
+/**
+ * A compound file opened by [`olekit_open`].
+ */
+typedef struct OlekitCfb OlekitCfb;
+
+/**
+ * An entry of a compound file, passed to the callback of [`olekit_list_entries`]. The strings
+ * are only valid during the call.
+ */
+typedef struct OlekitEntry {
+  /**
+   * The path of the entry from the root storage, its names separated by `/`.
+   */
+  const char *path;
+  /**
+   * The name of the entry, which [`olekit_stream_size`] and [`olekit_stream_read`] take.
+   */
+  const char *name;
+  /**
+   * The object type of the entry: 1 for a storage, 2 for a stream.
+   */
+  uint8_t object_type;
+  /**
+   * The size of a stream in bytes, 0 for a storage.
+   */
+  uint64_t size;
+} OlekitEntry;
+
+/**
+ * The callback of [`olekit_list_entries`], called with each entry and the user data.
+ */
+typedef void (*OlekitEntryCallback)(const struct OlekitEntry *entry, void *user_data);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Opens the compound file at a path, returning null on failure
+ *
+ * # Safety
+ *
+ * `path` must be null or a NUL-terminated string.
+ */
+struct OlekitCfb *olekit_open(const char *path);
+
+/**
+ * Closes a compound file opened by [`olekit_open`]. Closing null does nothing.
+ *
+ * # Safety
+ *
+ * `handle` must be null or a handle returned by [`olekit_open`] and not closed yet.
+ */
+void olekit_close(struct OlekitCfb *handle);
+
+/**
+ * Returns the size in bytes of a stream by its name, found as [`Cfb::stream_bytes`] finds it, or
+ * a negative error code
+ *
+ * # Safety
+ *
+ * `handle` must be a handle returned by [`olekit_open`] and not closed, and `name` null or a
+ * NUL-terminated string.
+ */
+int64_t olekit_stream_size(const struct OlekitCfb *handle, const char *name);
+
+/**
+ * Reads a stream by its name into a buffer, from its start and up to `len` bytes, returning the
+ * number of bytes read, which is short of `len` only at the end of the stream, or a negative
+ * error code. A buffer of the [size](olekit_stream_size) of the stream reads all of it.
+ *
+ * # Safety
+ *
+ * `handle` must be a handle returned by [`olekit_open`] and not closed, `name` null or a
+ * NUL-terminated string, and `buf` valid for writing `len` bytes unless `len` is 0.
+ */
+int64_t olekit_stream_read(const struct OlekitCfb *handle, const char *name, uint8_t *buf, size_t len);
+
+/**
+ * Calls the callback with every storage and stream of the compound file, depth first in the order
+ * of the sibling trees, each storage before what it contains, returning 0 or a negative error
+ * code. The root storage isn't listed.
+ *
+ * # Safety
+ *
+ * `handle` must be a handle returned by [`olekit_open`] and not closed. `user_data` is only
+ * passed to the callback.
+ */
+int64_t olekit_list_entries(const struct OlekitCfb *handle,
+                            OlekitEntryCallback callback,
+                            void *user_data);
+
+/**
+ * Returns the message of the last failed call on the calling thread, or null if none failed. The
+ * string is valid until the next failed call on the thread.
+ */
+const char *olekit_last_error_message(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* OLE_KIT_H */
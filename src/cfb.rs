@@ -1,18 +1,155 @@
+#![deny(clippy::cast_possible_truncation)]
+
 pub mod header;
+pub mod name;
+#[cfg(feature = "async")]
+pub mod r#async;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 mod fat;
+#[cfg(feature = "std")]
+mod annotate;
+#[cfg(feature = "zip")]
+mod archive;
+mod convert;
+#[cfg(feature = "std")]
+mod dedup;
 mod directory;
+#[cfg(feature = "std")]
+mod dump;
+#[cfg(feature = "std-fs")]
+mod edit;
+#[cfg(feature = "std")]
+mod embedded;
+mod error;
+#[cfg(feature = "std")]
+mod extents;
+#[cfg(any(feature = "std-fs", feature = "zip"))]
+mod extract;
+#[cfg(feature = "std")]
+mod format;
+#[cfg(feature = "std")]
+mod fragmentation;
+#[cfg(feature = "std")]
+mod index;
+#[cfg(feature = "std")]
+mod listing;
+#[cfg(feature = "std")]
+mod observe;
+#[cfg(feature = "std")]
+mod options;
+#[cfg(feature = "std")]
+mod quick_look;
+#[cfg(feature = "std")]
+mod read_at;
+#[cfg(feature = "std")]
+mod reader;
+#[cfg(feature = "std")]
+mod scan;
+#[cfg(feature = "std")]
+mod sector_map;
+mod slice;
+#[cfg(feature = "std")]
+mod storage;
+#[cfg(feature = "std")]
+mod truncation;
+#[cfg(feature = "std")]
+mod usage;
+#[cfg(feature = "std")]
+mod validate;
+mod warning;
 
-pub use fat::sector_number::SectorNumber as SectorNumber;
+pub use fat::sector_number::{ParseSectorNumberError, SectorNumber};
+#[cfg(feature = "std")]
+pub use annotate::{annotations_to_json, annotations_to_tsv, Annotation};
+pub use fat::Fat;
+#[cfg(feature = "std")]
+pub use fat::mini_fat::MiniFatTable;
+#[cfg(feature = "std")]
+pub use truncation::{TruncatedEntry, TruncationReport};
+#[cfg(feature = "std")]
+pub use usage::{ChildSize, SizeTotals, StorageSizeReport};
+#[cfg(feature = "std")]
+pub use validate::Violation;
+pub use warning::{Location, Rule, Warning};
+#[cfg(feature = "std")]
+pub use dedup::DuplicateGroup;
+pub use error::Error;
+pub use header::ForeignFormat;
+#[cfg(feature = "std-fs")]
+pub use edit::{MetadataEditor, UnflushedPolicy};
+#[cfg(feature = "std")]
+pub use embedded::{CompObj, EmbeddedObject, Ole10Native, Package};
+#[cfg(feature = "std-fs")]
+pub use extract::{ExtractReport, ExtractedStream};
+#[cfg(feature = "std")]
+pub use format::{Classification, DetectedFormat};
+#[cfg(feature = "std")]
+pub use fragmentation::{ChainStats, StreamFragmentation};
+#[cfg(feature = "std")]
+pub use index::IndexStats;
+#[cfg(feature = "std")]
+pub use observe::{IoObserver, ObservedRead, SectorReadCounter};
+#[cfg(feature = "std")]
+pub use options::{CfbOpenOptions, Limit, Limits};
+#[cfg(feature = "std")]
+pub use quick_look::{Presence, QuickLook};
+#[cfg(feature = "std")]
+pub use scan::{scan, EmbeddedCfb, Scan};
+#[cfg(feature = "std")]
+pub use sector_map::{Overlap, SectorMap, SectorOwner};
+#[cfg(feature = "std")]
+pub use read_at::{ReadAt, SharedSource};
+#[cfg(feature = "std")]
+pub use reader::{StreamReader, StreamSectors};
+pub use slice::{RawEntry, SliceCfb};
+#[cfg(feature = "std")]
+pub use storage::StorageHandle;
+pub use directory::StreamID;
+#[cfg(feature = "std")]
+pub use directory::entry::{Entry, CommonProps, OwnedEntry};
+pub use directory::entry::metadata::{self, ColorFlag, ObjectType, StateBits, StreamSize};
+#[cfg(feature = "std")]
+pub use directory::entry::impls::{RootStorage, Storage, Stream};
 
+#[cfg(feature = "std-fs")]
 use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, Write};
+#[cfg(feature = "std")]
+use std::cmp::Ordering;
+#[cfg(feature = "std")]
+use crate::cfb::name::{cmp_names_str, name_key};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::iter::FusedIterator;
+#[cfg(feature = "std")]
+use std::path::Path;
+#[cfg(feature = "std")]
+use std::sync::{Arc, OnceLock};
+#[cfg(feature = "std")]
+use std::convert::TryFrom;
+#[cfg(feature = "std")]
 use crate::cfb::header::{Header, SectorCount};
+#[cfg(feature = "std")]
 use core::fmt;
+#[cfg(feature = "std")]
 use std::fmt::Formatter;
-use crate::cfb::fat::Fat;
-use std::os::unix::fs::FileExt;
+#[cfg(feature = "std")]
+use crate::cfb::read_at::{SectorCache, SeekReader, Window};
+#[cfg(feature = "std")]
+use crate::cfb::index::SharedIndex;
+#[cfg(feature = "std")]
+use crate::cfb::convert::{entries_per_sector, sector_index, sector_numbers_per_sector, to_usize_saturating};
+#[cfg(feature = "std")]
+use crate::cfb::warning::Warnings;
+#[cfg(feature = "std")]
 use crate::cfb::directory::Directory;
-use crate::cfb::directory::entry::Entry;
-use crate::cfb::directory::entry::CommonProps;
+#[cfg(feature = "std")]
+use crate::cfb::fat::cache::{Cache, SharedFats};
+#[cfg(feature = "std")]
+use crate::cfb::fat::chain::Chain;
 
 /**
 A compound file is a structure that is used to store a hierarchy of storage objects and stream
@@ -33,20 +170,186 @@ which has a name that uniquely identifies it among the child objects of its pare
 The root storage object has no parent object. The root storage object also has no name. Because
 names are used to identify child objects, a name for the root storage object is unnecessary and the
 file format does not provide a representation for it.
+
+Cloning a `Cfb` is cheap: the clones share the same source of bytes, so that handles such as
+[`StreamReader`] can own one and be sent across threads. A `Cfb` is [`Send`] and [`Sync`], and the
+FAT and mini FAT sectors parsed while following chains are shared by the clones and by concurrent
+reads, so that streams read in parallel parse each of them once.
+
+Reading is bounded by [limits] derived from the size of the file, which [`CfbOpenOptions`] can
+tighten when processing untrusted files.
+
+The iterators over the structures are fused, returning None for good once they have ended. An error
+yielded for a single item, such as a malformed directory entry, doesn't end the iteration, while an
+error breaking a chain, such as a DIFAT sector which can't be read, is the last item.
+
+[limits]: Limits
  */
+#[cfg(feature = "std")]
+#[derive(Clone)]
 pub struct Cfb {
-    file: File,
+    source: SharedSource,
     sector_size: u32,
+    limits: Limits,
+    name_index: Arc<SharedIndex>,
+    lenient: bool,
+    /// Whether lookups compare names exactly rather than under the uppercase mapping.
+    exact_names: bool,
+    warnings: Warnings,
+    first_overlap: Arc<OnceLock<Option<Overlap>>>,
+    /// The path of the file read, if it was opened by its path.
+    path: Option<Arc<Path>>,
+    /// The FAT and mini FAT sectors parsed so far, shared with the clones made by `clone`.
+    fats: Arc<SharedFats>,
 }
 
+#[cfg(feature = "std")]
 impl Cfb {
-    /// Creates a compound file by reading the file at the path
-    pub fn from_path(path: &str) -> Result<Self, std::io::Error> {
-        let file = File::open(path)?;
-        let header = Header::new(&file);
-        let sector_size = 1 << header.sector_shift().0;
+    /// Creates a compound file by reading from the reader, such as a [`Cursor`] over bytes or a
+    /// decrypting wrapper, from its start to its end
+    ///
+    /// [`Cursor`]: std::io::Cursor
+    pub fn new<R: Read + Seek + Send + 'static>(reader: R) -> Result<Self, Error> {
+        Self::from_reader_at(reader, 0, None)
+    }
+
+    /// Creates a compound file by reading the file at the path, which is kept for the [`Debug`]
+    /// output and [`path`](Self::path)
+    #[cfg(feature = "std-fs")]
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        Ok(Self::from_file(open_file(path)?)?.with_path(path))
+    }
+
+    /// Creates a compound file by reading an open file, such as an unnamed temporary file, taking
+    /// ownership of the handle. Every read is positioned, so the cursor of the file may be anywhere.
+    #[cfg(feature = "std-fs")]
+    pub fn from_file(file: File) -> Result<Self, Error> {
+        Self::from_source(Arc::new(file))
+    }
+
+    /// Creates a compound file by mapping the file at the path into memory, so that reading the
+    /// fields of the header, the FAT and the entries indexes into the map instead of issuing a
+    /// system call each. Reads beyond the end of the map fail as they do for a file cut short.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this process or another, while the compound
+    /// file or any handle created from it is alive, as the map would change under the reads.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn from_path_mmap<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        Ok(Self::from_source(Arc::new(memmap2::Mmap::map(&open_file(path)?)?))?.with_path(path))
+    }
+
+    /// Creates a compound file by reading the file at the path, starting at the byte offset where the
+    /// compound file is stored. If `len` is given, reads are clamped to `offset + len`.
+    #[cfg(feature = "std-fs")]
+    pub fn from_path_at<P: AsRef<Path>>(path: P, offset: u64, len: Option<u64>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        Ok(Self::from_source(Arc::new(Window::new(open_file(path)?, offset, len)))?.with_path(path))
+    }
+
+    /// Creates a compound file by reading from the reader, starting at the byte offset where the
+    /// compound file is stored. If `len` is given, reads are clamped to `offset + len`.
+    pub fn from_reader_at<R: Read + Seek + Send + 'static>(reader: R, offset: u64, len: Option<u64>) -> Result<Self, Error> {
+        Self::from_source(Arc::new(Window::new(SeekReader::new(reader), offset, len)))
+    }
+
+    /// Creates a compound file held in memory, as when it is received over the network, without
+    /// writing it to a file first. Reads beyond the end of the bytes fail as they do for a file cut
+    /// short.
+    #[cfg(feature = "alloc-backend")]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        Self::from_source(Arc::new(bytes))
+    }
+
+    /// Creates a compound file by reading a reader which can't seek, such as a pipe, in one pass to
+    /// its end, holding the bytes in memory as [`from_bytes`](Self::from_bytes) does. The header is
+    /// checked as soon as it arrives, so that a reader of anything else than a compound file fails
+    /// without being read further.
+    #[cfg(feature = "alloc-backend")]
+    pub fn read_from(mut reader: impl Read) -> Result<Self, Error> {
+        let mut bytes = Vec::with_capacity(Header::LENGTH);
+        reader.by_ref().take(Header::LENGTH as u64).read_to_end(&mut bytes)?;
+        Self::from_source(Arc::new(bytes.clone()))?;
+        reader.read_to_end(&mut bytes)?;
+        Self::from_bytes(bytes)
+    }
+
+    /// Creates a compound file by reading from a custom [source](ReadAt), starting at the byte
+    /// offset where the compound file is stored. If `len` is given, reads are clamped to
+    /// `offset + len`.
+    pub fn from_read_at<S: ReadAt + Send + Sync + 'static>(source: S, offset: u64, len: Option<u64>) -> Result<Self, Error> {
+        Self::from_source(Arc::new(Window::new(source, offset, len)))
+    }
+
+    /// Creates a compound file over a source, validating its header, with the limits derived from
+    /// the size of the source
+    pub(crate) fn from_source(source: SharedSource) -> Result<Self, Error> {
+        Self::from_source_checked(source, false)
+    }
+
+    /// Creates a compound file over a source, checking its header strictly or leniently, with the
+    /// limits derived from the size of the source. The small reads of the fields go through a
+    /// [`SectorCache`], unless the source is already in memory.
+    pub(crate) fn from_source_checked(source: SharedSource, lenient: bool) -> Result<Self, Error> {
+        let source: SharedSource = if source.is_in_memory() { source } else { Arc::new(SectorCache::new(source)) };
+        let warnings = Warnings::default();
+        let sector_size = {
+            let header = Header::from_source(source.as_ref());
+            header.check(lenient, &warnings)?;
+            1 << header.sector_shift().0
+        };
+        let limits = Limits::for_size(source.size());
+
+        Ok(Self {
+            source,
+            sector_size,
+            limits,
+            name_index: Arc::default(),
+            lenient,
+            exact_names: false,
+            warnings,
+            first_overlap: Arc::default(),
+            path: None,
+            fats: Arc::default(),
+        })
+    }
+
+    /// Keeps the path of the file read
+    #[cfg(feature = "std-fs")]
+    pub(crate) fn with_path(self, path: &Path) -> Self {
+        Self { path: Some(path.into()), ..self }
+    }
+
+    /// Returns the path of the file read, if it was opened by its path
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
 
-        Ok(Self { file, sector_size })
+    /// Creates an independent handle over the same compound file, duplicating the handle of the
+    /// underlying file, so that workers can each read through their own without reopening the file
+    /// by its path. Sources in memory, or readers which can't be duplicated, are shared instead.
+    ///
+    /// Unlike [`clone`], which shares the source and everything parsed so far with the original,
+    /// the clone reads the header again and starts with caches of its own: the FAT sectors, the
+    /// name index, the warnings and the shared sectors found are those of its own reads, and its
+    /// reads don't contend with reads through the original.
+    ///
+    /// [`clone`]: Clone::clone
+    pub fn try_clone(&self) -> Result<Self, Error> {
+        let source = self.source.try_clone()?.unwrap_or_else(|| self.source.clone());
+        let warnings = Warnings::default();
+        Header::from_source(source.as_ref()).check(self.lenient, &warnings)?;
+        Ok(Self {
+            source,
+            name_index: Arc::new(self.name_index.unbuilt()),
+            warnings,
+            first_overlap: Arc::default(),
+            fats: Arc::default(),
+            ..self.clone()
+        })
     }
 
     /// Returns the sector size in bytes of the compound file
@@ -54,149 +357,996 @@ impl Cfb {
         self.sector_size
     }
 
+    /// Returns the limits on the resources spent reading the compound file
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Returns the deviations from the specification tolerated so far, those of the header from the
+    /// opening, and the others as the parts of the file holding them are read. A
+    /// [lenient](CfbOpenOptions::lenient) reading tolerates more of them.
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.warnings.to_vec()
+    }
+
+    /// Returns the size below which streams are stored in the mini stream, clamped to the 4,096
+    /// bytes of the specification when reading leniently
+    pub(crate) fn mini_stream_cutoff(&self) -> u64 {
+        if self.lenient { 0x1000 } else { self.header().mini_stream_cutoff_size() as u64 }
+    }
+
+    /// Returns whether a name found in the directory matches one looked up, compared
+    /// case-insensitively under the uppercase mapping of the sibling tree, or exactly if the file
+    /// was opened with [`exact_names`](CfbOpenOptions::exact_names)
+    pub(crate) fn names_match(&self, found: &str, name: &str) -> bool {
+        if self.exact_names { found == name } else { cmp_names_str(found, name) == Ordering::Equal }
+    }
+
+    /// Returns the key under which the names matching a name are indexed, as
+    /// [`names_match`](Self::names_match) compares them
+    pub(crate) fn name_key(&self, name: &str) -> String {
+        if self.exact_names { name.to_string() } else { name_key(name) }
+    }
+
     /// Returns the header of the compound file
-    pub fn header(&self) -> Header {
-        Header::new(&self.file)
+    pub fn header(&self) -> Header<'_> {
+        Header::from_source(self.source.as_ref())
     }
 }
 
+#[cfg(feature = "std")]
 impl Cfb {
     /// Returns a FAT structure by its sector number
-    pub(crate) fn fat(&self, sector_no: SectorNumber) -> Fat {
-        Fat::new(self.sector_bytes(sector_no))
+    pub(crate) fn fat(&self, sector_no: SectorNumber) -> Result<Fat, Error> {
+        Ok(Fat::new(sector_no, self.sector_bytes(sector_no)?))
     }
 
-    /// Returns a FAT structure by the sector number of a stream object
-    pub(crate) fn fat_by_stream_sector_no(&self, stream_sector_no: SectorNumber) -> Fat {
-        let no_of_sectors_per_fat = SectorCount(self.sector_size >> 2);
-        let difat_idx = stream_sector_no / no_of_sectors_per_fat;
-        self.fat(self.header().sector_no_of_fat(difat_idx))
+    /// Returns an iterator over the locations of every FAT sector, in order, listed by the header
+    /// until its first FREESECT entry and then by the chained DIFAT sectors, whose last entry links
+    /// to the next DIFAT sector. Reading a DIFAT sector which fails, or running past the
+    /// [chain length limit] of DIFAT sectors, yields an error which ends the iteration.
+    ///
+    /// [chain length limit]: Limits::max_chain_length
+    pub fn fat_sector_locations(&self) -> impl FusedIterator<Item = Result<SectorNumber, Error>> + '_ {
+        FatSectorLocations::new(self)
     }
 
-    /// Returns a mini-FAT structure by its sector number
-    pub(crate) fn mini_fat(&self, sector_no: SectorNumber) -> Fat {
-        let sector_no_count = SectorCount(self.sector_size >> std::mem::size_of::<SectorNumber>());
+    /// Returns a FAT sector by its index among the FAT sectors, returns None if the index is
+    /// beyond the FAT sectors listed in the DIFAT or the sector can't be read
+    pub fn fat_sector(&self, index: u32) -> Option<Fat> {
+        let location = self.fat_sector_locations().nth(sector_index(index))?.ok()?;
+        self.fat(location).ok()
+    }
 
-        let min_fat_idx = sector_no / sector_no_count;
+    /// Returns a FAT structure by the sector number of a stream object
+    pub(crate) fn fat_by_stream_sector_no(&self, stream_sector_no: SectorNumber) -> Result<Fat, Error> {
+        let no_of_sectors_per_fat = SectorCount(sector_numbers_per_sector(self.sector_size));
+        let difat_idx = stream_sector_no / no_of_sectors_per_fat;
+        let location = self.fat_sector_locations()
+            .nth(sector_index(difat_idx.0))
+            .unwrap_or(Err(Error::InvalidSectorNumber(stream_sector_no)))?;
+        self.fat(location)
+    }
 
-        let mut mini_fat_sector_number = self.header().first_mini_fat_sector_location();
+    /// Returns the whole mini FAT, following the chain of its sectors from the header. The chain is
+    /// cut where it loops back on itself.
+    pub fn mini_fat_table(&self) -> Result<MiniFatTable, Error> {
+        let sector_locations = self.sector_chain(self.header().first_mini_fat_sector_location())?;
+        let mut entries = Vec::new();
+        for sector_no in &sector_locations {
+            entries.extend(self.fat(*sector_no)?.iter());
+        }
+        Ok(MiniFatTable::new(sector_locations, entries))
+    }
 
-        for _ in 0..min_fat_idx.0 {
-            let fat = self.fat_by_stream_sector_no(mini_fat_sector_number);
-            mini_fat_sector_number = fat
-                .sector_number((mini_fat_sector_number % sector_no_count).0);
+    /// Returns the sectors of the FAT chain starting at a sector, cut where it loops back on itself,
+    /// failing if it is longer than the [chain length limit]
+    ///
+    /// [chain length limit]: Limits::max_chain_length
+    pub(crate) fn sector_chain(&self, start: SectorNumber) -> Result<Vec<SectorNumber>, Error> {
+        let mut cache = Cache::new(self);
+        let mut chain = Chain::new(start, false);
+        let mut sectors = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        while let Some(sector_no) = chain.get(sectors.len(), &mut cache)? {
+            if !visited.insert(sector_no) {
+                break;
+            }
+            sectors.push(sector_no);
         }
+        Ok(sectors)
+    }
 
-        Fat::new(self.sector_bytes(mini_fat_sector_number))
+    /// Gets the bytes of a sector by its sector number, failing with
+    /// [`Error::InvalidSectorNumber`] for a reserved value, or with [`Error::Truncated`] if the
+    /// source ends before the end of the sector. A file read leniently warns of the latter instead,
+    /// the bytes past the end of the source being zeroes.
+    #[inline]
+    pub(crate) fn sector_bytes(&self, sector_no: SectorNumber) -> Result<Vec<u8>, Error> {
+        let offset = self.byte_offset_of_sector(sector_no)?;
+        let mut bytes = vec![0u8; sector_index(self.sector_size)];
+        let read = self.source.read_at(&mut bytes, offset)?;
+        if read < bytes.len() {
+            if !self.lenient {
+                return Err(Error::Truncated { size: self.sector_size as u64, available: read as u64 });
+            }
+            self.warnings.push(Rule::TruncatedSector, Location::Sector { sector: sector_no.0, offset }, read as u64);
+        }
+        Ok(bytes)
     }
 
-    /// Returns a mini-FAT structure by the sector number of a stream object
-    pub(crate) fn mini_fat_by_stream_sector_no(&self, stream_sector_no: SectorNumber) -> Fat {
-        let no_of_sectors_per_fat = SectorCount(self.sector_size >> 2);
-        let mini_fat_idx = stream_sector_no / no_of_sectors_per_fat;
-        self.mini_fat(mini_fat_idx)
+    /// Checks that a sector lies wholly within the source, as [`sector_bytes`](Self::sector_bytes)
+    /// does, reading only its last byte unless the source ends before it
+    pub(crate) fn check_sector(&self, sector_no: SectorNumber) -> Result<(), Error> {
+        let offset = self.byte_offset_of_sector(sector_no)?;
+        if self.source.read_at(&mut [0], offset + self.sector_size as u64 - 1)? == 1 {
+            return Ok(());
+        }
+        self.sector_bytes(sector_no).map(drop)
     }
 
-    /// Gets the bytes of a sector by its sector number
-    #[inline]
-    pub(crate) fn sector_bytes(&self, sector_no: SectorNumber) -> Vec<u8> {
-        let mut bytes = vec![0u8; self.sector_size as usize];
-        self.file.read_at(&mut bytes,
-                          (sector_no + 1).byte_offset(self.sector_size));
-        bytes
+    /// Returns the byte offset of a sector from the start of the compound file, past the header
+    /// which takes the place of a sector before sector 0, failing with
+    /// [`Error::InvalidSectorNumber`] for a reserved value or an offset overflowing
+    pub fn byte_offset_of_sector(&self, sector_no: SectorNumber) -> Result<u64, Error> {
+        let sector_size = self.sector_size as u64;
+        sector_no.checked_byte_offset(sector_size)
+            .and_then(|offset| offset.checked_add(sector_size))
+            .ok_or(Error::InvalidSectorNumber(sector_no))
     }
 
-    /// Returns a directory structure by its index
-    pub(crate) fn directory(&self, index: u32) -> Directory {
-        Directory::new((self.header().first_directory_sector_location() + index + 1).byte_offset(self.sector_size),
-                       self.sector_size,
-                       &self.file)
+    /// Returns a directory structure by its index in the chain of directory sectors, returns None
+    /// if the chain ends before it
+    pub(crate) fn directory(&self, index: u32) -> Option<Directory<'_>> {
+        self.directories().nth(sector_index(index))
     }
 
     /// Returns an iterator over all directories of the compound file
-    pub(crate) fn directories(&self) -> Iter {
+    pub(crate) fn directories(&self) -> Iter<'_> {
         let first_directory_sector_location = self.header().first_directory_sector_location();
         Iter::new(first_directory_sector_location, self)
     }
 
-    /// Gets a directory entry by its name, returns None if not found
-    pub fn directory_entry(&self, name: &str) -> Option<Entry> {
-        self.directories()
-            .filter_map(|dir| {
-                dir
-                    .into_iter()
-                    .filter_map(Result::ok)
-                    .find(|entry| entry.name() == name)
-            })
-            .next()
+    /// Gets a directory entry by its stream ID, as linked to by the sibling and child IDs of the
+    /// entries, following the chain of directory sectors to the one holding it. An unallocated slot
+    /// is returned as [`Entry::Unknown`].
+    ///
+    /// Fails with [`Error::NoSuchStreamID`] if the stream ID is above MAXREGSID or beyond the
+    /// directory entry slots of the chain, with [`Error::LimitExceeded`] if it is beyond the
+    /// [directory entry limit], or with [`Error::Truncated`] if its directory sector lies past the
+    /// end of a file read strictly.
+    ///
+    /// [directory entry limit]: Limits::max_directory_entries
+    pub fn entry_by_id(&self, id: StreamID) -> Result<Entry<'_>, Error> {
+        if !id.is_regular() {
+            return Err(Error::NoSuchStreamID(id));
+        }
+        self.limits.check(Limit::DirectoryEntries, id.0 as u64 + 1)?;
+
+        let entries_per_sector = entries_per_sector(self.sector_size, Entry::LENGTH);
+        match self.directory(id.0 / entries_per_sector) {
+            Some(directory) => {
+                directory.check()?;
+                directory.entry(id.0 % entries_per_sector).map_err(Error::InvalidDirectory)
+            }
+            None => Err(Error::NoSuchStreamID(id)),
+        }
     }
 
-    /// Read the bytes of a stream object by its name, returns None if not found
-    pub fn stream_bytes(&self, name: &str) -> Option<Vec<u8>> {
-        self.directory_entry(name).and_then(|entry|
-            match entry {
-                Entry::Stream(stream) =>
-                    Some(stream.stream_bytes(self, None)),
-                Entry::RootStorage(root_storage) =>
-                    Some(root_storage.mini_stream_bytes(self)),
-                _ => None,
+    /// Gets the directory entry a sibling or child ID of the entry at an offset links to, as
+    /// [`entry_by_id`](Self::entry_by_id) does, failing with [`Error::InvalidDirectory`] for a link
+    /// to no slot, which is malformed. A file read leniently warns of such a link and takes it as
+    /// absent instead.
+    pub(crate) fn linked_entry(&self, from: u64, id: StreamID) -> Result<Option<Entry<'_>>, Error> {
+        match self.entry_by_id(id) {
+            Err(Error::NoSuchStreamID(_)) if self.lenient => {
+                self.warn_of_link(from, id);
+                Ok(None)
             }
-        )
-    }
-
-    /// Read the bytes of the mini stream
-    pub fn mini_stream_bytes(&self) -> Vec<u8> {
-        self.directories()
-            .next()
-            .map(|dir| dir.entry(0))
-            .map(|entry|
-                match entry {
-                    Ok(Entry::RootStorage(root)) =>
-                        root.mini_stream_bytes(&self),
-                    _ => panic!("impossible"),
+            Err(Error::NoSuchStreamID(_)) if !id.is_regular() =>
+                Err(Error::InvalidDirectory(format!("malformed link to reserved stream ID 0x{:08X}!", id.0))),
+            Err(Error::NoSuchStreamID(_)) => {
+                let entries_per_sector = entries_per_sector(self.sector_size, Entry::LENGTH);
+                let slots = self.directories().count() as u64 * entries_per_sector as u64;
+                Err(Error::InvalidDirectory(format!(
+                    "malformed link to stream ID {} beyond the {} directory entry slots!", id.0, slots
+                )))
+            }
+            result => result.map(Some),
+        }
+    }
+
+    /// Warns of a sibling or child ID of the entry at an offset which links to no slot, read
+    /// leniently
+    pub(crate) fn warn_of_link(&self, from: u64, id: StreamID) {
+        let name = match Entry::new(from, self) {
+            Ok(entry @ (Entry::RootStorage(_) | Entry::Storage(_) | Entry::Stream(_))) => entry.name(),
+            _ => String::new(),
+        };
+        self.warnings.push(Rule::StreamIdLink, Location::Entry { offset: from, name }, id.0 as u64);
+    }
+
+    /// Iterates over every directory entry slot in the order of the stream IDs, unallocated slots
+    /// included as [`Entry::Unknown`]. A slot whose object type is invalid is yielded as
+    /// [`Entry::Malformed`] when the file is read leniently, and as an error naming its stream ID
+    /// otherwise; either way the iteration goes on with the next slot. A directory sector past the
+    /// end of the source yields a single [`Error::Truncated`] in place of its slots, unless the file
+    /// is read leniently.
+    pub fn entries(&self) -> impl FusedIterator<Item = Result<Entry<'_>, Error>> + '_ {
+        self.directories().flat_map(|directory| {
+            let (err, slots) = match directory.check() {
+                Ok(()) => (None, Some(directory.into_iter().map(|entry| entry.map_err(Error::InvalidDirectory)))),
+                Err(err) => (Some(Err(err)), None),
+            };
+            err.into_iter().chain(slots.into_iter().flatten())
+        })
+    }
+
+    /**
+    Iterates over the allocated directory entries in the order of the stream IDs, as
+    [`entries`](Self::entries) does but passing over the unallocated slots, so that every
+    [`Entry::Unknown`] is left out while the errors and [`Entry::Malformed`] slots are kept.
+
+    ```
+    use ole_kit::cfb::Cfb;
+
+    let cfb = Cfb::from_path("tests_rsc/testing.doc")?;
+    for entry in cfb.allocated_entries() {
+        let entry = entry?;
+        println!("{} {:?}", entry.name(), entry.child_id());
+    }
+    # Ok::<(), ole_kit::cfb::Error>(())
+    ```
+     */
+    pub fn allocated_entries(&self) -> impl FusedIterator<Item = Result<Entry<'_>, Error>> + '_ {
+        self.entries().filter(|entry| !matches!(entry, Ok(Entry::Unknown)))
+    }
+
+    /// Gets a directory entry by its name, the first of the name in the order of the stream IDs,
+    /// failing with [`Error::NotFound`] if no entry has the name. Names are compared
+    /// case-insensitively under the uppercase mapping of the sibling tree, or exactly if the file
+    /// was opened with [`exact_names`](CfbOpenOptions::exact_names).
+    pub fn directory_entry(&self, name: &str) -> Result<Entry<'_>, Error> {
+        let entry = match self.index() {
+            Some(index) => index.first_by_name(self, name),
+            None => self.directories()
+                .filter_map(|dir| {
+                    dir
+                        .into_iter()
+                        .filter_map(Result::ok)
+                        .filter(|entry| !matches!(entry, Entry::Unknown | Entry::Malformed { .. }))
+                        .find(|entry| self.names_match(&entry.name(), name))
+                })
+                .next(),
+        };
+        entry.ok_or_else(|| Error::NotFound { name: name.to_string() })
+    }
+
+    /// Gets the directory entries of several names in one pass over the directory, keyed by the
+    /// names as given, each the first of the name in tree order as by
+    /// [`find_all_entries`](Self::find_all_entries), and the names not found are left out. The
+    /// names are compared as by [`directory_entry`](Self::directory_entry).
+    pub fn find_entries(&self, names: &[&str]) -> HashMap<String, Entry<'_>> {
+        self.find_all_entries(names)
+            .into_iter()
+            .filter_map(|(name, entries)| Some((name, entries.into_iter().next()?)))
+            .collect()
+    }
+
+    /// Gets every directory entry of several names in one pass over the directory, keyed by the
+    /// names as given, in tree order: the order of [`walk`](Self::walk), those not reachable from
+    /// the root storage last, in the order of the stream IDs. Only the names and links of the
+    /// entries are read, or nothing once the name index is built. Slots which can't be read are
+    /// passed over.
+    pub fn find_all_entries(&self, names: &[&str]) -> HashMap<String, Vec<Entry<'_>>> {
+        if let Some(index) = self.index() {
+            return names.iter()
+                .map(|name| (name.to_string(), index.all_by_name_in_tree_order(self, name)))
+                .filter(|(_, entries)| !entries.is_empty())
+                .collect();
+        }
+
+        let mut wanted: HashMap<String, Vec<&str>> = HashMap::new();
+        for name in names {
+            wanted.entry(self.name_key(name)).or_default().push(name);
+        }
+
+        // the links of every slot by its stream ID, and the entries of the names wanted
+        let mut links = Vec::new();
+        let mut matches = Vec::new();
+        for directory in self.directories() {
+            let readable = directory.check().is_ok();
+            for slot in 0..directory.len() {
+                if !readable {
+                    links.push(None);
+                    continue;
+                }
+                let entry = match directory.entry(slot) {
+                    Ok(Entry::Unknown) | Ok(Entry::Malformed { .. }) | Err(_) => None,
+                    Ok(entry) => Some(entry),
+                };
+                links.push(entry.as_ref().map(|entry| (entry.left_sibling_id(), entry.right_sibling_id(), entry.child_id())));
+                if let Some(entry) = entry.filter(|entry| wanted.contains_key(&self.name_key(&entry.name()))) {
+                    matches.push((StreamID(u32::try_from(links.len() - 1).unwrap_or(u32::MAX)), entry));
                 }
-            ).unwrap()
+            }
+        }
+        let ranks = storage::tree_order(&|id: StreamID| links.get(sector_index(id.0)).copied().flatten());
+        matches.sort_by_key(|(id, _)| ranks.get(id).copied().unwrap_or(usize::MAX));
+
+        let mut found: HashMap<String, Vec<Entry<'_>>> = HashMap::new();
+        for (_, entry) in matches {
+            for name in &wanted[&self.name_key(&entry.name())] {
+                found.entry(name.to_string()).or_default().push(entry.clone());
+            }
+        }
+        found
+    }
+
+    /// Gets a stream object by its name. Storage objects, and the root storage, of the same name
+    /// are passed over, so that a stream named `Root Entry` is found rather than the root storage.
+    /// Fails with [`Error::NotFound`] if no entry has the name, or with [`Error::NotAStream`] if
+    /// only storage objects do.
+    pub(crate) fn stream_by_name(&self, name: &str) -> Result<Stream<'_>, Error> {
+        let object_type = match self.directory_entry(name)? {
+            Entry::Stream(stream) => return Ok(stream),
+            Entry::RootStorage(_) => ObjectType::RootStorage,
+            _ => ObjectType::Storage,
+        };
+        self.entries()
+            .filter_map(Result::ok)
+            .find_map(|entry| match entry {
+                Entry::Stream(stream) if self.names_match(&stream.name(), name) => Some(stream),
+                _ => None,
+            })
+            .ok_or_else(|| Error::NotAStream { name: name.to_string(), object_type })
+    }
+
+    /// Read the bytes of a stream object by its name, failing with [`Error::NotFound`] if no entry
+    /// has the name, with [`Error::NotAStream`] if it names a storage object, or with the error of
+    /// reading the stream.
+    ///
+    /// The root storage is not a stream object, so its name fails with [`Error::NotAStream`] rather
+    /// than reading the mini stream holding the small streams, which
+    /// [`root_mini_stream`](Self::root_mini_stream) reads.
+    pub fn stream_bytes(&self, name: &str) -> Result<Vec<u8>, Error> {
+        self.stream_by_name(name)?.bytes()
+    }
+
+    /// Copies the bytes of a stream object by its name into a sink, as [`Stream::copy_to`] does,
+    /// returning the number of bytes written. The stream is found as by
+    /// [`stream_bytes`](Self::stream_bytes), but never held in memory as a whole.
+    pub fn copy_stream_to<W: Write + ?Sized>(&self, name: &str, sink: &mut W) -> Result<u64, Error> {
+        self.stream_by_name(name)?.copy_to(sink)
+    }
+
+    /// Reads the bytes of a stream object by its stream ID, as [`Stream::bytes`] does, failing with
+    /// [`Error::NoSuchStreamID`] as [`entry_by_id`](Self::entry_by_id) does, or with
+    /// [`Error::NotAStream`] if the slot holds a storage object, the root storage, or nothing
+    pub fn stream_bytes_by_id(&self, id: StreamID) -> Result<Vec<u8>, Error> {
+        let (name, object_type) = match self.entry_by_id(id)? {
+            Entry::Stream(stream) => return stream.bytes(),
+            Entry::RootStorage(root_storage) => (root_storage.name(), ObjectType::RootStorage),
+            Entry::Storage(storage) => (storage.name(), ObjectType::Storage),
+            Entry::Unknown | Entry::Malformed { .. } => (String::new(), ObjectType::Unknown),
+        };
+        Err(Error::NotAStream { name, object_type })
+    }
+
+    /// Returns the size in bytes of a stream object by its name, found as by
+    /// [`stream_bytes`](Self::stream_bytes), returns None if not found or not a stream object. Only
+    /// the directory is read, never a chain of the stream, and the size is the one
+    /// [`Stream::stream_size`] returns, whose most significant 32 bits are ignored in a version 3
+    /// file read leniently.
+    pub fn stream_len(&self, name: &str) -> Option<u64> {
+        Some(self.stream_by_name(name).ok()?.stream_size().0)
+    }
+
+    /// Returns whether a stream object by its name is stored in the mini stream, being smaller than
+    /// the cutoff size, returns None if not found or not a stream object. Only the directory is
+    /// read, as by [`stream_len`](Self::stream_len).
+    pub fn stream_is_in_mini_stream(&self, name: &str) -> Option<bool> {
+        Some(self.stream_len(name)? < self.mini_stream_cutoff())
+    }
+
+    /// Returns whether a stream object has a name, compared as by
+    /// [`directory_entry`](Self::directory_entry). The directory is scanned in the order of the
+    /// stream IDs up to the first match, unless the name index is built, and no stream is read.
+    /// Slots which can't be read are passed over.
+    pub fn contains_stream(&self, name: &str) -> bool {
+        self.contains(name, |entry| matches!(entry, Entry::Stream(_)))
+    }
+
+    /// Returns whether a storage object has a name, as [`contains_stream`](Self::contains_stream)
+    /// does for a stream object. Neither the root storage nor a stream object of the name matches.
+    pub fn contains_storage(&self, name: &str) -> bool {
+        self.contains(name, |entry| matches!(entry, Entry::Storage(_)))
+    }
+
+    /// Returns whether an entry passing a check of its kind has a name, looking the name up in the
+    /// name index once built
+    fn contains(&self, name: &str, is_kind: impl Fn(&Entry) -> bool) -> bool {
+        if let Some(index) = self.index() {
+            return index.all_by_name(self, name).iter().any(is_kind);
+        }
+        self.entries()
+            .filter_map(Result::ok)
+            .any(|entry| is_kind(&entry) && self.names_match(&entry.name(), name))
+    }
+
+    /// Calls a function with the bytes of a stream object by its name, in chunks of up to a sector,
+    /// as [`Stream::for_each_chunk`] does, returning the number of bytes passed. The stream is found
+    /// as by [`stream_bytes`](Self::stream_bytes).
+    pub fn for_each_chunk(&self, name: &str, f: impl FnMut(&[u8])) -> Result<u64, Error> {
+        self.stream_by_name(name)?.for_each_chunk(f)
+    }
+
+    /// Computes the digest of a stream object by its name, as [`Stream::digest`] does, with the
+    /// `digest` feature. The stream is found as by [`stream_bytes`](Self::stream_bytes).
+    #[cfg(feature = "digest")]
+    pub fn stream_digest<D: digest::Digest>(&self, name: &str) -> Result<digest::Output<D>, Error> {
+        self.stream_by_name(name)?.digest::<D>()
+    }
+
+    /// Returns the offset of the first occurrence of a byte pattern within a stream object by its
+    /// name, or None if it doesn't occur, without holding the whole stream in memory, as
+    /// [`Stream::find`] does. The stream is found as by [`stream_bytes`](Self::stream_bytes).
+    pub fn stream_find(&self, name: &str, needle: &[u8]) -> Result<Option<u64>, Error> {
+        self.stream_by_name(name)?.find(needle)
+    }
+
+    /// Returns the offsets of every occurrence of a byte pattern within a stream object by its
+    /// name, as [`Stream::find_all`] does. The stream is found as by
+    /// [`stream_bytes`](Self::stream_bytes).
+    pub fn stream_find_all(&self, name: &str, needle: &[u8]) -> Result<Vec<u64>, Error> {
+        self.stream_by_name(name)?.find_all(needle)
+    }
+
+    /// Read the bytes of a stream object by its name, returns None if not found, not a stream
+    /// object, or unreadable, as [`stream_bytes`](Self::stream_bytes) did before telling these
+    /// apart
+    #[deprecated(note = "use `stream_bytes`, whose error tells why the stream can't be read")]
+    pub fn stream_bytes_opt(&self, name: &str) -> Option<Vec<u8>> {
+        self.stream_bytes(name).ok()
+    }
+
+    /// Read `len` bytes of a stream object by its name starting at `offset`, returns None if not
+    /// found or not a stream object. Fewer bytes are returned when the range goes past the end of
+    /// the stream.
+    pub fn stream_bytes_range(&self, name: &str, offset: u64, len: usize) -> Option<Vec<u8>> {
+        self.try_stream_bytes_range(name, offset, len).ok()
+    }
+
+    /// Read `len` bytes of a stream object by its name starting at `offset`, as
+    /// [`stream_bytes_range`](Self::stream_bytes_range) does, failing as
+    /// [`stream_bytes`](Self::stream_bytes) does rather than returning None
+    pub fn try_stream_bytes_range(&self, name: &str, offset: u64, len: usize) -> Result<Vec<u8>, Error> {
+        let mut bytes = vec![0u8; len];
+        let read = self.stream_by_name(name)?.read_at(offset, &mut bytes)?;
+        bytes.truncate(read);
+        Ok(bytes)
+    }
+
+    /// Opens a stream object by its name for reading and seeking, returns None if not found or not
+    /// a stream object
+    pub fn open_stream(&self, name: &str) -> Option<StreamReader> {
+        self.try_open_stream(name).ok()
+    }
+
+    /// Opens a stream object by its name for reading and seeking, failing with
+    /// [`Error::NotFound`] if no entry has the name, or with [`Error::NotAStream`] if it names a
+    /// storage object or the root storage
+    pub fn try_open_stream(&self, name: &str) -> Result<StreamReader, Error> {
+        Ok(self.stream_by_name(name)?.reader())
+    }
+
+    /// Opens a stream object by its stream ID for reading and seeking, returns None if the entry
+    /// is not a stream object
+    pub fn open_stream_at_id(&self, id: StreamID) -> Option<StreamReader> {
+        match self.entry_by_id(id).ok()? {
+            Entry::Stream(stream) => Some(stream.reader()),
+            _ => None,
+        }
+    }
+
+    /// Opens a storage object by its path, whose components are separated by `/`, returns None if
+    /// not found or not a storage object. An empty path opens the root storage.
+    pub fn open_storage(&self, path: &str) -> Option<StorageHandle> {
+        StorageHandle::new(&self.entry_by_path(path).ok()??)
+    }
+
+    /// Gets a directory entry by its path, whose components are separated by `/`, returns None if
+    /// not found. Each component is compared with the names of the children of a storage as by
+    /// [`directory_entry`](Self::directory_entry). An empty path gets the root storage.
+    pub fn entry_by_path(&self, path: &str) -> Result<Option<Entry<'_>>, Error> {
+        let root_storage = Entry::RootStorage(self.try_root_storage()?);
+        storage::resolve(root_storage, path)
+    }
+
+    /**
+    Iterates over every storage object and stream object beneath the root storage with its path,
+    the names of the storages holding it from the root storage and then its own, as stored. The
+    hierarchy is walked depth first from the child of the root storage, the children of each
+    storage in the order of its sibling tree, which orders them by name, and each storage right
+    before its children. The root storage itself, whose path would be empty, isn't yielded.
+
+    ```
+    use ole_kit::cfb::{Cfb, Entry};
+
+    let cfb = Cfb::from_path("tests_rsc/testing.doc")?;
+    for item in cfb.walk() {
+        let (path, entry) = item?;
+        if let Entry::Stream(stream) = entry {
+            println!("{} ({} bytes)", path.join("/"), stream.stream_size().0);
+        }
+    }
+    # Ok::<(), ole_kit::cfb::Error>(())
+    ```
+
+    No stream is read. A sibling tree looping back on itself, a storage reached twice through the
+    child links, or a descent deeper than the [tree depth limit] yields an
+    [`Error::InvalidDirectory`] or [`Error::LimitExceeded`], which ends the iteration, as does a
+    first entry other than the root storage. A strictly read file whose sectors are
+    [claimed twice] yields nothing but [`Error::SharedSector`].
+
+    [tree depth limit]: Limits::max_tree_depth
+    [claimed twice]: Error::SharedSector
+     */
+    pub fn walk(&self) -> impl FusedIterator<Item = Result<(Vec<String>, Entry<'_>), Error>> + '_ {
+        let root_storage = self.try_root_storage()
+            .and_then(|root_storage| self.check_shared_sectors().map(|_| root_storage));
+        let (walk, err) = match root_storage {
+            Ok(root_storage) => (Some(storage::Walk::new(self, Entry::RootStorage(root_storage))), None),
+            Err(err) => (None, Some(Err(err))),
+        };
+        err.into_iter().chain(walk.into_iter().flatten())
+    }
+
+    /// Gets a directory entry by its path given as components, returns None if not found. Each
+    /// component is compared with the names of the children of a storage as by
+    /// [`directory_entry`](Self::directory_entry), and may hold any character, `/` or a control
+    /// character such as the one of `\u{1}Ole` alike. An empty path gets the root storage.
+    pub fn entry_at(&self, path: &[&str]) -> Result<Option<Entry<'_>>, Error> {
+        let root_storage = Entry::RootStorage(self.try_root_storage()?);
+        storage::resolve_components(root_storage, path)
+    }
+
+    /// Reads the bytes of a stream object by its path given as components, found as by
+    /// [`entry_at`](Self::entry_at), so that identically named streams in different storages can be
+    /// told apart. Fails with [`Error::NotFound`] naming the path, components joined by `/`, if
+    /// nothing is found, or with [`Error::NotAStream`] for a storage object or the root storage.
+    pub fn stream_bytes_at(&self, path: &[&str]) -> Result<Vec<u8>, Error> {
+        let object_type = match self.entry_at(path)? {
+            Some(Entry::Stream(stream)) => return stream.bytes(),
+            Some(Entry::RootStorage(_)) => ObjectType::RootStorage,
+            Some(_) => ObjectType::Storage,
+            None => return Err(Error::NotFound { name: path.join("/") }),
+        };
+        Err(Error::NotAStream { name: path.join("/"), object_type })
+    }
+
+    /// Gets the size of a stream object by its path, returns None if not found or not a stream
+    /// object. Only the directory entries along the path are read, never the stream data.
+    pub fn stream_size(&self, path: &str) -> Result<Option<StreamSize>, Error> {
+        Ok(match self.entry_by_path(path)? {
+            Some(Entry::Stream(stream)) => Some(stream.stream_size()),
+            _ => None,
+        })
+    }
+
+    /// Gets the type of an object by its path, returns None if not found. Only the directory entries
+    /// along the path are read.
+    pub fn entry_type(&self, path: &str) -> Result<Option<ObjectType>, Error> {
+        Ok(self.entry_by_path(path)?.map(|entry| match entry {
+            Entry::RootStorage(_) => ObjectType::RootStorage,
+            Entry::Storage(_) => ObjectType::Storage,
+            Entry::Stream(_) => ObjectType::Stream,
+            Entry::Unknown | Entry::Malformed { .. } => ObjectType::Unknown,
+        }))
+    }
+
+    /// Counts the allocated directory entries, those whose object type isn't unallocated, including
+    /// the root storage. Only the directory sectors are read, one at a time, without building an
+    /// entry for each slot.
+    ///
+    /// On a well-formed file this is one more than the number of entries beneath the root storage.
+    /// The slots are counted regardless of the sibling trees though, so an allocated entry which no
+    /// storage links to, or one with an invalid object type, is still counted.
+    pub fn entry_count(&self) -> Result<u64, Error> {
+        self.count_entries(|object_type| object_type != ObjectType::Unknown as u8)
+    }
+
+    /// Counts the directory entries of stream objects, see [`entry_count`](Self::entry_count)
+    pub fn stream_count(&self) -> Result<u64, Error> {
+        self.count_entries(|object_type| object_type == ObjectType::Stream as u8)
+    }
+
+    /// Counts the directory entries of storage objects, not including the root storage, see
+    /// [`entry_count`](Self::entry_count)
+    pub fn storage_count(&self) -> Result<u64, Error> {
+        self.count_entries(|object_type| object_type == ObjectType::Storage as u8)
+    }
+
+    fn count_entries(&self, counted: impl Fn(u8) -> bool) -> Result<u64, Error> {
+        let mut count = 0;
+        for directory in self.directories() {
+            count += directory.object_types()?.into_iter().filter(|object_type| counted(*object_type)).count() as u64;
+        }
+        Ok(count)
+    }
+
+    /// Returns the root storage entry, which is the first entry of the first directory sector
+    pub(crate) fn root_storage(&self) -> Option<RootStorage<'_>> {
+        self.try_root_storage().ok()
+    }
+
+    /// Gets the root storage, failing with [`Error::Truncated`] if the first directory sector lies
+    /// past the end of a strictly read file, or [`Error::InvalidDirectory`] if the first entry is
+    /// not the root storage
+    pub(crate) fn try_root_storage(&self) -> Result<RootStorage<'_>, Error> {
+        let directory = self.directories().next()
+            .ok_or_else(|| Error::InvalidDirectory("the first entry is not the root storage!".to_string()))?;
+        directory.check()?;
+        match directory.entry(0) {
+            Ok(Entry::RootStorage(root_storage)) => Ok(root_storage),
+            _ => Err(Error::InvalidDirectory("the first entry is not the root storage!".to_string())),
+        }
+    }
+
+    /// Read the bytes of the mini stream, the container of the streams smaller than the cutoff,
+    /// failing with [`Error::TruncatedChain`] if its chain is shorter than the size declared by the
+    /// root storage
+    pub fn mini_stream_bytes(&self) -> Result<Vec<u8>, Error> {
+        self.try_root_storage()?.mini_stream()
+    }
+
+    /// Read the bytes of the mini stream held by the root storage, as
+    /// [`mini_stream_bytes`](Self::mini_stream_bytes) does. This container of the small streams is
+    /// not user data, and is not read by the name of the root storage.
+    pub fn root_mini_stream(&self) -> Result<Vec<u8>, Error> {
+        self.mini_stream_bytes()
     }
 }
 
+#[cfg(feature = "std")]
 impl fmt::Debug for Cfb {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let mut fmt = f.debug_map();
+        if let Some(path) = &self.path {
+            fmt.entry(&"path", path);
+        }
         crate::debug_map_method_reflection!(fmt, self, sector_size, header);
         fmt.finish()
     }
 }
 
+/// Opens the file at a path, naming the path in the error if it fails
+#[cfg(feature = "std-fs")]
+pub(crate) fn open_file(path: &Path) -> Result<File, Error> {
+    File::open(path).map_err(|err| Error::io_at(path, err))
+}
+
+/// An iterator over the locations of the FAT sectors, listed by the header and then by the chained
+/// DIFAT sectors.
+#[cfg(feature = "std")]
+struct FatSectorLocations<'a> {
+    cfb: &'a Cfb,
+    /// The entries of the header, or of the DIFAT sector, left to yield, in reverse order.
+    entries: Vec<SectorNumber>,
+    next_difat_sector: SectorNumber,
+    visited: std::collections::HashSet<SectorNumber>,
+}
+
+#[cfg(feature = "std")]
+impl<'a> FatSectorLocations<'a> {
+    fn new(cfb: &'a Cfb) -> Self {
+        let header = cfb.header();
+        let mut entries = header.difat().sector_numbers().to_vec();
+        entries.reverse();
+
+        Self {
+            cfb,
+            entries,
+            next_difat_sector: header.first_difat_sector_location(),
+            visited: std::collections::HashSet::new(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> Iterator for FatSectorLocations<'a> {
+    type Item = Result<SectorNumber, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.entries.pop() {
+                // the first free entry ends the locations listed by the header or a DIFAT sector
+                Some(sector_no) if sector_no.is_free() => self.entries.clear(),
+                Some(sector_no) => return Some(Ok(sector_no)),
+                None => {
+                    let sector_no = self.next_difat_sector;
+                    if !sector_no.is_other() || !self.visited.insert(sector_no) {
+                        return None;
+                    }
+
+                    // an error ends the iteration
+                    self.next_difat_sector = SectorNumber::ENDOFCHAIN;
+                    let fat = self.cfb.limits.check(Limit::ChainLength, self.visited.len() as u64)
+                        .and_then(|_| self.cfb.fat(sector_no));
+                    let mut entries = match fat {
+                        Ok(fat) => fat.sector_numbers(),
+                        Err(err) => return Some(Err(err)),
+                    };
+
+                    self.next_difat_sector = entries.pop().unwrap_or(SectorNumber::ENDOFCHAIN);
+                    entries.reverse();
+                    self.entries = entries;
+                }
+            }
+        }
+    }
+
+    /// At least the locations left before the first FREESECT entry of the header or the DIFAT
+    /// sector being read, and exactly those once no DIFAT sector follows
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let listed = self.entries.iter().rev().take_while(|sector_no| !sector_no.is_free()).count();
+        let more = self.next_difat_sector.is_other() && !self.visited.contains(&self.next_difat_sector);
+        (listed, if more { None } else { Some(listed) })
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> FusedIterator for FatSectorLocations<'a> {}
+
+/// An iterator over the directory sectors, following their chain in the FAT until it ends, loops
+/// back on itself or holds more entries than the [directory entry limit].
+///
+/// [directory entry limit]: Limits::max_directory_entries
+#[cfg(feature = "std")]
 pub(crate) struct Iter<'a> {
     next_sector: SectorNumber,
+    visited: std::collections::HashSet<SectorNumber>,
     cfb: &'a Cfb,
 }
 
+#[cfg(feature = "std")]
 impl<'a> Iter<'a> {
     pub(crate) fn new(next_sector: SectorNumber, cfb: &'a Cfb) -> Self {
         Self {
             next_sector,
+            visited: std::collections::HashSet::new(),
             cfb,
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a> Iterator for Iter<'a> {
     type Item = Directory<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if !self.next_sector.is_other() {
+        // the entries of the sectors already yielded
+        let entries = self.visited.len() as u64 * entries_per_sector(self.cfb.sector_size, Entry::LENGTH) as u64;
+        if entries >= self.cfb.limits.max_directory_entries {
+            return None
+        }
+        if !self.next_sector.is_other() || !self.visited.insert(self.next_sector) {
             return None
         }
 
         let sector = self.next_sector;
+        let (offset, first_id) = match ((sector + 1).checked_byte_offset(self.cfb.sector_size as u64), u32::try_from(entries)) {
+            (Some(offset), Ok(first_id)) => (offset, first_id),
+            // a sector beyond the addressable ones ends the chain for good
+            _ => {
+                self.next_sector = SectorNumber::ENDOFCHAIN;
+                return None;
+            }
+        };
+
+        // a FAT sector which can't be read, or an entry past it, ends the chain
+        self.next_sector = Cache::new(self.cfb).next_sector(sector).ok().flatten()
+            .unwrap_or(SectorNumber::ENDOFCHAIN);
+
+        Some(Directory::new(sector, offset, self.cfb.sector_size, first_id, self.cfb))
+    }
+
+    /// At most the sectors holding the entries left under the [directory entry limit], as the count
+    /// of directory sectors in the header can't be trusted
+    ///
+    /// [directory entry limit]: Limits::max_directory_entries
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if !self.next_sector.is_other() || self.visited.contains(&self.next_sector) {
+            return (0, Some(0));
+        }
+        let entries_per_sector = entries_per_sector(self.cfb.sector_size, Entry::LENGTH) as u64;
+        let left = self.cfb.limits.max_directory_entries
+            .saturating_sub(self.visited.len() as u64 * entries_per_sector)
+            .div_ceil(entries_per_sector);
+        (0, Some(to_usize_saturating(left)))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> FusedIterator for Iter<'a> {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// Opens a fixture with a read counter, returning the sectors holding the FAT, the mini FAT and
+    /// the directory, which are the only ones metadata queries may read.
+    fn open_counting(path: &str) -> (Cfb, Arc<SectorReadCounter>, BTreeSet<SectorNumber>) {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new()
+            .instrument(counter.clone())
+            .open_read_at(std::fs::read(path).unwrap(), 0, None)
+            .unwrap();
+
+        let header = cfb.header();
+        let mut metadata_sectors: BTreeSet<_> = cfb.fat_sector_locations().map(Result::unwrap).collect();
+        metadata_sectors.extend(cfb.mini_fat_table().unwrap().sector_locations());
+        let mut sector = header.first_directory_sector_location();
+        while sector.is_other() {
+            metadata_sectors.insert(sector);
+            sector = cfb.fat_by_stream_sector_no(sector).unwrap().sector_number(sector.0 % sector_numbers_per_sector(cfb.sector_size)).unwrap();
+        }
+
+        counter.take_reads();
+        (cfb, counter, metadata_sectors)
+    }
+
+    fn assert_no_data_reads(counter: &SectorReadCounter, sector_size: u32, metadata_sectors: &BTreeSet<SectorNumber>) {
+        let data_sectors: Vec<_> = counter.sectors(sector_size).difference(metadata_sectors).copied().collect();
+        assert_eq!(data_sectors, [], "data sectors read");
+        counter.take_reads();
+    }
+
+    #[test]
+    fn stream_size_reads_no_stream_data() {
+        let (cfb, source, metadata_sectors) = open_counting("tests_rsc/hwp5.0.hwp");
+
+        assert_eq!(cfb.stream_size("ViewText/Section3").unwrap(), Some(StreamSize(174052)));
+        assert_eq!(cfb.stream_size("ViewText").unwrap(), None);
+        assert_eq!(cfb.stream_size("ViewText/Section9").unwrap(), None);
+        assert_eq!(cfb.stream_size("FileHeader/Section0").unwrap(), None);
+        assert_no_data_reads(&source, cfb.sector_size, &metadata_sectors);
+    }
+
+    #[test]
+    fn field_reads_hit_the_sector_cache() {
+        for path in ["tests_rsc/hwp5.0.hwp", "tests_rsc/testing.doc"] {
+            let bytes: SharedSource = Arc::new(std::fs::read(path).unwrap());
+            // counts the reads reaching the bytes, through the cache or not
+            let enumerate = |cache: bool| {
+                let counter = Arc::new(SectorReadCounter::new());
+                let mut source: SharedSource = Arc::new(crate::cfb::observe::ObservedSource::new(bytes.clone(), counter.clone()));
+                if cache {
+                    source = Arc::new(SectorCache::new(source));
+                }
+                let cfb = Cfb { source, ..Cfb::from_source(bytes.clone()).unwrap() };
+                let entries: Vec<String> = cfb.entries().map(|entry| format!("{:?}", entry.unwrap())).collect();
+                (entries, counter.read_count())
+            };
+
+            let (expected, uncached) = enumerate(false);
+            let (entries, cached) = enumerate(true);
+            assert_eq!(entries, expected);
+            assert!(cached * 10 <= uncached, "{} reads instead of {} for {}", cached, uncached, path);
+        }
+    }
+
+    #[test]
+    fn duplicates_have_caches_of_their_own() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        cfb.stream_bytes("WordDocument").unwrap();
+        let duplicate = cfb.try_clone().unwrap();
+        assert!(!Arc::ptr_eq(&duplicate.source, &cfb.source));
+        assert!(!Arc::ptr_eq(&duplicate.fats, &cfb.fats));
+        assert!(!Arc::ptr_eq(&duplicate.name_index, &cfb.name_index));
+        assert!(!Arc::ptr_eq(&duplicate.first_overlap, &cfb.first_overlap));
+
+        duplicate.warnings.push(Rule::TruncatedSector, Location::Sector { sector: 0, offset: 512 }, 0);
+        assert!(cfb.warnings().is_empty());
+        assert_eq!(duplicate.stream_bytes("WordDocument").unwrap(), cfb.stream_bytes("WordDocument").unwrap());
+    }
+
+    #[test]
+    fn in_memory_sources_skip_the_sector_cache() {
+        let bytes = std::fs::read("tests_rsc/testing.doc").unwrap();
+        assert!(Cfb::from_bytes(bytes.clone()).unwrap().source.is_in_memory());
+        let slice: &'static [u8] = Box::leak(bytes.into_boxed_slice());
+        assert!(Cfb::from_read_at(slice, 0, None).unwrap().source.is_in_memory());
+        assert!(!Cfb::from_path("tests_rsc/testing.doc").unwrap().source.is_in_memory());
+        #[cfg(feature = "mmap")]
+        assert!(unsafe { Cfb::from_path_mmap("tests_rsc/testing.doc") }.unwrap().source.is_in_memory());
+
+        // a duplicate shares the bytes, as there is no cache to keep apart
+        let cfb = Cfb::from_bytes(std::fs::read("tests_rsc/testing.doc").unwrap()).unwrap();
+        assert!(Arc::ptr_eq(&cfb.try_clone().unwrap().source, &cfb.source));
+    }
+
+    #[test]
+    fn directory_slots_are_bounded() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let directory = cfb.directory(1).unwrap();
+        assert!(matches!(directory.entry(3), Ok(Entry::Stream(_)) | Ok(Entry::Storage(_)) | Ok(Entry::Unknown)));
+        assert!(directory.entry(4).is_err());
+        assert!(directory.entry(u32::MAX).is_err());
+    }
+
+    /// Asserts that an iterator yields as many items as its size hint allows, and nothing once ended
+    fn assert_hinted<I: Iterator>(mut iter: I) -> usize {
+        let (lower, upper) = iter.size_hint();
+        let mut count = 0;
+        while iter.next().is_some() {
+            count += 1;
+        }
+        assert!(lower <= count && upper.is_none_or(|upper| count <= upper), "{:?} for {}", (lower, upper), count);
+        assert!(iter.next().is_none());
+        count
+    }
+
+    #[test]
+    fn iterators_keep_their_contract() {
+        for path in ["tests_rsc/hwp5.0.hwp", "tests_rsc/testing.doc"] {
+            let cfb = Cfb::from_path(path).unwrap();
+            for directory in cfb.directories() {
+                let len = directory.len() as usize;
+                let iter = directory.into_iter();
+                assert_eq!(iter.len(), len);
+                assert_eq!(assert_hinted(iter), len);
+            }
+            let sectors = assert_hinted(cfb.directories());
+            assert_eq!(assert_hinted(cfb.entries()), sectors * (cfb.sector_size / Entry::LENGTH) as usize);
+            assert_eq!(assert_hinted(cfb.fat_sector_locations()), cfb.header().no_of_fat_sectors().0 as usize);
+
+            let offsets = || cfb.entries().map(|entry| entry.unwrap().to_owned().map(|entry| entry.entry().unwrap().offset()))
+                .collect::<Vec<_>>();
+            assert_eq!(offsets(), offsets());
+        }
+    }
+
+    #[test]
+    fn entry_counts_agree_with_walk() {
+        for path in ["tests_rsc/hwp5.0.hwp", "tests_rsc/testing.doc"] {
+            let cfb = Cfb::from_path(path).unwrap();
+            let streams = cfb.walk().filter(|item| matches!(item, Ok((_, Entry::Stream(_))))).count() as u64;
+            let storages = cfb.walk().filter(|item| matches!(item, Ok((_, Entry::Storage(_))))).count() as u64;
+            assert_eq!(cfb.walk().count() as u64, streams + storages);
+
+            assert_eq!(cfb.stream_count().unwrap(), streams);
+            assert_eq!(cfb.storage_count().unwrap(), storages);
+            assert_eq!(cfb.entry_count().unwrap(), streams + storages + 1);
+        }
+    }
+
+    #[test]
+    fn entry_count_reads_no_stream_data() {
+        let (cfb, source, metadata_sectors) = open_counting("tests_rsc/hwp5.0.hwp");
+
+        assert!(cfb.entry_count().unwrap() > 1);
+        assert_no_data_reads(&source, cfb.sector_size, &metadata_sectors);
+    }
+
+    #[test]
+    fn stream_names_read_no_stream_data() {
+        let (cfb, source, metadata_sectors) = open_counting("tests_rsc/hwp5.0.hwp");
+
+        assert_eq!(cfb.stream_names_with_sizes().unwrap().len(), 53);
+        assert_eq!(cfb.entry_names().unwrap().len(), 58);
+        assert_no_data_reads(&source, cfb.sector_size, &metadata_sectors);
+    }
 
-        let fat = self.cfb.fat_by_stream_sector_no(sector);
-        self.next_sector = fat.sector_number(sector.0 % (self.cfb.sector_size >> std::mem::size_of::<SectorNumber>()));
+    #[test]
+    fn entry_type_reads_no_stream_data() {
+        let (cfb, source, metadata_sectors) = open_counting("tests_rsc/hwp5.0.hwp");
 
-        let dir = Some(Directory::new((sector + 1).byte_offset(self.cfb.sector_size),
-                            self.cfb.sector_size,
-                            &self.cfb.file));
-        dir
+        assert_eq!(cfb.entry_type("").unwrap(), Some(ObjectType::RootStorage));
+        assert_eq!(cfb.entry_type("BinData").unwrap(), Some(ObjectType::Storage));
+        assert_eq!(cfb.entry_type("/BodyText/Section0").unwrap(), Some(ObjectType::Stream));
+        assert_eq!(cfb.entry_type("BodyText/Section1").unwrap(), None);
+        assert_no_data_reads(&source, cfb.sector_size, &metadata_sectors);
     }
 }
@@ -0,0 +1,166 @@
+use crate::cfb::convert::sector_numbers_per_sector;
+use crate::cfb::header::Header;
+use crate::cfb::name::display_name;
+use crate::cfb::reader::MINI_SECTOR_SIZE;
+use crate::cfb::{Cfb, Error, SectorMap, SectorNumber, SectorOwner};
+use std::convert::TryFrom;
+use std::fmt::Write;
+use std::ops::Range;
+
+/// The fields of the header by their offset and length, named as the rules of the warnings are.
+const HEADER_FIELDS: [(u64, u64, &str); 18] = [
+    (0, 8, "header.signature"),
+    (8, 16, "header.clsid"),
+    (24, 2, "header.minor_version"),
+    (26, 2, "header.major_version"),
+    (28, 2, "header.byte_order"),
+    (30, 2, "header.sector_shift"),
+    (32, 2, "header.mini_sector_shift"),
+    (34, 6, "header.reserved"),
+    (40, 4, "header.directory_sector_count"),
+    (44, 4, "header.fat_sector_count"),
+    (48, 4, "header.first_directory_sector"),
+    (52, 4, "header.transaction_signature"),
+    (56, 4, "header.mini_stream_cutoff"),
+    (60, 4, "header.first_mini_fat_sector"),
+    (64, 4, "header.mini_fat_sector_count"),
+    (68, 4, "header.first_difat_sector"),
+    (72, 4, "header.difat_sector_count"),
+    (76, 436, "header.difat"),
+];
+
+/// A region of a compound file with what it holds, returned by [`Cfb::annotations`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Annotation {
+    /// The byte range of the region from the start of the compound file.
+    pub range: Range<u64>,
+    pub label: String,
+}
+
+impl Cfb {
+    /// Describes the file region by region, in the order of the offsets, for reading it in a hex
+    /// editor: each field of the header, then each sector labeled with its owner in the
+    /// [sector map] and its index in the owner's chain. The sectors of the mini stream are split
+    /// into their mini sectors, and the sectors no chain reaches are labeled free or unreferenced
+    /// as the FAT marks them.
+    ///
+    /// See [`annotations_to_tsv`] and [`annotations_to_json`] for writing them out.
+    ///
+    /// [sector map]: Cfb::sector_map
+    pub fn annotations(&self) -> Result<Vec<Annotation>, Error> {
+        let map = self.sector_map()?;
+        let sector_size = self.sector_size as u64;
+
+        let mut annotations: Vec<Annotation> = HEADER_FIELDS.iter()
+            .map(|(offset, len, label)| Annotation { range: *offset..offset + len, label: label.to_string() })
+            .collect();
+        if sector_size > Header::LENGTH as u64 {
+            annotations.push(Annotation { range: Header::LENGTH as u64..sector_size, label: "header.padding".to_string() });
+        }
+
+        let size = self.source.size().unwrap_or(0);
+        let sector_count = (size / sector_size).saturating_sub(1);
+        for sector in (0..sector_count).filter_map(|sector| u32::try_from(sector).ok()).map(SectorNumber) {
+            let start = (u64::from(sector.0) + 1) * sector_size;
+            match map.owner(sector) {
+                Some(SectorOwner::MiniStream) => self.annotate_mini_sectors(&map, sector, start, &mut annotations),
+                Some(owner) => {
+                    let index = map.index(sector, false).unwrap_or_default();
+                    annotations.push(Annotation { range: start..start + sector_size, label: sector_label(owner, index, false) });
+                }
+                None => {
+                    let label = if self.fat_entry(sector) == Some(SectorNumber::FREESECT) {
+                        format!("free sector {}", sector.0)
+                    } else {
+                        format!("unreferenced sector {}", sector.0)
+                    };
+                    annotations.push(Annotation { range: start..start + sector_size, label });
+                }
+            }
+        }
+
+        let end = (sector_count + 1) * sector_size;
+        if size > end {
+            annotations.push(Annotation { range: end..size, label: "trailing bytes".to_string() });
+        }
+        Ok(annotations)
+    }
+
+    /// Splits a sector of the mini stream into its mini sectors, each labeled with its owner
+    fn annotate_mini_sectors(&self, map: &SectorMap, sector: SectorNumber, start: u64, annotations: &mut Vec<Annotation>) {
+        let mini_sectors_per_sector = self.sector_size as u64 / MINI_SECTOR_SIZE;
+        let first = map.index(sector, false).unwrap_or_default() * mini_sectors_per_sector;
+        for i in 0..mini_sectors_per_sector {
+            let offset = start + i * MINI_SECTOR_SIZE;
+            let range = offset..offset + MINI_SECTOR_SIZE;
+            let label = match u32::try_from(first + i).ok().map(SectorNumber) {
+                Some(mini_sector) => match map.mini_owner(mini_sector) {
+                    Some(owner) => sector_label(owner, map.index(mini_sector, true).unwrap_or_default(), true),
+                    None => format!("unused mini sector {}", mini_sector.0),
+                },
+                None => "mini stream".to_string(),
+            };
+            annotations.push(Annotation { range, label });
+        }
+    }
+
+    /// Returns the entry of the FAT for a sector, or None if the FAT can't be read that far
+    fn fat_entry(&self, sector: SectorNumber) -> Option<SectorNumber> {
+        self.fat_by_stream_sector_no(sector).ok()?
+            .sector_number(sector.0 % sector_numbers_per_sector(self.sector_size))
+    }
+}
+
+/// Labels a sector, or a mini sector, by its owner and its index in the owner's chain
+fn sector_label(owner: &SectorOwner, index: u64, mini: bool) -> String {
+    let kind = if mini { "mini sector" } else { "sector" };
+    match owner {
+        SectorOwner::Stream(path) => {
+            let path: Vec<String> = path.split('/').map(display_name).collect();
+            format!("stream `{}` {} {}", path.join("/"), kind, index)
+        }
+        SectorOwner::Difat => format!("DIFAT {} {}", kind, index),
+        SectorOwner::Fat => format!("FAT {} {}", kind, index),
+        SectorOwner::MiniFat => format!("mini FAT {} {}", kind, index),
+        SectorOwner::Directory => format!("directory {} {}", kind, index),
+        SectorOwner::MiniStream => format!("mini stream {} {}", kind, index),
+    }
+}
+
+/// Writes annotations as tab-separated values, one region per line after a header line: its
+/// offset, its length and its label
+pub fn annotations_to_tsv(annotations: &[Annotation]) -> String {
+    let mut tsv = String::from("offset\tlength\tlabel\n");
+    for annotation in annotations {
+        let _ = writeln!(tsv, "{}\t{}\t{}", annotation.range.start,
+                         annotation.range.end - annotation.range.start, annotation.label);
+    }
+    tsv
+}
+
+/// Writes annotations as a JSON array of objects with the `offset`, `length` and `label` of each
+/// region, which hex editors can import as bookmarks
+pub fn annotations_to_json(annotations: &[Annotation]) -> String {
+    let mut json = String::from("[\n");
+    for (i, annotation) in annotations.iter().enumerate() {
+        let separator = if i + 1 < annotations.len() { "," } else { "" };
+        let _ = writeln!(json, "  {{\"offset\": {}, \"length\": {}, \"label\": \"{}\"}}{}", annotation.range.start,
+                         annotation.range.end - annotation.range.start, json_escape(&annotation.label), separator);
+    }
+    json.push_str("]\n");
+    json
+}
+
+/// Escapes a string for a JSON string literal
+fn json_escape(string: &str) -> String {
+    let mut escaped = String::with_capacity(string.len());
+    for c in string.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if c.is_control() => { let _ = write!(escaped, "\\u{:04x}", c as u32); }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
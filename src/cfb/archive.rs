@@ -0,0 +1,80 @@
+use crate::cfb::extract::walk_escaped;
+use crate::cfb::{Cfb, Entry, Error};
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use std::convert::TryFrom;
+use std::io::{self, Seek, Write};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{DateTime, ZipWriter};
+
+impl Cfb {
+    /**
+    Writes every stream of the compound file into a zip archive, each storage added as a directory,
+    so that a document can be inspected with any archive tool.
+
+    An entry is named by the names of the storages holding it and its own, joined by `/`, each
+    escaped as [`extract_to`](Self::extract_to) escapes file names, so that
+    `\u{5}SummaryInformation` is stored as `%05SummaryInformation`. A storage is dated by its
+    modified time and a stream, which has none of its own, by that of the storage holding it. An
+    entry is left undated where the time isn't recorded or can't be stored in a zip archive, which
+    dates from 1980 to 2107.
+
+    The streams are stored deflated, a sector at a time. Fails as reading the streams does, or with
+    [`Error::Io`] if the archive couldn't be written.
+     */
+    pub fn export_zip<W: Write + Seek>(&self, writer: W) -> Result<(), Error> {
+        let mut zip = ZipWriter::new(writer);
+        walk_escaped(self, &mut |item| {
+            let name = item.file_names.join("/");
+            match &item.entry {
+                Entry::Stream(stream) => {
+                    let options = options(modified_time(&item.parent)).large_file(stream.stream_size().0 > u32::MAX as u64);
+                    zip.start_file(name, options).map_err(zip_error)?;
+                    stream.copy_to(&mut zip)?;
+                }
+                storage => {
+                    zip.add_directory(name, options(modified_time(storage))).map_err(zip_error)?;
+                }
+            }
+            Ok(())
+        })?;
+        zip.finish().map_err(zip_error)?;
+        Ok(())
+    }
+}
+
+/// Returns the modified time of a storage, or None for any other entry
+fn modified_time(entry: &Entry) -> Option<NaiveDateTime> {
+    match entry {
+        Entry::RootStorage(root_storage) => root_storage.modified_time(),
+        Entry::Storage(storage) => storage.modified_time(),
+        _ => None,
+    }
+}
+
+/// Returns the options of an entry dated by a time, if it can be stored
+fn options(time: Option<NaiveDateTime>) -> SimpleFileOptions {
+    let options = SimpleFileOptions::default();
+    let time = time.and_then(|time| {
+        let byte = |value: u32| u8::try_from(value).ok();
+        DateTime::from_date_and_time(
+            u16::try_from(time.year()).ok()?,
+            byte(time.month())?,
+            byte(time.day())?,
+            byte(time.hour())?,
+            byte(time.minute())?,
+            byte(time.second())?,
+        ).ok()
+    });
+    match time {
+        Some(time) => options.last_modified_time(time),
+        None => options,
+    }
+}
+
+fn zip_error(err: ZipError) -> Error {
+    match err {
+        ZipError::Io(err) => Error::Io(err),
+        err => Error::Io(io::Error::other(err)),
+    }
+}
@@ -0,0 +1,148 @@
+//! Reading a compound file from an asynchronous reader, with the `async` feature.
+//!
+//! The structures of the file, which are the header, the DIFAT, the FAT, the mini FAT and the
+//! directory, are read ahead when opening, awaiting each sector. The directory entries are then
+//! read from memory, and only the stream data is read later, extent by extent.
+
+use crate::cfb::convert::{sector_index, to_usize_checked};
+use crate::cfb::header::Header;
+use crate::cfb::read_at::ReadAhead;
+use crate::cfb::{Cfb, Entry, Error, Limit, SectorNumber};
+use std::collections::HashSet;
+use std::io::SeekFrom;
+#[cfg(feature = "std-fs")]
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+use tokio::sync::Mutex;
+
+/// A compound file read from an asynchronous reader, such as a [`tokio::fs::File`].
+///
+/// Opening reads the structures of the file ahead, so that the [header](Self::header) and the
+/// [directory entries](Self::directory_entry) are answered from memory without awaiting, while the
+/// bytes of the streams are read when asked for. The entries only give access to their fields: the
+/// data of a stream is read by [`stream_bytes`](Self::stream_bytes), as reading it through the
+/// entry fails.
+pub struct AsyncCfb<R> {
+    reader: Mutex<R>,
+    cfb: Cfb,
+}
+
+#[cfg(feature = "std-fs")]
+impl AsyncCfb<tokio::fs::File> {
+    /// Opens the compound file at the path
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Self::new(tokio::fs::File::open(path).await?).await
+    }
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin + Send> AsyncCfb<R> {
+    /// Creates a compound file by reading from the reader, from its start to its end, checking the
+    /// header and reading the structures of the file ahead
+    pub async fn new(reader: R) -> Result<Self, Error> {
+        let reader = Mutex::new(reader);
+        let size = reader.lock().await.seek(SeekFrom::End(0)).await?;
+        let source = Arc::new(ReadAhead::new(Some(size)));
+        source.insert(0, read_at(&reader, 0, Header::LENGTH).await?);
+        let cfb = Cfb::from_source(source.clone())?;
+        let sector_size = cfb.sector_size;
+
+        // the DIFAT sectors, each linking to the next by its last entry
+        let mut visited = HashSet::new();
+        let mut next = cfb.header().first_difat_sector_location();
+        while next.is_other() && visited.insert(next) {
+            cfb.limits().check(Limit::ChainLength, visited.len() as u64)?;
+            read_sector(&reader, &source, next, sector_size).await?;
+            next = cfb.fat(next)?.sector_numbers().pop().unwrap_or(SectorNumber::ENDOFCHAIN);
+        }
+
+        // the FAT sectors they list, then the chains found in the FAT
+        let fat_sectors = cfb.fat_sector_locations().collect::<Result<Vec<_>, _>>()?;
+        for sector in fat_sectors.into_iter().filter(SectorNumber::is_other) {
+            read_sector(&reader, &source, sector, sector_size).await?;
+        }
+        let header = cfb.header();
+        for start in [header.first_directory_sector_location(), header.first_mini_fat_sector_location()] {
+            for sector in cfb.sector_chain(start)? {
+                read_sector(&reader, &source, sector, sector_size).await?;
+            }
+        }
+
+        Ok(Self { reader, cfb })
+    }
+
+    /// Returns the header, read when opening
+    pub fn header(&self) -> Header<'_> {
+        self.cfb.header()
+    }
+
+    /// Gets a directory entry by its name, as [`Cfb::directory_entry`] does, from the directory read
+    /// when opening
+    pub fn directory_entry(&self, name: &str) -> Result<Entry<'_>, Error> {
+        self.cfb.directory_entry(name)
+    }
+
+    /// Reads the bytes of a stream object by its name, as [`Cfb::stream_bytes`] does, awaiting the
+    /// read of each run of adjacent sectors. The chain of the stream is resolved before any read, so
+    /// a chain shorter than the stream fails with [`Error::TruncatedChain`] without reading.
+    pub async fn stream_bytes(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let (size, extents) = {
+            let stream = self.cfb.stream_by_name(name)?;
+            let size = stream.stream_size().0;
+            self.cfb.limits().check(Limit::BytesRead, size)?;
+            (size, self.cfb.extents(&stream)?)
+        };
+        self.read_extents(size, &extents).await
+    }
+
+    /// Reads the bytes of the mini stream, as [`Cfb::mini_stream_bytes`] does
+    pub async fn mini_stream_bytes(&self) -> Result<Vec<u8>, Error> {
+        let (size, extents) = {
+            let root_storage = self.cfb.try_root_storage()?;
+            let size = root_storage.stream_size().0;
+            self.cfb.limits().check(Limit::BytesRead, size)?;
+            (size, self.cfb.chain_extents(root_storage.starting_sector_location(), size, false)?)
+        };
+        self.read_extents(size, &extents).await
+    }
+
+    /// Reads extents one after the other, failing with [`Error::Truncated`] if the reader ends
+    /// before them
+    async fn read_extents(&self, size: u64, extents: &[(u64, u64)]) -> Result<Vec<u8>, Error> {
+        let mut bytes = Vec::with_capacity(to_usize_checked(size, "stream size")?);
+        for (offset, len) in extents {
+            let len = to_usize_checked(*len, "extent length")?;
+            let extent = read_at(&self.reader, *offset, len).await?;
+            let short = extent.len() < len;
+            bytes.extend_from_slice(&extent);
+            if short {
+                return Err(Error::Truncated { size, available: bytes.len() as u64 });
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// Reads a sector of the reader ahead into the source
+async fn read_sector<R: AsyncRead + AsyncSeek + Unpin>(reader: &Mutex<R>, source: &ReadAhead, sector: SectorNumber,
+                                                       sector_size: u32) -> Result<(), Error> {
+    let offset = (sector + 1).checked_byte_offset(sector_size as u64).ok_or(Error::InvalidSectorNumber(sector))?;
+    source.insert(offset, read_at(reader, offset, sector_index(sector_size)).await?);
+    Ok(())
+}
+
+/// Reads up to `len` bytes of the reader at an offset, fewer only if the reader ends before them
+async fn read_at<R: AsyncRead + AsyncSeek + Unpin>(reader: &Mutex<R>, offset: u64, len: usize) -> Result<Vec<u8>, Error> {
+    let mut reader = reader.lock().await;
+    reader.seek(SeekFrom::Start(offset)).await?;
+    let mut bytes = vec![0; len];
+    let mut read = 0;
+    while read < len {
+        match reader.read(&mut bytes[read..]).await? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    bytes.truncate(read);
+    Ok(bytes)
+}
@@ -0,0 +1,84 @@
+use crate::cfb::Error;
+use crate::cfb::slice::RawEntry;
+use core::convert::TryFrom;
+
+/// The length in bytes of a sector number in a FAT, mini FAT or DIFAT sector.
+const SECTOR_NUMBER_LENGTH: u32 = 4;
+
+// sector numbers and stream IDs are 32-bit indices, which every supported target can address
+const _: () = assert!(usize::BITS >= 32, "ole_kit needs a usize of at least 32 bits");
+
+/// Converts a size or offset read from the file into a length or index of memory, failing with
+/// [`Error::Overflow`] where the target can't address it, as a stream size over 4 GiB on a 32-bit
+/// target
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) fn to_usize_checked(value: u64, what: &'static str) -> Result<usize, Error> {
+    usize::try_from(value).map_err(|_| Error::Overflow { what, value })
+}
+
+/// Converts a size or offset read from the file into a length of memory, saturating where the
+/// target can't address it, for a length which is only compared with a smaller one
+pub(crate) fn to_usize_saturating(value: u64) -> usize {
+    usize::try_from(value).unwrap_or(usize::MAX)
+}
+
+/// Converts a sector number, stream ID, or other 32-bit index into an index of memory, which can't
+/// overflow on any supported target
+pub(crate) fn sector_index(index: u32) -> usize {
+    index as usize
+}
+
+/// Returns the number of entries of `entry_length` bytes which a sector holds, such as the 4
+/// directory entries of a 512-byte sector
+pub(crate) fn entries_per_sector(sector_size: u32, entry_length: u32) -> u32 {
+    sector_size / entry_length
+}
+
+/// Returns the number of sector numbers which a FAT, mini FAT or DIFAT sector holds
+pub(crate) fn sector_numbers_per_sector(sector_size: u32) -> u32 {
+    entries_per_sector(sector_size, SECTOR_NUMBER_LENGTH)
+}
+
+/// Returns the byte offset of the directory entry at an index of a directory sector, widened before
+/// multiplying so that it can't overflow
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) fn entry_offset(sector_offset: u64, index: u32) -> u64 {
+    sector_offset + u64::from(index) * u64::from(RawEntry::LENGTH)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::cfb::Entry;
+
+    #[test]
+    fn usize_boundaries() {
+        assert_eq!(to_usize_checked(0, "size").unwrap(), 0);
+        assert_eq!(to_usize_checked(u32::MAX as u64, "size").unwrap(), u32::MAX as usize);
+        assert_eq!(to_usize_saturating(u32::MAX as u64), u32::MAX as usize);
+        assert_eq!(sector_index(u32::MAX), 0xFFFFFFFF);
+
+        #[cfg(target_pointer_width = "32")]
+        {
+            assert!(matches!(to_usize_checked(1 << 32, "stream size"),
+                             Err(Error::Overflow { what: "stream size", value: 0x1_0000_0000 })));
+            assert_eq!(to_usize_saturating(u64::MAX), usize::MAX);
+        }
+        #[cfg(target_pointer_width = "64")]
+        {
+            assert_eq!(to_usize_checked(u64::MAX, "size").unwrap(), usize::MAX);
+            assert_eq!(to_usize_saturating(1 << 32), 0x1_0000_0000);
+        }
+    }
+
+    #[test]
+    fn sector_geometry() {
+        assert_eq!(sector_numbers_per_sector(512), 128);
+        assert_eq!(sector_numbers_per_sector(4096), 1024);
+        assert_eq!(entries_per_sector(512, Entry::LENGTH), 4);
+        assert_eq!(entries_per_sector(4096, Entry::LENGTH), 32);
+
+        assert_eq!(entry_offset(512, 3), 896);
+        assert_eq!(entry_offset(u32::MAX as u64 * 4096, u32::MAX), 0xFFFF_FFFF * 4096 + 0xFFFF_FFFF * 128);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::cfb::{storage, Cfb, Entry, Error};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// A group of streams holding byte-identical data, returned by [`Cfb::duplicate_streams`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DuplicateGroup {
+    /// The size in bytes of each of the streams.
+    pub size: u64,
+    /// The SHA-256 digest of the data shared by the streams.
+    pub digest: [u8; 32],
+    /// The full paths of the streams, whose components are separated by `/`, in the order of a
+    /// depth-first walk of the directory.
+    pub paths: Vec<String>,
+}
+
+impl Cfb {
+    /// Groups the streams holding byte-identical data, returning the groups of more than one stream
+    /// in the order of their first stream in a depth-first walk of the directory. Empty streams are
+    /// left out.
+    ///
+    /// The streams are first bucketed by size, so that only the streams sharing their size with
+    /// another are read, one at a time through a [`StreamReader`], to compute their digest.
+    ///
+    /// [`StreamReader`]: crate::cfb::StreamReader
+    pub fn duplicate_streams(&self) -> Result<Vec<DuplicateGroup>, Error> {
+        let root_storage = self.try_root_storage()?;
+
+        let mut streams = Vec::new();
+        storage::walk(self, Entry::RootStorage(root_storage), &mut |path, entry| {
+            if let Entry::Stream(stream) = entry {
+                let size = stream.stream_size().0;
+                if size > 0 {
+                    streams.push((path.to_string(), size, stream.reader()));
+                }
+            }
+            Ok(())
+        })?;
+
+        let mut sizes: HashMap<u64, usize> = HashMap::new();
+        for (_, size, _) in &streams {
+            *sizes.entry(*size).or_default() += 1;
+        }
+
+        let mut groups: Vec<DuplicateGroup> = Vec::new();
+        let mut group_by_digest: HashMap<(u64, [u8; 32]), usize> = HashMap::new();
+        for (path, size, mut reader) in streams {
+            if sizes[&size] < 2 {
+                continue;
+            }
+
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut reader, &mut hasher)?;
+            let digest: [u8; 32] = hasher.finalize().into();
+            let index = *group_by_digest.entry((size, digest)).or_insert_with(|| {
+                groups.push(DuplicateGroup { size, digest, paths: Vec::new() });
+                groups.len() - 1
+            });
+            groups[index].paths.push(path);
+        }
+
+        groups.retain(|group| group.paths.len() > 1);
+        Ok(groups)
+    }
+}
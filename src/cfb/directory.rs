@@ -1,5 +1,13 @@
-use std::fs::File;
-use crate::cfb::directory::entry::{Entry, CommonProps};
+#[cfg(feature = "std")]
+use crate::cfb::directory::entry::Entry;
+#[cfg(feature = "std")]
+use crate::cfb::{Cfb, Error};
+#[cfg(feature = "std")]
+use crate::cfb::SectorNumber;
+#[cfg(feature = "std")]
+use crate::cfb::convert::{entries_per_sector, entry_offset, sector_index};
+#[cfg(feature = "std")]
+use std::iter::FusedIterator;
 
 pub(crate) mod entry;
 
@@ -14,31 +22,62 @@ pub(crate) mod entry;
 /// [directory entry]: self::entry::Entry
 /// [compound file]: crate::cfb::Cfb
 /// [FAT]: crate::cfb::fat::Fat
-#[derive(Debug)]
+#[cfg(feature = "std")]
 pub(crate) struct Directory<'a> {
+    sector: SectorNumber,
     offset: u64,
     length: u32,
-    file: &'a File,
+    /// The stream ID of the first slot.
+    first_id: u32,
+    cfb: &'a Cfb,
 }
 
+#[cfg(feature = "std")]
 impl<'a> Directory<'a> {
-    pub(crate) fn new(offset: u64, byte_count: u32, file: &'a File) -> Self {
+    pub(crate) fn new(sector: SectorNumber, offset: u64, byte_count: u32, first_id: u32, cfb: &'a Cfb) -> Self {
         Self {
+            sector,
             offset,
-            length: byte_count / Entry::LENGTH,
-            file,
+            length: entries_per_sector(byte_count, Entry::LENGTH),
+            first_id,
+            cfb,
         }
     }
 
+    /// Reads the entry in a slot of the sector, failing if the index is beyond its slots
     pub(crate) fn entry(&self, index: u32) -> <Iter<'a> as Iterator>::Item {
-        Entry::new(self.offset + (index * Entry::LENGTH) as u64, self.file)
+        if index >= self.length {
+            return Err(format!("slot {} is beyond the {} slots of a directory sector!", index, self.length));
+        }
+        let id = self.first_id.checked_add(index)
+            .ok_or_else(|| format!("slot {} of the directory sector at offset {} has no stream ID!", index, self.offset))?;
+        Entry::at_slot(entry_offset(self.offset, index), StreamID(id), self.cfb)
+    }
+
+    /// Returns the offset of an entry slot, whether allocated or not
+    pub(crate) fn entry_offset(&self, index: u32) -> u64 {
+        entry_offset(self.offset, index)
     }
 
     pub(crate) fn len(&self) -> u32 {
         self.length
     }
+
+    /// Returns the raw object type byte of every slot, reading the whole sector at once. A sector
+    /// past the end of the source fails as [`check`](Self::check) does.
+    pub(crate) fn object_types(&self) -> Result<Vec<u8>, Error> {
+        let bytes = self.cfb.sector_bytes(self.sector)?;
+        Ok(bytes.chunks(Entry::LENGTH as usize).map(|entry| entry[Entry::OBJECT_TYPE as usize]).collect())
+    }
+
+    /// Fails with [`Error::Truncated`] if the source ends before the end of the sector, unless the
+    /// file is read leniently, in which case the slots past it read as unallocated
+    pub(crate) fn check(&self) -> Result<(), Error> {
+        self.cfb.check_sector(self.sector)
+    }
 }
 
+#[cfg(feature = "std")]
 impl<'a> IntoIterator for Directory<'a> {
     type Item = <Iter<'a> as Iterator>::Item;
     type IntoIter = Iter<'a>;
@@ -48,11 +87,13 @@ impl<'a> IntoIterator for Directory<'a> {
     }
 }
 
+#[cfg(feature = "std")]
 pub(crate) struct Iter<'a> {
     cursor: u32,
     directory: Directory<'a>,
 }
 
+#[cfg(feature = "std")]
 impl<'a> Iter<'a> {
     pub(crate) fn new(directory: Directory<'a>) -> Self {
         Self {
@@ -62,6 +103,7 @@ impl<'a> Iter<'a> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<'a> Iterator for Iter<'a> {
     type Item = Result<Entry<'a>, String>;
 
@@ -74,10 +116,39 @@ impl<'a> Iterator for Iter<'a> {
         self.cursor += 1;
         Some(item)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = sector_index(self.directory.len().saturating_sub(self.cursor));
+        (len, Some(len))
+    }
 }
 
+/// Every slot of the sector is yielded, a malformed one as an error which doesn't end the iteration.
+#[cfg(feature = "std")]
+impl<'a> ExactSizeIterator for Iter<'a> {}
+
+#[cfg(feature = "std")]
+impl<'a> FusedIterator for Iter<'a> {}
+
 /// Each [directory entry] is identified by a nonnegative number that is called the stream ID.
 ///
 /// [directory entry]: self::entry::Entry
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub struct StreamID(u32);
\ No newline at end of file
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct StreamID(pub u32);
+
+impl StreamID {
+    /// 0xFFFFFFFA
+    ///
+    /// Maximum regular stream ID (MAXREGSID = 0xFFFFFFFA).
+    pub const MAXREGSID: Self = Self(0xFFFFFFFA);
+    /// 0xFFFFFFFF
+    ///
+    /// Terminator or empty pointer (NOSTREAM = 0xFFFFFFFF).
+    pub const NOSTREAM: Self = Self(0xFFFFFFFF);
+
+    /// Returns whether the stream ID may identify a directory entry, i.e. it is no greater than
+    /// [MAXREGSID][Self::MAXREGSID]
+    pub fn is_regular(&self) -> bool {
+        self.0 <= Self::MAXREGSID.0
+    }
+}
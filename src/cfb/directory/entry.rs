@@ -1,11 +1,20 @@
-mod metadata;
-mod impls;
+pub mod metadata;
+#[cfg(feature = "std")]
+pub(crate) mod impls;
 
-use std::fs::File;
+#[cfg(feature = "std")]
 use crate::cfb::header::{FileSlice};
-use crate::cfb::directory::entry::metadata::{ObjectType, ColorFlag};
-use std::convert::TryInto;
+#[cfg(feature = "std")]
+use crate::cfb::{Cfb, Error, RawEntry, SectorNumber};
+#[cfg(feature = "std")]
+use crate::cfb::directory::entry::metadata::{ColorFlag, ObjectType, StreamSize};
+#[cfg(feature = "std")]
+use std::convert::{TryFrom, TryInto};
+#[cfg(feature = "std")]
+use crate::cfb::warning::{Location, Rule};
+#[cfg(feature = "std")]
 use crate::cfb::directory::StreamID;
+#[cfg(feature = "std")]
 use crate::cfb::directory::entry::impls::{RootStorage, Storage, Stream};
 
 /**
@@ -29,24 +38,197 @@ exceed the number of storage objects and stream objects due to unallocated direc
 [Right Sibling ID]: Entry::right_sibling_id
 [Left Sibling ID]: Entry::left_sibling_id
  */
-#[derive(Debug)]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
 pub enum Entry<'a> {
     RootStorage(RootStorage<'a>),
     Storage(Storage<'a>),
     Stream(Stream<'a>),
     Unknown,
+    /// A slot whose object type is none of the valid values, yielded in place of an error when the
+    /// file is read leniently. The rest of the slot is left unread, as none of its fields can be
+    /// trusted.
+    Malformed {
+        id: StreamID,
+        raw_type: u8,
+    },
 }
 
+#[cfg(feature = "std")]
 impl<'a> Entry<'a> {
-    pub(crate) const LENGTH: u32 = 128;
+    pub(crate) const LENGTH: u32 = RawEntry::LENGTH;
+    /// The offset of the object type byte within an entry.
+    pub(crate) const OBJECT_TYPE: u32 = 66;
     /// This field MUST be 0x00, 0x01, 0x02, or 0x05, depending on the actual type of object. All
     /// other values are not valid.
-    pub(crate) fn object_type(offset: u64, file: &'a File) -> Result<ObjectType, String> {
-        let byte = file.read_sized(offset + Self::NAME + 2, u8::from_ne_bytes);
+    pub(crate) fn object_type(offset: u64, cfb: &'a Cfb) -> Result<ObjectType, String> {
+        let byte = cfb.source.read_sized(offset + Self::NAME + 2, u8::from_ne_bytes);
         byte.try_into()
     }
+
+    /// Reads the entry in the slot of a stream ID. A slot whose object type is invalid yields
+    /// [`Entry::Malformed`], with a warning, when the file is read leniently, and fails naming the
+    /// stream ID otherwise.
+    pub(crate) fn at_slot(offset: u64, id: StreamID, cfb: &'a Cfb) -> Result<Self, String> {
+        let raw_type = cfb.source.read_sized(offset + Self::NAME + 2, u8::from_ne_bytes);
+        match ObjectType::try_from(raw_type) {
+            Ok(object_type) => {
+                let entry = Self::with_object_type(offset, cfb, object_type)?;
+                entry.check_name_length(id)?;
+                Ok(entry)
+            }
+            Err(_) if cfb.lenient => {
+                cfb.warnings.push(Rule::ObjectType, Location::Slot { id: id.0, offset }, raw_type as u64);
+                Ok(Self::Malformed { id, raw_type })
+            }
+            Err(_) => Err(format!("stream ID {} has the invalid object type 0x{:02X}!", id.0, raw_type)),
+        }
+    }
+
+    /// Fails naming the stream ID if the name length of an allocated entry is odd, beyond the 64
+    /// bytes of the name field, or doesn't point right after the terminating null character, or
+    /// warns of it when the file is read leniently, the name being decoded up to the first null
+    /// character instead.
+    fn check_name_length(&self, id: StreamID) -> Result<(), String> {
+        let (name_length, units, cfb) = match self {
+            Self::Stream(stream) => (stream.name_length(), stream.name_units(), stream.cfb),
+            Self::Storage(storage) => (storage.name_length(), storage.name_units(), storage.cfb),
+            Self::RootStorage(root_storage) => (root_storage.name_length(), root_storage.name_units(), root_storage.cfb),
+            Self::Unknown | Self::Malformed { .. } => return Ok(()),
+        };
+        if terminated_name_len(name_length, &units).is_some() {
+            return Ok(());
+        }
+        if cfb.lenient {
+            cfb.warnings.push(Rule::NameLength, Location::Entry { offset: self.offset(), name: self.name() },
+                              name_length as u64);
+            return Ok(());
+        }
+        Err(format!("stream ID {} has the invalid name length {}!", id.0, name_length))
+    }
+
+    fn with_object_type(offset: u64, cfb: &'a Cfb, object_type: ObjectType) -> Result<Self, String> {
+        let ret = match object_type {
+            ObjectType::Stream => Self::Stream(Stream::new(offset, cfb)?),
+            ObjectType::Storage => Self::Storage(Storage::new(offset, cfb)?),
+            ObjectType::RootStorage => Self::RootStorage(RootStorage::new(offset, cfb)?),
+            ObjectType::Unknown => Self::Unknown,
+        };
+        Ok(ret)
+    }
+
+    /// Returns an entry holding its own handle to the compound file, so that it can be stored
+    /// alongside the [`Cfb`] or sent to another thread, or None for an unallocated or malformed
+    /// entry.
+    pub fn to_owned(&self) -> Option<OwnedEntry> {
+        let (cfb, object_type) = match self {
+            Self::Stream(stream) => (stream.cfb, ObjectType::Stream),
+            Self::Storage(storage) => (storage.cfb, ObjectType::Storage),
+            Self::RootStorage(root_storage) => (root_storage.cfb, ObjectType::RootStorage),
+            Self::Unknown | Self::Malformed { .. } => return None,
+        };
+
+        Some(OwnedEntry {
+            cfb: cfb.clone(),
+            offset: self.offset(),
+            object_type,
+        })
+    }
+}
+
+/**
+A directory [entry] that holds its own handle to the compound file instead of borrowing the
+[`Cfb`], created by [`Entry::to_owned`].
+
+Owned entries outlive the compound file they were read from, so they can be collected by a function
+which opens the file itself:
+
+```
+use ole_kit::cfb::{Cfb, Error, OwnedEntry};
+
+fn entries(path: &str) -> Result<Vec<OwnedEntry>, Error> {
+    let cfb = Cfb::from_path(path)?;
+    Ok(cfb.entries().filter_map(Result::ok).filter_map(|entry| entry.to_owned()).collect())
+}
+
+let entries = entries("tests_rsc/testing.doc")?;
+let word_document = entries.iter().find(|entry| entry.name().as_deref() == Ok("WordDocument")).unwrap();
+assert_eq!(word_document.stream_size().unwrap().0, 4096);
+assert_eq!(word_document.stream_bytes()?.len(), 4096);
+# Ok::<(), Error>(())
+```
+
+[entry]: Entry
+ */
+#[cfg(feature = "std")]
+#[derive(Clone)]
+pub struct OwnedEntry {
+    cfb: Cfb,
+    offset: u64,
+    object_type: ObjectType,
+}
+
+#[cfg(feature = "std")]
+impl OwnedEntry {
+    /// Borrows the entry, which gives access to all of its getters
+    pub fn entry(&self) -> Result<Entry<'_>, String> {
+        Entry::with_object_type(self.offset, &self.cfb, self.object_type)
+    }
+
+    /// Returns the type of the object
+    pub fn object_type(&self) -> ObjectType {
+        self.object_type
+    }
+
+    /// Returns the name of the object, see [`CommonProps::name`]
+    pub fn name(&self) -> Result<String, String> {
+        Ok(self.entry()?.name())
+    }
+
+    /// Returns the size of the stream, or of the mini stream for the root storage, see
+    /// [`Stream::stream_size`]
+    pub fn stream_size(&self) -> Result<StreamSize, String> {
+        Ok(match self.entry()? {
+            Entry::Stream(stream) => stream.stream_size(),
+            Entry::Storage(storage) => storage.stream_size(),
+            Entry::RootStorage(root_storage) => root_storage.stream_size(),
+            _ => unreachable!("an owned entry is never unallocated or malformed"),
+        })
+    }
+
+    /// Returns the first sector of the stream, or of the mini stream for the root storage, see
+    /// [`Stream::starting_sector_location`]
+    pub fn starting_sector_location(&self) -> Result<SectorNumber, String> {
+        Ok(match self.entry()? {
+            Entry::Stream(stream) => stream.starting_sector_location(),
+            Entry::Storage(storage) => storage.starting_sector_location(),
+            Entry::RootStorage(root_storage) => root_storage.starting_sector_location(),
+            _ => unreachable!("an owned entry is never unallocated or malformed"),
+        })
+    }
+
+    /// Reads the bytes of the stream, as [`Stream::bytes`] does, failing with
+    /// [`Error::NotAStream`] for a storage object or the root storage
+    pub fn stream_bytes(&self) -> Result<Vec<u8>, Error> {
+        match self.entry().map_err(Error::InvalidDirectory)? {
+            Entry::Stream(stream) => stream.bytes(),
+            entry => Err(Error::NotAStream { name: entry.name(), object_type: self.object_type }),
+        }
+    }
 }
 
+/// Returns the number of UTF-16 code units of a name before its terminating null character, or
+/// None if the name length is odd, beyond the 64 bytes of the name field, or doesn't point right
+/// after a null character.
+pub(crate) fn terminated_name_len(name_length: u16, units: &[u16; 32]) -> Option<usize> {
+    if !name_length.is_multiple_of(2) || !(2..=64).contains(&name_length) {
+        return None;
+    }
+    let len = name_length as usize / 2 - 1;
+    (units[len] == 0).then_some(len)
+}
+
+#[cfg(feature = "std")]
 macro_rules! impl_for_prop {
     ($self:ident, $method_name:ident) => {
         match $self {
@@ -58,63 +240,93 @@ macro_rules! impl_for_prop {
     };
 }
 
-impl<'a> CommonProps<'a> for Entry<'a> {
-    fn new(offset: u64, file: &'a File) -> Result<Self, String> {
-        let ret = match Self::object_type(offset, file)? {
-            ObjectType::Stream => Self::Stream(Stream::new(offset, file)?),
-            ObjectType::Storage => Self::Storage(Storage::new(offset, file)?),
-            ObjectType::RootStorage => Self::RootStorage(RootStorage::new(offset, file)?),
-            ObjectType::Unknown => Self::Unknown,
-        };
-        Ok(ret)
+/// The getters of [`CommonProps`], callable on an entry without importing the trait. Each panics
+/// for [`Entry::Unknown`] and [`Entry::Malformed`], which hold none of the fields.
+#[cfg(feature = "std")]
+impl<'a> Entry<'a> {
+    /// Returns the base offset of the entry, see [`CommonProps::offset`]
+    pub fn offset(&self) -> u64 {
+        impl_for_prop!(self, offset)
     }
 
-    fn offset(&self) -> u64 {
-        impl_for_prop!(self, offset)
+    /// Returns the name of the object, see [`CommonProps::name`]
+    pub fn name(&self) -> String {
+        impl_for_prop!(self, name)
+    }
+
+    /// Returns the length of the name in bytes, see [`CommonProps::name_length`]
+    pub fn name_length(&self) -> u16 {
+        impl_for_prop!(self, name_length)
+    }
+
+    /// Returns the color of the entry in the red-black tree, see [`CommonProps::color_flag`]
+    pub fn color_flag(&self) -> ColorFlag {
+        impl_for_prop!(self, color_flag)
+    }
+
+    /// Returns the stream ID of the left sibling, see [`CommonProps::left_sibling_id`]
+    pub fn left_sibling_id(&self) -> Option<StreamID> {
+        impl_for_prop!(self, left_sibling_id)
+    }
+
+    /// Returns the stream ID of the right sibling, see [`CommonProps::right_sibling_id`]
+    pub fn right_sibling_id(&self) -> Option<StreamID> {
+        impl_for_prop!(self, right_sibling_id)
+    }
+
+    /// Returns the stream ID of the child object, see [`CommonProps::child_id`]
+    pub fn child_id(&self) -> Option<StreamID> {
+        impl_for_prop!(self, child_id)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a> CommonProps<'a> for Entry<'a> {
+    fn new(offset: u64, cfb: &'a Cfb) -> Result<Self, String> {
+        Self::with_object_type(offset, cfb, Self::object_type(offset, cfb)?)
     }
 
-    fn file(&self) -> &File {
-        impl_for_prop!(self, file)
+    fn offset(&self) -> u64 {
+        Entry::offset(self)
     }
 
     fn name(&self) -> String {
-        impl_for_prop!(self, name)
+        Entry::name(self)
     }
 
     fn name_length(&self) -> u16 {
-        impl_for_prop!(self, name_length)
+        Entry::name_length(self)
     }
 
     fn color_flag(&self) -> ColorFlag {
-        impl_for_prop!(self, color_flag)
+        Entry::color_flag(self)
     }
 
     fn left_sibling_id(&self) -> Option<StreamID> {
-        impl_for_prop!(self, left_sibling_id)
+        Entry::left_sibling_id(self)
     }
 
     fn right_sibling_id(&self) -> Option<StreamID> {
-        impl_for_prop!(self, right_sibling_id)
+        Entry::right_sibling_id(self)
     }
 
     fn child_id(&self) -> Option<StreamID> {
-        impl_for_prop!(self, child_id)
+        Entry::child_id(self)
     }
 }
 
+#[cfg(feature = "std")]
 pub trait CommonProps<'a>: Sized {
     const NAME: u64 = 64;
     const CLSID: u64 = 16;
     const STATE_BITS: u64 = 4;
     const TIME: u64 = 8;
 
-    /// Creates an entry from a base offset and the source file.
-    fn new(offset: u64, file: &'a File) -> Result<Self, String>;
+    /// Creates an entry from a base offset and the compound file it belongs to.
+    fn new(offset: u64, cfb: &'a Cfb) -> Result<Self, String>;
 
     fn offset(&self) -> u64;
 
-    fn file(&self) -> &File;
-
     /**
     This field MUST contain a Unicode string for the storage or stream name encoded in UTF-16. The
     name MUST be terminated with a UTF-16 terminating null character. Thus, storage and stream names
@@ -137,16 +349,22 @@ pub trait CommonProps<'a>: Sized {
 
     /// This field contains the stream ID of the left sibling.
     ///
-    /// If there is no left sibling, the field MUST be set to NOSTREAM (0xFFFFFFFF).
+    /// If there is no left sibling, the field MUST be set to NOSTREAM (0xFFFFFFFF). Any other value
+    /// above MAXREGSID is returned as is, and fails as a malformed link when followed, unless the
+    /// file is read leniently, which warns of it and returns None.
     fn left_sibling_id(&self) -> Option<StreamID>;
 
     /// This field contains the stream ID of the right sibling.
     ///
-    /// If there is no right sibling, the field MUST be set to NOSTREAM (0xFFFFFFFF).
+    /// If there is no right sibling, the field MUST be set to NOSTREAM (0xFFFFFFFF). Any other value
+    /// above MAXREGSID is returned as is, and fails as a malformed link when followed, unless the
+    /// file is read leniently, which warns of it and returns None.
     fn right_sibling_id(&self) -> Option<StreamID>;
 
     /// This field contains the stream ID of a child object.
     ///
-    /// If there is no right sibling, the field MUST be set to NOSTREAM (0xFFFFFFFF).
+    /// If there is no right sibling, the field MUST be set to NOSTREAM (0xFFFFFFFF). Any other value
+    /// above MAXREGSID is returned as is, and fails as a malformed link when followed, unless the
+    /// file is read leniently, which warns of it and returns None.
     fn child_id(&self) -> Option<StreamID>;
 }
@@ -1,15 +1,20 @@
 use crate::cfb::directory::entry::CommonProps;
-use std::fs::File;
-use crate::cfb::directory::entry::metadata::{ColorFlag, StreamSize};
+use crate::cfb::directory::entry::metadata::{self, ColorFlag, StateBits, StreamSize};
 use crate::cfb::directory::StreamID;
 use crate::cfb::header::FileSlice;
-use std::convert::TryInto;
-use std::os::unix::fs::FileExt;
-use chrono::{NaiveDateTime, Duration};
+use chrono::NaiveDateTime;
 use crate::cfb::fat::sector_number::SectorNumber;
 use crate::cfb::Cfb;
-use crate::cfb::fat;
-use crate::cfb::SectorCount;
+use crate::cfb::{Error, Limit, StreamReader, StreamSectors};
+use crate::cfb::warning::{Location, Rule};
+use crate::cfb::directory::entry::{terminated_name_len, Entry};
+use crate::cfb::convert::{sector_index, to_usize_checked, to_usize_saturating};
+use crate::cfb::fat::cache::Cache;
+use crate::cfb::name::display_name;
+use crate::cfb::fat::chain::strict_chain;
+use crate::cfb::reader::{MAX_READ_RUN, MINI_SECTOR_SIZE};
+use std::convert::TryFrom;
+use std::io::{Read, Write};
 use core::fmt;
 use std::fmt::Formatter;
 
@@ -26,9 +31,7 @@ macro_rules! impl_cls_id {
             /// zeroes by default. If this value is not all zeroes, the object class GUID can be used as a
             /// parameter to start applications.
             pub fn cls_id(&self) -> [u8; 16] {
-                let mut bytes = [0; Self::CLSID as usize];
-                self.file.read_at(&mut bytes, self.offset() + Self::NAME + 16);
-                bytes
+                self.cfb.source.read_sized(self.offset() + Self::NAME + 16, |bytes| bytes)
             }
         }
     };
@@ -44,8 +47,44 @@ macro_rules! impl_state_bits {
             /// provide no way for applications to retrieve state bits from a stream object. If an
             /// implementation of the file format enables applications to create storage objects without
             /// explicitly setting state bits, it MUST write all zeroes by default.
-            pub fn state_bits(&self) -> u32 {
-                self.read_sized(Self::NAME + 16 + Self::CLSID, u32::from_le_bytes)
+            pub fn state_bits(&self) -> StateBits {
+                StateBits::from_bits_retain(self.read_sized(Self::NAME + 16 + Self::CLSID, u32::from_le_bytes))
+            }
+        }
+    };
+}
+
+macro_rules! impl_times {
+    ($type:ident) => {
+        impl<'a> $type<'a> {
+            /// This field contains the creation time for a storage object, or all zeroes to indicate that
+            /// the creation time of the storage object was not recorded.
+            ///
+            /// The Windows FILETIME structure is used to represent this field in UTC. For a stream object,
+            /// this field MUST be all zeroes. For a root storage object, this field MUST be all zeroes, and
+            /// the creation time is retrieved or set on the compound file itself.
+            pub fn creation_time(&self) -> Option<NaiveDateTime> {
+                metadata::from_filetime(self.creation_filetime())
+            }
+
+            /// This field contains the modification time for a storage object, or all zeroes to indicate
+            /// that the modified time of the storage object was not recorded.
+            ///
+            /// The Windows FILETIME structure is used to represent this field in UTC. For a stream object,
+            /// this field MUST be all zeroes. For a root storage object, this field MAY be set to all
+            /// zeroes, and the modified time is retrieved or set on the compound file itself.
+            pub fn modified_time(&self) -> Option<NaiveDateTime> {
+                metadata::from_filetime(self.modified_filetime())
+            }
+
+            /// Returns the raw FILETIME of the creation time, which may be too large for a date time
+            pub(crate) fn creation_filetime(&self) -> u64 {
+                self.read_sized(Self::NAME + 16 + Self::CLSID + Self::STATE_BITS, u64::from_le_bytes)
+            }
+
+            /// Returns the raw FILETIME of the modified time, which may be too large for a date time
+            pub(crate) fn modified_filetime(&self) -> u64 {
+                self.read_sized(Self::NAME + 16 + Self::CLSID + Self::STATE_BITS + Self::TIME, u64::from_le_bytes)
             }
         }
     };
@@ -75,20 +114,97 @@ macro_rules! impl_stream_size {
             object, this field MUST be set to all zeroes.
 
             - For a version 3 compound file 512-byte sector size, the value of this field MUST be less than
-            or equal to 0x80000000. (Equivalently, this requirement can be stated: the size of a stream or
-            of the mini stream in a version 3 compound file MUST be less than or equal to 2 gigabytes (GB).)
-            Note that as a consequence of this requirement, the most significant 32 bits of this field MUST
-            be zero in a version 3 compound file. However, implementers should be aware that some older
-            implementations did not initialize the most significant 32 bits of this field, and these bits
-            might therefore be nonzero in files that are otherwise valid version 3 compound files. Although
-            this document does not normatively specify parser behavior, it is recommended that parsers
-            ignore the most significant 32 bits of this field in version 3 compound files, treating it as if
-            its value were zero, unless there is a specific reason to do otherwise (for example, a parser
-            whose purpose is to verify the correctness of a compound file).
+              or equal to 0x80000000. (Equivalently, this requirement can be stated: the size of a stream or
+              of the mini stream in a version 3 compound file MUST be less than or equal to 2 gigabytes (GB).)
+              Note that as a consequence of this requirement, the most significant 32 bits of this field MUST
+              be zero in a version 3 compound file. However, implementers should be aware that some older
+              implementations did not initialize the most significant 32 bits of this field, and these bits
+              might therefore be nonzero in files that are otherwise valid version 3 compound files. Although
+              this document does not normatively specify parser behavior, it is recommended that parsers
+              ignore the most significant 32 bits of this field in version 3 compound files, treating it as if
+              its value were zero, unless there is a specific reason to do otherwise (for example, a parser
+              whose purpose is to verify the correctness of a compound file).
+
+            A [lenient](crate::cfb::CfbOpenOptions::lenient) reading ignores the most significant 32
+            bits in version 3 compound files, warning of them.
              */
             pub fn stream_size(&self) -> StreamSize {
-                StreamSize(self.read_sized(Self::NAME + 16 + Self::CLSID + Self::STATE_BITS + Self::TIME + Self::TIME + 4,
-                                u64::from_le_bytes))
+                let size = self.read_sized(Self::NAME + 16 + Self::CLSID + Self::STATE_BITS + Self::TIME + Self::TIME + 4,
+                                           u64::from_le_bytes);
+                if self.cfb.lenient && size >> 32 != 0 && self.cfb.header().major_version().0 == 3 {
+                    self.cfb.warnings.push(Rule::StreamSizeHighBits,
+                                           Location::Entry { offset: self.offset, name: self.name() },
+                                           size);
+                    return StreamSize(size & 0xFFFFFFFF);
+                }
+                StreamSize(size)
+            }
+        }
+    };
+}
+
+macro_rules! impl_children {
+    ($type:ident) => {
+        impl<'a> $type<'a> {
+            /// Returns the entries of the objects contained in the storage, in the order of the
+            /// red-black tree rooted at the [child][CommonProps::child_id].
+            pub fn children(&self) -> Result<Vec<Entry<'a>>, String> {
+                self.try_children().map_err(|err| match err {
+                    Error::InvalidDirectory(reason) => reason,
+                    err => err.to_string(),
+                })
+            }
+
+            /// Returns the entries of the objects contained in the storage, failing with the
+            /// [`Error`] of a corrupted sibling tree or an exceeded limit.
+            pub(crate) fn try_children(&self) -> Result<Vec<Entry<'a>>, Error> {
+                let mut children = Vec::new();
+                let mut visited = std::collections::HashSet::new();
+                let mut ancestors: Vec<Entry<'a>> = Vec::new();
+                let mut next = self.child_id();
+                // the offset of the entry holding the link to the next one
+                let mut from = self.offset;
+
+                loop {
+                    while let Some(id) = next {
+                        if !visited.insert(id) {
+                            return Err(Error::InvalidDirectory(format!("the sibling tree loops at stream ID {}!", id.0)));
+                        }
+
+                        let entry = match self.cfb.linked_entry(from, id)? {
+                            Some(entry) => entry,
+                            None => {
+                                next = None;
+                                continue;
+                            }
+                        };
+                        match entry {
+                            Entry::Unknown => return Err(Error::InvalidDirectory(
+                                format!("stream ID {} links to an unallocated entry!", id.0)
+                            )),
+                            // only read leniently; its sibling links can't be trusted, so the
+                            // subtree below it is left out
+                            Entry::Malformed { .. } => {
+                                next = None;
+                                continue;
+                            }
+                            _ => {}
+                        }
+
+                        next = entry.left_sibling_id();
+                        from = entry.offset();
+                        ancestors.push(entry);
+                    }
+
+                    match ancestors.pop() {
+                        Some(entry) => {
+                            next = entry.right_sibling_id();
+                            from = entry.offset();
+                            children.push(entry);
+                        }
+                        None => return Ok(children),
+                    }
+                }
             }
         }
     };
@@ -97,176 +213,393 @@ macro_rules! impl_stream_size {
 /// A storage object in a compound file that must be accessed before any other storage objects
 /// and stream objects are referenced. It is the uppermost parent object in the storage object
 /// and stream object hierarchy.
+///
+/// Every field of the record can be read, but its creation time MUST be all zeroes, which
+/// [`Cfb::validate`] flags otherwise.
+#[derive(Clone)]
 pub struct RootStorage<'a> {
     offset: u64,
-    file: &'a File,
+    pub(crate) cfb: &'a Cfb,
 }
 
 impl_cls_id!(RootStorage);
 impl_state_bits!(RootStorage);
+impl_times!(RootStorage);
 impl_starting_sector_location!(RootStorage);
 impl_stream_size!(RootStorage);
 
+impl_children!(RootStorage);
+
 impl<'a> RootStorage<'a> {
     pub fn mini_stream_range(&self) -> (SectorNumber, StreamSize) {
         (self.starting_sector_location(), self.stream_size())
     }
 
-    pub fn mini_stream_bytes(&self, cfb: &Cfb) -> Vec<u8> {
-        let mut fat_cache = fat::cache::Cache::new(cfb);
-
-        let no_of_sectors_per_fat = SectorCount(cfb.sector_size >> 2);
-
-        let mut idx = self.starting_sector_location();
-
-        let mut stream_bytes = Vec::new();
-        stream_bytes.reserve_exact(self.stream_size().0 as usize);
-
-        loop {
-            let mut sector_bytes = cfb.sector_bytes(idx);
+    /// Returns the regular sectors backing the mini stream, in the order of their chain, which ends
+    /// at a reserved sector number, at a sector already in the chain, or at the [chain length
+    /// limit]. The chain may hold more sectors than the size of the mini stream needs.
+    ///
+    /// [chain length limit]: crate::cfb::Limits::max_chain_length
+    pub fn mini_stream_sectors(&self) -> Result<Vec<SectorNumber>, Error> {
+        self.cfb.sector_chain(self.starting_sector_location())
+    }
 
-            stream_bytes.append(&mut sector_bytes);
+    /// Returns the regular sectors backing the mini stream, in the order of their chain, as
+    /// [`Stream::sector_chain`] does, failing where the chain breaks rather than ending there as
+    /// [`mini_stream_sectors`](Self::mini_stream_sectors) does.
+    pub fn mini_stream_chain(&self) -> Result<Vec<SectorNumber>, Error> {
+        strict_chain(self.starting_sector_location(), false, &mut Cache::new(self.cfb))
+    }
 
-            let fat = fat_cache.fat(idx);
+    /// Locates mini sectors in the file: each as the regular sector of the mini stream holding it,
+    /// from the [chain of the mini stream](Self::mini_stream_chain), and the byte offset of the mini
+    /// sector within it, for reading the data of a [mini sector chain] directly. The chain of the
+    /// mini stream is followed once for all of the mini sectors.
+    ///
+    /// Fails as the chain of the mini stream does, with [`Error::InvalidSectorNumber`] for a
+    /// reserved value, or with [`Error::TruncatedChain`] for a mini sector beyond the chain.
+    ///
+    /// [mini sector chain]: Stream::mini_sector_chain
+    pub fn mini_sector_locations(&self, mini_sectors: &[SectorNumber]) -> Result<Vec<(SectorNumber, u64)>, Error> {
+        let chain = self.mini_stream_chain()?;
+        let sector_size = self.cfb.sector_size() as u64;
+        mini_sectors.iter()
+            .map(|mini_sector| {
+                let offset = mini_sector.checked_byte_offset(MINI_SECTOR_SIZE)
+                    .ok_or(Error::InvalidSectorNumber(*mini_sector))?;
+                let sector = usize::try_from(offset / sector_size).ok()
+                    .and_then(|index| chain.get(index))
+                    .ok_or(Error::TruncatedChain {
+                        size: offset + MINI_SECTOR_SIZE,
+                        available: chain.len() as u64 * sector_size,
+                    })?;
+                Ok((*sector, offset % sector_size))
+            })
+            .collect()
+    }
 
-            let next = fat.sector_number((idx % no_of_sectors_per_fat).0);
+    /// Reads the bytes of the mini stream, which holds the stream objects smaller than the
+    /// [cutoff size], from its [sectors](Self::mini_stream_sectors), failing with
+    /// [`Error::TruncatedChain`] if they are fewer than its size needs, or [`Error::Truncated`] if
+    /// the file ends before them.
+    ///
+    /// [cutoff size]: crate::cfb::header::Header::mini_stream_cutoff_size
+    pub fn mini_stream(&self) -> Result<Vec<u8>, Error> {
+        let size = self.stream_size().0;
+        self.cfb.limits().check(Limit::BytesRead, size)?;
+
+        let sector_size = self.cfb.sector_size() as u64;
+        let sectors = self.mini_stream_sectors()?;
+        let capacity = sectors.len() as u64 * sector_size;
+        if capacity < size {
+            return Err(Error::TruncatedChain { size, available: capacity });
+        }
 
-            if !next.is_other() { break }
+        let mut bytes = vec![0u8; to_usize_checked(size, "mini stream size")?];
+        let (len, unit) = (bytes.len(), sector_index(self.cfb.sector_size()));
+        let mut start = 0;
+        // sectors lying one after another in the file are read at once
+        while start * unit < len {
+            let mut end = start + 1;
+            while end * unit < len
+                && (end - start) * unit < MAX_READ_RUN
+                && sectors[end - 1].0.checked_add(1) == Some(sectors[end].0) {
+                end += 1;
+            }
 
-            idx = next;
+            let sector = sectors[start];
+            let offset = (sector + 1).checked_byte_offset(sector_size)
+                .ok_or(Error::InvalidSectorNumber(sector))?;
+            let run = &mut bytes[start * unit..len.min(end * unit)];
+            let read = self.cfb.source.read_at(run, offset)?;
+            if read < run.len() {
+                return Err(Error::Truncated { size, available: start as u64 * sector_size + read as u64 });
+            }
+            start = end;
         }
+        Ok(bytes)
+    }
 
-        stream_bytes
+    #[deprecated(note = "use `RootStorage::mini_stream`, which reads from the compound file of the entry")]
+    pub fn mini_stream_bytes(&self, _cfb: &Cfb) -> Vec<u8> {
+        self.mini_stream().unwrap_or_default()
     }
 }
 
 /// An object in a compound file that is analogous to a file system directory. The parent object
 /// of a storage object must be another storage object or the root storage object.
+///
+/// Every field of the record can be read, but its starting sector location and stream size MUST be
+/// all zeroes, which [`Cfb::validate`] flags otherwise.
+#[derive(Clone)]
 pub struct Storage<'a> {
     offset: u64,
-    file: &'a File,
+    pub(crate) cfb: &'a Cfb,
 }
 
 impl_cls_id!(Storage);
 impl_state_bits!(Storage);
+impl_times!(Storage);
+impl_starting_sector_location!(Storage);
+impl_stream_size!(Storage);
 
-impl<'a> Storage<'a> {
-    /// This field contains the creation time for a storage object, or all zeroes to indicate that
-    /// the creation time of the storage object was not recorded.
-    ///
-    /// The Windows FILETIME structure is used to represent this field in UTC. For a stream object,
-    /// this field MUST be all zeroes. For a root storage object, this field MUST be all zeroes, and
-    /// the creation time is retrieved or set on the compound file itself.
-    pub fn creation_time(&self) -> Option<NaiveDateTime> {
-        let nano_secs = self.read_sized(Self::NAME + 16 + Self::CLSID + Self::STATE_BITS, u64::from_le_bytes);
-        if nano_secs != 0 {
-            let date_time =
-                NaiveDateTime::from_timestamp((nano_secs / 1_000_000_000) as i64,
-                                              (nano_secs % 1_000_000_000) as u32);
-            Some(date_time - Duration::seconds(11_644_473_600))
-        } else {
-            None
-        }
-    }
-
-    /// This field contains the modification time for a storage object, or all zeroes to indicate
-    /// that the modified time of the storage object was not recorded.
-    ///
-    /// The Windows FILETIME structure is used to represent this field in UTC. For a stream object,
-    /// this field MUST be all zeroes. For a root storage object, this field MAY be set to all
-    /// zeroes, and the modified time is retrieved or set on the compound file itself.
-    pub fn modified_time(&self) -> Option<NaiveDateTime> {
-        let nano_secs = self.read_sized(Self::NAME + 16 + Self::CLSID + Self::STATE_BITS + Self::TIME, u64::from_le_bytes);
-        if nano_secs != 0 {
-            let date_time =
-                NaiveDateTime::from_timestamp((nano_secs / 1_000_000_000) as i64,
-                                              (nano_secs % 1_000_000_000) as u32);
-            Some(date_time - Duration::seconds(11_644_473_600))
-        } else {
-            None
-        }
-    }
-}
+impl_children!(Storage);
 
 /// An object in a compound file that is analogous to a file system file. The parent object of a
 /// stream object must be a storage object or the root storage object.
+///
+/// Every field of the record can be read, but its object class GUID and times MUST be all zeroes,
+/// and its state bits SHOULD be, which [`Cfb::validate`] flags otherwise.
+#[derive(Clone)]
 pub struct Stream<'a> {
     offset: u64,
-    file: &'a File,
+    pub(crate) cfb: &'a Cfb,
 }
 
+impl_cls_id!(Stream);
+impl_state_bits!(Stream);
+impl_times!(Stream);
 impl_starting_sector_location!(Stream);
 impl_stream_size!(Stream);
 
 impl<'a> Stream<'a> {
-    pub fn stream_bytes(&self, cfb: &Cfb, root_entry_bytes: Option<Vec<u8>>) -> Vec<u8> {
-        let mut fat_cache = fat::cache::Cache::new(cfb);
+    /// Opens the stream for reading and seeking.
+    pub fn reader(&self) -> StreamReader {
+        let size = self.stream_size().0;
+        let mini_stream_starting_sector_location =
+            if size < self.cfb.mini_stream_cutoff() {
+                Some(self.cfb
+                    .root_storage()
+                    .map_or(SectorNumber::ENDOFCHAIN, |root_storage| root_storage.starting_sector_location()))
+            } else {
+                None
+            };
+
+        StreamReader::new(self.cfb, self.starting_sector_location(), size, mini_stream_starting_sector_location)
+    }
 
-        let no_of_sectors_per_fat = SectorCount(cfb.sector_size >> 2);
+    /// Reads the bytes of the stream, failing before reading any if its size is beyond the
+    /// [limit] of bytes read, or if the file isn't read [leniently] and either any of its sectors
+    /// is [claimed twice] or the size is beyond the bytes of the sectors of the file, with
+    /// [`Error::InvalidDirectory`]. The bytes are allocated as they are read, so a size which the
+    /// chain of the stream falls short of fails without allocating it.
+    ///
+    /// [limit]: crate::cfb::Limits::max_bytes_read
+    /// [leniently]: crate::cfb::CfbOpenOptions::lenient
+    /// [claimed twice]: crate::cfb::Error::SharedSector
+    pub fn bytes(&self) -> Result<Vec<u8>, Error> {
+        self.check_read()?;
+
+        // the bytes are read a run of sectors at a time, and only allocated as they are read, however
+        // large the stream claims to be
+        let size = self.stream_size().0;
+        let mut reader = self.reader();
+        let mut bytes = Vec::new();
+        while (bytes.len() as u64) < size {
+            let start = bytes.len();
+            bytes.resize(start + to_usize_saturating(size - start as u64).min(MAX_READ_RUN), 0);
+            reader.read_exact(&mut bytes[start..])?;
+        }
+        Ok(bytes)
+    }
 
-        let mut idx = self.starting_sector_location();
+    /// Returns the sectors backing the stream, in the order of its chain, which are the mini sectors
+    /// of the mini stream for a stream smaller than the [cutoff size], from its starting sector
+    /// location to the ENDOFCHAIN of the FAT, or the mini FAT. The chain is followed to its end,
+    /// however many sectors the stream size needs, and only the tables are read.
+    ///
+    /// Fails with [`Error::BrokenChain`] if the chain links to FREESECT, FATSECT, DIFSECT or
+    /// another reserved value, loops back on itself, or runs off the FAT, and with
+    /// [`Error::LimitExceeded`] if it is longer than the [chain length limit].
+    ///
+    /// [cutoff size]: crate::cfb::header::Header::mini_stream_cutoff_size
+    /// [chain length limit]: crate::cfb::Limits::max_chain_length
+    pub fn sector_chain(&self) -> Result<Vec<SectorNumber>, Error> {
+        let mini = self.stream_size().0 < self.cfb.mini_stream_cutoff();
+        strict_chain(self.starting_sector_location(), mini, &mut Cache::new(self.cfb))
+    }
 
-        let mut stream_bytes = Vec::new();
-        stream_bytes.reserve_exact(self.stream_size().0 as usize);
+    /// Returns the mini sectors of the stream, in the order of its chain in the mini FAT, followed to
+    /// its end and failing as [`sector_chain`](Self::sector_chain) does. The chain is walked in the
+    /// mini FAT whatever the size of the stream, so it only makes sense for a stream smaller than the
+    /// [cutoff size], whose sector chain it is. See [`RootStorage::mini_sector_locations`] for
+    /// where the mini sectors lie in the file.
+    ///
+    /// [cutoff size]: crate::cfb::header::Header::mini_stream_cutoff_size
+    pub fn mini_sector_chain(&self) -> Result<Vec<SectorNumber>, Error> {
+        strict_chain(self.starting_sector_location(), true, &mut Cache::new(self.cfb))
+    }
 
-        if cfb.header().mini_stream_cutoff_size() as u64 <= self.stream_size().0 {
-            loop {
-                let mut sector_bytes = cfb.sector_bytes(idx);
+    /// Returns an iterator over the bytes of the stream a sector, or a mini sector, at a time, in
+    /// the order of its chain, for scanning a stream without holding all of it in memory. The first
+    /// item is the error [`bytes`](Self::bytes) would fail with before reading any bytes, except
+    /// for the [limit] of bytes read, which is enforced as the sectors are read.
+    ///
+    /// [limit]: crate::cfb::Limits::max_bytes_read
+    pub fn sectors(&self) -> StreamSectors {
+        StreamSectors::new(self.reader(), self.cfb.check_shared_sectors().err())
+    }
 
-                stream_bytes.append(&mut sector_bytes);
+    /// Copies the bytes of the stream into a sink, a sector at a time, without holding more than a
+    /// megabyte of the stream in memory, and returns the number of bytes written, which is the
+    /// [stream size](Self::stream_size). A stream stored in the mini stream reads only its own
+    /// mini sectors.
+    ///
+    /// Fails before writing any bytes as [`bytes`](Self::bytes) does. A failure of the source or of
+    /// the sink midway leaves the bytes copied so far in the sink.
+    pub fn copy_to<W: Write + ?Sized>(&self, sink: &mut W) -> Result<u64, Error> {
+        self.try_for_each_chunk(|chunk| sink.write_all(chunk))
+    }
 
-                let fat = fat_cache.fat(idx);
+    /// Calls a function with the bytes of the stream in order, in chunks of up to a sector, as
+    /// [`copy_to`](Self::copy_to) writes them, and returns the number of bytes passed, which is the
+    /// [stream size](Self::stream_size). An empty stream never calls the function.
+    pub fn for_each_chunk(&self, mut f: impl FnMut(&[u8])) -> Result<u64, Error> {
+        self.try_for_each_chunk(|chunk| {
+            f(chunk);
+            Ok(())
+        })
+    }
 
-                let next = fat.sector_number((idx % no_of_sectors_per_fat).0);
+    /// Computes the digest of the bytes of the stream, fed a chunk at a time as by
+    /// [`for_each_chunk`](Self::for_each_chunk), with the `digest` feature. The digest equals the
+    /// one of [`bytes`](Self::bytes), and that of no input for an empty stream.
+    #[cfg(feature = "digest")]
+    pub fn digest<D: digest::Digest>(&self) -> Result<digest::Output<D>, Error> {
+        let mut hasher = D::new();
+        self.for_each_chunk(|chunk| hasher.update(chunk))?;
+        Ok(hasher.finalize())
+    }
 
-                if !next.is_other() { break }
+    /// Returns the offset within the stream of the first occurrence of a byte pattern, or None if
+    /// it doesn't occur, reading the stream a run of sectors at a time and keeping only the last
+    /// `needle.len() - 1` bytes of the run before, so that an occurrence straddling sectors is
+    /// found. An empty needle is found at offset 0. Fails as [`bytes`](Self::bytes) does, or as
+    /// reading the sectors up to the occurrence does.
+    pub fn find(&self, needle: &[u8]) -> Result<Option<u64>, Error> {
+        let mut found = None;
+        self.search(needle, |offset| {
+            found = Some(offset);
+            false
+        })?;
+        Ok(found)
+    }
 
-                idx = next;
-            }
-        } else {
-            let root_entry_bytes = root_entry_bytes.unwrap_or_else(||
-                cfb.mini_stream_bytes());
+    /// Returns the offsets within the stream of every occurrence of a byte pattern, overlapping
+    /// ones included, in order, searched as by [`find`](Self::find). An empty needle is found at
+    /// every offset up to the stream size.
+    pub fn find_all(&self, needle: &[u8]) -> Result<Vec<u64>, Error> {
+        let mut found = Vec::new();
+        self.search(needle, |offset| {
+            found.push(offset);
+            true
+        })?;
+        Ok(found)
+    }
 
-            loop {
-                let mini_sector = &root_entry_bytes[idx.0 as usize * 64..][..64];
+    /// Passes the offset of each occurrence of a byte pattern to a function, stopping once it
+    /// returns false
+    fn search(&self, needle: &[u8], mut on_match: impl FnMut(u64) -> bool) -> Result<(), Error> {
+        let size = self.stream_size().0;
+        self.check_read()?;
 
-                stream_bytes.clone_from_slice(mini_sector);
+        if needle.is_empty() {
+            let _ = (0..=size).all(on_match);
+            return Ok(());
+        }
 
-                let mini_fat = fat_cache.mini_fat(idx);
+        let mut reader = self.reader();
+        let mut buf = vec![0; to_usize_saturating(size).min(MAX_READ_RUN)];
+        // the bytes left of the previous runs, too few to hold the needle, then those of the run
+        let mut window = Vec::new();
+        let mut window_offset = 0;
+        loop {
+            let read = reader.read(&mut buf)?;
+            if read == 0 {
+                return Ok(());
+            }
+            window.extend_from_slice(&buf[..read]);
+            for (index, candidate) in window.windows(needle.len()).enumerate() {
+                if candidate == needle && !on_match(window_offset + index as u64) {
+                    return Ok(());
+                }
+            }
+            let consumed = window.len() - window.len().min(needle.len() - 1);
+            window.drain(..consumed);
+            window_offset += consumed as u64;
+        }
+    }
 
-                let next = mini_fat.sector_number((idx % no_of_sectors_per_fat).0);
+    /// Fails as reading the whole stream does before reading any of it, see [`bytes`](Self::bytes)
+    fn check_read(&self) -> Result<(), Error> {
+        let size = self.stream_size().0;
+        self.cfb.limits().check(Limit::BytesRead, size)?;
+        if !self.cfb.lenient {
+            // every sector but the header's slot could belong to the stream
+            let sector_size = self.cfb.sector_size() as u64;
+            let capacity = self.cfb.source.size().map(|len| len.saturating_sub(sector_size));
+            if let Some(capacity) = capacity.filter(|capacity| size > *capacity) {
+                return Err(Error::InvalidDirectory(format!(
+                    "the stream `{}` declares {} bytes, beyond the {} bytes of sectors of the file!",
+                    display_name(&self.name()), size, capacity)));
+            }
+        }
+        self.cfb.check_shared_sectors()
+    }
 
-                if !next.is_other() { break }
+    /// Passes the bytes of the stream to a function a chunk at a time, stopping at its first failure
+    fn try_for_each_chunk(&self, mut f: impl FnMut(&[u8]) -> std::io::Result<()>) -> Result<u64, Error> {
+        self.check_read()?;
 
-                idx = next;
+        // the bytes are read a run of sectors at a time, and passed a sector at a time
+        let mut reader = self.reader();
+        let mut buf = vec![0; to_usize_saturating(self.stream_size().0).min(MAX_READ_RUN)];
+        let mut passed = 0;
+        loop {
+            let read = reader.read(&mut buf)?;
+            if read == 0 {
+                return Ok(passed);
+            }
+            for chunk in buf[..read].chunks(sector_index(self.cfb.sector_size)) {
+                f(chunk)?;
             }
+            passed += read as u64;
         }
+    }
+
+    /// Reads bytes of the stream starting at `offset` into `buf`, returning the number of bytes
+    /// read, which is only short of `buf.len()` when the end of the stream is reached.
+    ///
+    /// For many scattered reads within the same stream, a [reader] remembers the sectors it has
+    /// located between calls to [`StreamReader::read_at`].
+    ///
+    /// [reader]: Self::reader
+    pub fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize, Error> {
+        Ok(self.reader().read_at(offset, buf)?)
+    }
 
-        stream_bytes
+    #[deprecated(note = "use `Stream::bytes`, which reads from the compound file of the entry")]
+    pub fn stream_bytes(&self, _cfb: &Cfb, _root_entry_bytes: Option<Vec<u8>>) -> Vec<u8> {
+        self.bytes().unwrap_or_default()
     }
 }
 
 macro_rules! impl_entry_props {
     ($type:ident) => {
         impl<'a> CommonProps<'a> for $type<'a> {
-            fn new(offset: u64, file: &'a File) -> Result<Self, String> {
-                Ok(Self { offset, file })
+            fn new(offset: u64, cfb: &'a Cfb) -> Result<Self, String> {
+                Ok(Self { offset, cfb })
             }
 
             fn offset(&self) -> u64 {
                 self.offset
             }
 
-            fn file(&self) -> &File {
-                self.file
-            }
-
             fn name(&self) -> String {
-                let bytes = self.read_bytes(0, self.name_length() as usize - 1);
-                let utf16 = unsafe {
-                    std::slice::from_raw_parts(bytes.as_ptr() as *const u16, bytes.len() >> 1)
-                };
-                String::from_utf16_lossy(&utf16)
+                let units = self.name_units();
+                let len = terminated_name_len(self.name_length(), &units)
+                    .unwrap_or_else(|| units.iter().position(|unit| *unit == 0).unwrap_or(units.len()));
+                String::from_utf16_lossy(&units[..len])
             }
 
             fn name_length(&self) -> u16 {
@@ -274,35 +607,60 @@ macro_rules! impl_entry_props {
             }
 
             fn color_flag(&self) -> ColorFlag {
-                let byte = self.read_sized(Self::NAME + 3, u8::from_ne_bytes);
-                byte.try_into().unwrap()
+                let color_flag = ColorFlag::from_byte(self.read_sized(Self::NAME + 3, u8::from_ne_bytes));
+                if let ColorFlag::Unknown(byte) = color_flag {
+                    self.cfb.warnings.push(Rule::ColorFlag,
+                                           Location::Entry { offset: self.offset, name: self.name() },
+                                           byte as u64);
+                }
+                color_flag
             }
 
             fn left_sibling_id(&self) -> Option<StreamID> {
-                let raw_value = self.read_sized(Self::NAME + 4, u32::from_le_bytes);
-                if raw_value != u32::MAX { Some(StreamID(raw_value)) } else { None }
+                self.link(Self::NAME + 4)
             }
 
             fn right_sibling_id(&self) -> Option<StreamID> {
-                let raw_value = self.read_sized(Self::NAME + 8, u32::from_le_bytes);
-                if raw_value != u32::MAX { Some(StreamID(raw_value)) } else { None }
+                self.link(Self::NAME + 8)
             }
 
             fn child_id(&self) -> Option<StreamID> {
-                let raw_value = self.read_sized(Self::NAME + 12, u32::from_le_bytes);
-                if raw_value != u32::MAX { Some(StreamID(raw_value)) } else { None }
+                self.link(Self::NAME + 12)
             }
         }
 
-        impl<'a> FileSlice for $type<'a> {
-            #[inline]
-            fn read_bytes(&self, offset: u64, size: usize) -> Vec<u8> {
-                self.file.read_bytes(offset + self.offset, size)
+        impl<'a> $type<'a> {
+            /// Reads a sibling or child ID at an offset within the entry, NOSTREAM being no link.
+            /// Any other value above MAXREGSID is taken as no link too, with a warning, when the
+            /// file is read leniently.
+            fn link(&self, offset: u64) -> Option<StreamID> {
+                let id = StreamID(self.read_sized(offset, u32::from_le_bytes));
+                if id == StreamID::NOSTREAM {
+                    return None;
+                }
+                if !id.is_regular() && self.cfb.lenient {
+                    self.cfb.warn_of_link(self.offset, id);
+                    return None;
+                }
+                Some(id)
             }
 
+            /// Returns the UTF-16 code units of the whole name field, never reading past it whatever
+            /// the name length
+            pub(crate) fn name_units(&self) -> [u16; 32] {
+                let bytes: [u8; 64] = self.read_sized(0, |bytes| bytes);
+                let mut units = [0; 32];
+                for (unit, bytes) in units.iter_mut().zip(bytes.chunks_exact(2)) {
+                    *unit = u16::from_le_bytes([bytes[0], bytes[1]]);
+                }
+                units
+            }
+        }
+
+        impl<'a> FileSlice for $type<'a> {
             #[inline]
             fn read_sized<T, const N: usize>(&self, offset: u64, constructor: impl FnOnce([u8; N]) -> T) -> T {
-                self.file.read_sized(offset + self.offset, constructor)
+                self.cfb.source.read_sized(offset + self.offset, constructor)
             }
         }
     };
@@ -312,63 +670,35 @@ impl_entry_props!(RootStorage);
 impl_entry_props!(Storage);
 impl_entry_props!(Stream);
 
-impl<'a> fmt::Debug for RootStorage<'a> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut fmt = f.debug_map();
-        crate::debug_map_method_reflection!(
-            fmt,
-            self,
-            name,
-            name_length,
-            color_flag,
-            left_sibling_id,
-            right_sibling_id,
-            child_id,
-            cls_id,
-            state_bits,
-            starting_sector_location,
-            mini_stream_range
-        );
-        fmt.finish()
-    }
-}
-
-impl<'a> fmt::Debug for Storage<'a> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut fmt = f.debug_map();
-        crate::debug_map_method_reflection!(
-            fmt,
-            self,
-            name,
-            name_length,
-            color_flag,
-            left_sibling_id,
-            right_sibling_id,
-            child_id,
-            cls_id,
-            state_bits,
-            creation_time,
-            modified_time
-        );
-        fmt.finish()
-    }
+/// Formats every field of the 128-byte record, in the same shape for every type of entry, whether
+/// or not the specification allows the type a value other than zeroes.
+macro_rules! impl_entry_debug {
+    ($type:ident) => {
+        impl<'a> fmt::Debug for $type<'a> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+                let mut fmt = f.debug_map();
+                crate::debug_map_method_reflection!(
+                    fmt,
+                    self,
+                    name,
+                    name_length,
+                    color_flag,
+                    left_sibling_id,
+                    right_sibling_id,
+                    child_id,
+                    cls_id,
+                    state_bits,
+                    creation_time,
+                    modified_time,
+                    starting_sector_location,
+                    stream_size
+                );
+                fmt.finish()
+            }
+        }
+    };
 }
 
-impl<'a> fmt::Debug for Stream<'a> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let mut fmt = f.debug_map();
-        crate::debug_map_method_reflection!(
-            fmt,
-            self,
-            name,
-            name_length,
-            color_flag,
-            left_sibling_id,
-            right_sibling_id,
-            child_id,
-            starting_sector_location,
-            stream_size
-        );
-        fmt.finish()
-    }
-}
\ No newline at end of file
+impl_entry_debug!(RootStorage);
+impl_entry_debug!(Storage);
+impl_entry_debug!(Stream);
@@ -1,8 +1,45 @@
-use std::convert::TryFrom;
+//! The types of the fields of a directory entry.
 
+use alloc::format;
+use alloc::string::String;
+use core::convert::TryFrom;
+use core::fmt;
+use core::fmt::Formatter;
+#[cfg(feature = "std")]
+use chrono::{DateTime, NaiveDateTime};
+
+/// The number of seconds from 1601-01-01, the epoch of a FILETIME, to the Unix epoch.
+#[cfg(feature = "std")]
+const FILETIME_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// Converts a Windows FILETIME, counting 100-nanosecond intervals since 1601-01-01 UTC, into a
+/// date time, or None for all zeroes, which indicates that the time was not recorded.
+#[cfg(feature = "std")]
+pub(crate) fn from_filetime(filetime: u64) -> Option<NaiveDateTime> {
+    if filetime == 0 {
+        return None;
+    }
+
+    let secs = (filetime / 10_000_000) as i64 - FILETIME_EPOCH_OFFSET;
+    let nanos = (filetime % 10_000_000) as u32 * 100;
+    Some(DateTime::from_timestamp(secs, nanos)?.naive_utc())
+}
+
+/// Converts a date time in UTC into a Windows FILETIME, truncated to 100-nanosecond intervals, or
+/// None if it precedes 1601-01-01 or overflows
+#[cfg(feature = "std-fs")]
+pub(crate) fn to_filetime(date_time: NaiveDateTime) -> Option<u64> {
+    let utc = date_time.and_utc();
+    let secs = u64::try_from(utc.timestamp().checked_add(FILETIME_EPOCH_OFFSET)?).ok()?;
+    secs.checked_mul(10_000_000)?.checked_add(utc.timestamp_subsec_nanos() as u64 / 100)
+}
+
+/// The type of the object described by a directory entry.
 #[repr(u8)]
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-pub(crate) enum ObjectType {
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ObjectType {
+    /// An unallocated entry, or an entry of an unknown type.
     Unknown = 0x00,
     /// An object in a compound file that is analogous to a file system directory. The parent object
     /// of a storage object must be another storage object or the root storage object.
@@ -30,24 +67,146 @@ impl TryFrom<u8> for ObjectType {
     }
 }
 
-#[repr(u8)]
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+/// The color of a directory entry in the red-black tree of its siblings.
+///
+/// Only writing balances the tree by the colors, so reading tolerates any other value as
+/// [`Unknown`](Self::Unknown).
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum ColorFlag {
     Red,
     Black,
+    /// A value other than 0x00 and 0x01, as written by some tools.
+    Unknown(u8),
+}
+
+impl ColorFlag {
+    /// Reads the color from its byte, any value but 0x00 and 0x01 being [`Unknown`](Self::Unknown)
+    pub fn from_byte(byte: u8) -> Self {
+        match byte {
+            0x00 => Self::Red,
+            0x01 => Self::Black,
+            _ => Self::Unknown(byte),
+        }
+    }
+
+    /// Returns the byte of the color
+    pub fn to_byte(&self) -> u8 {
+        match self {
+            Self::Red => 0x00,
+            Self::Black => 0x01,
+            Self::Unknown(byte) => *byte,
+        }
+    }
 }
 
 impl TryFrom<u8> for ColorFlag {
     type Error = &'static str;
 
+    /// Reads the color from its byte, failing for any value but 0x00 and 0x01
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            x if x == Self::Red as u8 => Ok(Self::Red),
-            x if x == Self::Black as u8 => Ok(Self::Black),
-            _ => Err("invalid value for entry::metadata::ColorFlag!"),
+        match Self::from_byte(value) {
+            Self::Unknown(_) => Err("invalid value for entry::metadata::ColorFlag!"),
+            color_flag => Ok(color_flag),
         }
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-pub struct StreamSize(pub u64);
\ No newline at end of file
+/// The size in bytes of a stream object, or of the mini stream for the root storage.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct StreamSize(pub u64);
+
+/// The user-defined flags of a storage object or the root storage, whose meaning is up to the
+/// application which wrote them.
+///
+/// The flags are displayed as a hexadecimal value followed by the indices of the bits which are
+/// set, such as `0x00000005 [0, 2]`.
+#[derive(Copy, Clone, Default, Eq, PartialEq, Hash)]
+pub struct StateBits(u32);
+
+impl StateBits {
+    /// Creates the flags from their raw value, keeping every bit since all of them are defined by
+    /// applications
+    pub const fn from_bits_retain(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    /// Returns the raw value of the flags
+    pub const fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns whether no bit is set
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns whether the bit at an index, from 0 for the least significant bit, is set
+    pub const fn is_set(&self, index: u32) -> bool {
+        index < u32::BITS && self.0 & (1 << index) != 0
+    }
+
+    /// Returns the flags with the bit at an index set or cleared
+    #[must_use]
+    pub const fn with(self, index: u32, set: bool) -> Self {
+        if index >= u32::BITS {
+            self
+        } else if set {
+            Self(self.0 | 1 << index)
+        } else {
+            Self(self.0 & !(1 << index))
+        }
+    }
+
+    /// Returns an iterator over the indices of the bits which are set, in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = u32> {
+        let bits = self.0;
+        (0..u32::BITS).filter(move |index| bits & (1 << index) != 0)
+    }
+}
+
+impl From<u32> for StateBits {
+    fn from(bits: u32) -> Self {
+        Self::from_bits_retain(bits)
+    }
+}
+
+impl From<StateBits> for u32 {
+    fn from(state_bits: StateBits) -> Self {
+        state_bits.bits()
+    }
+}
+
+impl fmt::Display for StateBits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:08X} [", self.0)?;
+        for (i, index) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", index)?;
+        }
+        f.write_str("]")
+    }
+}
+
+impl fmt::Debug for StateBits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "StateBits({})", self)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn state_bits_rendering() {
+        let state_bits = StateBits::from_bits_retain(0x8000_0005);
+        assert_eq!(state_bits.iter().collect::<Vec<_>>(), [0, 2, 31]);
+        assert_eq!(state_bits.to_string(), "0x80000005 [0, 2, 31]");
+        assert_eq!(format!("{:?}", StateBits::default()), "StateBits(0x00000000 [])");
+        assert!(state_bits.is_set(2) && !state_bits.is_set(1) && !state_bits.is_set(40));
+        assert_eq!(state_bits.with(2, false).with(1, true).bits(), 0x8000_0003);
+        assert_eq!(u32::from(StateBits::from(7)), 7);
+    }
+}
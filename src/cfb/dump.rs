@@ -0,0 +1,94 @@
+use crate::cfb::convert::to_usize_saturating;
+use crate::cfb::{Cfb, Error, SectorNumber};
+use std::fmt::Write;
+use std::ops::Range;
+
+/// The number of bytes on a line of a dump.
+const LINE: usize = 16;
+/// The number of bytes read at a time while dumping, a whole number of lines.
+const CHUNK: usize = 256 * LINE;
+/// The length of a line of a dump holding 16 bytes and an offset of 8 digits.
+const LINE_LENGTH: usize = 79;
+
+impl Cfb {
+    /**
+    Dumps a range of the bytes of a stream object by its name, as a consumer of the stream reads
+    them, 16 bytes to a line: the offset of the first byte within the stream, the bytes in
+    hexadecimal, a gap after the eighth, and the bytes between `|` with `.` for any but printable
+    ASCII, as `hexdump -C` does:
+
+    ```text
+    00000000  ec a5 c1 00 47 00 09 04  00 00 00 00 00 00 00 00  |....G...........|
+    ```
+
+    The range is cut at the stream size, so that it may reach past it, and a range starting there
+    dumps nothing. The stream is found as by [`stream_bytes`](Self::stream_bytes), and read through
+    its chain in the FAT or the mini FAT a few sectors at a time.
+     */
+    pub fn dump_stream(&self, name: &str, range: Range<u64>) -> Result<String, Error> {
+        let stream = self.stream_by_name(name)?;
+        let size = stream.stream_size().0;
+        let range = range.start.min(size)..range.end.min(size);
+        let mut reader = stream.reader();
+
+        let mut dump = String::with_capacity(capacity(&range));
+        let mut buf = [0; CHUNK];
+        let mut position = range.start;
+        while position < range.end {
+            let len = to_usize_saturating(range.end - position).min(CHUNK);
+            let read = reader.read_at(position, &mut buf[..len])?;
+            if read == 0 {
+                break;
+            }
+            push_lines(&mut dump, position, &buf[..read]);
+            position += read as u64;
+        }
+        Ok(dump)
+    }
+
+    /// Dumps the bytes of a sector as [`dump_stream`](Self::dump_stream) does, each line starting
+    /// with the offset within the file. The dump ends with the file, so that of a sector beyond it
+    /// is empty. Fails with [`Error::InvalidSectorNumber`] for a reserved value.
+    pub fn dump_sector(&self, sector_no: SectorNumber) -> Result<String, Error> {
+        let sector_size = self.sector_size as u64;
+        let offset = self.byte_offset_of_sector(sector_no)?;
+
+        let mut dump = String::with_capacity(capacity(&(0..sector_size)));
+        let mut buf = [0; CHUNK];
+        let mut position = 0;
+        while position < sector_size {
+            let len = to_usize_saturating(sector_size - position).min(CHUNK);
+            let read = self.source.read_at(&mut buf[..len], offset + position)?;
+            if read == 0 {
+                break;
+            }
+            push_lines(&mut dump, offset + position, &buf[..read]);
+            position += read as u64;
+        }
+        Ok(dump)
+    }
+}
+
+/// Returns the length of the dump of a range, when its offsets take 8 digits
+fn capacity(range: &Range<u64>) -> usize {
+    to_usize_saturating((range.end - range.start).div_ceil(LINE as u64)).saturating_mul(LINE_LENGTH)
+}
+
+/// Appends the lines of a dump of bytes starting at an offset
+fn push_lines(dump: &mut String, offset: u64, bytes: &[u8]) {
+    for (index, line) in bytes.chunks(LINE).enumerate() {
+        let _ = write!(dump, "{:08x} ", offset + (index * LINE) as u64);
+        for column in 0..LINE {
+            if column == LINE / 2 {
+                dump.push(' ');
+            }
+            match line.get(column) {
+                Some(byte) => { let _ = write!(dump, " {:02x}", byte); }
+                None => dump.push_str("   "),
+            }
+        }
+        dump.push_str("  |");
+        dump.extend(line.iter().map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' }));
+        dump.push_str("|\n");
+    }
+}
@@ -0,0 +1,229 @@
+use crate::cfb::directory::entry::metadata;
+use crate::cfb::read_at::{ReadAt, WriteAt};
+use crate::cfb::{Cfb, Entry, Error, StateBits};
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The offset of the CLSID within the header, and the length of the header.
+const HEADER_CLSID: u64 = 8;
+const HEADER_LENGTH: u64 = 512;
+/// The offsets of the fields within a directory entry.
+const ENTRY_CLSID: u64 = 80;
+const ENTRY_STATE_BITS: u64 = 96;
+/// The creation time, directly followed by the modified time.
+const ENTRY_TIMES: u64 = 100;
+
+/// An editing session over a compound file, created by [`Cfb::open_for_metadata`], which fixes
+/// fields of the header and the directory entries in place.
+///
+/// Edits only overwrite bytes within the header and the existing 128-byte directory entries, never
+/// allocating or moving sectors. They are held as pending changes, one per edited field, until
+/// [`flush`](Self::flush) writes them to the file and reads them back. Dropping an editor with
+/// pending changes discards them as its [`UnflushedPolicy`] tells, never writing them. Edits which
+/// the specification forbids, such as setting the CLSID of a stream object, are refused with
+/// [`Error::InvalidEdit`].
+pub struct MetadataEditor {
+    cfb: Cfb,
+    file: Arc<File>,
+    /// The bytes of the pending changes by their offset in the file.
+    pending: Mutex<BTreeMap<u64, Vec<u8>>>,
+    unflushed_policy: UnflushedPolicy,
+    /// The callback passed the number of changes discarded under [`UnflushedPolicy::Warn`].
+    unflushed_warning: Option<Box<dyn Fn(usize) + Send + Sync>>,
+}
+
+/// What a [`MetadataEditor`] dropped with pending changes does with them. In no case are they
+/// written to the file.
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum UnflushedPolicy {
+    /// Discards the pending changes silently, as a rollback of the session.
+    #[default]
+    Discard,
+    /// Discards the pending changes, passing how many were lost to the callback set with
+    /// [`MetadataEditor::set_unflushed_warning`], if any.
+    Warn,
+    /// Panics, unless the thread is already panicking, so that a missing flush fails tests loudly.
+    Panic,
+}
+
+impl Cfb {
+    /// Opens the compound file at the path for editing its metadata in place
+    pub fn open_for_metadata<P: AsRef<Path>>(path: P) -> Result<MetadataEditor, Error> {
+        let path = path.as_ref();
+        let file = Arc::new(OpenOptions::new().read(true).write(true).open(path).map_err(|err| Error::io_at(path, err))?);
+        let mut cfb = Self::from_source(file.clone())?.with_path(path);
+        // read the file without keeping sectors, so that the flushed edits read back
+        cfb.source = file.clone();
+        Ok(MetadataEditor {
+            cfb,
+            file,
+            pending: Mutex::new(BTreeMap::new()),
+            unflushed_policy: UnflushedPolicy::default(),
+            unflushed_warning: None,
+        })
+    }
+}
+
+impl MetadataEditor {
+    /// Returns the compound file being edited, which reads the edits already flushed
+    pub fn cfb(&self) -> &Cfb {
+        &self.cfb
+    }
+
+    /// Sets what dropping the editor with pending changes does with them, discarding them silently
+    /// by default
+    pub fn set_unflushed_policy(&mut self, policy: UnflushedPolicy) -> &mut Self {
+        self.unflushed_policy = policy;
+        self
+    }
+
+    /// Sets the callback through which dropping the editor under [`UnflushedPolicy::Warn`] reports
+    /// the number of pending changes discarded, such as to a logger of the caller
+    pub fn set_unflushed_warning<F: Fn(usize) + Send + Sync + 'static>(&mut self, warning: F) -> &mut Self {
+        self.unflushed_warning = Some(Box::new(warning));
+        self
+    }
+
+    /// Returns whether there are edits not flushed to the file yet
+    pub fn is_dirty(&self) -> bool {
+        self.pending_changes() > 0
+    }
+
+    /// Returns the number of fields edited and not flushed to the file yet, a field edited twice
+    /// counting once
+    pub fn pending_changes(&self) -> usize {
+        self.pending().len()
+    }
+
+    /// Discards the edits not flushed to the file yet
+    pub fn discard(&self) {
+        self.pending().clear();
+    }
+
+    /// Writes the pending changes to the file, those within sectors first, then those within the
+    /// header, syncing the data to the device after each, and reads them back. The changes are
+    /// still pending if writing any of them fails.
+    pub fn flush(&self) -> Result<(), Error> {
+        let mut pending = self.pending();
+        let (header, sectors): (Vec<_>, Vec<_>) = pending.iter()
+            .partition(|(offset, _)| **offset < HEADER_LENGTH);
+
+        for changes in [sectors, header] {
+            if changes.is_empty() {
+                continue;
+            }
+            for (offset, bytes) in &changes {
+                WriteAt::write_at(self.file.as_ref(), bytes, **offset)?;
+            }
+            WriteAt::sync_data(self.file.as_ref())?;
+        }
+
+        for (offset, bytes) in pending.iter() {
+            let mut read_back = vec![0u8; bytes.len()];
+            let read = ReadAt::read_at(self.file.as_ref(), &mut read_back, *offset)?;
+            if read != bytes.len() || &read_back != bytes {
+                return Err(Error::InvalidEdit(format!("the {} bytes written at offset {} don't read back", bytes.len(), offset)));
+            }
+        }
+        pending.clear();
+        Ok(())
+    }
+
+    /// Sets the creation and modified times of a storage object by its path, None writing all
+    /// zeroes, which indicates that the time was not recorded. The root storage only has a
+    /// modified time, and stream objects have neither.
+    pub fn set_entry_times(&self,
+                           path: &str,
+                           creation: Option<NaiveDateTime>,
+                           modified: Option<NaiveDateTime>) -> Result<(), Error> {
+        let entry = self.entry(path)?;
+        match entry {
+            Entry::Stream(_) => return Err(must_be_zero("times", "a stream object")),
+            Entry::RootStorage(_) if creation.is_some() => return Err(must_be_zero("creation time", "the root storage")),
+            _ => {}
+        }
+
+        let filetime = |date_time: Option<NaiveDateTime>| match date_time {
+            Some(date_time) => metadata::to_filetime(date_time)
+                .ok_or_else(|| Error::InvalidEdit(format!("{} can't be represented as a FILETIME", date_time))),
+            None => Ok(0),
+        };
+        let mut bytes = [0u8; 16];
+        bytes[..8].copy_from_slice(&filetime(creation)?.to_le_bytes());
+        bytes[8..].copy_from_slice(&filetime(modified)?.to_le_bytes());
+        self.write(entry.offset() + ENTRY_TIMES, &bytes)
+    }
+
+    /// Sets the CLSID of a storage object or the root storage by its path
+    pub fn set_cls_id(&self, path: &str, cls_id: [u8; 16]) -> Result<(), Error> {
+        let entry = self.entry(path)?;
+        if let Entry::Stream(_) = entry {
+            return Err(must_be_zero("CLSID", "a stream object"));
+        }
+        self.write(entry.offset() + ENTRY_CLSID, &cls_id)
+    }
+
+    /// Sets the state bits of a storage object or the root storage by its path
+    pub fn set_state_bits(&self, path: &str, state_bits: StateBits) -> Result<(), Error> {
+        let entry = self.entry(path)?;
+        if let Entry::Stream(_) = entry {
+            return Err(must_be_zero("state bits", "a stream object"));
+        }
+        self.write(entry.offset() + ENTRY_STATE_BITS, &state_bits.bits().to_le_bytes())
+    }
+
+    /// Sets the CLSID of the header, which the specification reserves and requires to be all
+    /// zeroes, so that only a non-zero CLSID written by a faulty implementation can be cleared
+    pub fn set_header_clsid(&self, clsid: [u8; 16]) -> Result<(), Error> {
+        if clsid != [0; 16] {
+            return Err(must_be_zero("CLSID", "the header"));
+        }
+        self.write(HEADER_CLSID, &clsid)
+    }
+
+    /// Gets an entry by its path, failing if not found
+    fn entry(&self, path: &str) -> Result<Entry<'_>, Error> {
+        self.cfb.entry_by_path(path)?
+            .ok_or_else(|| Error::InvalidEdit(format!("no entry at path `{}`", path)))
+    }
+
+    /// Holds bytes to write at an offset of the file until the next flush, replacing those already
+    /// pending there
+    fn write(&self, offset: u64, bytes: &[u8]) -> Result<(), Error> {
+        self.pending().insert(offset, bytes.to_vec());
+        Ok(())
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, BTreeMap<u64, Vec<u8>>> {
+        // the map is never left half updated, so a panic while holding it doesn't matter
+        self.pending.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for MetadataEditor {
+    fn drop(&mut self) {
+        let pending = self.pending_changes();
+        if pending == 0 {
+            return;
+        }
+        match self.unflushed_policy {
+            UnflushedPolicy::Discard => {}
+            UnflushedPolicy::Warn => {
+                if let Some(warning) = &self.unflushed_warning {
+                    warning(pending);
+                }
+            }
+            UnflushedPolicy::Panic if !std::thread::panicking() =>
+                panic!("{} metadata edits dropped without a flush", pending),
+            UnflushedPolicy::Panic => {}
+        }
+    }
+}
+
+fn must_be_zero(field: &str, object: &str) -> Error {
+    Error::InvalidEdit(format!("the {} of {} must be all zeroes", field, object))
+}
@@ -0,0 +1,246 @@
+use crate::cfb::{Cfb, CommonProps, Entry, Error, Limit, ObjectType, Stream};
+use crate::cfb::header::Header;
+use crate::cfb::read_at::StreamSource;
+use std::convert::TryInto;
+use std::sync::Arc;
+
+/// The name of the storage holding the embedded objects of a Word document.
+const OBJECT_POOL: &str = "ObjectPool";
+const COMP_OBJ: &str = "\u{1}CompObj";
+const OLE10_NATIVE: &str = "\u{1}Ole10Native";
+
+/// An object embedded in a document, returned by [`Cfb::embedded_objects`], as found in its
+/// storage beneath `ObjectPool`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EmbeddedObject {
+    /// The path of the storage of the object, such as `ObjectPool/_1234567890`.
+    pub path: String,
+    /// The number naming the storage of the object after its `_`, which the document refers to the
+    /// object by.
+    pub object_id: Option<u32>,
+    /// The CLSID of the storage, identifying the application of the object.
+    pub cls_id: [u8; 16],
+    /// The `\x01CompObj` stream, if present and well-formed.
+    pub comp_obj: Option<CompObj>,
+    /// The `\x01Ole10Native` stream of an OLE 1.0 object, such as a package, if present and
+    /// well-formed.
+    pub ole10_native: Option<Ole10Native>,
+    /// The names of the streams of the storage, such as `\x01Ole`, `\x03ObjInfo` and the content
+    /// streams, in the order of the sibling tree.
+    pub streams: Vec<String>,
+}
+
+/// The `\x01CompObj` stream, which describes the application of an object.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CompObj {
+    /// The display name of the type of the object, such as `Microsoft Word Document`.
+    pub user_type: String,
+    /// The ProgID of the application of the object, such as `Word.Document.8`, absent from some
+    /// older streams.
+    pub prog_id: Option<String>,
+}
+
+impl CompObj {
+    /// Parses a `\x01CompObj` stream, whose ANSI user type, clipboard format and ProgID follow a
+    /// 28-byte header, returning None if it is too short for the user type
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut cursor = bytes.get(28..)?;
+        let user_type = ansi(length_prefixed(&mut cursor)?);
+
+        // AnsiClipboardFormat, either a marker followed by a format ID or a length-prefixed string
+        let prog_id = (|| {
+            match u32::from_le_bytes(cursor.get(..4)?.try_into().ok()?) {
+                0 => cursor = &cursor[4..],
+                0xFFFFFFFF | 0xFFFFFFFE => cursor = cursor.get(8..)?,
+                _ => { let _ = length_prefixed(&mut cursor)?; }
+            }
+            Some(ansi(length_prefixed(&mut cursor)?))
+        })();
+
+        Some(Self { user_type, prog_id })
+    }
+}
+
+/// The `\x01Ole10Native` stream, which holds the native data of an OLE 1.0 object.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Ole10Native {
+    /// The size in bytes of the native data, as declared by the stream.
+    pub size: u32,
+    /// The file wrapped by the native data, if they are those of a package created by the Object
+    /// Packager.
+    pub package: Option<Package>,
+}
+
+/// A file wrapped by the Object Packager.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Package {
+    /// The label displayed for the package, usually the name of the file.
+    pub label: String,
+    /// The path of the file when it was packaged.
+    pub source_path: String,
+    /// The path of the file extracted while the package was edited.
+    pub temp_path: String,
+    /// The size in bytes of the file.
+    pub data_size: u32,
+}
+
+impl Ole10Native {
+    /// Parses an `\x01Ole10Native` stream, returning None if it is too short for its size
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let size = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?);
+        Some(Self { size, package: Package::parse(&bytes[4..]) })
+    }
+}
+
+impl Package {
+    /// Parses the native data of a package: a type of 2, the null-terminated label and source
+    /// path, a marker, the length-prefixed temporary path, then the length-prefixed file
+    fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut cursor = bytes;
+        if u16::from_le_bytes(cursor.get(..2)?.try_into().ok()?) != 2 {
+            return None;
+        }
+        cursor = &cursor[2..];
+
+        let label = null_terminated(&mut cursor)?;
+        let source_path = null_terminated(&mut cursor)?;
+        let _marker = cursor.get(..4)?;
+        cursor = &cursor[4..];
+        let temp_path = ansi(length_prefixed(&mut cursor)?);
+        let data_size = u32::from_le_bytes(cursor.get(..4)?.try_into().ok()?);
+
+        Some(Self { label, source_path, temp_path, data_size })
+    }
+}
+
+impl Cfb {
+    /// Lists the objects embedded in a Word document, one per storage beneath the `ObjectPool`
+    /// storage, with their `\x01CompObj` and `\x01Ole10Native` streams parsed. Returns an empty list
+    /// if there is no `ObjectPool` storage.
+    pub fn embedded_objects(&self) -> Result<Vec<EmbeddedObject>, Error> {
+        let object_pool = match self.entry_by_path(OBJECT_POOL)? {
+            Some(Entry::Storage(object_pool)) => object_pool,
+            _ => return Ok(Vec::new()),
+        };
+
+        let mut objects = Vec::new();
+        for child in object_pool.try_children()? {
+            let storage = match child {
+                Entry::Storage(storage) => storage,
+                _ => continue,
+            };
+            let name = storage.name();
+            let path = format!("{}/{}", OBJECT_POOL, name);
+            let stream = |stream: &str| self.optional_stream_bytes(&[OBJECT_POOL, &name, stream]);
+
+            objects.push(EmbeddedObject {
+                object_id: name.strip_prefix('_').and_then(|id| id.parse().ok()),
+                cls_id: storage.cls_id(),
+                comp_obj: stream(COMP_OBJ)?.as_deref().and_then(CompObj::parse),
+                ole10_native: stream(OLE10_NATIVE)?.as_deref().and_then(Ole10Native::parse),
+                streams: storage.try_children()?
+                    .iter()
+                    .filter(|entry| matches!(entry, Entry::Stream(_)))
+                    .map(Entry::name)
+                    .collect(),
+                path,
+            });
+        }
+        Ok(objects)
+    }
+
+    /// Opens the compound file stored in a stream object, as an object embedded in a document
+    /// often is, such as a workbook inside a Word document. The stream is read through in place
+    /// rather than copied into memory. The nested file is read with the leniency and the limits of
+    /// this one, and can open the files nested in it in turn.
+    ///
+    /// Fails with [`Error::NotFound`] if nothing is found at the path, with [`Error::NotAStream`]
+    /// for a storage object or the root storage, and as opening any compound file does if the
+    /// stream doesn't hold one, such as with [`Error::NotCfb`].
+    pub fn open_nested(&self, path: &str) -> Result<Cfb, Error> {
+        let object_type = match self.entry_by_path(path)? {
+            Some(Entry::Stream(stream)) => return self.open_nested_stream(&stream),
+            Some(Entry::RootStorage(_)) => ObjectType::RootStorage,
+            Some(_) => ObjectType::Storage,
+            None => return Err(Error::NotFound { name: path.to_string() }),
+        };
+        Err(Error::NotAStream { name: path.to_string(), object_type })
+    }
+
+    /// Visits every entry beneath the root storage as [`walk`](Self::walk) yields them, then
+    /// descends into each stream holding a compound file, visiting the entries of the nested file
+    /// with the path of the stream prefixed to theirs, so that `Embedded/ObjectPool/Document` is
+    /// the `ObjectPool/Document` stream of the file nested in the `Embedded` stream.
+    ///
+    /// A stream is descended into if it starts with the signature of a compound file, and is
+    /// opened as by [`open_nested`](Self::open_nested), failing if it turns out to be corrupted.
+    /// Paths longer than the [tree depth limit] fail, which bounds how deep files nest. An error
+    /// returned by `visit` ends the walk.
+    ///
+    /// [tree depth limit]: crate::cfb::Limits::max_tree_depth
+    pub fn walk_nested(&self, mut visit: impl FnMut(&[String], &Entry<'_>) -> Result<(), Error>) -> Result<(), Error> {
+        self.walk_nested_beneath(&[], &mut visit)
+    }
+
+    fn walk_nested_beneath<F: FnMut(&[String], &Entry<'_>) -> Result<(), Error>>(&self, outer: &[String], visit: &mut F) -> Result<(), Error> {
+        for item in self.walk() {
+            let (names, entry) = item?;
+            let path = [outer, &names].concat();
+            visit(&path, &entry)?;
+
+            if let Entry::Stream(stream) = &entry {
+                if holds_cfb(stream)? {
+                    self.limits().check(Limit::TreeDepth, path.len() as u64 + 1)?;
+                    self.open_nested_stream(stream)?.walk_nested_beneath(&path, visit)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Opens the compound file stored in a stream object of this file
+    fn open_nested_stream(&self, stream: &Stream<'_>) -> Result<Cfb, Error> {
+        self.check_shared_sectors()?;
+
+        let mut nested = Cfb::from_source_checked(Arc::new(StreamSource::new(stream.reader().without_read_limit())), self.lenient)?;
+        nested.limits = self.limits;
+        Ok(nested)
+    }
+
+    /// Reads the bytes of a stream object by its path given as components, returns None if not
+    /// found or not a stream object
+    fn optional_stream_bytes(&self, path: &[&str]) -> Result<Option<Vec<u8>>, Error> {
+        match self.entry_at(path)? {
+            Some(Entry::Stream(stream)) => stream.bytes().map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Whether a stream starts with the signature of a compound file.
+fn holds_cfb(stream: &Stream<'_>) -> Result<bool, Error> {
+    let mut signature = [0u8; 8];
+    Ok(stream.read_at(0, &mut signature)? == signature.len() && u64::from_le_bytes(signature) == Header::MAGIC)
+}
+
+/// Reads a string prefixed by its length in bytes, advancing the cursor past it.
+fn length_prefixed<'a>(cursor: &mut &'a [u8]) -> Option<&'a [u8]> {
+    let len = u32::from_le_bytes(cursor.get(..4)?.try_into().ok()?) as usize;
+    let string = cursor.get(4..4usize.checked_add(len)?)?;
+    *cursor = &cursor[4 + len..];
+    Some(string)
+}
+
+/// Reads a null-terminated ANSI string, advancing the cursor past its terminator.
+fn null_terminated(cursor: &mut &[u8]) -> Option<String> {
+    let len = cursor.iter().position(|byte| *byte == 0)?;
+    let string = ansi(&cursor[..len]);
+    *cursor = &cursor[len + 1..];
+    Some(string)
+}
+
+/// Decodes an ANSI string up to its first null character, replacing what isn't UTF-8.
+fn ansi(bytes: &[u8]) -> String {
+    let bytes = bytes.split(|byte| *byte == 0).next().unwrap_or_default();
+    String::from_utf8_lossy(bytes).into_owned()
+}
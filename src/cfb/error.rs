@@ -0,0 +1,159 @@
+use crate::cfb::header::ForeignFormat;
+use crate::cfb::{ObjectType, SectorNumber, StreamID};
+#[cfg(feature = "std")]
+use crate::cfb::{Limit, Overlap};
+use alloc::string::String;
+use core::fmt;
+use core::fmt::Formatter;
+
+/// The error type for reading a [compound file].
+///
+/// [compound file]: crate::cfb::Cfb
+///
+/// Without the `std` feature, the variants raised by reading a source, enforcing limits and
+/// checking chains for overlaps, which only [`Cfb`](crate::cfb::Cfb) does, are left out.
+#[derive(Debug)]
+pub enum Error {
+    /// The underlying source failed to read.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// The header signature is not 0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1, so the source
+    /// isn't a compound file, with the format it holds instead if its magic number is a well-known
+    /// one, such as that of a ZIP archive for an Office Open XML document.
+    NotCfb {
+        signature: u64,
+        detected: Option<ForeignFormat>,
+    },
+    /// A header field holds a value that the specification does not allow.
+    InvalidHeader {
+        field: &'static str,
+        value: u32,
+    },
+    /// A sector chain ends before the size declared for its stream, after `available` bytes.
+    TruncatedChain {
+        size: u64,
+        available: u64,
+    },
+    /// A sector number locating a sector is a reserved value, or its byte offset overflows.
+    InvalidSectorNumber(SectorNumber),
+    /// A sector chain followed to its end links a sector to a reserved value other than ENDOFCHAIN,
+    /// to a sector already in the chain, or, as `None`, to nothing as the sector lies beyond the FAT.
+    BrokenChain {
+        sector: SectorNumber,
+        next: Option<SectorNumber>,
+    },
+    /// The source ends before the data of a stream, or the end of a sector, after `available` of
+    /// its `size` bytes, as when a file is cut short.
+    Truncated {
+        size: u64,
+        available: u64,
+    },
+    /// The directory is corrupted, such as a sibling tree linking to an unallocated entry.
+    InvalidDirectory(String),
+    /// An in-place edit is forbidden by the specification, targets no entry, or doesn't read back.
+    InvalidEdit(String),
+    /// Reading needed more of a resource than the [limits] the compound file was opened with allow.
+    ///
+    /// [limits]: crate::cfb::Limits
+    #[cfg(feature = "std")]
+    LimitExceeded {
+        which: Limit,
+        limit: u64,
+        observed: u64,
+    },
+    /// A size or offset read from the file is too large for the target to address in memory, as a
+    /// stream over 4 GiB read into memory on a 32-bit target.
+    Overflow {
+        what: &'static str,
+        value: u64,
+    },
+    /// A sector is claimed by two owners, such as two streams sharing a sector, which a strict
+    /// reading refuses as readers disagree on what such a file holds.
+    #[cfg(feature = "std")]
+    SharedSector(Overlap),
+    /// No entry has the name looked up.
+    NotFound {
+        name: String,
+    },
+    /// No directory entry slot has the stream ID looked up, as it lies beyond the slots of the
+    /// directory, or is a reserved value above MAXREGSID.
+    NoSuchStreamID(StreamID),
+    /// The entry of the name looked up is a storage object, or the root storage, rather than a
+    /// stream object.
+    NotAStream {
+        name: String,
+        object_type: ObjectType,
+    },
+}
+
+impl Error {
+    /// Wraps an I/O error of opening the file at a path, naming the path in its message
+    #[cfg(feature = "std-fs")]
+    pub(crate) fn io_at(path: &std::path::Path, err: std::io::Error) -> Self {
+        Self::Io(std::io::Error::new(err.kind(), format!("{}: {}", path.display(), err)))
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Io(err) => write!(f, "I/O error: {}", err),
+            Self::NotCfb { signature, detected: None } =>
+                write!(f, "invalid compound file signature 0x{:016X}", signature),
+            Self::NotCfb { detected: Some(format), .. } =>
+                write!(f, "not a compound file but {}", format),
+            Self::InvalidHeader { field, value } =>
+                write!(f, "invalid value 0x{:X} for header field `{}`", value, field),
+            Self::TruncatedChain { size, available } =>
+                write!(f, "the sector chain ends after {} of the {} bytes of its stream", available, size),
+            Self::Truncated { size, available } =>
+                write!(f, "the source ends after {} of the {} bytes of a stream or sector", available, size),
+            Self::InvalidSectorNumber(sector_no) => write!(f, "sector number {} locates no sector", sector_no),
+            Self::BrokenChain { sector, next: None } =>
+                write!(f, "the sector chain runs off the FAT at sector {}", sector),
+            Self::BrokenChain { sector, next: Some(next) } if next.is_other() =>
+                write!(f, "the sector chain loops back from sector {} to sector {}", sector, next),
+            Self::BrokenChain { sector, next: Some(next) } =>
+                write!(f, "the sector chain links sector {} to {}", sector, next),
+            Self::InvalidDirectory(reason) => write!(f, "invalid directory: {}", reason),
+            Self::InvalidEdit(reason) => write!(f, "invalid edit: {}", reason),
+            #[cfg(feature = "std")]
+            Self::LimitExceeded { which, limit, observed } =>
+                write!(f, "the {} limit of {} is exceeded by {}", which, limit, observed),
+            Self::Overflow { what, value } => write!(f, "the {} of {} overflows the address space", what, value),
+            #[cfg(feature = "std")]
+            Self::SharedSector(overlap) => write!(f, "overlapping chains: {}", overlap),
+            Self::NotFound { name } => write!(f, "no entry is named `{}`", name),
+            Self::NoSuchStreamID(id) => write!(f, "no directory entry slot has stream ID {}", id.0),
+            Self::NotAStream { name, object_type } =>
+                write!(f, "the entry named `{}` is not a stream object but of type {:?}", name, object_type),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    /// Wraps an I/O error, unless it carries an error of the compound file raised by a reader,
+    /// which is unwrapped.
+    fn from(err: std::io::Error) -> Self {
+        match err.get_ref().and_then(|inner| inner.downcast_ref::<Self>()) {
+            Some(&Self::TruncatedChain { size, available }) => Self::TruncatedChain { size, available },
+            Some(&Self::Truncated { size, available }) => Self::Truncated { size, available },
+            Some(&Self::InvalidSectorNumber(sector_no)) => Self::InvalidSectorNumber(sector_no),
+            Some(&Self::LimitExceeded { which, limit, observed }) => Self::LimitExceeded { which, limit, observed },
+            Some(&Self::Overflow { what, value }) => Self::Overflow { what, value },
+            _ => Self::Io(err),
+        }
+    }
+}
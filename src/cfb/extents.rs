@@ -0,0 +1,85 @@
+use crate::cfb::fat::cache::Cache;
+use crate::cfb::fat::chain::Chain;
+use crate::cfb::reader::MINI_SECTOR_SIZE;
+use crate::cfb::{Cfb, Entry, Error, ObjectType, SectorNumber, Stream};
+use std::convert::TryFrom;
+
+impl Cfb {
+    /// Returns where the data of the stream object at a path lies in the file, as `(offset, length)`
+    /// pairs from the start of the compound file in the order of the stream, for handing the reads
+    /// to another I/O scheduler. Adjacent sectors are coalesced into one extent, and the last extent
+    /// ends with the stream. A stream smaller than the cutoff is resolved through the mini FAT and
+    /// the chain of the mini stream down to the offsets of its mini sectors in the file.
+    ///
+    /// Only the directory and the tables are read. Reading the extents one after the other yields
+    /// the bytes of the stream, so the same checks as reading apply: the call fails with
+    /// [`Error::TruncatedChain`] if a chain is shorter than the stream needs, and, unless the file is
+    /// read [leniently], with [`Error::SharedSector`] if any sector is claimed twice.
+    ///
+    /// [leniently]: crate::cfb::CfbOpenOptions::lenient
+    pub fn stream_extents(&self, path: &str) -> Result<Vec<(u64, u64)>, Error> {
+        let object_type = match self.entry_by_path(path)? {
+            Some(Entry::Stream(stream)) => return self.extents(&stream),
+            Some(Entry::RootStorage(_)) => ObjectType::RootStorage,
+            Some(_) => ObjectType::Storage,
+            None => return Err(Error::NotFound { name: path.to_string() }),
+        };
+        Err(Error::NotAStream { name: path.to_string(), object_type })
+    }
+
+    /// Returns the extents of a stream object, failing as reading it would
+    pub(crate) fn extents(&self, stream: &Stream) -> Result<Vec<(u64, u64)>, Error> {
+        self.check_shared_sectors()?;
+        let size = stream.stream_size().0;
+        self.chain_extents(stream.starting_sector_location(), size, size < self.mini_stream_cutoff())
+    }
+
+    /// Returns the extents of the first `size` bytes of the chain starting at a sector, or at a mini
+    /// sector of the mini stream, failing if the chain is shorter
+    pub(crate) fn chain_extents(&self, start: SectorNumber, size: u64, mini: bool) -> Result<Vec<(u64, u64)>, Error> {
+        let sector_size = self.sector_size as u64;
+        let mut cache = Cache::new(self);
+        let mut extents: Vec<(u64, u64)> = Vec::new();
+        let mut push = |offset: u64, len: u64| match extents.last_mut() {
+            Some((last_offset, last_len)) if *last_offset + *last_len == offset => *last_len += len,
+            _ => extents.push((offset, len)),
+        };
+
+        if mini {
+            let root_storage = self.try_root_storage()?;
+            let mut mini_stream = Chain::new(root_storage.starting_sector_location(), false);
+            let mut chain = Chain::new(start, true);
+            let (mut index, mut position) = (0, 0);
+            while position < size {
+                let mini_sector = chain.get(index, &mut cache)?
+                    .ok_or(Error::TruncatedChain { size, available: position })?;
+                // the offset of the mini sector within the mini stream, and the sector holding it
+                let offset = mini_sector.0 as u64 * MINI_SECTOR_SIZE;
+                let sector = usize::try_from(offset / sector_size).ok()
+                    .map_or(Ok(None), |index| mini_stream.get(index, &mut cache))?
+                    .ok_or(Error::TruncatedChain { size, available: position })?;
+                let len = MINI_SECTOR_SIZE.min(size - position);
+                push(file_offset(sector, sector_size)? + offset % sector_size, len);
+                index += 1;
+                position += len;
+            }
+        } else {
+            let mut chain = Chain::new(start, false);
+            let (mut index, mut position) = (0, 0);
+            while position < size {
+                let sector = chain.get(index, &mut cache)?
+                    .ok_or(Error::TruncatedChain { size, available: position })?;
+                let len = sector_size.min(size - position);
+                push(file_offset(sector, sector_size)?, len);
+                index += 1;
+                position += len;
+            }
+        }
+        Ok(extents)
+    }
+}
+
+/// Returns the offset of a sector in the file, which follows the header
+fn file_offset(sector: SectorNumber, sector_size: u64) -> Result<u64, Error> {
+    (sector + 1).checked_byte_offset(sector_size).ok_or(Error::InvalidSectorNumber(sector))
+}
@@ -0,0 +1,179 @@
+use crate::cfb::storage::children;
+use crate::cfb::{Cfb, Entry, Error, Limit};
+use std::collections::HashSet;
+#[cfg(feature = "std-fs")]
+use std::fs::{self, File};
+#[cfg(feature = "std-fs")]
+use std::path::{Path, PathBuf};
+
+/// A stream written by [`Cfb::extract_to`].
+#[cfg(feature = "std-fs")]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExtractedStream {
+    /// The names of the storages holding the stream from the root storage, then of the stream
+    /// itself, as stored.
+    pub names: Vec<String>,
+    /// The file the stream was written to, within the destination.
+    pub path: PathBuf,
+    /// The number of bytes written, which is the size of the stream.
+    pub size: u64,
+}
+
+/// What [`Cfb::extract_to`] wrote.
+#[cfg(feature = "std-fs")]
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ExtractReport {
+    /// The directories created for the storages, storage by storage depth first, each in the order
+    /// of its sibling tree.
+    pub storages: Vec<PathBuf>,
+    /// The streams written, in the same order.
+    pub streams: Vec<ExtractedStream>,
+}
+
+#[cfg(feature = "std-fs")]
+impl Cfb {
+    /**
+    Writes every stream of the compound file to a file beneath a destination directory, each
+    storage recreated as a directory, so that a document can be inspected with the tools of the
+    file system. The destination and the directories are created if missing, and files in the way
+    are overwritten.
+
+    Each name is turned into a file name which every common file system accepts, with `%` and the
+    characters they forbid or alter percent-escaped, as the UTF-8 bytes of each in hexadecimal, such
+    as `%05SummaryInformation` for `\u{5}SummaryInformation`:
+
+    - the control characters,
+    - `/`, `\`, `:`, `*`, `?`, `"`, `<`, `>` and `|`,
+    - a trailing `.` or space, and the dots of the names `.` and `..`.
+
+    An empty name becomes `%`. Distinct names thus never share a file name but for case, and a name
+    whose file name is taken in its directory, as compared case-insensitively, gets a `~1` suffix,
+    or the first of `~2`, `~3`, ... that is free, in the order of the sibling tree. The report tells
+    which file holds which stream.
+
+    Fails as reading the streams does, or with [`Error::Io`] naming the path which couldn't be
+    written, leaving what was written so far in place.
+     */
+    pub fn extract_to(&self, dest: impl AsRef<Path>) -> Result<ExtractReport, Error> {
+        let dest = dest.as_ref();
+        fs::create_dir_all(dest).map_err(|err| Error::io_at(dest, err))?;
+
+        let mut report = ExtractReport::default();
+        walk_escaped(self, &mut |item| {
+            let path = item.file_names.iter().fold(dest.to_path_buf(), |path, file_name| path.join(file_name));
+            match &item.entry {
+                Entry::Stream(stream) => {
+                    let mut file = File::create(&path).map_err(|err| Error::io_at(&path, err))?;
+                    let size = stream.copy_to(&mut file).map_err(|err| match err {
+                        Error::Io(err) => Error::io_at(&path, err),
+                        err => err,
+                    })?;
+                    report.streams.push(ExtractedStream { names: item.names, path, size });
+                }
+                _ => {
+                    fs::create_dir_all(&path).map_err(|err| Error::io_at(&path, err))?;
+                    report.storages.push(path);
+                }
+            }
+            Ok(())
+        })?;
+        Ok(report)
+    }
+}
+
+/// A storage object or stream object visited by [`walk_escaped`].
+pub(crate) struct EscapedEntry<'a> {
+    /// The names of the storages holding the entry from the root storage, then of the entry itself.
+    pub(crate) names: Vec<String>,
+    /// The names escaped into file names unique in their directories, as [`Cfb::extract_to`] does.
+    pub(crate) file_names: Vec<String>,
+    pub(crate) entry: Entry<'a>,
+    /// The storage holding the entry, or the root storage.
+    #[cfg_attr(not(feature = "zip"), allow(dead_code))]
+    pub(crate) parent: Entry<'a>,
+}
+
+/// Visits every storage object and stream object of a compound file storage by storage depth
+/// first, the children of each in the order of its sibling tree, with their names escaped into file
+/// names. Descending deeper than the [tree depth limit], reaching a storage twice, or a strictly
+/// read file whose sectors are [claimed twice], fails.
+///
+/// [tree depth limit]: crate::cfb::Limits::max_tree_depth
+/// [claimed twice]: crate::cfb::Error::SharedSector
+pub(crate) fn walk_escaped<'a>(cfb: &'a Cfb,
+                               visit: &mut dyn FnMut(EscapedEntry<'a>) -> Result<(), Error>) -> Result<(), Error> {
+    let root_storage = cfb.try_root_storage()?;
+    cfb.check_shared_sectors()?;
+
+    let mut visited = HashSet::new();
+    let mut pending = vec![(Vec::new(), Vec::new(), Entry::RootStorage(root_storage))];
+    while let Some((names, file_names, storage)) = pending.pop() {
+        if !visited.insert(storage.offset()) {
+            return Err(Error::InvalidDirectory(format!("the storage at `{}` is reached twice!", names.join("/"))));
+        }
+        cfb.limits().check(Limit::TreeDepth, names.len() as u64 + 1)?;
+
+        let mut taken = HashSet::new();
+        let mut storages = Vec::new();
+        for child in children(storage.clone())? {
+            if !matches!(child, Entry::Stream(_) | Entry::Storage(_)) {
+                continue;
+            }
+            let name = child.name();
+            let mut item = EscapedEntry {
+                file_names: file_names.clone(),
+                names: names.clone(),
+                entry: child.clone(),
+                parent: storage.clone(),
+            };
+            item.file_names.push(unique(file_name(&name), &mut taken));
+            item.names.push(name);
+            if let Entry::Storage(_) = child {
+                storages.push((item.names.clone(), item.file_names.clone(), child));
+            }
+            visit(item)?;
+        }
+        // pushed in reverse so that the storages are descended into in order
+        pending.extend(storages.into_iter().rev());
+    }
+    Ok(())
+}
+
+/// Returns the file name that [`Cfb::extract_to`] gives an entry name, escaped as it describes,
+/// which is injective
+fn file_name(name: &str) -> String {
+    if name.is_empty() {
+        return "%".to_string();
+    }
+
+    let dots = name == "." || name == "..";
+    let trailing = name.len() - name.trim_end_matches(['.', ' ']).len();
+    let mut escaped = String::with_capacity(name.len());
+    for (index, c) in name.char_indices() {
+        let forbidden = c.is_control()
+            || matches!(c, '%' | '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|')
+            || dots
+            || index >= name.len() - trailing;
+        if forbidden {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// Returns a file name not yet taken in a directory, as compared case-insensitively, suffixed by
+/// `~` and the first free number if taken, and takes it
+fn unique(file_name: String, taken: &mut HashSet<String>) -> String {
+    let mut candidate = file_name.clone();
+    let mut suffix = 0;
+    while !taken.insert(candidate.to_lowercase()) {
+        suffix += 1;
+        candidate = format!("{}~{}", file_name, suffix);
+    }
+    candidate
+}
@@ -1,51 +1,86 @@
 use crate::cfb::fat::sector_number::SectorNumber;
-use std::convert::TryInto;
+use alloc::vec::Vec;
 use core::fmt;
-use std::fmt::Formatter;
+use core::fmt::Formatter;
 
 pub mod sector_number;
+#[cfg(feature = "std")]
 pub(crate) mod cache;
+#[cfg(feature = "std")]
+pub(crate) mod chain;
+#[cfg(feature = "std")]
+pub(crate) mod mini_fat;
 
 /// The FAT is an array of [sector numbers] that represent the allocation of space within the file,
 /// grouped into FAT sectors. Each stream is represented in the FAT by a sector chain, in much the
 /// same fashion as a FAT file system.
 /// 
+/// A `Fat` is a read-only view over a single FAT sector, obtained from [`Cfb::fat_sector`]. The
+/// same structure also holds mini FAT sectors, whose entries chain the sectors of the mini stream.
+///
 /// [sector numbers]: crate::cfb::fat::sector_number::SectorNumber
-pub(crate) struct Fat {
-    pub(crate) data: Vec<u8>,
+/// [`Cfb::fat_sector`]: crate::cfb::Cfb::fat_sector
+pub struct Fat {
+    location: SectorNumber,
+    data: Vec<u8>,
 }
 
 impl Fat {
-    pub(crate) fn new(data: Vec<u8>) -> Self {
-        Self { data }
+    pub(crate) fn new(location: SectorNumber, data: Vec<u8>) -> Self {
+        Self { location, data }
     }
 
-    const U32_SIZE: usize = std::mem::size_of::<u32>();
+    const U32_SIZE: usize = core::mem::size_of::<u32>();
+
+    /// Returns the sector number the FAT sector was loaded from
+    pub fn location(&self) -> SectorNumber {
+        self.location
+    }
+
+    /// Returns the number of entries in the FAT sector
+    pub fn len(&self) -> usize {
+        self.data.len() / Self::U32_SIZE
+    }
+
+    /// Returns whether the FAT sector has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the entry at an index, which is the sector following the sector of the same index
+    /// relative to the FAT sector in its chain, or None if out of bounds
+    pub fn get(&self, index: usize) -> Option<SectorNumber> {
+        let bytes = self.data.get(index.checked_mul(Self::U32_SIZE)?..)?.get(..Self::U32_SIZE)?;
+        Some(SectorNumber(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])))
+    }
+
+    /// Returns an iterator over the entries of the FAT sector
+    pub fn iter(&self) -> impl Iterator<Item = SectorNumber> + '_ {
+        self.data
+            .chunks_exact(Self::U32_SIZE)
+            .map(|bytes| SectorNumber(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])))
+    }
 
     pub(crate) fn sector_numbers(&self) -> Vec<SectorNumber> {
-        (0..(self.data.len() / Self::U32_SIZE))
-            .filter_map(|i| self.data[i * Self::U32_SIZE..][..Self::U32_SIZE].try_into().ok())
-            .map(|slice|
-                SectorNumber(u32::from_le_bytes(slice))
-            )
-            .collect()
+        self.iter().collect()
     }
 
-    pub(crate) fn sector_number(&self, index: u32) -> SectorNumber {
-        let data = self.data[(index as usize * Self::U32_SIZE)..][..Self::U32_SIZE].to_owned();
-        SectorNumber(u32::from_le_bytes(data.try_into().unwrap()))
+    /// Returns the entry at an index, or None if the index is past the entries of the sector, as
+    /// when chain math runs off a corrupted file
+    pub(crate) fn sector_number(&self, index: u32) -> Option<SectorNumber> {
+        self.get(index as usize)
     }
 }
 
 impl fmt::Debug for Fat {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         f.debug_list()
             .entries(self.sector_numbers())
             .finish()
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::cfb::Cfb;
@@ -53,9 +88,15 @@ mod tests {
     #[test]
     fn basic_read_write() {
         let a = [10; 12];
-        let fat = Fat { data: a.to_vec() };
+        let fat = Fat { location: SectorNumber(0), data: a.to_vec() };
         assert_eq!(fat.sector_numbers(), [SectorNumber(168430090), SectorNumber(168430090), SectorNumber(168430090)]);
-        assert_eq!(fat.sector_number(2), SectorNumber(168430090));
+        assert_eq!(fat.sector_number(2), Some(SectorNumber(168430090)));
+        assert_eq!(fat.sector_number(3), None);
+        assert_eq!(fat.sector_number(u32::MAX), None);
+        assert_eq!(fat.len(), 3);
+        assert_eq!(fat.get(2), Some(SectorNumber(168430090)));
+        assert_eq!(fat.get(3), None);
+        assert_eq!(fat.iter().collect::<Vec<_>>(), fat.sector_numbers());
     }
 
     #[test]
@@ -105,8 +146,15 @@ mod tests {
             SectorNumber::FREESECT, SectorNumber::FREESECT, SectorNumber::FREESECT];
 
         let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
-        let fat = cfb.fat_by_stream_sector_no(SectorNumber(0));
+        let fat = cfb.fat_by_stream_sector_no(SectorNumber(0)).unwrap();
 
         assert_eq!(fat.sector_numbers(), expected);
+
+        let fat = cfb.fat_sector(0).unwrap();
+        assert_eq!(fat.location(), SectorNumber(0x1C));
+        assert_eq!(fat.len(), 128);
+        assert_eq!(fat.iter().filter(|sector_no| *sector_no == SectorNumber::ENDOFCHAIN).count(), 6);
+        assert_eq!(fat.iter().filter(|sector_no| sector_no.is_free()).count(), 99);
+        assert!(cfb.fat_sector(1).is_none());
     }
 }
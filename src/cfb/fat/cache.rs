@@ -1,79 +1,84 @@
-use crate::cfb::Cfb;
+use crate::cfb::{Cfb, Error, Limits};
 use crate::cfb::fat::Fat;
 use crate::cfb::fat::sector_number::SectorNumber;
 use crate::cfb::header::SectorCount;
+use crate::cfb::convert::{sector_index, sector_numbers_per_sector};
+use std::sync::OnceLock;
 
-macro_rules! populated_fats {
-    ($count:expr) => {
-        {
-            let mut vec = Vec::new();
-            vec.reserve_exact($count);
-            for _ in 0..$count {
-                vec.push(None);
-            }
-            vec
-        }
-    };
+/// The FAT and mini FAT sectors parsed so far, held by a compound file and shared with its clones,
+/// so that the chains followed by concurrent reads parse each sector once.
+#[derive(Default)]
+pub(crate) struct SharedFats {
+    fat: Table,
+    /// The locations of the mini FAT sectors are resolved from their chain. The chain is trusted
+    /// over the count in the header, which only the validator compares it with.
+    mini_fat: Table,
 }
 
-pub(crate) struct Cache<'a> {
-    cfb: &'a Cfb,
-    fats: Vec<Option<Fat>>,
-    mini_fats: Vec<Option<Fat>>,
-    no_of_sectors_per_fat: SectorCount,
+/// The sectors of a FAT or mini FAT, their locations resolved on first use, and each of them parsed
+/// on first use. A lookup racing another may resolve or parse the same sector twice, of which one
+/// result is kept.
+#[derive(Default)]
+struct Table {
+    sectors: OnceLock<Vec<(SectorNumber, OnceLock<Fat>)>>,
 }
 
-impl<'a> Cache<'a> {
-    pub(crate) fn new(cfb: &'a Cfb) -> Self {
-        let header = cfb.header();
-        let no_of_sectors_per_fat = SectorCount(cfb.sector_size >> std::mem::size_of::<SectorNumber>());
-        let fats = populated_fats!(109);
-        let mini_fats = populated_fats!(header.no_of_fat_sectors().0 as usize);
+impl Table {
+    /// Returns the sector number at an index of the table, or `None` beyond its sectors
+    fn sector_number(&self,
+                     cfb: &Cfb,
+                     index: SectorNumber,
+                     locations: impl FnOnce() -> Result<Vec<SectorNumber>, Error>) -> Result<Option<SectorNumber>, Error> {
+        let no_of_sectors_per_fat = SectorCount(sector_numbers_per_sector(cfb.sector_size));
+        let sectors = match self.sectors.get() {
+            Some(sectors) => sectors,
+            None => {
+                let sectors = locations()?.into_iter().map(|location| (location, OnceLock::new())).collect();
+                self.sectors.get_or_init(|| sectors)
+            }
+        };
+        let (location, slot) = match sectors.get(sector_index((index / no_of_sectors_per_fat).0)) {
+            Some(sector) => sector,
+            None => return Ok(None),
+        };
 
-        Self {
-            cfb,
-            fats,
-            mini_fats,
-            no_of_sectors_per_fat,
-        }
+        let fat = match slot.get() {
+            Some(fat) => fat,
+            None => {
+                let fat = cfb.fat(*location)?;
+                slot.get_or_init(|| fat)
+            }
+        };
+        Ok(fat.sector_number((index % no_of_sectors_per_fat).0))
     }
+}
 
-    pub(crate) fn fat(&mut self, sector_no: SectorNumber) -> &Fat {
-        let fat_idx = sector_no / self.no_of_sectors_per_fat;
-
-        let ptr = unsafe { self.fats.get_unchecked_mut(fat_idx.0 as usize) };
-        if ptr.is_none() {
-            let fat = self.cfb.fat(self.cfb.header().sector_no_of_fat(fat_idx));
-            *ptr = Some(fat);
-        }
+/// A handle following chains through the FAT and mini FAT sectors shared by a compound file.
+pub(crate) struct Cache {
+    cfb: Cfb,
+}
 
-        ptr.as_ref().unwrap()
+impl Cache {
+    pub(crate) fn new(cfb: &Cfb) -> Self {
+        Self { cfb: cfb.clone() }
     }
 
-    pub(crate) fn mini_fat(&mut self, sector_no: SectorNumber) -> &Fat {
-        let no_of_sectors_per_fat = self.no_of_sectors_per_fat;
-        let mini_fat_idx = sector_no / no_of_sectors_per_fat;
-
-        if self.mini_fats[mini_fat_idx.0 as usize].as_ref().is_some() {
-            self.mini_fats[mini_fat_idx.0 as usize].as_ref().unwrap()
-        }
-        else {
-            let mut mini_fat_sector_number = self.cfb
-                .header()
-                .first_mini_fat_sector_location();
-
-            for _ in 0..mini_fat_idx.0 {
-                let fat = self.fat(mini_fat_sector_number);
-                mini_fat_sector_number = fat
-                    .sector_number((mini_fat_sector_number % no_of_sectors_per_fat).0);
-            }
+    /// Returns the limits of the compound file the chains are read from
+    pub(crate) fn limits(&self) -> Limits {
+        self.cfb.limits()
+    }
 
-            let mini_fat_data = self.cfb.sector_bytes(mini_fat_sector_number);
-            let mini_fat = Fat::new(mini_fat_data);
+    /// Returns the sector following a sector in its FAT chain, or `None` if the sector lies beyond
+    /// the FAT sectors listed in the DIFAT.
+    pub(crate) fn next_sector(&mut self, sector_no: SectorNumber) -> Result<Option<SectorNumber>, Error> {
+        let cfb = &self.cfb;
+        cfb.fats.fat.sector_number(cfb, sector_no, || cfb.fat_sector_locations().collect())
+    }
 
-            let ptr = unsafe { self.mini_fats.get_unchecked_mut(mini_fat_idx.0 as usize) };
-            ptr.replace(mini_fat);
-            ptr.as_ref().unwrap()
-        }
+    /// Returns the mini sector following a mini sector in its mini FAT chain, or `None` if the
+    /// mini sector lies beyond the mini FAT sectors found in their chain.
+    pub(crate) fn next_mini_sector(&mut self, sector_no: SectorNumber) -> Result<Option<SectorNumber>, Error> {
+        let cfb = &self.cfb;
+        cfb.fats.mini_fat.sector_number(cfb, sector_no, || cfb.sector_chain(cfb.header().first_mini_fat_sector_location()))
     }
 }
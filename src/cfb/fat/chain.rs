@@ -0,0 +1,76 @@
+use crate::cfb::fat::cache::Cache;
+use crate::cfb::fat::sector_number::SectorNumber;
+use crate::cfb::{Error, Limit};
+use std::collections::HashSet;
+
+/// A sector chain in the [FAT] or the mini FAT, resolved only as far as it has been accessed.
+///
+/// [FAT]: crate::cfb::fat::Fat
+pub(crate) struct Chain {
+    sectors: Vec<SectorNumber>,
+    next: SectorNumber,
+    mini: bool,
+}
+
+impl Chain {
+    /// Creates a chain starting at a sector of the FAT, or a mini sector of the mini FAT.
+    pub(crate) fn new(start: SectorNumber, mini: bool) -> Self {
+        Self {
+            sectors: Vec::new(),
+            next: start,
+            mini,
+        }
+    }
+
+    /// Returns the sector number at a position of the chain, or `None` if the chain ends before it,
+    /// failing if the position is beyond the [chain length limit].
+    ///
+    /// [chain length limit]: crate::cfb::Limits::max_chain_length
+    pub(crate) fn get(&mut self, index: usize, cache: &mut Cache) -> Result<Option<SectorNumber>, Error> {
+        while self.sectors.len() <= index {
+            if !self.next.is_other() {
+                return Ok(None);
+            }
+            cache.limits().check(Limit::ChainLength, self.sectors.len() as u64 + 1)?;
+
+            let current = self.next;
+            self.sectors.push(current);
+            self.next = if self.mini {
+                cache.next_mini_sector(current)?
+            } else {
+                cache.next_sector(current)?
+            }.unwrap_or(SectorNumber::ENDOFCHAIN);
+        }
+
+        Ok(Some(self.sectors[index]))
+    }
+}
+
+/// Returns every sector, or mini sector, of the chain starting at a sector up to its ENDOFCHAIN,
+/// failing with [`Error::BrokenChain`] where it links elsewhere than to a sector not yet in it, or
+/// with [`Error::InvalidSectorNumber`] if it starts at a reserved value other than ENDOFCHAIN, which
+/// starts an empty chain
+pub(crate) fn strict_chain(start: SectorNumber, mini: bool, cache: &mut Cache) -> Result<Vec<SectorNumber>, Error> {
+    if start == SectorNumber::ENDOFCHAIN {
+        return Ok(Vec::new());
+    }
+    if !start.is_other() {
+        return Err(Error::InvalidSectorNumber(start));
+    }
+
+    let mut sectors = Vec::new();
+    let mut visited = HashSet::new();
+    let mut current = start;
+    loop {
+        cache.limits().check(Limit::ChainLength, sectors.len() as u64 + 1)?;
+        sectors.push(current);
+        visited.insert(current);
+
+        let next = if mini { cache.next_mini_sector(current)? } else { cache.next_sector(current)? };
+        match next {
+            Some(next) if next == SectorNumber::ENDOFCHAIN => return Ok(sectors),
+            Some(next) if next.is_other() && !visited.contains(&next) => current = next,
+            next => return Err(Error::BrokenChain { sector: current, next }),
+        }
+    }
+}
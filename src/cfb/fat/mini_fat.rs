@@ -0,0 +1,60 @@
+use crate::cfb::fat::sector_number::SectorNumber;
+use core::fmt;
+use std::fmt::Formatter;
+
+/// A read-only view over the whole mini FAT, obtained from [`Cfb::mini_fat_table`], whose entries
+/// are those of every mini FAT sector, concatenated in the order of the chain of the mini FAT.
+///
+/// Each entry describes a 64-byte mini sector of the mini stream, so that the entry at an index is
+/// the mini sector following that mini sector in its chain.
+///
+/// [`Cfb::mini_fat_table`]: crate::cfb::Cfb::mini_fat_table
+pub struct MiniFatTable {
+    sector_locations: Vec<SectorNumber>,
+    entries: Vec<SectorNumber>,
+}
+
+impl MiniFatTable {
+    pub(crate) fn new(sector_locations: Vec<SectorNumber>, entries: Vec<SectorNumber>) -> Self {
+        Self { sector_locations, entries }
+    }
+
+    /// Returns the sector numbers of the mini FAT sectors, in the order of their chain
+    pub fn sector_locations(&self) -> &[SectorNumber] {
+        &self.sector_locations
+    }
+
+    /// Returns the number of entries, which is the number of mini sectors the mini FAT can describe
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether the mini FAT has no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the entry of a mini sector by its index in the mini stream, or None if out of bounds
+    pub fn get(&self, mini_sector_index: usize) -> Option<SectorNumber> {
+        self.entries.get(mini_sector_index).copied()
+    }
+
+    /// Returns an iterator over the entries of the mini FAT
+    pub fn iter(&self) -> impl Iterator<Item = SectorNumber> + '_ {
+        self.entries.iter().copied()
+    }
+
+    /// Returns the index of the last mini sector which is not free, if any
+    pub fn last_allocated(&self) -> Option<usize> {
+        self.entries.iter().rposition(|sector_no| !sector_no.is_free())
+    }
+}
+
+impl fmt::Debug for MiniFatTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entry(&"sector_locations", &self.sector_locations)
+            .entry(&"entries", &self.entries)
+            .finish()
+    }
+}
@@ -1,14 +1,15 @@
-use std::ops::{Add, Div, Rem};
+use alloc::string::{String, ToString};
+use core::ops::{Add, Div, Rem};
 use core::fmt;
-use std::fmt::Formatter;
+use core::fmt::Formatter;
+use core::str::FromStr;
 use crate::cfb::header::SectorCount;
-use std::convert::TryInto;
 
 /** The sector number can be used as an index into the [FAT] array to continue along the chain.
 
 Special values are reserved for chain terminators (ENDOFCHAIN = 0xFFFFFFFE), free sectors
 (FREESECT = 0xFFFFFFFF), and sectors that contain storage for FAT sectors (FATSECT = 0xFFFFFFFD) or
-DIFAT Sectors (DIFSECT = 0xFFFFFFC), which are not chained in the same way as the others.
+DIFAT Sectors (DIFSECT = 0xFFFFFFFC), which are not chained in the same way as the others.
 
 The locations of FAT sectors are read from the DIFAT. The FAT is represented in itself, but not
 by a chain. A special reserved sector number (FATSECT = 0xFFFFFFFD) is used to mark sectors that are
@@ -20,14 +21,14 @@ byte offset `Sector Size`, not at 0.
 
 [FAT]: crate::cfb::fat::Fat
  */
-#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct SectorNumber(pub u32);
 
 impl SectorNumber {
-    /// 0xFFFFFFC
+    /// 0xFFFFFFFC
     ///
-    /// DIFAT Sectors (DIFSECT = 0xFFFFFFC), which are not chained in the same way as the others.
-    pub const DIFSECT: Self = Self(0xFFFFFFC);
+    /// DIFAT Sectors (DIFSECT = 0xFFFFFFFC), which are not chained in the same way as the others.
+    pub const DIFSECT: Self = Self(0xFFFFFFFC);
     /// 0xFFFFFFFD
     ///
     /// Sectors that contain storage for FAT sectors (FATSECT = 0xFFFFFFFD).
@@ -63,8 +64,13 @@ impl SectorNumber {
 }
 
 impl SectorNumber {
-    pub(crate) fn byte_offset(&self, sector_size: impl TryInto<u64>) -> u64 {
-        self.0 as u64 * sector_size.try_into().unwrap_or(0)
+    /// Returns the sector number multiplied by a sector size, or None for a reserved value, which
+    /// locates no sector, or if the multiplication overflows.
+    pub fn checked_byte_offset(&self, sector_size: u64) -> Option<u64> {
+        if !self.is_other() {
+            return None;
+        }
+        (self.0 as u64).checked_mul(sector_size)
     }
 }
 
@@ -92,15 +98,96 @@ impl Rem<SectorCount> for SectorNumber {
     }
 }
 
+impl fmt::Display for SectorNumber {
+    /// Formats a reserved value by its name, such as `ENDOFCHAIN`, and any other as 8 hexadecimal
+    /// digits, such as `0x0000002A`
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::FREESECT => f.write_str("FREESECT"),
+            Self::FATSECT => f.write_str("FATSECT"),
+            Self::ENDOFCHAIN => f.write_str("ENDOFCHAIN"),
+            Self::DIFSECT => f.write_str("DIFSECT"),
+            Self(v) => write!(f, "0x{:08X}", v),
+        }
+    }
+}
+
 impl fmt::Debug for SectorNumber {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let str = match *self {
-            Self::FREESECT => "FREESECT".to_string(),
-            Self::FATSECT => "FATSECT".to_string(),
-            Self::ENDOFCHAIN => "ENDOFCHAIN".to_string(),
-            Self::DIFSECT => "DIFSECT".to_string(),
-            Self(v) => format!("0x{:08X}", v),
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "SectorNumber({})", self)
+    }
+}
+
+impl FromStr for SectorNumber {
+    type Err = ParseSectorNumberError;
+
+    /// Parses a decimal number, a hexadecimal number prefixed with `0x`, or the name of a reserved
+    /// value in any case, such as `42`, `0x2A` or `endofchain`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let reserved = [Self::FREESECT, Self::FATSECT, Self::ENDOFCHAIN, Self::DIFSECT];
+        if let Some(sector_no) = reserved.iter().find(|sector_no| sector_no.to_string().eq_ignore_ascii_case(s)) {
+            return Ok(*sector_no);
+        }
+
+        let parsed = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => u32::from_str_radix(hex, 16),
+            None => s.parse(),
         };
-        f.write_str(&format!("SectorNumber({})", str))
+        parsed.map(Self).map_err(|_| ParseSectorNumberError(s.to_string()))
+    }
+}
+
+/// The error of parsing a [`SectorNumber`] from a string which is neither a number nor the name of
+/// a reserved value.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseSectorNumberError(String);
+
+impl fmt::Display for ParseSectorNumberError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid sector number `{}`", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseSectorNumberError {}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_byte_offset() {
+        assert_eq!(SectorNumber(0).checked_byte_offset(512), Some(0));
+        assert_eq!(SectorNumber(3).checked_byte_offset(4096), Some(3 * 4096));
+        assert_eq!(SectorNumber(0xFFFFFFFA).checked_byte_offset(512), Some(0xFFFFFFFA * 512));
+        assert_eq!(SectorNumber(2).checked_byte_offset(u64::MAX), None);
+        for reserved in [SectorNumber::DIFSECT, SectorNumber::FATSECT, SectorNumber::ENDOFCHAIN, SectorNumber::FREESECT] {
+            assert_eq!(reserved.checked_byte_offset(512), None);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn display_round_trips() {
+        for sector_no in [SectorNumber::DIFSECT, SectorNumber::FATSECT, SectorNumber::ENDOFCHAIN,
+                          SectorNumber::FREESECT, SectorNumber(0), SectorNumber(0x2A), SectorNumber(0xFFFFFFFA)] {
+            assert_eq!(sector_no.to_string().parse(), Ok(sector_no));
+        }
+        assert_eq!(SectorNumber::ENDOFCHAIN.to_string(), "ENDOFCHAIN");
+        assert_eq!(SectorNumber(0x2A).to_string(), "0x0000002A");
+        assert_eq!(format!("{:?}", SectorNumber(0x2A)), "SectorNumber(0x0000002A)");
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!("42".parse(), Ok(SectorNumber(42)));
+        assert_eq!("0x2A".parse(), Ok(SectorNumber(42)));
+        assert_eq!("0X2a".parse(), Ok(SectorNumber(42)));
+        assert_eq!("EndOfChain".parse(), Ok(SectorNumber::ENDOFCHAIN));
+        assert_eq!("freesect".parse(), Ok(SectorNumber::FREESECT));
+        assert_eq!("fatsect".parse(), Ok(SectorNumber::FATSECT));
+        assert_eq!("difsect".parse(), Ok(SectorNumber::DIFSECT));
+        for invalid in ["", "0x", "-1", "0x1FFFFFFFF", "sector", "4294967296"] {
+            assert_eq!(invalid.parse::<SectorNumber>(), Err(ParseSectorNumberError(invalid.to_string())));
+        }
+    }
+}
@@ -0,0 +1,226 @@
+//! A C interface for listing and extracting the streams of a compound file, with the `ffi`
+//! feature.
+//!
+//! The functions are prefixed with `olekit_` and take an opaque [`OlekitCfb`] handle, which
+//! [`olekit_open`] creates and [`olekit_close`] frees. Names and paths are NUL-terminated UTF-8
+//! strings. Calls returning a count return a negative `OLEKIT_ERR_*` code on failure, and
+//! [`olekit_last_error_message`] then tells what failed. The layout is the one cbindgen generates,
+//! as in `include/ole_kit.h`.
+//!
+//! Linking from C or C++ needs a static or dynamic library, which can be built by
+//! `cargo rustc --release --features ffi --crate-type staticlib`, or `cdylib`.
+
+use crate::cfb::storage::walk;
+use crate::cfb::{Cfb, Entry, Error, ObjectType};
+use std::cell::RefCell;
+use std::convert::TryFrom;
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+/// A pointer or a string argument is null, or a string isn't UTF-8.
+pub const OLEKIT_ERR_INVALID_ARGUMENT: i64 = -1;
+/// The file can't be opened or read.
+pub const OLEKIT_ERR_IO: i64 = -2;
+/// The file isn't a compound file.
+pub const OLEKIT_ERR_NOT_CFB: i64 = -3;
+/// The compound file is corrupted or cut short.
+pub const OLEKIT_ERR_CORRUPT: i64 = -4;
+/// Reading needs more than the limits of the compound file allow.
+pub const OLEKIT_ERR_LIMIT_EXCEEDED: i64 = -5;
+/// No entry has the name looked up.
+pub const OLEKIT_ERR_NOT_FOUND: i64 = -6;
+/// The entry of the name looked up is a storage rather than a stream.
+pub const OLEKIT_ERR_NOT_A_STREAM: i64 = -7;
+/// The library panicked, which is a bug.
+pub const OLEKIT_ERR_PANIC: i64 = -8;
+
+/// A compound file opened by [`olekit_open`].
+pub struct OlekitCfb {
+    cfb: Cfb,
+}
+
+/// An entry of a compound file, passed to the callback of [`olekit_list_entries`]. The strings
+/// are only valid during the call.
+#[repr(C)]
+pub struct OlekitEntry {
+    /// The path of the entry from the root storage, its names separated by `/`.
+    pub path: *const c_char,
+    /// The name of the entry, which [`olekit_stream_size`] and [`olekit_stream_read`] take.
+    pub name: *const c_char,
+    /// The object type of the entry: 1 for a storage, 2 for a stream.
+    pub object_type: u8,
+    /// The size of a stream in bytes, 0 for a storage.
+    pub size: u64,
+}
+
+/// The callback of [`olekit_list_entries`], called with each entry and the user data.
+pub type OlekitEntryCallback = extern "C" fn(entry: *const OlekitEntry, user_data: *mut c_void);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Opens the compound file at a path, returning null on failure
+///
+/// # Safety
+///
+/// `path` must be null or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn olekit_open(path: *const c_char) -> *mut OlekitCfb {
+    let handle = guard(|| {
+        let cfb = Cfb::from_path(string_arg(path, "path")?).map_err(Failure::from)?;
+        Ok(Box::into_raw(Box::new(OlekitCfb { cfb })))
+    });
+    handle.unwrap_or(ptr::null_mut())
+}
+
+/// Closes a compound file opened by [`olekit_open`]. Closing null does nothing.
+///
+/// # Safety
+///
+/// `handle` must be null or a handle returned by [`olekit_open`] and not closed yet.
+#[no_mangle]
+pub unsafe extern "C" fn olekit_close(handle: *mut OlekitCfb) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Returns the size in bytes of a stream by its name, found as [`Cfb::stream_bytes`] finds it, or
+/// a negative error code
+///
+/// # Safety
+///
+/// `handle` must be a handle returned by [`olekit_open`] and not closed, and `name` null or a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn olekit_stream_size(handle: *const OlekitCfb, name: *const c_char) -> i64 {
+    count(guard(|| {
+        let stream = cfb_arg(handle)?.stream_by_name(string_arg(name, "name")?).map_err(Failure::from)?;
+        Ok(stream.stream_size().0)
+    }))
+}
+
+/// Reads a stream by its name into a buffer, from its start and up to `len` bytes, returning the
+/// number of bytes read, which is short of `len` only at the end of the stream, or a negative
+/// error code. A buffer of the [size](olekit_stream_size) of the stream reads all of it.
+///
+/// # Safety
+///
+/// `handle` must be a handle returned by [`olekit_open`] and not closed, `name` null or a
+/// NUL-terminated string, and `buf` valid for writing `len` bytes unless `len` is 0.
+#[no_mangle]
+pub unsafe extern "C" fn olekit_stream_read(handle: *const OlekitCfb,
+                                            name: *const c_char,
+                                            buf: *mut u8,
+                                            len: usize) -> i64 {
+    count(guard(|| {
+        let cfb = cfb_arg(handle)?;
+        let name = string_arg(name, "name")?;
+        if buf.is_null() && len > 0 {
+            return Err(Failure::invalid("buf is null"));
+        }
+        let buf: &mut [u8] = if len == 0 { &mut [] } else { std::slice::from_raw_parts_mut(buf, len) };
+        let read = cfb.stream_by_name(name).and_then(|stream| stream.read_at(0, buf)).map_err(Failure::from)?;
+        Ok(read as u64)
+    }))
+}
+
+/// Calls the callback with every storage and stream of the compound file, depth first in the order
+/// of the sibling trees, each storage before what it contains, returning 0 or a negative error
+/// code. The root storage isn't listed.
+///
+/// # Safety
+///
+/// `handle` must be a handle returned by [`olekit_open`] and not closed. `user_data` is only
+/// passed to the callback.
+#[no_mangle]
+pub unsafe extern "C" fn olekit_list_entries(handle: *const OlekitCfb,
+                                             callback: OlekitEntryCallback,
+                                             user_data: *mut c_void) -> i64 {
+    count(guard(|| {
+        let cfb = cfb_arg(handle)?;
+        let root_storage = cfb.try_root_storage()?;
+        walk(cfb, Entry::RootStorage(root_storage), &mut |path, entry| {
+            // a name holding NUL can't be passed as a C string, and is passed empty
+            let path = CString::new(path).unwrap_or_default();
+            let name = CString::new(entry.name()).unwrap_or_default();
+            let (object_type, size) = match entry {
+                Entry::Stream(stream) => (ObjectType::Stream, stream.stream_size().0),
+                _ => (ObjectType::Storage, 0),
+            };
+            let entry = OlekitEntry {
+                path: path.as_ptr(),
+                name: name.as_ptr(),
+                object_type: object_type as u8,
+                size,
+            };
+            callback(&entry, user_data);
+            Ok(())
+        })?;
+        Ok(0)
+    }))
+}
+
+/// Returns the message of the last failed call on the calling thread, or null if none failed. The
+/// string is valid until the next failed call on the thread.
+#[no_mangle]
+pub extern "C" fn olekit_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last_error| last_error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Why a call failed, as its error code and message
+struct Failure {
+    code: i64,
+    message: String,
+}
+
+impl Failure {
+    fn invalid(message: &str) -> Self {
+        Self { code: OLEKIT_ERR_INVALID_ARGUMENT, message: message.to_string() }
+    }
+}
+
+impl From<Error> for Failure {
+    fn from(err: Error) -> Self {
+        let code = match &err {
+            Error::Io(_) => OLEKIT_ERR_IO,
+            Error::NotCfb { .. } => OLEKIT_ERR_NOT_CFB,
+            Error::LimitExceeded { .. } => OLEKIT_ERR_LIMIT_EXCEEDED,
+            Error::NotFound { .. } | Error::NoSuchStreamID(_) => OLEKIT_ERR_NOT_FOUND,
+            Error::NotAStream { .. } => OLEKIT_ERR_NOT_A_STREAM,
+            _ => OLEKIT_ERR_CORRUPT,
+        };
+        Self { code, message: err.to_string() }
+    }
+}
+
+/// Runs the body of a call, keeping a panic from unwinding into the caller, and records the
+/// message of a failure for [`olekit_last_error_message`]
+fn guard<T>(body: impl FnOnce() -> Result<T, Failure>) -> Result<T, i64> {
+    let failure = match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(value)) => return Ok(value),
+        Ok(Err(failure)) => failure,
+        Err(_) => Failure { code: OLEKIT_ERR_PANIC, message: "ole_kit panicked".to_string() },
+    };
+    let message = CString::new(failure.message).unwrap_or_default();
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+    Err(failure.code)
+}
+
+/// Returns a count as the result of a call, or its error code
+fn count(result: Result<u64, i64>) -> i64 {
+    result.map_or_else(|code| code, |count| i64::try_from(count).unwrap_or(i64::MAX))
+}
+
+unsafe fn cfb_arg<'a>(handle: *const OlekitCfb) -> Result<&'a Cfb, Failure> {
+    handle.as_ref().map(|handle| &handle.cfb).ok_or_else(|| Failure::invalid("handle is null"))
+}
+
+unsafe fn string_arg<'a>(string: *const c_char, what: &str) -> Result<&'a str, Failure> {
+    if string.is_null() {
+        return Err(Failure::invalid(&format!("{} is null", what)));
+    }
+    CStr::from_ptr(string).to_str().map_err(|_| Failure::invalid(&format!("{} isn't UTF-8", what)))
+}
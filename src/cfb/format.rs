@@ -0,0 +1,172 @@
+use crate::cfb::{Cfb, CompObj, Entry};
+use std::convert::TryFrom;
+
+/// A document type stored in a compound file, as guessed by [`Cfb::detect_format`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum DetectedFormat {
+    /// A Word 97-2003 document
+    WordDoc,
+    /// An Excel 97-2003 workbook
+    Excel97,
+    /// A PowerPoint 97-2003 presentation
+    PowerPoint97,
+    /// A Windows Installer package
+    Msi,
+    /// An Outlook message
+    OutlookMsg,
+    /// A Hangul Word Processor 5.0 document
+    Hwp,
+    /// An Office Open XML document encrypted with Office encryption
+    EncryptedOoxml,
+    /// None of the above
+    Unknown,
+}
+
+/// The result of classifying a compound file, with a confidence from 0 to 100 which is lowered
+/// when signals disagree.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Classification {
+    pub format: DetectedFormat,
+    pub confidence: u8,
+}
+
+/// Builds the bytes of a CLSID as stored in a directory entry from its textual form.
+const fn clsid(data1: u32, data2: u16, data3: u16, data4: [u8; 8]) -> [u8; 16] {
+    let d1 = data1.to_le_bytes();
+    let d2 = data2.to_le_bytes();
+    let d3 = data3.to_le_bytes();
+    [d1[0], d1[1], d1[2], d1[3], d2[0], d2[1], d3[0], d3[1],
+        data4[0], data4[1], data4[2], data4[3], data4[4], data4[5], data4[6], data4[7]]
+}
+
+const OLE_DATA4: [u8; 8] = [0xC0, 0, 0, 0, 0, 0, 0, 0x46];
+
+/// Root storage CLSIDs registered by the applications writing each format.
+const CLSIDS: [([u8; 16], DetectedFormat); 7] = [
+    (clsid(0x00020906, 0, 0, OLE_DATA4), DetectedFormat::WordDoc),
+    (clsid(0x00020900, 0, 0, OLE_DATA4), DetectedFormat::WordDoc),
+    (clsid(0x00020820, 0, 0, OLE_DATA4), DetectedFormat::Excel97),
+    (clsid(0x00020810, 0, 0, OLE_DATA4), DetectedFormat::Excel97),
+    (clsid(0x64818D10, 0x4F9B, 0x11CF, [0x86, 0xEA, 0x00, 0xAA, 0x00, 0xB9, 0x29, 0xE8]), DetectedFormat::PowerPoint97),
+    (clsid(0x000C1084, 0, 0, OLE_DATA4), DetectedFormat::Msi),
+    (clsid(0x00020D0B, 0, 0, OLE_DATA4), DetectedFormat::OutlookMsg),
+];
+
+/// Prefixes of the ProgIDs found in the `\x01CompObj` stream.
+const PROG_IDS: [(&str, DetectedFormat); 4] = [
+    ("Word.Document", DetectedFormat::WordDoc),
+    ("Excel.Sheet", DetectedFormat::Excel97),
+    ("PowerPoint.Show", DetectedFormat::PowerPoint97),
+    ("HWP", DetectedFormat::Hwp),
+];
+
+/// Names of streams of the root storage which only appear in one format.
+const STREAMS: [(&str, DetectedFormat); 6] = [
+    ("WordDocument", DetectedFormat::WordDoc),
+    ("Workbook", DetectedFormat::Excel97),
+    ("Book", DetectedFormat::Excel97),
+    ("PowerPoint Document", DetectedFormat::PowerPoint97),
+    ("__properties_version1.0", DetectedFormat::OutlookMsg),
+    ("FileHeader", DetectedFormat::Hwp),
+];
+
+const CLSID_WEIGHT: u32 = 30;
+const PROG_ID_WEIGHT: u32 = 30;
+const STREAM_WEIGHT: u32 = 60;
+const SIGNATURE_WEIGHT: u32 = 90;
+
+const HWP_SIGNATURE: &[u8] = b"HWP Document File";
+
+/// Tables of a Windows Installer database are stored in streams whose names are compressed into
+/// code points from U+3800 to U+4840, the latter prefixing the name of every table.
+const MSI_TABLE_PREFIX: char = '\u{4840}';
+
+impl Cfb {
+    /// Guesses the type of the document stored in the compound file, see [`Cfb::classify`]
+    pub fn detect_format(&self) -> DetectedFormat {
+        self.classify().format
+    }
+
+    /**
+    Guesses the type of the document stored in the compound file by combining several signals: the
+    streams of the root storage, the CLSID of the root storage, the ProgID stored in the
+    `\x01CompObj` stream and the signature of the `FileHeader` stream of HWP documents.
+
+    The presence of both the `EncryptionInfo` and `EncryptedPackage` streams is decisive, whatever
+    the other signals, since encrypted OOXML documents are wrapped in a compound file that may carry
+    the CLSID of the application which encrypted them. Otherwise each signal votes for a format with
+    a weight, stream names and signatures weighing more than CLSIDs and ProgIDs which are commonly
+    left unset.
+     */
+    pub fn classify(&self) -> Classification {
+        let root_storage = match self.root_storage() {
+            Some(root_storage) => root_storage,
+            None => return Classification { format: DetectedFormat::Unknown, confidence: 0 },
+        };
+        let names: Vec<String> = root_storage.children()
+            .unwrap_or_default()
+            .iter()
+            .map(Entry::name)
+            .collect();
+        let has_stream = |name: &str| names.iter().any(|n| n == name);
+
+        if has_stream("EncryptionInfo") && has_stream("EncryptedPackage") {
+            return Classification { format: DetectedFormat::EncryptedOoxml, confidence: 100 };
+        }
+
+        let mut votes = Vec::new();
+
+        let cls_id = root_storage.cls_id();
+        votes.extend(CLSIDS.iter()
+            .filter(|(clsid, _)| *clsid == cls_id)
+            .map(|(_, format)| (*format, CLSID_WEIGHT)));
+
+        if let Some(prog_id) = self.stream_bytes("\u{1}CompObj").ok().as_deref().and_then(CompObj::parse).and_then(|comp_obj| comp_obj.prog_id) {
+            votes.extend(PROG_IDS.iter()
+                .filter(|(prefix, _)| prog_id.starts_with(prefix))
+                .map(|(_, format)| (*format, PROG_ID_WEIGHT)));
+        }
+
+        votes.extend(STREAMS.iter()
+            .filter(|(name, _)| has_stream(name))
+            .map(|(_, format)| (*format, STREAM_WEIGHT)));
+
+        if names.iter().any(|name| name.starts_with(MSI_TABLE_PREFIX)) {
+            votes.push((DetectedFormat::Msi, STREAM_WEIGHT));
+        }
+
+        if has_stream("FileHeader") {
+            let mut signature = [0u8; HWP_SIGNATURE.len()];
+            let signed = self.open_stream("FileHeader")
+                .map(|mut reader| std::io::Read::read_exact(&mut reader, &mut signature).is_ok())
+                .unwrap_or(false);
+            if signed && signature == HWP_SIGNATURE {
+                votes.push((DetectedFormat::Hwp, SIGNATURE_WEIGHT));
+            }
+        }
+
+        tally(&votes)
+    }
+}
+
+/// Picks the format with the highest total weight, scaling its confidence by its share of all the
+/// votes.
+fn tally(votes: &[(DetectedFormat, u32)]) -> Classification {
+    let mut totals: Vec<(DetectedFormat, u32)> = Vec::new();
+    for (format, weight) in votes {
+        match totals.iter_mut().find(|(f, _)| f == format) {
+            Some((_, total)) => *total += weight,
+            None => totals.push((*format, *weight)),
+        }
+    }
+
+    let all: u32 = totals.iter().map(|(_, total)| total).sum();
+    match totals.into_iter().max_by_key(|(_, total)| *total) {
+        Some((format, total)) => Classification {
+            format,
+            // the share of all the votes is at most 1, so the confidence is at most 100
+            confidence: u8::try_from(total.min(100) * total / all).unwrap_or(100),
+        },
+        None => Classification { format: DetectedFormat::Unknown, confidence: 0 },
+    }
+}
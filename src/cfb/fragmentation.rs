@@ -0,0 +1,125 @@
+use crate::cfb::fat::cache::Cache;
+use crate::cfb::fat::chain::Chain;
+use crate::cfb::storage::walk_unchecked;
+use crate::cfb::{Cfb, Entry, Error, SectorNumber};
+use std::collections::HashSet;
+
+/// The layout of a sector chain in the file, returned by [`Cfb::chain_stats`].
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct ChainStats {
+    /// The number of sectors of the chain.
+    pub length: u64,
+    /// The number of runs of the chain, each sector of a run directly following the previous one in
+    /// the file.
+    pub runs: u64,
+    /// The number of sectors of the longest run.
+    pub longest_run: u64,
+}
+
+impl ChainStats {
+    /// Measures a chain from its sectors, in the order of the chain
+    fn of(sectors: &[SectorNumber]) -> Self {
+        let mut stats = Self::default();
+        let mut run = 0;
+        let mut previous: Option<SectorNumber> = None;
+        for sector in sectors {
+            if previous.is_none_or(|previous| previous.0.checked_add(1) != Some(sector.0)) {
+                stats.runs += 1;
+                run = 0;
+            }
+            run += 1;
+            stats.longest_run = stats.longest_run.max(run);
+            previous = Some(*sector);
+        }
+        stats.length = sectors.len() as u64;
+        stats
+    }
+
+    /// Returns the share of the steps from a sector of the chain to the next which jump elsewhere
+    /// in the file, from 0 for a contiguous chain to 1 for a chain of which no two sectors are
+    /// adjacent. A chain of a single sector, or none, is contiguous.
+    pub fn fragmentation(&self) -> f64 {
+        if self.length <= 1 {
+            return 0.0;
+        }
+        (self.runs - 1) as f64 / (self.length - 1) as f64
+    }
+}
+
+/// The layout of the chain of a stream object, returned by [`Cfb::fragmentation_report`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StreamFragmentation {
+    pub path: String,
+    /// Whether the stream is stored in mini sectors of the mini stream, in which case its chain is
+    /// one of the mini FAT.
+    pub mini: bool,
+    pub stats: ChainStats,
+}
+
+impl Cfb {
+    /// Measures the FAT chain starting at a sector: its length, and how it breaks into runs of
+    /// adjacent sectors, which slow reads down as each run is a seek. Only the FAT is read. The
+    /// chain is cut where it loops back on itself.
+    pub fn chain_stats(&self, start: SectorNumber) -> Result<ChainStats, Error> {
+        Ok(ChainStats::of(&self.sector_chain(start)?))
+    }
+
+    /// Measures the chain of every stream object, in the order of the sibling trees, from the FAT,
+    /// or from the mini FAT for a stream smaller than the cutoff. Only the directory and the
+    /// tables are read.
+    pub fn fragmentation_report(&self) -> Result<Vec<StreamFragmentation>, Error> {
+        let root_storage = match self.root_storage() {
+            Some(root_storage) => root_storage,
+            None => return Ok(Vec::new()),
+        };
+        let mut cache = Cache::new(self);
+        let mut report = Vec::new();
+        walk_unchecked(self, Entry::RootStorage(root_storage), &mut |path, entry| {
+            if let Entry::Stream(stream) = entry {
+                let mini = stream.stream_size().0 < self.mini_stream_cutoff();
+                let sectors = chain_sectors(stream.starting_sector_location(), mini, &mut cache)?;
+                report.push(StreamFragmentation { path: path.to_string(), mini, stats: ChainStats::of(&sectors) });
+            }
+            Ok(())
+        })?;
+        Ok(report)
+    }
+}
+
+/// Returns the sectors, or mini sectors, of a chain, cut where it loops back on itself
+fn chain_sectors(start: SectorNumber, mini: bool, cache: &mut Cache) -> Result<Vec<SectorNumber>, Error> {
+    let mut chain = Chain::new(start, mini);
+    let mut sectors = Vec::new();
+    let mut visited = HashSet::new();
+    while let Some(sector) = chain.get(sectors.len(), cache)? {
+        if !visited.insert(sector) {
+            break;
+        }
+        sectors.push(sector);
+    }
+    Ok(sectors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(sectors: &[u32]) -> ChainStats {
+        ChainStats::of(&sectors.iter().copied().map(SectorNumber).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn runs_of_adjacent_sectors() {
+        assert_eq!(stats(&[]), ChainStats { length: 0, runs: 0, longest_run: 0 });
+        assert_eq!(stats(&[7]), ChainStats { length: 1, runs: 1, longest_run: 1 });
+        assert_eq!(stats(&[3, 4, 5, 6]), ChainStats { length: 4, runs: 1, longest_run: 4 });
+        assert_eq!(stats(&[3, 4, 9, 10, 11, 2]), ChainStats { length: 6, runs: 3, longest_run: 3 });
+        // a chain running backwards is fragmented throughout
+        assert_eq!(stats(&[5, 4, 3]), ChainStats { length: 3, runs: 3, longest_run: 1 });
+
+        assert_eq!(stats(&[7]).fragmentation(), 0.0);
+        assert_eq!(stats(&[3, 4, 5, 6]).fragmentation(), 0.0);
+        assert_eq!(stats(&[3, 4, 9, 10, 11, 2]).fragmentation(), 0.4);
+        assert_eq!(stats(&[5, 4, 3]).fragmentation(), 1.0);
+    }
+}
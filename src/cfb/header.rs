@@ -1,13 +1,21 @@
+#[cfg(feature = "std-fs")]
 use std::fs::File;
-use std::os::unix::fs::FileExt;
 use core::fmt;
-use std::fmt::Formatter;
-use std::mem::transmute;
+use core::fmt::Formatter;
+use core::mem::transmute;
+use core::ops::RangeInclusive;
+use core::convert::TryFrom;
 use crate::cfb::SectorNumber;
+use crate::cfb::Error;
+#[cfg(feature = "std")]
+use crate::cfb::read_at::ReadAt;
+use crate::cfb::warning::Rule;
+#[cfg(feature = "std")]
+use crate::cfb::warning::{Location, Warnings};
 
 macro_rules! read_type {
     ($self:ident, $offset:expr, $type:ident) => {
-        $self.file.read_sized($offset, $type::from_le_bytes)
+        $self.source.read_sized($offset, $type::from_le_bytes)
     }
 }
 
@@ -24,11 +32,80 @@ crate::impl_for_hex_debug!(SectorShift, "4");
 pub struct SectorCount(pub u32);
 crate::impl_for_hex_debug!(SectorCount, "8");
 
+/// A format other than a compound file, recognized by its magic number where the signature of a
+/// compound file was expected, as reported by [`Error::NotCfb`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum ForeignFormat {
+    /// A ZIP archive, such as an Office Open XML document (`.docx`, `.xlsx`, `.pptx`), starting
+    /// with `PK\x03\x04`, or `PK\x05\x06` for an empty archive.
+    Zip,
+    /// A PDF document, starting with `%PDF`.
+    Pdf,
+    /// A plain-text XML document, such as a Word 2003 XML document, starting with `<?xml` after an
+    /// optional UTF-8 byte order mark.
+    Xml,
+    /// A Word for Windows 2.0 document, starting with `\xDB\xA5`.
+    Word2,
+}
+
+impl ForeignFormat {
+    /// Recognizes a format by the first 8 bytes of a file
+    pub(crate) fn sniff(magic: [u8; 8]) -> Option<Self> {
+        let text = magic.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&magic);
+        match magic {
+            [b'P', b'K', 3, 4, ..] | [b'P', b'K', 5, 6, ..] => Some(Self::Zip),
+            [b'%', b'P', b'D', b'F', ..] => Some(Self::Pdf),
+            [0xDB, 0xA5, ..] => Some(Self::Word2),
+            _ if text.starts_with(b"<?xml") => Some(Self::Xml),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ForeignFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Zip => f.write_str("a ZIP archive, such as an Office Open XML (.docx, .xlsx, .pptx) document"),
+            Self::Pdf => f.write_str("a PDF document"),
+            Self::Xml => f.write_str("an XML document"),
+            Self::Word2 => f.write_str("a Word for Windows 2.0 document"),
+        }
+    }
+}
+
 /// The structure at the beginning of a [compound file].
 ///
 /// [compound file]: crate::cfb::Cfb
 pub struct Header<'a> {
-    file: &'a File,
+    source: HeaderSource<'a>,
+}
+
+/// The bytes a [`Header`] is read from: a source backing a compound file, or a byte slice.
+#[derive(Copy, Clone)]
+enum HeaderSource<'a> {
+    #[cfg(feature = "std")]
+    Source(&'a dyn ReadAt),
+    Bytes(&'a [u8]),
+}
+
+impl HeaderSource<'_> {
+    /// Reads a value of a fix-sized data type by the offset and constructor, the bytes past the end
+    /// of the source being zeroes
+    fn read_sized<T, const N: usize>(&self, offset: u64, constructor: impl FnOnce([u8; N]) -> T) -> T {
+        match self {
+            #[cfg(feature = "std")]
+            Self::Source(source) => source.read_sized(offset, constructor),
+            Self::Bytes(bytes) => {
+                let mut array = [0; N];
+                let start = usize::try_from(offset).map_or(bytes.len(), |offset| offset.min(bytes.len()));
+                let available = &bytes[start..];
+                let len = available.len().min(N);
+                array[..len].copy_from_slice(&available[..len]);
+                constructor(array)
+            }
+        }
+    }
 }
 
 impl<'a> Header<'a> {
@@ -36,8 +113,103 @@ impl<'a> Header<'a> {
     const CLSID: u64 = 16;
     const RESERVED: u64 = 6;
 
+    /// The value of the [signature] field of every compound file.
+    ///
+    /// [signature]: Self::signature
+    pub(crate) const MAGIC: u64 = 0xe11ab1a1e011cfd0;
+
+    /// The size in bytes of the header structure, excluding the zero padding of version 4 files.
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) const LENGTH: usize = 512;
+
+    /// The sector shifts which a [lenient](crate::cfb::CfbOpenOptions::lenient) reading accepts, with
+    /// a warning, besides the one of the major version: sectors of 512 bytes to 64 KiB, none smaller
+    /// than the header, which the first sector follows.
+    pub const LENIENT_SECTOR_SHIFTS: RangeInclusive<u16> = 9..=16;
+
+    #[cfg(feature = "std-fs")]
     pub fn new(file: &'a File) -> Self {
-        Self { file }
+        Self::from_source(file)
+    }
+
+    #[cfg(feature = "std")]
+    pub(crate) fn from_source(source: &'a dyn ReadAt) -> Self {
+        Self { source: HeaderSource::Source(source) }
+    }
+
+    /// Reads the header from the start of a byte slice, such as a whole compound file held in
+    /// memory. The fields past the end of the slice read as zeroes.
+    pub fn from_bytes(bytes: &'a [u8]) -> Self {
+        Self { source: HeaderSource::Bytes(bytes) }
+    }
+
+    /// Checks the fields that a reader depends on to locate anything else in the compound file:
+    /// the signature, the byte order mark, and the sector shifts paired with the major version.
+    pub fn validate(&self) -> Result<(), Error> {
+        self.check_fields(false, &mut |_, _, _| {})
+    }
+
+    /// Checks the header as [`validate`](Self::validate) does, except that a lenient check only
+    /// warns of the fields which the reader can do without: the byte order mark, a sector shift
+    /// other than that of the major version within [`LENIENT_SECTOR_SHIFTS`], the mini sector shift
+    /// and the mini stream cutoff size. A minor version other than 0x003E is warned of either way.
+    ///
+    /// [`LENIENT_SECTOR_SHIFTS`]: Self::LENIENT_SECTOR_SHIFTS
+    #[cfg(feature = "std")]
+    pub(crate) fn check(&self, lenient: bool, warnings: &Warnings) -> Result<(), Error> {
+        self.check_fields(lenient, &mut |rule, offset, value| warnings.push(rule, Location::Header { offset }, value))
+    }
+
+    /// Checks the header as [`check`](Self::check) does, passing the rule, the offset and the value
+    /// of each field warned of to `warn`
+    fn check_fields(&self, lenient: bool, warn: &mut dyn FnMut(Rule, u64, u64)) -> Result<(), Error> {
+        let signature = self.signature().0;
+        if signature != Self::MAGIC {
+            return Err(Error::NotCfb { signature, detected: ForeignFormat::sniff(signature.to_le_bytes()) });
+        }
+
+        let minor_version = self.minor_version().0;
+        if minor_version != 0x003E {
+            warn(Rule::MinorVersion, 24, minor_version as u64);
+        }
+
+        let mut tolerate = |rule, offset, field, value: u32| {
+            if lenient {
+                warn(rule, offset, value as u64);
+                Ok(())
+            } else {
+                Err(Error::InvalidHeader { field, value })
+            }
+        };
+
+        let byte_order = self.byte_order();
+        if byte_order != 0xFFFE {
+            tolerate(Rule::ByteOrder, 28, "byte_order", byte_order as u32)?;
+        }
+
+        let major_version = self.major_version().0;
+        let sector_shift = self.sector_shift().0;
+        match (major_version, sector_shift) {
+            (3, 0x0009) | (4, 0x000C) => {}
+            (3, _) | (4, _) if Self::LENIENT_SECTOR_SHIFTS.contains(&sector_shift) =>
+                tolerate(Rule::SectorShift, 30, "sector_shift", sector_shift as u32)?,
+            (3, _) | (4, _) =>
+                return Err(Error::InvalidHeader { field: "sector_shift", value: sector_shift as u32 }),
+            _ =>
+                return Err(Error::InvalidHeader { field: "major_version", value: major_version as u32 }),
+        }
+
+        let mini_sector_shift = self.mini_sector_shift().0;
+        if mini_sector_shift != 0x0006 {
+            tolerate(Rule::MiniSectorShift, 32, "mini_sector_shift", mini_sector_shift as u32)?;
+        }
+
+        let mini_stream_cutoff_size = self.mini_stream_cutoff_size();
+        if lenient && mini_stream_cutoff_size != 0x1000 {
+            tolerate(Rule::MiniStreamCutoff, 56, "mini_stream_cutoff_size", mini_stream_cutoff_size)?;
+        }
+
+        Ok(())
     }
 
     pub fn is_signature_invalid(&self) -> bool {
@@ -80,6 +252,10 @@ impl<'a> Header<'a> {
 
     - If Major Version is 3, the Sector Shift MUST be 0x0009, specifying a sector size of 512 bytes.
     - If Major Version is 4, the Sector Shift MUST be 0x000C, specifying a sector size of 4096 bytes.
+
+    A [lenient](crate::cfb::CfbOpenOptions::lenient) reading accepts any shift within
+    [`LENIENT_SECTOR_SHIFTS`](Self::LENIENT_SECTOR_SHIFTS), as written by some third-party
+    libraries, and derives the sector size from it.
      */
     pub fn sector_shift(&self) -> SectorShift {
         SectorShift(read_type!(self, Self::SIGNATURE + Self::CLSID + 6, u16))
@@ -99,7 +275,7 @@ impl<'a> Header<'a> {
     /// [compound file].
     ///
     /// - If Major Version is 3, the Number of Directory Sectors MUST be zero. This field is not
-    /// supported for version 3 compound files.
+    ///   supported for version 3 compound files.
     ///
     /// [directory]: crate::cfb::directory::Directory
     /// [compound file]: crate::cfb::Cfb
@@ -170,25 +346,24 @@ impl<'a> Header<'a> {
     compound file.
 
     - For version 4 compound files, the header size (512 bytes) is less than the sector size (4,096
-    bytes), so the remaining part of the header (3,584 bytes) MUST be filled with all zeroes.
+      bytes), so the remaining part of the header (3,584 bytes) MUST be filled with all zeroes.
 
     [FAT]: crate::cfb::fat::Fat
      */
     pub(crate) fn difat(&self) -> Difat<109> {
-        let mut bytes = [0u8; 109 * std::mem::size_of::<u32>()];
-        self.file.read_at(&mut bytes, Self::SIGNATURE + Self::CLSID + 10 + Self::RESERVED + 36);
-        Difat(unsafe { transmute(bytes) })
+        self.source.read_sized(Self::SIGNATURE + Self::CLSID + 10 + Self::RESERVED + 36,
+                               |bytes: [u8; 109 * core::mem::size_of::<u32>()]|
+                                   Difat(unsafe { transmute::<[u8; 436], [SectorNumber; 109]>(bytes) }))
     }
 
     /// Returns the sector number of a [FAT] sector by its index in the [DIFAT].
     ///
     /// [FAT]: crate::cfb::fat::Fat
     /// [DIFAT]: self::Difat
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
     pub(crate) fn sector_no_of_fat(&self, index: SectorNumber) -> SectorNumber {
-        SectorNumber(read_type!(self, Self::SIGNATURE + Self::CLSID + 10 + Self::RESERVED + 36 + index.byte_offset(std::mem::size_of::<u32>()), u32))
+        SectorNumber(read_type!(self, Self::SIGNATURE + Self::CLSID + 10 + Self::RESERVED + 36 + index.0 as u64 * core::mem::size_of::<u32>() as u64, u32))
     }
-
-    // TODO: map also the DIFAT sectors outside of the header
 }
 
 /// `double-indirect file allocation table`
@@ -200,8 +375,15 @@ impl<'a> Header<'a> {
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub(crate) struct Difat<const N: usize>([SectorNumber; N]);
 
+impl<const N: usize> Difat<N> {
+    /// Returns the FAT sector locations, including the unused FREESECT entries
+    pub(crate) fn sector_numbers(&self) -> &[SectorNumber; N] {
+        &self.0
+    }
+}
+
 impl<'a> fmt::Debug for Header<'a> {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         let mut fmt = f.debug_map();
         crate::debug_map_method_reflection!(fmt, self,
             signature,
@@ -225,24 +407,17 @@ impl<'a> fmt::Debug for Header<'a> {
     }
 }
 
+#[cfg(feature = "std")]
 pub(crate) trait FileSlice {
-    /// Reads bytes from the file by the offset and size.
-    fn read_bytes(&self, offset: u64, size: usize) -> Vec<u8>;
-
     /// Reads a value of a fix-sized data type from the file by the offset and constructor.
     fn read_sized<T, const N: usize>(&self, offset: u64, constructor: impl FnOnce([u8; N]) -> T) -> T;
 }
 
-impl FileSlice for File {
-    fn read_bytes(&self, offset: u64, size: usize) -> Vec<u8> {
-        let mut bytes = vec![0; size];
-        self.read_at(&mut bytes, offset);
-        bytes
-    }
-
+#[cfg(feature = "std")]
+impl<S: ReadAt + ?Sized> FileSlice for S {
     fn read_sized<T, const N: usize>(&self, offset: u64, constructor: impl FnOnce([u8; N]) -> T) -> T {
         let mut bytes = [0; N];
-        self.read_at(&mut bytes, offset);
+        let _ = self.read_at(&mut bytes, offset);
         constructor(bytes)
     }
 }
@@ -0,0 +1,254 @@
+use crate::cfb::convert::sector_index;
+use crate::cfb::directory::StreamID;
+use crate::cfb::{storage, Cfb, CommonProps, Entry, Error, Limit};
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// The memory held by the name index of a compound file, returned by [`Cfb::index_stats`].
+///
+/// The index holds one record per directory entry slot, and one per allocated entry, so that its
+/// size is bounded by the [directory entry limit].
+///
+/// [`Cfb::index_stats`]: crate::cfb::Cfb::index_stats
+/// [directory entry limit]: crate::cfb::Limits::max_directory_entries
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct IndexStats {
+    /// The number of directory entry slots indexed.
+    pub slots: usize,
+    /// The number of entries reachable from the root storage by their path.
+    pub paths: usize,
+    /// An estimate of the bytes held by the index.
+    pub bytes: usize,
+}
+
+/// An index of the directory entries by their names, so that repeated lookups don't read the names
+/// along the sibling trees again. It is built from a single pass over the directory, and only
+/// covers names and links, which editing metadata never changes.
+pub(crate) struct NameIndex {
+    /// The offset of every directory entry slot by its stream ID.
+    offsets: Vec<u64>,
+    /// The stream ID of every storage, and the root storage, by its offset.
+    storages: HashMap<u64, StreamID>,
+    /// The first child with a name in the sibling tree of a storage, keyed by [`Cfb::name_key`].
+    children: HashMap<(StreamID, String), StreamID>,
+    /// The allocated entries with a name in the order of the directory sectors, keyed by
+    /// [`Cfb::name_key`].
+    by_name: HashMap<String, Vec<StreamID>>,
+    /// The rank in tree order of every entry reachable from the root storage.
+    tree_ranks: HashMap<StreamID, usize>,
+}
+
+impl NameIndex {
+    /// Builds the index over the whole directory, failing if any sibling tree is corrupted, in
+    /// which case lookups fall back to reading the tree so that they fail as they would without
+    /// an index
+    fn build(cfb: &Cfb) -> Result<Self, Error> {
+        let mut index = Self {
+            offsets: Vec::new(),
+            storages: HashMap::new(),
+            children: HashMap::new(),
+            by_name: HashMap::new(),
+            tree_ranks: HashMap::new(),
+        };
+
+        for directory in cfb.directories() {
+            for slot in 0..directory.len() {
+                let id = u32::try_from(index.offsets.len())
+                    .map_err(|_| Error::InvalidDirectory("the directory holds more slots than stream IDs!".to_string()))?;
+                let id = StreamID(id);
+                index.offsets.push(directory.entry_offset(slot));
+                match directory.entry(slot) {
+                    Ok(Entry::Unknown) | Ok(Entry::Malformed { .. }) | Err(_) => {}
+                    Ok(entry) => {
+                        if !matches!(entry, Entry::Stream(_)) {
+                            index.storages.insert(entry.offset(), id);
+                        }
+                        index.by_name.entry(cfb.name_key(&entry.name())).or_default().push(id);
+                    }
+                }
+            }
+        }
+
+        let mut visited = HashSet::new();
+        let mut pending = vec![(StreamID(0), 0)];
+        while let Some((storage, depth)) = pending.pop() {
+            if !visited.insert(storage) {
+                return Err(Error::InvalidDirectory(format!("the storage at stream ID {} is reached twice!", storage.0)));
+            }
+            cfb.limits().check(Limit::TreeDepth, depth + 1)?;
+
+            let storage_entry = index.entry(cfb, storage)?;
+            for child in index.sibling_tree(cfb, storage_entry.child_id())? {
+                let entry = index.entry(cfb, child)?;
+                index.children.entry((storage, cfb.name_key(&entry.name()))).or_insert(child);
+                if !matches!(entry, Entry::Stream(_)) {
+                    pending.push((child, depth + 1));
+                }
+            }
+        }
+
+        let links = |id: StreamID| index.entry(cfb, id).ok()
+            .map(|entry| (entry.left_sibling_id(), entry.right_sibling_id(), entry.child_id()));
+        index.tree_ranks = storage::tree_order(&links);
+        Ok(index)
+    }
+
+    /// Returns the stream IDs of a sibling tree in order, as [`try_children`] does but through the
+    /// offsets of the index
+    ///
+    /// [`try_children`]: crate::cfb::Storage::try_children
+    fn sibling_tree(&self, cfb: &Cfb, mut next: Option<StreamID>) -> Result<Vec<StreamID>, Error> {
+        let mut siblings = Vec::new();
+        let mut visited = HashSet::new();
+        let mut ancestors = Vec::new();
+
+        loop {
+            while let Some(id) = next {
+                if !visited.insert(id) {
+                    return Err(Error::InvalidDirectory(format!("the sibling tree loops at stream ID {}!", id.0)));
+                }
+                next = self.entry(cfb, id)?.left_sibling_id();
+                ancestors.push(id);
+            }
+
+            match ancestors.pop() {
+                Some(id) => {
+                    next = self.entry(cfb, id)?.right_sibling_id();
+                    siblings.push(id);
+                }
+                None => return Ok(siblings),
+            }
+        }
+    }
+
+    /// Gets an allocated entry by its stream ID
+    fn entry<'a>(&self, cfb: &'a Cfb, id: StreamID) -> Result<Entry<'a>, Error> {
+        let offset = self.offsets.get(sector_index(id.0))
+            .ok_or_else(|| Error::InvalidDirectory(format!("malformed link to stream ID {} beyond the directory!", id.0)))?;
+        match Entry::new(*offset, cfb).map_err(Error::InvalidDirectory)? {
+            Entry::Unknown => Err(Error::InvalidDirectory(format!("stream ID {} links to an unallocated entry!", id.0))),
+            entry => Ok(entry),
+        }
+    }
+
+    /// Descends from a storage, or the root storage, through the storages named by the components
+    /// of a path, as [`resolve_components`] does
+    ///
+    /// [`resolve_components`]: crate::cfb::storage::resolve_components
+    pub(crate) fn resolve<'a>(&self, cfb: &'a Cfb, storage: &Entry<'_>, components: &[&str]) -> Result<Option<Entry<'a>>, Error> {
+        let mut id = match self.storages.get(&storage.offset()) {
+            Some(id) => *id,
+            None => return Ok(None),
+        };
+
+        for (depth, name) in components.iter().enumerate() {
+            if depth > 0 && !self.storages.contains_key(&self.offsets[sector_index(id.0)]) {
+                return Ok(None);
+            }
+            cfb.limits().check(Limit::TreeDepth, depth as u64 + 1)?;
+
+            id = match self.children.get(&(id, cfb.name_key(name))) {
+                Some(child) => *child,
+                None => return Ok(None),
+            };
+        }
+
+        self.entry(cfb, id).map(Some)
+    }
+
+    /// Gets the first allocated entry with a name in the order of the directory sectors
+    pub(crate) fn first_by_name<'a>(&self, cfb: &'a Cfb, name: &str) -> Option<Entry<'a>> {
+        self.entry(cfb, *self.by_name.get(&cfb.name_key(name))?.first()?).ok()
+    }
+
+    /// Gets every allocated entry with a name in the order of the directory sectors, passing over
+    /// those which can't be read
+    pub(crate) fn all_by_name<'a>(&self, cfb: &'a Cfb, name: &str) -> Vec<Entry<'a>> {
+        self.by_name.get(&cfb.name_key(name))
+            .map_or_else(Vec::new, |ids| ids.iter().filter_map(|id| self.entry(cfb, *id).ok()).collect())
+    }
+
+    /// Gets every allocated entry with a name in tree order, those not reachable from the root
+    /// storage last, in the order of the directory sectors
+    pub(crate) fn all_by_name_in_tree_order<'a>(&self, cfb: &'a Cfb, name: &str) -> Vec<Entry<'a>> {
+        let mut ids = self.by_name.get(&cfb.name_key(name)).cloned().unwrap_or_default();
+        ids.sort_by_key(|id| self.tree_ranks.get(id).copied().unwrap_or(usize::MAX));
+        ids.into_iter().filter_map(|id| self.entry(cfb, id).ok()).collect()
+    }
+
+    fn stats(&self) -> IndexStats {
+        let id = std::mem::size_of::<StreamID>();
+        let names = |names: &mut dyn Iterator<Item = &String>| names
+            .map(|name| std::mem::size_of::<String>() + name.capacity())
+            .sum::<usize>();
+
+        IndexStats {
+            slots: self.offsets.len(),
+            paths: self.children.len(),
+            bytes: self.offsets.capacity() * std::mem::size_of::<u64>()
+                + self.storages.capacity() * (std::mem::size_of::<u64>() + id)
+                + self.children.capacity() * 2 * id + names(&mut self.children.keys().map(|(_, name)| name))
+                + self.by_name.values().map(|ids| ids.capacity() * id).sum::<usize>()
+                + self.by_name.capacity() * std::mem::size_of::<Vec<StreamID>>() + names(&mut self.by_name.keys())
+                + self.tree_ranks.capacity() * (id + std::mem::size_of::<usize>()),
+        }
+    }
+}
+
+/// The name index shared by the clones of a compound file, built on the first lookup once enabled.
+#[derive(Default)]
+pub(crate) struct SharedIndex {
+    enabled: AtomicBool,
+    index: OnceLock<Option<NameIndex>>,
+}
+
+impl SharedIndex {
+    pub(crate) fn enable(&self) {
+        self.enabled.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns an index enabled alike, yet to be built
+    pub(crate) fn unbuilt(&self) -> Self {
+        Self { enabled: AtomicBool::new(self.enabled.load(Ordering::Relaxed)), index: OnceLock::new() }
+    }
+
+    /// Returns the index, building it if enabled, or None if not enabled or the directory is
+    /// corrupted
+    pub(crate) fn get(&self, cfb: &Cfb) -> Option<&NameIndex> {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return None;
+        }
+        self.index.get_or_init(|| NameIndex::build(cfb).ok()).as_ref()
+    }
+}
+
+impl Cfb {
+    /// Builds the name index of the compound file, so that the lookups by path or by name which
+    /// follow, through this compound file, its clones, or its storage handles, don't read the
+    /// names along the sibling trees again. Opening with [`CfbOpenOptions::name_index`] builds it
+    /// on the first lookup instead.
+    ///
+    /// Building reads the whole directory once, and fails if any sibling tree is corrupted, in
+    /// which case lookups keep reading the trees.
+    ///
+    /// [`CfbOpenOptions::name_index`]: crate::cfb::CfbOpenOptions::name_index
+    pub fn build_index(&self) -> Result<(), Error> {
+        self.name_index.enable();
+        if self.name_index.get(self).is_some() {
+            return Ok(());
+        }
+        NameIndex::build(self).map(|_| ())
+    }
+
+    /// Returns the memory held by the name index, or None if it isn't built
+    pub fn index_stats(&self) -> Option<IndexStats> {
+        self.name_index.index.get()?.as_ref().map(NameIndex::stats)
+    }
+
+    /// Returns the name index, building it if enabled
+    pub(crate) fn index(&self) -> Option<&NameIndex> {
+        self.name_index.get(self)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::cfb::name::display_name;
+use crate::cfb::storage::walk_named;
+use crate::cfb::{Cfb, Entry, Error};
+
+impl Cfb {
+    /// Returns the path of every stream object, in the order of the sibling trees, each storage
+    /// before what it contains. The names are [escaped for display](display_name), so the paths are
+    /// meant for reading, logging or matching rather than for opening the streams. Only the
+    /// directory is read.
+    pub fn stream_names(&self) -> Result<Vec<String>, Error> {
+        Ok(self.stream_names_with_sizes()?.into_iter().map(|(path, _)| path).collect())
+    }
+
+    /// Returns the path of every stream object with its size in bytes, see
+    /// [`stream_names`](Self::stream_names)
+    pub fn stream_names_with_sizes(&self) -> Result<Vec<(String, u64)>, Error> {
+        let mut names = Vec::new();
+        self.walk_display(&mut |path, entry| {
+            if let Entry::Stream(stream) = entry {
+                names.push((path.to_string(), stream.stream_size().0));
+            }
+        })?;
+        Ok(names)
+    }
+
+    /// Returns the path of every storage object and stream object, not including the root
+    /// storage, see [`stream_names`](Self::stream_names)
+    pub fn entry_names(&self) -> Result<Vec<String>, Error> {
+        let mut names = Vec::new();
+        self.walk_display(&mut |path, _| names.push(path.to_string()))?;
+        Ok(names)
+    }
+
+    fn walk_display(&self, visit: &mut dyn FnMut(&str, &Entry)) -> Result<(), Error> {
+        let root_storage = self.try_root_storage()?;
+        walk_named(self, Entry::RootStorage(root_storage), &display_name, &mut |path, entry| {
+            visit(path, entry);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,139 @@
+//! The comparison of directory entry names, which orders the red-black tree of the siblings of a
+//! storage, and their escaping for display.
+//!
+//! Names are compared by length first, then code unit by code unit after an uppercase mapping. The
+//! mapping is the simple case conversion of the Unicode Default Case Conversion Algorithm, applied
+//! to each UTF-16 code unit on its own, as Windows does, so it diverges from [`str::to_uppercase`]:
+//!
+//! - characters whose uppercase expands into several, such as `ß` into `SS`, are left unchanged;
+//! - characters outside the Basic Multilingual Plane, encoded as surrogate pairs, are left
+//!   unchanged, even where Unicode defines an uppercase for them;
+//! - the length compared is the number of UTF-16 code units, so a character outside the Basic
+//!   Multilingual Plane counts twice.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// Maps a UTF-16 code unit to its uppercase as the specification does, leaving surrogates and
+/// characters whose uppercase isn't a single code unit unchanged
+pub fn uppercase(unit: u16) -> u16 {
+    let c = match char::from_u32(unit as u32) {
+        Some(c) => c,
+        None => return unit,
+    };
+
+    let mut upper = c.to_uppercase();
+    match (upper.next(), upper.next()) {
+        (Some(upper), None) if (upper as u32) <= 0xFFFF => upper as u16,
+        _ => unit,
+    }
+}
+
+/// Escapes a name for display within a path, so that it can't be mistaken for several components
+/// nor garble a terminal: `\` becomes `\\`, and `/` and control characters, such as the `\u{5}`
+/// starting the names of property set streams, become Rust's `\u{..}` escapes.
+pub fn display_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '/' => escaped.push_str("\\u{2f}"),
+            c if c.is_control() => escaped.extend(c.escape_unicode()),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Compares two names encoded in UTF-16, without their terminating null character, in the order of
+/// the red-black tree of siblings: the shorter name first, then the first code unit differing
+/// after the [uppercase mapping](uppercase)
+pub fn cmp_names(a: &[u16], b: &[u16]) -> Ordering {
+    a.len().cmp(&b.len()).then_with(|| {
+        a.iter().map(|unit| uppercase(*unit)).cmp(b.iter().map(|unit| uppercase(*unit)))
+    })
+}
+
+/// Compares two names in the order of the red-black tree of siblings, see [`cmp_names`]
+pub fn cmp_names_str(a: &str, b: &str) -> Ordering {
+    let a: Vec<u16> = a.encode_utf16().collect();
+    let b: Vec<u16> = b.encode_utf16().collect();
+    cmp_names(&a, &b)
+}
+
+/// Maps a name to its code units after the [uppercase mapping](uppercase), so that two names are
+/// equal under [`cmp_names`] if and only if their keys are equal
+#[cfg(feature = "std")]
+pub(crate) fn name_key(name: &str) -> String {
+    let units: Vec<u16> = name.encode_utf16().map(uppercase).collect();
+    // the mapping leaves surrogates unchanged, so the units stay valid UTF-16
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uppercase_mapping() {
+        let cases = [
+            ('a', 'A'),
+            ('Z', 'Z'),
+            ('1', '1'),
+            ('é', 'É'),
+            ('ÿ', 'Ÿ'),
+            ('ω', 'Ω'),
+            ('ж', 'Ж'),
+            // the uppercase of titlecase digraphs is a single character
+            ('ǅ', 'Ǆ'),
+            // expansions are left unchanged, where to_uppercase gives "SS", "FF" and "ʼN"
+            ('ß', 'ß'),
+            ('ﬀ', 'ﬀ'),
+            ('ŉ', 'ŉ'),
+        ];
+        for (c, expected) in cases {
+            assert_eq!(uppercase(c as u16), expected as u16, "{}", c);
+        }
+
+        // the surrogates of a character outside the Basic Multilingual Plane are left unchanged
+        let deseret: Vec<u16> = "\u{10428}".encode_utf16().collect();
+        assert_eq!(deseret.iter().map(|unit| uppercase(*unit)).collect::<Vec<_>>(), deseret);
+    }
+
+    #[test]
+    fn display_names() {
+        let cases = [
+            ("WordDocument", "WordDocument"),
+            ("\u{5}SummaryInformation", "\\u{5}SummaryInformation"),
+            ("\u{1}CompObj", "\\u{1}CompObj"),
+            ("a/b", "a\\u{2f}b"),
+            ("a\\b", "a\\\\b"),
+            ("Ünïcödé 名前", "Ünïcödé 名前"),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(display_name(name), expected);
+        }
+    }
+
+    #[test]
+    fn name_ordering() {
+        let cases = [
+            ("B", "AA", Ordering::Less),
+            ("abc", "ABC", Ordering::Equal),
+            ("abc", "ABD", Ordering::Less),
+            ("WordDocument", "1Table", Ordering::Greater),
+            ("Data", "data", Ordering::Equal),
+            ("straße", "STRASSE", Ordering::Less),
+            ("straße", "STRAßE", Ordering::Equal),
+            // a character outside the Basic Multilingual Plane counts as two code units
+            ("\u{10428}", "AB", Ordering::Greater),
+            ("\u{10428}", "\u{10400}", Ordering::Greater),
+            ("\u{5}SummaryInformation", "\u{5}summaryinformation", Ordering::Equal),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(cmp_names_str(a, b), expected, "{:?} {:?}", a, b);
+            assert_eq!(cmp_names_str(b, a), expected.reverse(), "{:?} {:?}", b, a);
+        }
+    }
+}
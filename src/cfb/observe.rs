@@ -0,0 +1,124 @@
+use crate::cfb::header::Header;
+use crate::cfb::read_at::{ReadAt, SharedSource};
+use crate::cfb::SectorNumber;
+use core::fmt;
+use std::collections::BTreeSet;
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+use std::sync::{Arc, Mutex};
+
+/// A positioned read of the source of a compound file, reported to an [`IoObserver`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ObservedRead {
+    /// The offset of the read from the start of the compound file.
+    pub offset: u64,
+    /// The number of bytes asked for.
+    pub len: usize,
+    /// The number of bytes read, or None if the read failed.
+    pub read: Option<usize>,
+}
+
+/// An observer of every read a compound file makes of its source, set with
+/// [`CfbOpenOptions::instrument`], for measuring how many reads an operation performs.
+///
+/// The observer is called on the thread which reads, after each read, and is shared with the
+/// clones of the compound file and the readers created from it.
+///
+/// [`CfbOpenOptions::instrument`]: crate::cfb::CfbOpenOptions::instrument
+pub trait IoObserver: Send + Sync {
+    fn on_read(&self, read: &ObservedRead);
+}
+
+/// An [`IoObserver`] recording every read, which counts them and tells the sectors they touched.
+#[derive(Debug, Default)]
+pub struct SectorReadCounter {
+    reads: Mutex<Vec<ObservedRead>>,
+}
+
+impl SectorReadCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the number of reads recorded
+    pub fn read_count(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// Returns the number of bytes asked for by the reads recorded
+    pub fn bytes_read(&self) -> u64 {
+        self.lock().iter().map(|read| read.len as u64).sum()
+    }
+
+    /// Returns the reads recorded, clearing them, so that the reads of the next operation can be
+    /// told apart
+    pub fn take_reads(&self) -> Vec<ObservedRead> {
+        std::mem::take(&mut *self.lock())
+    }
+
+    /// Returns the sectors touched by the reads recorded, in order, for a compound file of the
+    /// sector size. Reads within the header, which precedes sector 0, touch no sector.
+    pub fn sectors(&self, sector_size: u32) -> BTreeSet<SectorNumber> {
+        let sector_size = u64::from(sector_size);
+        let mut sectors = BTreeSet::new();
+        for read in self.lock().iter().filter(|read| read.len > 0) {
+            let first = read.offset.max(Header::LENGTH as u64) / sector_size;
+            let last = (read.offset + read.len as u64 - 1) / sector_size;
+            // the offset of sector n is (n + 1) times the sector size
+            sectors.extend((first..=last).filter(|index| *index > 0)
+                .filter_map(|index| u32::try_from(index - 1).ok())
+                .map(SectorNumber));
+        }
+        sectors
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<ObservedRead>> {
+        self.reads.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl IoObserver for SectorReadCounter {
+    fn on_read(&self, read: &ObservedRead) {
+        self.lock().push(*read);
+    }
+}
+
+/// An observer set on the [options](crate::cfb::CfbOpenOptions), which can't derive [`Debug`]
+/// through the trait object.
+#[derive(Clone)]
+pub(crate) struct Instrument(pub(crate) Arc<dyn IoObserver>);
+
+impl fmt::Debug for Instrument {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("IoObserver")
+    }
+}
+
+/// A source reporting every read of another source to an observer.
+pub(crate) struct ObservedSource {
+    source: SharedSource,
+    observer: Arc<dyn IoObserver>,
+}
+
+impl ObservedSource {
+    pub(crate) fn new(source: SharedSource, observer: Arc<dyn IoObserver>) -> Self {
+        Self { source, observer }
+    }
+}
+
+impl ReadAt for ObservedSource {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let result = self.source.read_at(buf, offset);
+        self.observer.on_read(&ObservedRead { offset, len: buf.len(), read: result.as_ref().ok().copied() });
+        result
+    }
+
+    fn try_clone(&self) -> std::io::Result<Option<SharedSource>> {
+        Ok(self.source.try_clone()?
+            .map(|source| Arc::new(ObservedSource::new(source, self.observer.clone())) as SharedSource))
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.source.size()
+    }
+}
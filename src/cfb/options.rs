@@ -0,0 +1,261 @@
+use crate::cfb::observe::{Instrument, IoObserver, ObservedSource};
+use crate::cfb::read_at::{ReadAt, SeekReader, SharedSource, Window};
+use crate::cfb::reader::MINI_SECTOR_SIZE;
+#[cfg(feature = "std-fs")]
+use crate::cfb::open_file;
+use crate::cfb::{Cfb, Error};
+use core::fmt;
+use std::fmt::Formatter;
+#[cfg(feature = "std-fs")]
+use std::fs::File;
+use std::io::{Read, Seek};
+#[cfg(feature = "std-fs")]
+use std::path::Path;
+use std::sync::Arc;
+
+/// A resource which reading a compound file may exhaust, bounded by a [limit].
+///
+/// [limit]: Limits
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Limit {
+    /// The bytes read from the source for a single stream.
+    BytesRead,
+    /// The sectors of a single chain.
+    ChainLength,
+    /// The directory entries reachable by stream ID.
+    DirectoryEntries,
+    /// The storages descended through when resolving a path.
+    TreeDepth,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::BytesRead => "bytes read",
+            Self::ChainLength => "chain length",
+            Self::DirectoryEntries => "directory entries",
+            Self::TreeDepth => "tree depth",
+        })
+    }
+}
+
+/// The ceilings on the resources spent reading a compound file, so that a hostile file can't make
+/// an operation run away. A limit tripping fails the operation with [`Error::LimitExceeded`].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Limits {
+    /// The maximum number of bytes read for a single stream.
+    pub max_bytes_read: u64,
+    /// The maximum number of sectors, or mini sectors, of a single chain.
+    pub max_chain_length: u64,
+    /// The maximum number of directory entries, bounding the stream IDs that can be reached.
+    pub max_directory_entries: u64,
+    /// The maximum number of storages descended through when resolving a path.
+    pub max_tree_depth: u64,
+}
+
+impl Limits {
+    /// The deepest nesting of storages accepted by default.
+    pub const DEFAULT_TREE_DEPTH: u64 = 256;
+
+    /// Derives the limits from the size of the source: no stream holds more bytes than the file, no
+    /// chain has more sectors than the file has mini sectors and no directory more entries than fit
+    /// in it. Without a known size, only the tree depth is bounded.
+    pub fn for_size(size: Option<u64>) -> Self {
+        match size {
+            Some(size) => Self {
+                max_bytes_read: size,
+                max_chain_length: size / MINI_SECTOR_SIZE,
+                max_directory_entries: size / 128,
+                max_tree_depth: Self::DEFAULT_TREE_DEPTH,
+            },
+            None => Self {
+                max_bytes_read: u64::MAX,
+                max_chain_length: u64::MAX,
+                max_directory_entries: u64::MAX,
+                max_tree_depth: Self::DEFAULT_TREE_DEPTH,
+            },
+        }
+    }
+
+    /// Fails with [`Error::LimitExceeded`] if an observed amount of a resource exceeds its limit
+    pub(crate) fn check(&self, which: Limit, observed: u64) -> Result<(), Error> {
+        let limit = match which {
+            Limit::BytesRead => self.max_bytes_read,
+            Limit::ChainLength => self.max_chain_length,
+            Limit::DirectoryEntries => self.max_directory_entries,
+            Limit::TreeDepth => self.max_tree_depth,
+        };
+
+        if observed > limit {
+            Err(Error::LimitExceeded { which, limit, observed })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/**
+Options for opening a compound file, such as the [limits] on the resources spent reading it.
+
+```no_run
+use ole_kit::cfb::CfbOpenOptions;
+
+let cfb = CfbOpenOptions::new()
+    .max_bytes_read(50 << 20)
+    .open("untrusted.doc")?;
+let word_document = cfb.stream_bytes("WordDocument")?;
+# Ok::<(), ole_kit::cfb::Error>(())
+```
+
+[limits]: Limits
+ */
+#[derive(Debug, Clone, Default)]
+pub struct CfbOpenOptions {
+    max_bytes_read: Option<u64>,
+    max_chain_length: Option<u64>,
+    max_directory_entries: Option<u64>,
+    max_tree_depth: Option<u64>,
+    name_index: bool,
+    lenient: bool,
+    exact_names: bool,
+    instrument: Option<Instrument>,
+}
+
+impl CfbOpenOptions {
+    /// Creates options whose limits are derived from the size of the file, see [`Limits::for_size`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of bytes read for a single stream
+    pub fn max_bytes_read(&mut self, limit: u64) -> &mut Self {
+        self.max_bytes_read = Some(limit);
+        self
+    }
+
+    /// Sets the maximum number of sectors of a single chain
+    pub fn max_chain_length(&mut self, limit: u64) -> &mut Self {
+        self.max_chain_length = Some(limit);
+        self
+    }
+
+    /// Sets the maximum number of directory entries, bounding the stream IDs that can be reached
+    pub fn max_directory_entries(&mut self, limit: u64) -> &mut Self {
+        self.max_directory_entries = Some(limit);
+        self
+    }
+
+    /// Sets the maximum number of storages descended through when resolving a path
+    pub fn max_tree_depth(&mut self, limit: u64) -> &mut Self {
+        self.max_tree_depth = Some(limit);
+        self
+    }
+
+    /// Sets whether to build the [name index](Cfb::build_index) on the first lookup by path or by
+    /// name, which pays off when looking up many entries of the same file. Off by default, so that
+    /// a single lookup only reads the directory entries along its path.
+    pub fn name_index(&mut self, enabled: bool) -> &mut Self {
+        self.name_index = enabled;
+        self
+    }
+
+    /// Sets whether to tolerate the deviations from the specification which reading can do
+    /// without, such as a wrong byte order mark, warning of them through [`Cfb::warnings`] instead
+    /// of failing. Off by default.
+    pub fn lenient(&mut self, enabled: bool) -> &mut Self {
+        self.lenient = enabled;
+        self
+    }
+
+    /// Sets whether lookups by name or by path compare names exactly, code unit by code unit,
+    /// instead of case-insensitively under the uppercase mapping of the sibling tree, which the
+    /// specification mandates. Off by default, so that `worddocument` finds `WordDocument`.
+    pub fn exact_names(&mut self, enabled: bool) -> &mut Self {
+        self.exact_names = enabled;
+        self
+    }
+
+    /// Sets an observer called after every read of the source, such as a [`SectorReadCounter`],
+    /// for measuring how many reads the operations on the compound file perform
+    ///
+    /// [`SectorReadCounter`]: crate::cfb::SectorReadCounter
+    pub fn instrument(&mut self, observer: Arc<dyn IoObserver>) -> &mut Self {
+        self.instrument = Some(Instrument(observer));
+        self
+    }
+
+    /// Opens the compound file at the path
+    #[cfg(feature = "std-fs")]
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> Result<Cfb, Error> {
+        let path = path.as_ref();
+        Ok(self.open_file(open_file(path)?)?.with_path(path))
+    }
+
+    /// Opens the compound file read from an open file, see [`Cfb::from_file`]
+    #[cfg(feature = "std-fs")]
+    pub fn open_file(&self, file: File) -> Result<Cfb, Error> {
+        self.open_source(Arc::new(file))
+    }
+
+    /// Opens the compound file at the path by mapping it into memory, see [`Cfb::from_path_mmap`]
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated while the compound file or any handle created
+    /// from it is alive.
+    #[cfg(feature = "mmap")]
+    pub unsafe fn open_mmap<P: AsRef<Path>>(&self, path: P) -> Result<Cfb, Error> {
+        let path = path.as_ref();
+        Ok(self.open_source(Arc::new(memmap2::Mmap::map(&open_file(path)?)?))?.with_path(path))
+    }
+
+    /// Opens the compound file stored in the file at the path, starting at the byte offset where the
+    /// compound file is stored. If `len` is given, reads are clamped to `offset + len`.
+    #[cfg(feature = "std-fs")]
+    pub fn open_at<P: AsRef<Path>>(&self, path: P, offset: u64, len: Option<u64>) -> Result<Cfb, Error> {
+        let path = path.as_ref();
+        Ok(self.open_source(Arc::new(Window::new(open_file(path)?, offset, len)))?.with_path(path))
+    }
+
+    /// Opens the compound file read from the reader, starting at the byte offset where the compound
+    /// file is stored. If `len` is given, reads are clamped to `offset + len`.
+    pub fn open_reader_at<R: Read + Seek + Send + 'static>(&self, reader: R, offset: u64, len: Option<u64>) -> Result<Cfb, Error> {
+        self.open_source(Arc::new(Window::new(SeekReader::new(reader), offset, len)))
+    }
+
+    /// Opens the compound file held in memory
+    #[cfg(feature = "alloc-backend")]
+    pub fn open_bytes(&self, bytes: Vec<u8>) -> Result<Cfb, Error> {
+        self.open_source(Arc::new(bytes))
+    }
+
+    /// Opens the compound file read from a custom [source](ReadAt), starting at the byte offset
+    /// where the compound file is stored. If `len` is given, reads are clamped to `offset + len`.
+    pub fn open_read_at<S: ReadAt + Send + Sync + 'static>(&self, source: S, offset: u64, len: Option<u64>) -> Result<Cfb, Error> {
+        self.open_source(Arc::new(Window::new(source, offset, len)))
+    }
+
+    /// Opens the compound file over a source, overriding the limits derived from its size with the
+    /// ones set
+    pub(crate) fn open_source(&self, source: SharedSource) -> Result<Cfb, Error> {
+        let source = match &self.instrument {
+            Some(Instrument(observer)) => Arc::new(ObservedSource::new(source, observer.clone())),
+            None => source,
+        };
+        let mut cfb = Cfb::from_source_checked(source, self.lenient)?;
+
+        let defaults = cfb.limits;
+        cfb.limits = Limits {
+            max_bytes_read: self.max_bytes_read.unwrap_or(defaults.max_bytes_read),
+            max_chain_length: self.max_chain_length.unwrap_or(defaults.max_chain_length),
+            max_directory_entries: self.max_directory_entries.unwrap_or(defaults.max_directory_entries),
+            max_tree_depth: self.max_tree_depth.unwrap_or(defaults.max_tree_depth),
+        };
+        cfb.exact_names = self.exact_names;
+        if self.name_index {
+            cfb.name_index.enable();
+        }
+        Ok(cfb)
+    }
+}
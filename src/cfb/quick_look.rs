@@ -0,0 +1,185 @@
+use crate::cfb::convert::{entries_per_sector, to_usize_saturating};
+use crate::cfb::directory::StreamID;
+use crate::cfb::header::Header;
+use crate::cfb::read_at::{ReadAhead, ReadAt};
+use crate::cfb::warning::Warnings;
+use crate::cfb::{Cfb, CommonProps, Entry, Error};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Names of the root storage objects holding a VBA project: `Macros` in Word documents,
+/// `_VBA_PROJECT_CUR` in Excel workbooks, and `VBA` in a standalone project.
+const MACRO_NAMES: [&str; 3] = ["Macros", "_VBA_PROJECT_CUR", "VBA"];
+
+/// Names of the root streams of an Office Open XML package encrypted with Office encryption.
+const ENCRYPTION_NAMES: [&str; 2] = ["EncryptionInfo", "EncryptedPackage"];
+
+/// Whether something was found in a compound file, or unknown when the answer lies beyond what was
+/// read.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum Presence {
+    Present,
+    Absent,
+    Unknown,
+}
+
+/// The answers of [`Cfb::quick_look`], gathered from the header and the first directory sector.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct QuickLook {
+    pub major_version: u16,
+    pub minor_version: u16,
+    pub sector_size: u32,
+    /// The number of FAT sectors declared by the header.
+    pub fat_sectors: u32,
+    /// The number of directory sectors declared by the header, which version 3 leaves as None.
+    pub directory_sectors: Option<u32>,
+    /// The number of mini FAT sectors declared by the header.
+    pub mini_fat_sectors: u32,
+    /// The number of DIFAT sectors declared by the header.
+    pub difat_sectors: u32,
+    /// The object class GUID of the root storage, or None if the root storage isn't in what was read.
+    pub root_clsid: Option<[u8; 16]>,
+    /// The names of the objects directly contained in the root storage which lie in the first
+    /// directory sector, in the order of the sibling tree, up to the number asked for.
+    pub root_entries: Vec<String>,
+    /// Whether [`root_entries`](Self::root_entries) names every object of the root storage.
+    pub root_entries_complete: bool,
+    /// Whether the root storage holds a VBA project, as a `Macros`, `_VBA_PROJECT_CUR` or `VBA`
+    /// storage.
+    pub macros: Presence,
+    /// Whether the root storage holds the `EncryptionInfo` or `EncryptedPackage` stream of an
+    /// encrypted Office Open XML package. Encryption within the streams of the binary formats, such
+    /// as the flag of a Word document, can't be told without reading them and isn't looked for.
+    pub encrypted_package: Presence,
+    /// The number of reads made of the source, at most [`QuickLook::READ_BUDGET`].
+    pub reads: u32,
+    /// The number of bytes read from the source.
+    pub bytes_read: u64,
+}
+
+impl QuickLook {
+    /// The most reads [`Cfb::quick_look`] makes: one of the header and one of the first directory
+    /// sector.
+    pub const READ_BUDGET: u32 = 2;
+}
+
+impl Cfb {
+    /**
+    Answers the common questions of triage, for many files at a time, with at most
+    [`QuickLook::READ_BUDGET`] reads of the source: the version and the counts declared by the
+    header, the CLSID of the root storage, the names of up to `max_entries` objects of the root
+    storage, and whether the file holds macros or an encrypted package.
+
+    Only the header and the first directory sector are read. No chain is followed, nor any stream
+    read, so the objects of the root storage linked to entries beyond the first directory sector are
+    left out, and a question whose answer may lie there is answered [`Presence::Unknown`]. A file
+    truncated within the first directory sector gives fewer answers rather than an error.
+
+    Fails only if reading the header fails.
+     */
+    pub fn quick_look(&self, max_entries: usize) -> Result<QuickLook, Error> {
+        let source = ReadAhead::new(self.source.size());
+        let mut reads = 0;
+        let mut bytes_read = 0;
+
+        let mut header = vec![0; Header::LENGTH];
+        reads += 1;
+        let read = self.source.read_at(&mut header, 0)?;
+        header.truncate(read);
+        bytes_read += read as u64;
+        source.insert(0, header);
+
+        let first_directory_sector = Header::from_source(&source).first_directory_sector_location();
+        if let Some(offset) = (first_directory_sector + 1).checked_byte_offset(self.sector_size as u64) {
+            let mut sector = vec![0; to_usize_saturating(self.sector_size as u64)];
+            reads += 1;
+            // a failed read leaves the directory out, as a truncated file does
+            if let Ok(read) = self.source.read_at(&mut sector, offset) {
+                sector.truncate(read);
+                bytes_read += read as u64;
+                source.insert(offset, sector);
+            }
+        }
+
+        // a compound file over what was read, whose reads of anything else fail, and whose directory
+        // sector cut short is read leniently
+        let view = Cfb {
+            source: Arc::new(source),
+            lenient: true,
+            name_index: Arc::default(),
+            warnings: Warnings::default(),
+            first_overlap: Arc::default(),
+            fats: Arc::default(),
+            ..self.clone()
+        };
+        let header = view.header();
+        let root_storage = view.root_storage();
+        let (names, complete) = match &root_storage {
+            Some(root_storage) => root_names(&view, root_storage.child_id()),
+            None => (Vec::new(), false),
+        };
+        let presence = |candidates: &[&str]| {
+            if names.iter().any(|name| candidates.iter().any(|candidate| name.eq_ignore_ascii_case(candidate))) {
+                Presence::Present
+            } else if complete {
+                Presence::Absent
+            } else {
+                Presence::Unknown
+            }
+        };
+
+        Ok(QuickLook {
+            major_version: header.major_version().0,
+            minor_version: header.minor_version().0,
+            sector_size: self.sector_size,
+            fat_sectors: header.no_of_fat_sectors().0,
+            directory_sectors: header.no_of_directory_sectors().map(|count| count.0),
+            mini_fat_sectors: header.no_of_mini_fat_sectors().0,
+            difat_sectors: header.no_of_difat_sectors().0,
+            root_clsid: root_storage.as_ref().map(|root_storage| root_storage.cls_id()),
+            macros: presence(&MACRO_NAMES),
+            encrypted_package: presence(&ENCRYPTION_NAMES),
+            root_entries_complete: complete && names.len() <= max_entries,
+            root_entries: names.into_iter().take(max_entries).collect(),
+            reads,
+            bytes_read,
+        })
+    }
+}
+
+/// Returns the names of the sibling tree rooted at a child of the root storage in its order, as
+/// far as it lies in the first directory sector, and whether all of it does
+fn root_names(view: &Cfb, child_id: Option<StreamID>) -> (Vec<String>, bool) {
+    let entries_per_sector = entries_per_sector(view.sector_size, Entry::LENGTH);
+    let mut names = Vec::new();
+    let mut complete = true;
+    let mut visited = HashSet::new();
+    let mut ancestors = Vec::new();
+    let mut next = child_id;
+    // read slot by slot, so that a sector cut short yields the slots before the cut
+    let directory = view.directory(0);
+
+    loop {
+        while let Some(id) = next {
+            next = None;
+            let entry = match &directory {
+                Some(directory) if id.0 < entries_per_sector && visited.insert(id) => directory.entry(id.0).ok(),
+                _ => None,
+            };
+            match entry {
+                Some(entry @ (Entry::Storage(_) | Entry::Stream(_))) => {
+                    next = entry.left_sibling_id();
+                    ancestors.push(entry);
+                }
+                _ => complete = false,
+            }
+        }
+        match ancestors.pop() {
+            Some(entry) => {
+                names.push(entry.name());
+                next = entry.right_sibling_id();
+            }
+            None => return (names, complete),
+        }
+    }
+}
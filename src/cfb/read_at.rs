@@ -0,0 +1,444 @@
+#[cfg(feature = "std-fs")]
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+use crate::cfb::convert::to_usize_saturating;
+use crate::cfb::StreamReader;
+
+/// A source shared between a compound file and the handles created from it.
+pub type SharedSource = Arc<dyn ReadAt + Send + Sync>;
+
+/// A source of bytes that can be read at arbitrary positions, backing a [compound file].
+///
+/// It is implemented for [`File`], byte slices and vectors, and memory maps with the `mmap`
+/// feature, and can be implemented for any other container with random access, such as an
+/// encrypted one decrypting on the fly, to open it with [`Cfb::from_read_at`].
+///
+/// A source is shared by a compound file, its clones and the stream readers created from it, which
+/// may read it concurrently from several threads through a shared reference, hence it must be
+/// [`Send`] and [`Sync`]. Reads must not depend on each other: nothing is assumed about the order
+/// in which they happen, and reading the same range twice must give the same bytes. A source
+/// needing a cursor can serialize its reads behind a lock, as [`Cfb::from_reader_at`] does.
+///
+/// [compound file]: crate::cfb::Cfb
+/// [`Cfb::from_read_at`]: crate::cfb::Cfb::from_read_at
+/// [`Cfb::from_reader_at`]: crate::cfb::Cfb::from_reader_at
+pub trait ReadAt {
+    /// Reads bytes starting at `offset` into `buf`, returning the number of bytes read. The count
+    /// is only short of `buf.len()` when the end of the source is reached.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize>;
+
+    /// Duplicates the underlying handle, so that the duplicate can be read without contending with
+    /// the original. Returns None for a source which is better shared, such as a buffer in memory.
+    fn try_clone(&self) -> std::io::Result<Option<SharedSource>> {
+        Ok(None)
+    }
+
+    /// Returns the size in bytes of the source, or None if it can't be known, from which the
+    /// default [limits] are derived.
+    ///
+    /// [limits]: crate::cfb::Limits
+    fn size(&self) -> Option<u64> {
+        None
+    }
+
+    /// Returns whether the bytes are already in memory, such as those of a buffer or a memory map,
+    /// so that reading the fields one at a time costs no more than copying them. The blocks last
+    /// read are then not kept aside, which would only add a copy and a lock to every read.
+    fn is_in_memory(&self) -> bool {
+        false
+    }
+}
+
+impl<T: ReadAt + ?Sized> ReadAt for Arc<T> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self.as_ref().read_at(buf, offset)
+    }
+
+    fn try_clone(&self) -> std::io::Result<Option<SharedSource>> {
+        self.as_ref().try_clone()
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.as_ref().size()
+    }
+
+    fn is_in_memory(&self) -> bool {
+        self.as_ref().is_in_memory()
+    }
+}
+
+/// Reads from a file at an offset without moving a cursor shared with other reads
+#[cfg(all(feature = "std-fs", unix))]
+fn read_file_at(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    std::os::unix::fs::FileExt::read_at(file, buf, offset)
+}
+
+/// Reads from a file at an offset. The cursor of the file moves, but every read of the compound
+/// file is positioned, so none relies on it.
+#[cfg(all(feature = "std-fs", windows))]
+fn read_file_at(file: &File, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+    std::os::windows::fs::FileExt::seek_read(file, buf, offset)
+}
+
+/// Fails to read from a file on targets without positioned reads, such as `wasm32-unknown-unknown`,
+/// where no file can be opened anyway
+#[cfg(all(feature = "std-fs", not(any(unix, windows))))]
+fn read_file_at(_file: &File, _buf: &mut [u8], _offset: u64) -> std::io::Result<usize> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(feature = "std-fs")]
+impl ReadAt for File {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let mut read = 0;
+        while read < buf.len() {
+            match read_file_at(self, &mut buf[read..], offset + read as u64) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(read)
+    }
+
+    fn try_clone(&self) -> std::io::Result<Option<SharedSource>> {
+        Ok(Some(Arc::new(File::try_clone(self)?)))
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.metadata().ok().map(|metadata| metadata.len())
+    }
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let start = to_usize_saturating(offset).min(self.len());
+        let len = buf.len().min(self.len() - start);
+        buf[..len].copy_from_slice(&self[start..][..len]);
+        Ok(len)
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+
+    fn is_in_memory(&self) -> bool {
+        true
+    }
+}
+
+impl ReadAt for &[u8] {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        (**self).read_at(buf, offset)
+    }
+
+    fn size(&self) -> Option<u64> {
+        (**self).size()
+    }
+
+    fn is_in_memory(&self) -> bool {
+        true
+    }
+}
+
+impl ReadAt for Vec<u8> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self.as_slice().read_at(buf, offset)
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.as_slice().size()
+    }
+
+    fn is_in_memory(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ReadAt for memmap2::Mmap {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self.as_ref().read_at(buf, offset)
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+
+    fn is_in_memory(&self) -> bool {
+        true
+    }
+}
+
+/// A destination of bytes that can be written at arbitrary positions, the counterpart of [`ReadAt`]
+/// for editing a compound file in place.
+#[cfg(feature = "std-fs")]
+pub(crate) trait WriteAt {
+    /// Writes all of `buf` starting at `offset`
+    fn write_at(&self, buf: &[u8], offset: u64) -> std::io::Result<()>;
+
+    /// Flushes the written bytes to the underlying storage
+    fn sync_data(&self) -> std::io::Result<()>;
+}
+
+/// Writes all of a buffer to a file at an offset
+#[cfg(all(feature = "std-fs", unix))]
+fn write_file_at(file: &File, buf: &[u8], offset: u64) -> std::io::Result<()> {
+    std::os::unix::fs::FileExt::write_all_at(file, buf, offset)
+}
+
+/// Writes all of a buffer to a file at an offset, as Windows has no counterpart of `write_all_at`
+#[cfg(all(feature = "std-fs", windows))]
+fn write_file_at(file: &File, mut buf: &[u8], mut offset: u64) -> std::io::Result<()> {
+    while !buf.is_empty() {
+        match std::os::windows::fs::FileExt::seek_write(file, buf, offset) {
+            Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+            Ok(n) => {
+                buf = &buf[n..];
+                offset += n as u64;
+            }
+            Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(())
+}
+
+/// Fails to write to a file on targets without positioned writes
+#[cfg(all(feature = "std-fs", not(any(unix, windows))))]
+fn write_file_at(_file: &File, _buf: &[u8], _offset: u64) -> std::io::Result<()> {
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(feature = "std-fs")]
+impl WriteAt for File {
+    fn write_at(&self, buf: &[u8], offset: u64) -> std::io::Result<()> {
+        write_file_at(self, buf, offset)
+    }
+
+    fn sync_data(&self) -> std::io::Result<()> {
+        File::sync_data(self)
+    }
+}
+
+/// Adapts a reader implementing [`Read`] and [`Seek`] into a positional source, serializing the
+/// reads behind a lock since each of them moves the cursor of the reader. The lock also makes the
+/// source shareable across threads.
+pub(crate) struct SeekReader<R>(Mutex<R>);
+
+impl<R> SeekReader<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self(Mutex::new(reader))
+    }
+}
+
+impl<R: Read + Seek> ReadAt for SeekReader<R> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let mut reader = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        reader.seek(SeekFrom::Start(offset))?;
+
+        let mut read = 0;
+        while read < buf.len() {
+            match reader.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(read)
+    }
+
+    fn size(&self) -> Option<u64> {
+        let mut reader = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        reader.seek(SeekFrom::End(0)).ok()
+    }
+}
+
+/// A range of another source, through which every offset of a compound file stored at a non-zero
+/// position is rebased. Reads are clamped to the end of the range when it has a length.
+pub(crate) struct Window<S> {
+    source: S,
+    offset: u64,
+    len: Option<u64>,
+}
+
+impl<S: ReadAt> Window<S> {
+    pub(crate) fn new(source: S, offset: u64, len: Option<u64>) -> Self {
+        Self { source, offset, len }
+    }
+}
+
+impl<S: ReadAt> ReadAt for Window<S> {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let buf = match self.len {
+            Some(len) => {
+                let available = len.saturating_sub(offset).min(buf.len() as u64);
+                &mut buf[..to_usize_saturating(available)]
+            }
+            None => buf,
+        };
+
+        match self.offset.checked_add(offset) {
+            Some(offset) => self.source.read_at(buf, offset),
+            None => Ok(0),
+        }
+    }
+
+    fn try_clone(&self) -> std::io::Result<Option<SharedSource>> {
+        Ok(self.source.try_clone()?
+            .map(|source| Arc::new(Window::new(source, self.offset, self.len)) as SharedSource))
+    }
+
+    fn size(&self) -> Option<u64> {
+        let available = self.source.size().map(|size| size.saturating_sub(self.offset));
+        match (available, self.len) {
+            (Some(available), Some(len)) => Some(available.min(len)),
+            (available, len) => available.or(len),
+        }
+    }
+
+    fn is_in_memory(&self) -> bool {
+        self.source.is_in_memory()
+    }
+}
+
+/// A stream object read as the source of the compound file nested in it, serializing the reads
+/// behind a lock since the reader remembers the sectors it located.
+pub(crate) struct StreamSource {
+    reader: Mutex<StreamReader>,
+    len: u64,
+}
+
+impl StreamSource {
+    pub(crate) fn new(reader: StreamReader) -> Self {
+        let len = reader.len();
+        Self { reader: Mutex::new(reader), len }
+    }
+}
+
+impl ReadAt for StreamSource {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let mut reader = self.reader.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        reader.read_at(offset, buf)
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.len)
+    }
+}
+
+/// A source holding the regions of a file read ahead, failing the reads of any other region within
+/// the file, so that a compound file over it never reads more than was read ahead.
+pub(crate) struct ReadAhead {
+    regions: RwLock<BTreeMap<u64, Vec<u8>>>,
+    size: Option<u64>,
+}
+
+impl ReadAhead {
+    pub(crate) fn new(size: Option<u64>) -> Self {
+        Self { regions: RwLock::default(), size }
+    }
+
+    pub(crate) fn insert(&self, offset: u64, bytes: Vec<u8>) {
+        self.regions.write().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(offset, bytes);
+    }
+}
+
+impl ReadAt for ReadAhead {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let regions = self.regions.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match regions.range(..=offset).next_back() {
+            Some((start, bytes)) if offset - start < bytes.len() as u64 => {
+                let bytes = &bytes[to_usize_saturating(offset - start)..];
+                let len = buf.len().min(bytes.len());
+                buf[..len].copy_from_slice(&bytes[..len]);
+                Ok(len)
+            }
+            _ if self.size.is_some_and(|size| offset >= size) => Ok(0),
+            _ => Err(std::io::Error::other(format!("offset {} was not read ahead", offset))),
+        }
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.size
+    }
+}
+
+/// A source keeping the blocks of 512 bytes last read from another source, so that the fields of a
+/// directory entry, or the sector numbers of a FAT sector, read one after the other hit memory
+/// instead of issuing a read each. Every sector starts on a block, the header filling the first.
+/// Reads of a whole block or more, or spanning blocks, such as those of the stream data, go to the
+/// source directly.
+pub(crate) struct SectorCache {
+    source: SharedSource,
+    /// The blocks by their index, the most recently used first.
+    blocks: Mutex<VecDeque<(u64, Arc<[u8]>)>>,
+}
+
+impl SectorCache {
+    /// The size in bytes of a block, the smallest sector size.
+    const BLOCK_SIZE: u64 = 512;
+    /// The number of blocks kept, enough for a directory sector and the FAT sector locating the
+    /// next one to stay while entries are read across them.
+    const CAPACITY: usize = 4;
+
+    pub(crate) fn new(source: SharedSource) -> Self {
+        Self { source, blocks: Mutex::default() }
+    }
+
+    /// Returns the bytes of a block, reading it from the source if it isn't kept
+    fn block(&self, index: u64) -> std::io::Result<Arc<[u8]>> {
+        {
+            let mut blocks = self.lock();
+            if let Some(position) = blocks.iter().position(|(kept, _)| *kept == index) {
+                let block = blocks.remove(position).expect("the position was just found");
+                blocks.push_front(block.clone());
+                return Ok(block.1);
+            }
+        }
+
+        // read without holding the lock, so that other readers of kept blocks don't wait
+        let mut bytes = vec![0; to_usize_saturating(Self::BLOCK_SIZE)];
+        let read = self.source.read_at(&mut bytes, index * Self::BLOCK_SIZE)?;
+        bytes.truncate(read);
+        let bytes: Arc<[u8]> = bytes.into();
+        let mut blocks = self.lock();
+        blocks.push_front((index, bytes.clone()));
+        blocks.truncate(Self::CAPACITY);
+        Ok(bytes)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, VecDeque<(u64, Arc<[u8]>)>> {
+        self.blocks.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl ReadAt for SectorCache {
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let within = offset % Self::BLOCK_SIZE;
+        if buf.is_empty() || buf.len() as u64 >= Self::BLOCK_SIZE || within + buf.len() as u64 > Self::BLOCK_SIZE {
+            return self.source.read_at(buf, offset);
+        }
+
+        let block = self.block(offset / Self::BLOCK_SIZE)?;
+        let bytes = block.get(to_usize_saturating(within)..).unwrap_or_default();
+        let len = buf.len().min(bytes.len());
+        buf[..len].copy_from_slice(&bytes[..len]);
+        Ok(len)
+    }
+
+    /// Always returns a cache of its own, empty, over a duplicate of the source, or over the source
+    /// itself if it is better shared.
+    fn try_clone(&self) -> std::io::Result<Option<SharedSource>> {
+        let source = self.source.try_clone()?.unwrap_or_else(|| self.source.clone());
+        Ok(Some(Arc::new(SectorCache::new(source))))
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.source.size()
+    }
+}
@@ -0,0 +1,300 @@
+use std::io::{Read, Seek, SeekFrom};
+use crate::cfb::Cfb;
+use crate::cfb::fat::cache::Cache;
+use crate::cfb::fat::chain::Chain;
+use crate::cfb::{Error, Limit, SectorNumber};
+use crate::cfb::convert::{to_usize_checked, to_usize_saturating};
+
+/// The size in bytes of a sector of the mini stream.
+pub(crate) const MINI_SECTOR_SIZE: u64 = 64;
+
+/// The most bytes read from the source at once, over a run of sectors lying one after another.
+pub(crate) const MAX_READ_RUN: usize = 1 << 20;
+
+/// A handle over the bytes of a stream object implementing [`Read`] and [`Seek`], created by
+/// [`Cfb::open_stream`].
+///
+/// The sectors backing the stream are located through the FAT, or the mini FAT for streams stored
+/// in the mini stream, only when a read reaches them. Seeking beyond the end of the stream is
+/// allowed, and reads from there return no bytes, as with a [`File`].
+///
+/// The reader holds its own handle to the compound file, so it can outlive the [`Cfb`] it was
+/// opened from and be sent to another thread.
+///
+/// The bytes read through the reader count against the [limit] of the compound file, past which
+/// reads fail with an error converting into [`Error::LimitExceeded`].
+///
+/// [`File`]: std::fs::File
+/// [limit]: crate::cfb::Limits::max_bytes_read
+pub struct StreamReader {
+    cfb: Cfb,
+    cache: Cache,
+    chain: Chain,
+    /// The chain of the mini stream holding the stream, if the stream is smaller than the cutoff.
+    mini_stream_chain: Option<Chain>,
+    size: u64,
+    position: u64,
+    /// The bytes read from the source so far, counted against the limit of the compound file.
+    bytes_read: u64,
+}
+
+impl StreamReader {
+    pub(crate) fn new(cfb: &Cfb,
+                      starting_sector_location: SectorNumber,
+                      size: u64,
+                      mini_stream_starting_sector_location: Option<SectorNumber>) -> Self {
+        Self {
+            cfb: cfb.clone(),
+            cache: Cache::new(cfb),
+            chain: Chain::new(starting_sector_location, mini_stream_starting_sector_location.is_some()),
+            mini_stream_chain: mini_stream_starting_sector_location.map(|sector_no| Chain::new(sector_no, false)),
+            size,
+            position: 0,
+            bytes_read: 0,
+        }
+    }
+
+    /// Lifts the limit on the bytes read, for a reader backing the source of a nested compound file,
+    /// which reads the same sectors again and again and whose reads are bounded by its own limits
+    pub(crate) fn without_read_limit(mut self) -> Self {
+        self.cfb.limits.max_bytes_read = u64::MAX;
+        self
+    }
+
+    /// Returns the size in bytes of the stream
+    pub fn len(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns whether the stream has no bytes
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    /// Reads bytes of the stream starting at `position` into `buf` without moving the cursor of the
+    /// reader, returning the number of bytes read. The count is only short of `buf.len()` when the
+    /// end of the stream is reached, or the end of a source cut short, reading from which fails with
+    /// an error converting into [`Error::Truncated`].
+    ///
+    /// The sectors located by previous reads are remembered, so scattered reads through the same
+    /// reader only follow the chain once, and the sectors lying one after another in the file are
+    /// read from it at once, up to a megabyte.
+    pub fn read_at(&mut self, position: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+        let unit_size = self.unit_size();
+        let mut read = 0;
+
+        while read < buf.len() && position + (read as u64) < self.size {
+            let position = position + read as u64;
+            let remaining_in_unit = unit_size - position % unit_size;
+            let len = (buf.len() - read)
+                .min(to_usize_saturating(remaining_in_unit))
+                .min(to_usize_saturating(self.size - position));
+
+            let bytes_read = self.bytes_read + len as u64;
+            self.cfb.limits.check(Limit::BytesRead, bytes_read).map_err(into_io)?;
+
+            let offset = self.source_offset(position)?;
+            let len = self.extend_run(position, offset, len, buf.len() - read);
+            let bytes_read = self.bytes_read + len as u64;
+            let n = self.cfb.source.read_at(&mut buf[read..][..len], offset)?;
+            self.bytes_read = bytes_read;
+            if n < len {
+                // the bytes before the end of the source are returned first, so that the error of
+                // the next read carries the same count as the truncation report
+                read += n;
+                if read > 0 {
+                    return Ok(read);
+                }
+                return Err(into_io(Error::Truncated { size: self.size, available: position }));
+            }
+
+            read += n;
+        }
+
+        Ok(read)
+    }
+
+    /// Returns the length of the run of bytes starting at `position`, found at `offset` in the
+    /// source, extended from the `len` bytes of its first sector over the following sectors while
+    /// they lie right after it in the source, so that a contiguous chain is read at once.
+    ///
+    /// The run stops short of `max_len`, of [`MAX_READ_RUN`], of the end of the stream and of the
+    /// limit of bytes read, and before a sector which can't be located, whose failure is left to
+    /// the read of that sector.
+    fn extend_run(&mut self, position: u64, offset: u64, len: usize, max_len: usize) -> usize {
+        let unit_size = self.unit_size();
+        let max_len = max_len.min(MAX_READ_RUN);
+        let mut len = len;
+        loop {
+            let next = position + len as u64;
+            let next_len = (max_len - len.min(max_len))
+                .min(to_usize_saturating(unit_size))
+                .min(to_usize_saturating(self.size.saturating_sub(next)));
+            if next_len == 0 || self.cfb.limits.check(Limit::BytesRead, self.bytes_read + (len + next_len) as u64).is_err() {
+                return len;
+            }
+            match self.source_offset(next) {
+                Ok(next_offset) if next_offset == offset + len as u64 => len += next_len,
+                _ => return len,
+            }
+        }
+    }
+
+    /// Returns the number of bytes of the stream, from its start, which lie within a source of
+    /// `source_len` bytes, stopping at the first byte beyond it or where the chain breaks.
+    ///
+    /// Only the FAT, or the mini FAT, is read to locate the sectors, never the stream data.
+    pub(crate) fn available(&mut self, source_len: u64) -> Result<u64, Error> {
+        let unit_size = self.unit_size();
+        let mut position = 0;
+        while position < self.size {
+            let len = unit_size.min(self.size - position);
+            let offset = match self.source_offset(position).map_err(Error::from) {
+                Ok(offset) => offset,
+                Err(Error::TruncatedChain { .. }) => break,
+                Err(err) => return Err(err),
+            };
+
+            let present = source_len.saturating_sub(offset).min(len);
+            position += present;
+            if present < len {
+                break;
+            }
+        }
+        Ok(position)
+    }
+
+    /// Returns the size of the sectors of the chain, which are mini sectors for a stream stored in
+    /// the mini stream.
+    pub(crate) fn unit_size(&self) -> u64 {
+        if self.mini_stream_chain.is_some() { MINI_SECTOR_SIZE } else { self.cfb.sector_size as u64 }
+    }
+
+    /// Returns the absolute byte offset in the source of a byte offset within the stream.
+    fn source_offset(&mut self, position: u64) -> std::io::Result<u64> {
+        let unit_size = self.unit_size();
+        let sector_size = self.cfb.sector_size as u64;
+        // the chain is broken from the start of the sector holding the position
+        let (size, available) = (self.size, position - position % unit_size);
+        let index = to_usize_checked(position / unit_size, "sector index").map_err(into_io)?;
+        let sector_no = self.chain.get(index, &mut self.cache)
+            .map_err(into_io)?
+            .ok_or_else(|| broken_chain(size, available))?;
+
+        match self.mini_stream_chain.as_mut() {
+            Some(mini_stream_chain) => {
+                let mini_stream_offset = sector_offset(sector_no, unit_size)? + position % unit_size;
+                let index = to_usize_checked(mini_stream_offset / sector_size, "sector index").map_err(into_io)?;
+                let container_sector_no = mini_stream_chain
+                    .get(index, &mut self.cache)
+                    .map_err(into_io)?
+                    .ok_or_else(|| broken_chain(size, available))?;
+                Ok(sector_offset(container_sector_no + 1, sector_size)? + mini_stream_offset % sector_size)
+            }
+            None => Ok(sector_offset(sector_no + 1, sector_size)? + position % unit_size),
+        }
+    }
+}
+
+/// An iterator over the bytes of a stream object a sector at a time, created by
+/// [`Stream::sectors`](crate::cfb::Stream::sectors).
+///
+/// Each item holds the bytes of the next sector of the chain, or of the next mini sector for a
+/// stream stored in the mini stream, and the last one only the bytes left of the stream size. The
+/// iterator ends with the stream, however the chain goes on, and so can't be led around a chain
+/// looping back on itself. A chain ending, or reaching a FREESECT or any other reserved value,
+/// before the stream size yields [`Error::TruncatedChain`], after which the iterator ends.
+pub struct StreamSectors {
+    reader: StreamReader,
+    position: u64,
+    /// The error to yield before any sector, from the checks made when reading a whole stream.
+    pending: Option<Error>,
+}
+
+impl StreamSectors {
+    pub(crate) fn new(reader: StreamReader, pending: Option<Error>) -> Self {
+        Self { reader, position: 0, pending }
+    }
+}
+
+impl Iterator for StreamSectors {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.pending.take() {
+            self.position = self.reader.size;
+            return Some(Err(err));
+        }
+        if self.position >= self.reader.size {
+            return None;
+        }
+
+        let unit_size = self.reader.unit_size();
+        let len = unit_size.min(self.reader.size - self.position);
+        let mut bytes = vec![0; to_usize_saturating(len)];
+        let mut filled = 0;
+        // a read only stops short before the end of the stream to report what it reads up to a
+        // failure, which the next read of the remaining bytes raises
+        while filled < bytes.len() {
+            match self.reader.read_at(self.position + filled as u64, &mut bytes[filled..]) {
+                Ok(read) => filled += read,
+                Err(err) => {
+                    self.position = self.reader.size;
+                    return Some(Err(err.into()));
+                }
+            }
+        }
+        self.position += len;
+        Some(Ok(bytes))
+    }
+}
+
+impl std::iter::FusedIterator for StreamSectors {}
+
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.read_at(self.position, buf)?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for StreamReader {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.size.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+
+        match position {
+            Some(position) => {
+                self.position = position;
+                Ok(position)
+            }
+            None => Err(std::io::Error::new(std::io::ErrorKind::InvalidInput,
+                                            "invalid seek to a negative or overflowing position")),
+        }
+    }
+}
+
+/// Returns the error of a chain ending before the size of its stream, which converts back into
+/// [`Error::TruncatedChain`] when it reaches an API returning [`Error`].
+fn broken_chain(size: u64, available: u64) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::UnexpectedEof, Error::TruncatedChain { size, available })
+}
+
+/// Returns the byte offset of a sector, failing with [`Error::InvalidSectorNumber`] for a sector
+/// number which locates none.
+fn sector_offset(sector_no: SectorNumber, sector_size: u64) -> std::io::Result<u64> {
+    sector_no.checked_byte_offset(sector_size).ok_or_else(|| into_io(Error::InvalidSectorNumber(sector_no)))
+}
+
+/// Carries an error of the compound file through [`Read`], which converts back into the [`Error`]
+/// when it reaches an API returning it.
+fn into_io(err: Error) -> std::io::Error {
+    match err {
+        Error::Io(err) => err,
+        err => std::io::Error::other(err),
+    }
+}
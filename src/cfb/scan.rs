@@ -0,0 +1,230 @@
+use std::collections::BTreeSet;
+use std::io::{Read, Seek, SeekFrom};
+use std::iter::FusedIterator;
+use crate::cfb::{Cfb, Error, SectorNumber};
+use crate::cfb::header::Header;
+use crate::cfb::fat::Fat;
+use crate::cfb::convert::{sector_index, sector_numbers_per_sector, to_usize_checked, to_usize_saturating};
+
+/// The number of bytes searched for the signature per read.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+const SIGNATURE_LEN: usize = std::mem::size_of::<u64>();
+
+/// Searches a source of bytes for [compound files] embedded at any offset, aligned or not.
+///
+/// Every occurrence of the header signature is checked by parsing the header at that position, so
+/// random matches of the 8-byte signature are rejected without reading further. The extent of each
+/// accepted compound file is estimated from its FAT, and signatures occurring within the extent of
+/// a compound file already found (for example, a compound file stored in one of its streams) are
+/// not reported again.
+///
+/// The iteration ends after the first I/O error.
+///
+/// [compound files]: crate::cfb::Cfb
+pub fn scan<R: Read + Seek>(reader: R) -> Scan<R> {
+    Scan {
+        reader,
+        position: 0,
+        covered_until: 0,
+        finished: false,
+    }
+}
+
+/// A compound file found by [`scan`] within a larger source, located by its offset and size, of
+/// which nothing is kept in memory.
+///
+/// [`scan`]: crate::cfb::scan
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct EmbeddedCfb {
+    offset: u64,
+    size: u64,
+}
+
+impl EmbeddedCfb {
+    /// Returns the byte offset of the compound file within the scanned source
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the estimated size in bytes of the compound file, which is truncated if the scanned
+    /// source ends before the last sector allocated by the FAT
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Opens the compound file over its sub-range of a reader of the scanned source, such as the
+    /// file scanned opened again, reading nothing past the sub-range
+    pub fn open<R: Read + Seek + Send + 'static>(&self, reader: R) -> Result<Cfb, Error> {
+        Cfb::from_reader_at(reader, self.offset, Some(self.size))
+    }
+}
+
+/// An iterator over the compound files embedded in a source, created by [`scan`].
+///
+/// [`scan`]: crate::cfb::scan
+pub struct Scan<R> {
+    reader: R,
+    position: u64,
+    covered_until: u64,
+    finished: bool,
+}
+
+impl<R: Read + Seek> Scan<R> {
+    /// Reads bytes at an absolute offset, returning the number of bytes read before the end of the
+    /// source.
+    fn read_at(&mut self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self.reader.seek(SeekFrom::Start(offset))?;
+        let mut read = 0;
+        while read < buf.len() {
+            match self.reader.read(&mut buf[read..]) {
+                Ok(0) => break,
+                Ok(n) => read += n,
+                Err(ref err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(read)
+    }
+
+    /// Returns the offset of the next signature at or after the current position.
+    fn find_signature(&mut self) -> std::io::Result<Option<u64>> {
+        let signature = Header::MAGIC.to_le_bytes();
+        let mut chunk = vec![0u8; CHUNK_SIZE];
+
+        loop {
+            let read = self.read_at(&mut chunk, self.position)?;
+            if read < SIGNATURE_LEN {
+                return Ok(None);
+            }
+
+            if let Some(index) = chunk[..read]
+                .windows(SIGNATURE_LEN)
+                .position(|window| window == signature) {
+                return Ok(Some(self.position + index as u64));
+            }
+
+            if read < CHUNK_SIZE {
+                return Ok(None);
+            }
+
+            // keeps the tail so that a signature split across two chunks is still found
+            self.position += (read - SIGNATURE_LEN + 1) as u64;
+        }
+    }
+
+    /// Parses the header at an offset, returning the compound file there or `None` if the header
+    /// is not valid.
+    fn probe(&mut self, offset: u64) -> Result<Option<EmbeddedCfb>, Error> {
+        let mut header_bytes = vec![0u8; Header::LENGTH];
+        if self.read_at(&mut header_bytes, offset)? < Header::LENGTH {
+            return Ok(None);
+        }
+
+        let header = Header::from_source(&header_bytes);
+        if header.validate().is_err() {
+            return Ok(None);
+        }
+
+        // the estimate can't go past the end of the source, whatever the FAT claims
+        let available = self.reader.seek(SeekFrom::End(0))?.saturating_sub(offset);
+        let size = self.estimate_size(offset, &header, available)?.min(available);
+        Ok(Some(EmbeddedCfb { offset, size }))
+    }
+
+    /// Estimates the size of the compound file at an offset as the end of the last sector that its
+    /// FAT does not mark as free. Only as many FAT sectors as the `available` bytes can hold are
+    /// looked up, and a DIFAT sector met twice ends the chain.
+    fn estimate_size(&mut self, offset: u64, header: &Header, available: u64) -> Result<u64, Error> {
+        let sector_size = 1u64 << header.sector_shift().0;
+        let entries_per_sector = sector_index(sector_numbers_per_sector(1u32 << header.sector_shift().0));
+        let no_of_fat_sectors = sector_index(header.no_of_fat_sectors().0)
+            .min(to_usize_saturating(available / sector_size));
+
+        let mut fat_sector_locations: Vec<SectorNumber> = (0..header.no_of_fat_sectors().0.min(109))
+            .map(|index| header.sector_no_of_fat(SectorNumber(index)))
+            .collect();
+
+        // the FAT sectors beyond the first 109 are listed by the chained DIFAT sectors
+        let mut sector_bytes = vec![0u8; to_usize_checked(sector_size, "sector size")?];
+        let mut difat_sector = header.first_difat_sector_location();
+        let mut no_of_difat_sectors = header.no_of_difat_sectors().0;
+        let mut visited = BTreeSet::new();
+        while fat_sector_locations.len() < no_of_fat_sectors && difat_sector.is_other() && no_of_difat_sectors > 0
+            && visited.insert(difat_sector) {
+            let sector_offset = (difat_sector + 1).checked_byte_offset(sector_size)
+                .ok_or(Error::InvalidSectorNumber(difat_sector))?;
+            self.read_at(&mut sector_bytes, offset + sector_offset)?;
+            let entries = Fat::new(difat_sector, sector_bytes.clone()).sector_numbers();
+            fat_sector_locations.extend(entries[..entries_per_sector - 1].iter()
+                .take(no_of_fat_sectors - fat_sector_locations.len()));
+            difat_sector = entries[entries_per_sector - 1];
+            no_of_difat_sectors -= 1;
+        }
+
+        let mut last_sector = None;
+        for (fat_index, location) in fat_sector_locations.into_iter().enumerate() {
+            if !location.is_other() {
+                continue;
+            }
+
+            sector_bytes.iter_mut().for_each(|byte| *byte = 0xFF);
+            let sector_offset = (location + 1).checked_byte_offset(sector_size)
+                .ok_or(Error::InvalidSectorNumber(location))?;
+            self.read_at(&mut sector_bytes, offset + sector_offset)?;
+
+            let allocated = Fat::new(location, sector_bytes.clone())
+                .sector_numbers()
+                .iter()
+                .rposition(|sector_no| !sector_no.is_free());
+            if let Some(index) = allocated {
+                last_sector = Some((fat_index * entries_per_sector + index) as u64);
+            }
+        }
+
+        // the header occupies the sector before sector #0
+        Ok(last_sector.map_or(sector_size, |sector| (sector + 2) * sector_size))
+    }
+}
+
+impl<R: Read + Seek> Iterator for Scan<R> {
+    type Item = Result<EmbeddedCfb, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let offset = match self.find_signature() {
+                Ok(Some(offset)) => offset,
+                Ok(None) => {
+                    self.finished = true;
+                    break;
+                }
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err.into()));
+                }
+            };
+
+            self.position = offset + 1;
+            if offset < self.covered_until {
+                continue;
+            }
+
+            match self.probe(offset) {
+                Ok(Some(embedded)) => {
+                    self.covered_until = offset + embedded.size();
+                    self.position = self.covered_until.max(self.position);
+                    return Some(Ok(embedded));
+                }
+                Ok(None) => continue,
+                Err(err) => {
+                    self.finished = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl<R: Read + Seek> FusedIterator for Scan<R> {}
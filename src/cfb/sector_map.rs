@@ -0,0 +1,199 @@
+use crate::cfb::fat::cache::Cache;
+use crate::cfb::fat::chain::Chain;
+use crate::cfb::reader::MINI_SECTOR_SIZE;
+use crate::cfb::storage::walk_unchecked;
+use crate::cfb::{Cfb, Entry, Error, Limit, SectorNumber};
+use core::fmt;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Formatter;
+
+/// What a sector, or a mini sector, of a compound file is allocated to.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum SectorOwner {
+    /// A DIFAT sector, in the chain starting from the header.
+    Difat,
+    /// A FAT sector, listed by the header or a DIFAT sector.
+    Fat,
+    /// A mini FAT sector, in the chain starting from the header.
+    MiniFat,
+    /// A directory sector, in the chain starting from the header.
+    Directory,
+    /// A sector of the mini stream, in the chain of the root storage.
+    MiniStream,
+    /// A sector, or a mini sector, of a stream object, by its path.
+    Stream(String),
+}
+
+impl fmt::Display for SectorOwner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Difat => f.write_str("the DIFAT"),
+            Self::Fat => f.write_str("the FAT"),
+            Self::MiniFat => f.write_str("the mini FAT"),
+            Self::Directory => f.write_str("the directory"),
+            Self::MiniStream => f.write_str("the mini stream"),
+            Self::Stream(path) => write!(f, "stream `{}`", path),
+        }
+    }
+}
+
+/// A sector, or a mini sector, claimed by two owners, found by [`Cfb::sector_map`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Overlap {
+    pub sector: SectorNumber,
+    /// Whether the sector is a mini sector of the mini stream.
+    pub mini: bool,
+    /// The owner which claims the sector first, in the order of the [`SectorOwner`] variants and
+    /// then of the sibling trees.
+    pub first: SectorOwner,
+    pub second: SectorOwner,
+}
+
+impl fmt::Display for Overlap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let kind = if self.mini { "mini sector" } else { "sector" };
+        write!(f, "{} {} is claimed by both {} and {}", kind, self.sector, self.first, self.second)
+    }
+}
+
+/// The owner of every allocated sector and mini sector of a compound file, returned by
+/// [`Cfb::sector_map`].
+///
+/// A stream object only claims the sectors which hold its declared size, as those are the ones a
+/// reader reads, while the structures claim their whole chains.
+#[derive(Debug, Clone, Default)]
+pub struct SectorMap {
+    owners: HashMap<SectorNumber, SectorOwner>,
+    mini_owners: HashMap<SectorNumber, SectorOwner>,
+    /// The index of each sector, and mini sector, within the chain of its first owner.
+    indices: HashMap<(SectorNumber, bool), u64>,
+    overlaps: Vec<Overlap>,
+}
+
+impl SectorMap {
+    /// Returns the owner of a sector, the first one if it is claimed by several, or None if no
+    /// chain reaches it
+    pub fn owner(&self, sector: SectorNumber) -> Option<&SectorOwner> {
+        self.owners.get(&sector)
+    }
+
+    /// Returns the owner of a mini sector by its index in the mini stream, the first one if it is
+    /// claimed by several, or None if no chain reaches it
+    pub fn mini_owner(&self, mini_sector: SectorNumber) -> Option<&SectorOwner> {
+        self.mini_owners.get(&mini_sector)
+    }
+
+    /// Returns every sector claimed by more than one owner, once for each owner after the first
+    pub fn overlaps(&self) -> &[Overlap] {
+        &self.overlaps
+    }
+
+    /// Returns the index of a sector, or a mini sector, within the chain of its first owner
+    pub(crate) fn index(&self, sector: SectorNumber, mini: bool) -> Option<u64> {
+        self.indices.get(&(sector, mini)).copied()
+    }
+
+    fn claim(&mut self, sectors: impl IntoIterator<Item = SectorNumber>, mini: bool, owner: &SectorOwner) {
+        for (index, sector) in sectors.into_iter().enumerate() {
+            let owners = if mini { &mut self.mini_owners } else { &mut self.owners };
+            match owners.get(&sector) {
+                // a chain looping through the same owner is not an overlap
+                Some(first) if first == owner => {}
+                Some(first) => {
+                    let first = first.clone();
+                    self.overlaps.push(Overlap { sector, mini, first, second: owner.clone() });
+                }
+                None => {
+                    owners.insert(sector, owner.clone());
+                    self.indices.insert((sector, mini), index as u64);
+                }
+            }
+        }
+    }
+}
+
+impl Cfb {
+    /// Maps every sector reached by the chains of the DIFAT, the FAT, the mini FAT, the directory
+    /// and the mini stream, and every sector or mini sector of the stream objects, to its owner.
+    /// Only the structures are read, never the stream data.
+    ///
+    /// Readers disagree on what a file holds where two owners claim the same sector, so the
+    /// sectors claimed twice are recorded as [overlaps](SectorMap::overlaps).
+    pub fn sector_map(&self) -> Result<SectorMap, Error> {
+        let header = self.header();
+        let mut map = SectorMap::default();
+
+        map.claim(self.difat_sector_locations()?, false, &SectorOwner::Difat);
+        map.claim(self.fat_sector_locations().collect::<Result<Vec<_>, _>>()?, false, &SectorOwner::Fat);
+        map.claim(self.sector_chain(header.first_mini_fat_sector_location())?, false, &SectorOwner::MiniFat);
+        map.claim(self.sector_chain(header.first_directory_sector_location())?, false, &SectorOwner::Directory);
+
+        let root_storage = match self.root_storage() {
+            Some(root_storage) => root_storage,
+            None => return Ok(map),
+        };
+        let mut cache = Cache::new(self);
+        let sector_size = self.sector_size as u64;
+        let mini_stream = chain_prefix(root_storage.starting_sector_location(), false,
+                                       root_storage.stream_size().0.div_ceil(sector_size), &mut cache)?;
+        map.claim(mini_stream, false, &SectorOwner::MiniStream);
+
+        walk_unchecked(self, Entry::RootStorage(root_storage), &mut |path, entry| {
+            if let Entry::Stream(stream) = entry {
+                let size = stream.stream_size().0;
+                let mini = size < self.mini_stream_cutoff();
+                let unit = if mini { MINI_SECTOR_SIZE } else { sector_size };
+                let sectors = chain_prefix(stream.starting_sector_location(), mini, size.div_ceil(unit), &mut cache)?;
+                map.claim(sectors, mini, &SectorOwner::Stream(path.to_string()));
+            }
+            Ok(())
+        })?;
+
+        Ok(map)
+    }
+
+    /// Returns the DIFAT sectors, following the last entry of each from the header, cut where the
+    /// chain loops back on itself
+    fn difat_sector_locations(&self) -> Result<Vec<SectorNumber>, Error> {
+        let mut sectors = Vec::new();
+        let mut visited = HashSet::new();
+        let mut next = self.header().first_difat_sector_location();
+        while next.is_other() && visited.insert(next) {
+            self.limits.check(Limit::ChainLength, sectors.len() as u64 + 1)?;
+            sectors.push(next);
+            next = self.fat(next)?.sector_numbers().pop().unwrap_or(SectorNumber::ENDOFCHAIN);
+        }
+        Ok(sectors)
+    }
+
+    /// Fails with [`Error::SharedSector`] if the file isn't read leniently and any sector is claimed
+    /// by two owners. The sector map is built once, on the first check, for the clones too. A file
+    /// whose sector map can't be built passes, leaving the reads to fail on their own.
+    pub(crate) fn check_shared_sectors(&self) -> Result<(), Error> {
+        if self.lenient {
+            return Ok(());
+        }
+        let overlap = self.first_overlap.get_or_init(|| {
+            self.sector_map().ok().and_then(|map| map.overlaps.into_iter().next())
+        });
+        match overlap {
+            Some(overlap) => Err(Error::SharedSector(overlap.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns up to `len` sectors, or mini sectors, of a chain, cut where it loops back on itself
+fn chain_prefix(start: SectorNumber, mini: bool, len: u64, cache: &mut Cache) -> Result<Vec<SectorNumber>, Error> {
+    let mut chain = Chain::new(start, mini);
+    let mut sectors = Vec::new();
+    let mut visited = HashSet::new();
+    while (sectors.len() as u64) < len {
+        match chain.get(sectors.len(), cache)? {
+            Some(sector) if visited.insert(sector) => sectors.push(sector),
+            _ => break,
+        }
+    }
+    Ok(sectors)
+}
@@ -0,0 +1,285 @@
+use crate::cfb::convert::{sector_index, sector_numbers_per_sector, to_usize_saturating};
+use crate::cfb::directory::entry::terminated_name_len;
+use crate::cfb::fat::Fat;
+use crate::cfb::header::Header;
+use crate::cfb::{ColorFlag, Error, ObjectType, SectorNumber, StateBits, StreamID, StreamSize};
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::fmt::Formatter;
+#[cfg(feature = "std")]
+use chrono::NaiveDateTime;
+
+/// A compound file held in a byte slice, parsed without the standard library: the header, the
+/// chains of the FAT and the directory entries, which is what the `alloc` feature builds.
+///
+/// The locations of the FAT sectors are resolved when parsing, and every other structure is read
+/// from the slice when asked for. A chain is cut where it loops back on itself, and the directory
+/// ends at the first of its sectors beyond the slice, as there are no [limits] to bound them by.
+/// Reading streams, and everything else, is left to [`Cfb`].
+///
+/// [limits]: crate::cfb::Limits
+/// [`Cfb`]: crate::cfb::Cfb
+pub struct SliceCfb<'a> {
+    bytes: &'a [u8],
+    sector_size: u32,
+    fat_sectors: Vec<SectorNumber>,
+}
+
+impl<'a> SliceCfb<'a> {
+    /// Parses the compound file held in a byte slice, failing if its header doesn't
+    /// [validate](Header::validate)
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+        let header = Header::from_bytes(bytes);
+        header.validate()?;
+        let mut cfb = Self { bytes, sector_size: 1 << header.sector_shift().0, fat_sectors: Vec::new() };
+        cfb.fat_sectors = cfb.resolve_fat_sectors();
+        Ok(cfb)
+    }
+
+    pub fn header(&self) -> Header<'a> {
+        Header::from_bytes(self.bytes)
+    }
+
+    pub fn sector_size(&self) -> u32 {
+        self.sector_size
+    }
+
+    /// Returns the bytes of a sector, cut short at the end of the slice, or None for a reserved
+    /// sector number or a sector beyond the slice
+    pub fn sector(&self, sector_no: SectorNumber) -> Option<&'a [u8]> {
+        sector(self.bytes, self.sector_size, sector_no)
+    }
+
+    /// Returns the locations of every FAT sector, in order, listed by the header until its first
+    /// FREESECT entry and then by the chained DIFAT sectors, as
+    /// [`Cfb::fat_sector_locations`](crate::cfb::Cfb::fat_sector_locations) does. The DIFAT
+    /// sectors end at the first one beyond the slice or linked twice.
+    pub fn fat_sector_locations(&self) -> &[SectorNumber] {
+        &self.fat_sectors
+    }
+
+    /// Returns a FAT sector by its index among the FAT sectors, returns None if the index is
+    /// beyond the FAT sectors listed in the DIFAT or the sector is beyond the slice
+    pub fn fat_sector(&self, index: u32) -> Option<Fat> {
+        let location = *self.fat_sectors.get(sector_index(index))?;
+        Some(Fat::new(location, self.sector(location)?.to_vec()))
+    }
+
+    /// Returns the sector following a sector in its FAT chain, or None if the sector lies beyond
+    /// the FAT sectors listed in the DIFAT or the slice
+    pub fn next_sector(&self, sector_no: SectorNumber) -> Option<SectorNumber> {
+        let per_sector = sector_numbers_per_sector(self.sector_size);
+        self.fat_sector(sector_no.0 / per_sector)?.sector_number(sector_no.0 % per_sector)
+    }
+
+    /// Returns the sectors of the FAT chain starting at a sector, cut where it loops back on itself
+    /// or leaves the FAT
+    pub fn sector_chain(&self, start: SectorNumber) -> Vec<SectorNumber> {
+        let mut sectors = Vec::new();
+        let mut visited = BTreeSet::new();
+        let mut sector_no = start;
+        while sector_no.is_other() && visited.insert(sector_no) {
+            sectors.push(sector_no);
+            sector_no = self.next_sector(sector_no).unwrap_or(SectorNumber::ENDOFCHAIN);
+        }
+        sectors
+    }
+
+    /// Returns an iterator over every slot of the directory, unallocated ones included, in the
+    /// order of their stream IDs, from the first directory sector along its chain
+    pub fn entries(&self) -> impl Iterator<Item = RawEntry<'a>> + 'a {
+        let (bytes, sector_size) = (self.bytes, self.sector_size);
+        self.sector_chain(self.header().first_directory_sector_location())
+            .into_iter()
+            .map_while(move |sector_no| sector(bytes, sector_size, sector_no))
+            .flat_map(|sector| sector.chunks_exact(sector_index(RawEntry::LENGTH)))
+            .zip(0..)
+            .map(|(bytes, id)| RawEntry { id: StreamID(id), bytes })
+    }
+
+    /// Returns the entry of a stream ID, or None if the directory ends before it
+    pub fn entry(&self, id: StreamID) -> Option<RawEntry<'a>> {
+        self.entries().nth(sector_index(id.0))
+    }
+
+    fn resolve_fat_sectors(&self) -> Vec<SectorNumber> {
+        let header = self.header();
+        let mut locations: Vec<_> = header.difat().sector_numbers().iter()
+            .copied()
+            .take_while(|sector_no| !sector_no.is_free())
+            .collect();
+
+        let mut visited = BTreeSet::new();
+        let mut next = header.first_difat_sector_location();
+        while next.is_other() && visited.insert(next) {
+            let mut entries = match self.sector(next) {
+                Some(sector) => Fat::new(next, sector.to_vec()).sector_numbers(),
+                None => break,
+            };
+            // the last entry links to the next DIFAT sector
+            next = entries.pop().unwrap_or(SectorNumber::ENDOFCHAIN);
+            locations.extend(entries.into_iter().take_while(|sector_no| !sector_no.is_free()));
+        }
+        locations
+    }
+}
+
+impl fmt::Debug for SliceCfb<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut fmt = f.debug_map();
+        crate::debug_map_method_reflection!(fmt, self, sector_size, header);
+        fmt.finish()
+    }
+}
+
+/// Returns the bytes of a sector of a slice, see [`SliceCfb::sector`]
+fn sector(bytes: &[u8], sector_size: u32, sector_no: SectorNumber) -> Option<&[u8]> {
+    let offset = sector_no.checked_byte_offset(sector_size as u64)?.checked_add(sector_size as u64)?;
+    let bytes = bytes.get(to_usize_saturating(offset)..).filter(|bytes| !bytes.is_empty())?;
+    Some(&bytes[..bytes.len().min(sector_index(sector_size))])
+}
+
+/// A directory entry decoded from its 128 bytes in a [`SliceCfb`], giving the same fields as an
+/// [`Entry`](crate::cfb::Entry) does, as they are stored: the deviations which a lenient reading
+/// tolerates are neither warned of nor corrected.
+#[derive(Copy, Clone)]
+pub struct RawEntry<'a> {
+    id: StreamID,
+    bytes: &'a [u8],
+}
+
+impl<'a> RawEntry<'a> {
+    /// The size in bytes of a directory entry.
+    pub(crate) const LENGTH: u32 = 128;
+
+    const NAME: usize = 64;
+    const CLSID: usize = Self::NAME + 16;
+    const STATE_BITS: usize = Self::CLSID + 16;
+    const CREATION_TIME: usize = Self::STATE_BITS + 4;
+    const MODIFIED_TIME: usize = Self::CREATION_TIME + 8;
+    const STARTING_SECTOR: usize = Self::MODIFIED_TIME + 8;
+    const STREAM_SIZE: usize = Self::STARTING_SECTOR + 4;
+
+    fn read<const N: usize>(&self, offset: usize) -> [u8; N] {
+        let mut bytes = [0; N];
+        bytes.copy_from_slice(&self.bytes[offset..offset + N]);
+        bytes
+    }
+
+    /// Returns the stream ID of the slot
+    pub fn id(&self) -> StreamID {
+        self.id
+    }
+
+    /// Returns the type of the object, or None for a byte which is none of the valid values
+    pub fn object_type(&self) -> Option<ObjectType> {
+        ObjectType::try_from(self.bytes[Self::NAME + 2]).ok()
+    }
+
+    /// Returns the name, decoded up to the terminating null character, or up to the first null
+    /// character if the name length doesn't point right after one
+    pub fn name(&self) -> String {
+        let mut units = [0; 32];
+        for (unit, bytes) in units.iter_mut().zip(self.bytes[..Self::NAME].chunks_exact(2)) {
+            *unit = u16::from_le_bytes([bytes[0], bytes[1]]);
+        }
+        let len = terminated_name_len(self.name_length(), &units)
+            .unwrap_or_else(|| units.iter().position(|unit| *unit == 0).unwrap_or(units.len()));
+        String::from_utf16_lossy(&units[..len])
+    }
+
+    pub fn name_length(&self) -> u16 {
+        u16::from_le_bytes(self.read(Self::NAME))
+    }
+
+    pub fn color_flag(&self) -> ColorFlag {
+        ColorFlag::from_byte(self.bytes[Self::NAME + 3])
+    }
+
+    pub fn left_sibling_id(&self) -> Option<StreamID> {
+        self.stream_id(Self::NAME + 4)
+    }
+
+    pub fn right_sibling_id(&self) -> Option<StreamID> {
+        self.stream_id(Self::NAME + 8)
+    }
+
+    pub fn child_id(&self) -> Option<StreamID> {
+        self.stream_id(Self::NAME + 12)
+    }
+
+    fn stream_id(&self, offset: usize) -> Option<StreamID> {
+        let raw_value = u32::from_le_bytes(self.read(offset));
+        if raw_value != u32::MAX { Some(StreamID(raw_value)) } else { None }
+    }
+
+    pub fn cls_id(&self) -> [u8; 16] {
+        self.read(Self::CLSID)
+    }
+
+    pub fn state_bits(&self) -> StateBits {
+        StateBits::from_bits_retain(u32::from_le_bytes(self.read(Self::STATE_BITS)))
+    }
+
+    /// Returns the raw FILETIME of the creation time, counting 100-nanosecond intervals since
+    /// 1601-01-01 UTC, or 0 if it was not recorded
+    pub fn creation_filetime(&self) -> u64 {
+        u64::from_le_bytes(self.read(Self::CREATION_TIME))
+    }
+
+    /// Returns the raw FILETIME of the modified time, see
+    /// [`creation_filetime`](Self::creation_filetime)
+    pub fn modified_filetime(&self) -> u64 {
+        u64::from_le_bytes(self.read(Self::MODIFIED_TIME))
+    }
+
+    /// Returns the creation time, or None if it was not recorded, with the `std` feature
+    #[cfg(feature = "std")]
+    pub fn creation_time(&self) -> Option<NaiveDateTime> {
+        crate::cfb::metadata::from_filetime(self.creation_filetime())
+    }
+
+    /// Returns the modified time, or None if it was not recorded, with the `std` feature
+    #[cfg(feature = "std")]
+    pub fn modified_time(&self) -> Option<NaiveDateTime> {
+        crate::cfb::metadata::from_filetime(self.modified_filetime())
+    }
+
+    pub fn starting_sector_location(&self) -> SectorNumber {
+        SectorNumber(u32::from_le_bytes(self.read(Self::STARTING_SECTOR)))
+    }
+
+    /// Returns the size of the stream, or of the mini stream for the root storage, with the most
+    /// significant 32 bits as stored even in a version 3 compound file
+    pub fn stream_size(&self) -> StreamSize {
+        StreamSize(u64::from_le_bytes(self.read(Self::STREAM_SIZE)))
+    }
+}
+
+impl fmt::Debug for RawEntry<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut fmt = f.debug_map();
+        crate::debug_map_method_reflection!(
+            fmt,
+            self,
+            id,
+            object_type,
+            name,
+            name_length,
+            color_flag,
+            left_sibling_id,
+            right_sibling_id,
+            child_id,
+            cls_id,
+            state_bits,
+            creation_filetime,
+            modified_filetime,
+            starting_sector_location,
+            stream_size
+        );
+        fmt.finish()
+    }
+}
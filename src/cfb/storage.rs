@@ -0,0 +1,280 @@
+use crate::cfb::directory::StreamID;
+use crate::cfb::{Cfb, Entry, Error, Limit, OwnedEntry, StreamReader};
+use std::collections::{HashMap, HashSet};
+use std::iter::FusedIterator;
+
+/// A handle over a storage object, or the root storage, created by [`Cfb::open_storage`], through
+/// which its children can be listed and opened by name.
+///
+/// Like an [`OwnedEntry`], the handle holds its own handle to the compound file, so that formats
+/// nesting objects in storages, such as the recipients and attachments of an Outlook message, can
+/// be navigated one level at a time.
+///
+/// [`Cfb::open_storage`]: crate::cfb::Cfb::open_storage
+#[derive(Clone)]
+pub struct StorageHandle {
+    entry: OwnedEntry,
+}
+
+impl StorageHandle {
+    /// Creates a handle over an entry, returns None if it is not a storage object or the root
+    /// storage
+    pub(crate) fn new(entry: &Entry) -> Option<Self> {
+        match entry {
+            Entry::RootStorage(_) | Entry::Storage(_) => Some(Self { entry: entry.to_owned()? }),
+            _ => None,
+        }
+    }
+
+    /// Borrows the entry of the storage
+    pub fn entry(&self) -> Result<Entry<'_>, String> {
+        self.entry.entry()
+    }
+
+    /// Returns the name of the storage
+    pub fn name(&self) -> Result<String, String> {
+        Ok(self.entry()?.name())
+    }
+
+    /// Returns the entries of the objects contained in the storage
+    pub fn children(&self) -> Result<Vec<Entry<'_>>, String> {
+        match self.entry()? {
+            Entry::RootStorage(root_storage) => root_storage.children(),
+            Entry::Storage(storage) => storage.children(),
+            entry => Err(format!("{} is not a storage object!", entry.name())),
+        }
+    }
+
+    /// Gets the entry of an object contained in the storage by its path relative to the storage,
+    /// whose components are separated by `/`, returns None if not found
+    pub fn entry_by_path(&self, path: &str) -> Option<Entry<'_>> {
+        resolve(self.entry().ok()?, path).ok()?
+    }
+
+    /// Opens a stream object by its path relative to the storage for reading and seeking, returns
+    /// None if not found or not a stream object
+    pub fn open_stream(&self, path: &str) -> Option<StreamReader> {
+        match self.entry_by_path(path)? {
+            Entry::Stream(stream) => Some(stream.reader()),
+            _ => None,
+        }
+    }
+
+    /// Opens a storage object by its path relative to the storage, returns None if not found or
+    /// not a storage object
+    pub fn open_storage(&self, path: &str) -> Option<StorageHandle> {
+        Self::new(&self.entry_by_path(path)?)
+    }
+}
+
+/// Returns the children of a storage object or the root storage.
+pub(crate) fn children(entry: Entry<'_>) -> Result<Vec<Entry<'_>>, Error> {
+    match entry {
+        Entry::RootStorage(root_storage) => root_storage.try_children(),
+        Entry::Storage(storage) => storage.try_children(),
+        _ => Err(Error::InvalidDirectory(format!("{} is not a storage object!", entry.name()))),
+    }
+}
+
+/// Descends from an entry through the storages named by the components of a path, empty components
+/// being skipped, as [`resolve_components`] does.
+pub(crate) fn resolve<'a>(entry: Entry<'a>, path: &str) -> Result<Option<Entry<'a>>, Error> {
+    let components: Vec<&str> = path.split('/').filter(|name| !name.is_empty()).collect();
+    resolve_components(entry, &components)
+}
+
+/// Descends from an entry through the storages named by a path given as its components, each
+/// compared with the names of the children as [`Cfb::names_match`] does, through the name index
+/// once built. Only the directory entries along the path are read. Descending deeper than the
+/// [tree depth limit] fails.
+///
+/// [tree depth limit]: crate::cfb::Limits::max_tree_depth
+pub(crate) fn resolve_components<'a>(mut entry: Entry<'a>, components: &[&str]) -> Result<Option<Entry<'a>>, Error> {
+    for (depth, name) in components.iter().enumerate() {
+        let cfb = match entry {
+            Entry::RootStorage(ref root_storage) => root_storage.cfb,
+            Entry::Storage(ref storage) => storage.cfb,
+            _ => return Ok(None),
+        };
+        if let Some(index) = cfb.index() {
+            return index.resolve(cfb, &entry, &components[depth..]);
+        }
+        cfb.limits().check(Limit::TreeDepth, depth as u64 + 1)?;
+
+        let child = children(entry)?
+            .into_iter()
+            .find(|child| cfb.names_match(&child.name(), name));
+        entry = match child {
+            Some(child) => child,
+            None => return Ok(None),
+        };
+    }
+
+    Ok(Some(entry))
+}
+
+/// The stream IDs linked from a directory entry: its left sibling, its right sibling and its child.
+pub(crate) type Links = (Option<StreamID>, Option<StreamID>, Option<StreamID>);
+
+/// Ranks the stream IDs reachable from the root storage in tree order, the order of [`Cfb::walk`]
+/// with the root storage first: depth first, each storage right before its children, which come in
+/// the order of their sibling tree. Only the links of the entries are read, through `links`, which
+/// gives None for a slot that can't be read. A stream ID reached twice keeps its first rank.
+pub(crate) fn tree_order(links: &dyn Fn(StreamID) -> Option<Links>) -> HashMap<StreamID, usize> {
+    let mut ranks = HashMap::new();
+    ranks.insert(StreamID(0), 0);
+    let mut pending = vec![sibling_tree(links, links(StreamID(0)).and_then(|(_, _, child)| child))];
+    while let Some(siblings) = pending.last_mut() {
+        let id = match siblings.pop() {
+            Some(id) => id,
+            None => {
+                pending.pop();
+                continue;
+            }
+        };
+        if ranks.contains_key(&id) {
+            continue;
+        }
+        ranks.insert(id, ranks.len());
+        pending.push(sibling_tree(links, links(id).and_then(|(_, _, child)| child)));
+    }
+    ranks
+}
+
+/// Returns the stream IDs of a sibling tree in reverse order, stopping at a stream ID met twice
+fn sibling_tree(links: &dyn Fn(StreamID) -> Option<Links>, mut next: Option<StreamID>) -> Vec<StreamID> {
+    let mut siblings = Vec::new();
+    let mut visited = HashSet::new();
+    let mut ancestors = Vec::new();
+    loop {
+        while let Some(id) = next.filter(|id| visited.insert(*id)) {
+            next = links(id).and_then(|(left, _, _)| left);
+            ancestors.push(id);
+        }
+        match ancestors.pop() {
+            Some(id) => {
+                next = links(id).and_then(|(_, right, _)| right);
+                siblings.push(id);
+            }
+            None => break,
+        }
+    }
+    siblings.reverse();
+    siblings
+}
+
+/// Visits every entry beneath a storage, depth first in the order of each sibling tree, with its
+/// path relative to the storage, whose components are separated by `/`. Descending deeper than the
+/// [tree depth limit], reaching a storage twice, or a strictly read file whose sectors are
+/// [claimed twice], fails.
+///
+/// [tree depth limit]: crate::cfb::Limits::max_tree_depth
+/// [claimed twice]: crate::cfb::Error::SharedSector
+pub(crate) fn walk<'a>(cfb: &Cfb,
+                       entry: Entry<'a>,
+                       visit: &mut dyn FnMut(&str, &Entry<'a>) -> Result<(), Error>) -> Result<(), Error> {
+    walk_named(cfb, entry, &|name| name.to_string(), visit)
+}
+
+/// Visits every entry beneath a storage as [`walk`] does, with the components of the paths mapped
+/// from the names of the entries.
+pub(crate) fn walk_named<'a>(cfb: &Cfb,
+                             entry: Entry<'a>,
+                             component: &dyn Fn(&str) -> String,
+                             visit: &mut dyn FnMut(&str, &Entry<'a>) -> Result<(), Error>) -> Result<(), Error> {
+    cfb.check_shared_sectors()?;
+    walk_components(cfb, entry, component, visit)
+}
+
+/// Visits every entry beneath a storage as [`walk`] does, without refusing a file whose sectors
+/// are claimed twice, for mapping the sectors themselves.
+pub(crate) fn walk_unchecked<'a>(cfb: &Cfb,
+                                 entry: Entry<'a>,
+                                 visit: &mut dyn FnMut(&str, &Entry<'a>) -> Result<(), Error>) -> Result<(), Error> {
+    walk_components(cfb, entry, &|name| name.to_string(), visit)
+}
+
+fn walk_components<'a>(cfb: &Cfb,
+                       entry: Entry<'a>,
+                       component: &dyn Fn(&str) -> String,
+                       visit: &mut dyn FnMut(&str, &Entry<'a>) -> Result<(), Error>) -> Result<(), Error> {
+    let mut visited = HashSet::new();
+    let mut pending = vec![(String::new(), 0, entry)];
+    while let Some((path, depth, entry)) = pending.pop() {
+        if depth > 0 {
+            visit(&path, &entry)?;
+        }
+        if let Entry::Stream(_) = entry {
+            continue;
+        }
+        if !visited.insert(entry.offset()) {
+            return Err(Error::InvalidDirectory(format!("the storage at `{}` is reached twice!", path)));
+        }
+        cfb.limits().check(Limit::TreeDepth, depth + 1)?;
+
+        // pushed in reverse so that the children are visited in order
+        for child in children(entry)?.into_iter().rev() {
+            let name = component(&child.name());
+            let child_path = if path.is_empty() { name } else { format!("{}/{}", path, name) };
+            pending.push((child_path, depth + 1, child));
+        }
+    }
+    Ok(())
+}
+
+/// An iterator over every entry beneath the root storage with the names of the storages holding it
+/// and its own, created by [`Cfb::walk`].
+pub(crate) struct Walk<'a> {
+    cfb: &'a Cfb,
+    /// The entries left to yield with their paths and depths, the next one last.
+    pending: Vec<(Vec<String>, u64, Entry<'a>)>,
+    /// The storage yielded last, whose children are pushed before the next entry is yielded.
+    expanding: Option<(Vec<String>, u64, Entry<'a>)>,
+    /// The offsets of the storages descended into.
+    visited: HashSet<u64>,
+}
+
+impl<'a> Walk<'a> {
+    pub(crate) fn new(cfb: &'a Cfb, root_storage: Entry<'a>) -> Self {
+        Self { cfb, pending: Vec::new(), expanding: Some((Vec::new(), 0, root_storage)), visited: HashSet::new() }
+    }
+
+    /// Pushes the children of a storage, failing if it is reached twice, is deeper than the tree
+    /// depth limit, or its sibling tree is corrupted
+    fn expand(&mut self, names: Vec<String>, depth: u64, storage: Entry<'a>) -> Result<(), Error> {
+        if !self.visited.insert(storage.offset()) {
+            return Err(Error::InvalidDirectory(format!("the storage at `{}` is reached twice!", names.join("/"))));
+        }
+        self.cfb.limits().check(Limit::TreeDepth, depth + 1)?;
+
+        // pushed in reverse so that the children are yielded in order
+        for child in children(storage)?.into_iter().rev() {
+            let mut child_names = names.clone();
+            child_names.push(child.name());
+            self.pending.push((child_names, depth + 1, child));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for Walk<'a> {
+    type Item = Result<(Vec<String>, Entry<'a>), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some((names, depth, storage)) = self.expanding.take() {
+            if let Err(err) = self.expand(names, depth, storage) {
+                // an error ends the iteration
+                self.pending.clear();
+                return Some(Err(err));
+            }
+        }
+
+        let (names, depth, entry) = self.pending.pop()?;
+        if !matches!(entry, Entry::Stream(_)) {
+            self.expanding = Some((names.clone(), depth, entry.clone()));
+        }
+        Some(Ok((names, entry)))
+    }
+}
+
+impl<'a> FusedIterator for Walk<'a> {}
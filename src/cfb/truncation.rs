@@ -0,0 +1,103 @@
+use crate::cfb::warning::Warnings;
+use crate::cfb::{storage, Cfb, Entry, Error, SectorNumber, StreamReader};
+
+/// The diagnosis of a compound file cut short, returned by [`Cfb::truncation_report`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TruncationReport {
+    /// The length in bytes of the file.
+    pub file_len: u64,
+    /// The entries whose data extends past the end of the file, in the order of a depth-first walk
+    /// of the directory.
+    pub entries: Vec<TruncatedEntry>,
+    /// Whether a FAT sector extends past the end of the file.
+    pub fat_truncated: bool,
+    /// Whether a directory sector extends past the end of the file, in which case the entries it
+    /// held are missing from the report.
+    pub directory_truncated: bool,
+    /// Whether a mini FAT sector extends past the end of the file.
+    pub mini_fat_truncated: bool,
+}
+
+impl TruncationReport {
+    /// Returns whether anything extends past the end of the file
+    pub fn is_truncated(&self) -> bool {
+        !self.entries.is_empty() || self.fat_truncated || self.directory_truncated || self.mini_fat_truncated
+    }
+}
+
+/// An entry whose data extends past the end of the file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TruncatedEntry {
+    /// The full path of the entry, whose components are separated by `/`, which is empty for the
+    /// root storage, whose data is the mini stream.
+    pub path: String,
+    /// The size in bytes declared by the entry.
+    pub declared: u64,
+    /// The number of bytes of the data, from its start, which lie within the file. A
+    /// [`StreamReader`] fails with [`Error::Truncated`] carrying the same count.
+    pub available: u64,
+}
+
+impl Cfb {
+    /// Diagnoses a compound file cut short, reporting the entries whose data extends past the end
+    /// of the file and whether the FAT, the directory and the mini FAT themselves are cut.
+    ///
+    /// Only the FAT, the mini FAT and the directory are read, never the stream data. Their sectors
+    /// are read leniently, so that those cut short read as zeroes past the end of the file.
+    pub fn truncation_report(&self) -> Result<TruncationReport, Error> {
+        // the warnings of the lenient reading aren't those of the compound file
+        let view = Cfb { lenient: true, warnings: Warnings::default(), ..self.clone() };
+        view.lenient_truncation_report()
+    }
+
+    fn lenient_truncation_report(&self) -> Result<TruncationReport, Error> {
+        let file_len = self.source.size().ok_or_else(|| Error::Io(std::io::Error::new(
+            std::io::ErrorKind::Unsupported, "the length of the source is unknown")))?;
+        let sector_size = self.sector_size as u64;
+        // a sector ends a sector size past its byte offset, which the header precedes
+        let past_end = |sector_no: &SectorNumber| {
+            !matches!(sector_no.checked_byte_offset(sector_size), Some(offset) if offset + 2 * sector_size <= file_len)
+        };
+
+        let fat_sectors = self.fat_sector_locations().collect::<Result<Vec<_>, _>>()?;
+        let header = self.header();
+        let directory_sectors = self.sector_chain(header.first_directory_sector_location())?;
+        let mini_fat_sectors = self.sector_chain(header.first_mini_fat_sector_location())?;
+        let mut report = TruncationReport {
+            file_len,
+            entries: Vec::new(),
+            fat_truncated: fat_sectors.iter().any(past_end),
+            directory_truncated: directory_sectors.iter().any(past_end),
+            mini_fat_truncated: mini_fat_sectors.iter().any(past_end),
+        };
+
+        let root_storage = match self.root_storage() {
+            Some(root_storage) => root_storage,
+            None if report.directory_truncated => return Ok(report),
+            None => return Err(Error::InvalidDirectory("the first entry is not the root storage!".to_string())),
+        };
+
+        let mut entries = Vec::new();
+        let (start, size) = root_storage.mini_stream_range();
+        let mut mini_stream = StreamReader::new(self, start, size.0, None);
+        entries.push((String::new(), size.0, mini_stream.available(file_len)?));
+
+        let walked = storage::walk(self, Entry::RootStorage(root_storage), &mut |path, entry| {
+            if let Entry::Stream(stream) = entry {
+                let declared = stream.stream_size().0;
+                entries.push((path.to_string(), declared, stream.reader().available(file_len)?));
+            }
+            Ok(())
+        });
+        // the entries beyond the end of the file can't be walked
+        if !report.directory_truncated {
+            walked?;
+        }
+
+        report.entries = entries.into_iter()
+            .filter(|(_, declared, available)| available < declared)
+            .map(|(path, declared, available)| TruncatedEntry { path, declared, available })
+            .collect();
+        Ok(report)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::cfb::reader::MINI_SECTOR_SIZE;
+use crate::cfb::storage::{children, walk};
+use crate::cfb::{Cfb, Entry, Error, ObjectType};
+
+/// The bytes used by a subtree of a compound file.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub struct SizeTotals {
+    /// The sum of the sizes declared by the stream objects.
+    pub declared: u64,
+    /// The sum of the bytes of the sectors, or mini sectors, allocated to the stream objects, each
+    /// stream rounded up to whole sectors. The difference with [`declared`](Self::declared) is the
+    /// slack left at the ends of the last sectors.
+    pub allocated: u64,
+    /// The number of storage objects and stream objects.
+    pub entries: u64,
+}
+
+impl SizeTotals {
+    fn add(&mut self, other: Self) {
+        self.declared += other.declared;
+        self.allocated += other.allocated;
+        self.entries += other.entries;
+    }
+}
+
+/// The bytes used by an object directly contained in a storage, and everything beneath it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ChildSize {
+    pub name: String,
+    pub object_type: ObjectType,
+    /// The totals of the object, counting itself among the entries.
+    pub totals: SizeTotals,
+}
+
+/// The bytes used by a storage, returned by [`Cfb::storage_size`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StorageSizeReport {
+    /// The totals of everything beneath the storage, not counting itself among the entries.
+    pub totals: SizeTotals,
+    /// The objects directly contained in the storage, in the order of the sibling tree.
+    pub children: Vec<ChildSize>,
+}
+
+impl Cfb {
+    /// Sums the bytes used by the streams beneath a storage, as `du` does for a directory, with a
+    /// breakdown by the objects it directly contains. An empty path is the root storage. Returns
+    /// None if the path isn't a storage object. Only the directory is read.
+    ///
+    /// The bytes allocated to a stream are its size rounded up to the sector size, or to the mini
+    /// sector size of 64 bytes for a stream smaller than the [mini stream cutoff size].
+    ///
+    /// [mini stream cutoff size]: crate::cfb::header::Header::mini_stream_cutoff_size
+    pub fn storage_size(&self, path: &str) -> Result<Option<StorageSizeReport>, Error> {
+        let storage = match self.entry_by_path(path)? {
+            Some(storage @ (Entry::RootStorage(_) | Entry::Storage(_))) => storage,
+            _ => return Ok(None),
+        };
+
+        let mut report = StorageSizeReport { totals: SizeTotals::default(), children: Vec::new() };
+        for child in children(storage)? {
+            let name = child.name();
+            let mut totals = self.entry_size(&child);
+            let object_type = match child {
+                Entry::Stream(_) => ObjectType::Stream,
+                _ => {
+                    walk(self, child, &mut |_, entry| {
+                        totals.add(self.entry_size(entry));
+                        Ok(())
+                    })?;
+                    ObjectType::Storage
+                }
+            };
+            report.totals.add(totals);
+            report.children.push(ChildSize { name, object_type, totals });
+        }
+        Ok(Some(report))
+    }
+
+    /// Returns the bytes used by a single entry, not counting what a storage contains
+    fn entry_size(&self, entry: &Entry) -> SizeTotals {
+        let declared = match entry {
+            Entry::Stream(stream) => stream.stream_size().0,
+            _ => 0,
+        };
+        let unit = if declared < self.mini_stream_cutoff() { MINI_SECTOR_SIZE } else { self.sector_size as u64 };
+        SizeTotals { declared, allocated: declared.div_ceil(unit) * unit, entries: 1 }
+    }
+}
@@ -0,0 +1,261 @@
+use crate::cfb::reader::MINI_SECTOR_SIZE;
+use crate::cfb::{Cfb, ColorFlag, Entry, ObjectType, Overlap, SectorNumber};
+use crate::cfb::storage::walk_unchecked;
+use crate::cfb::convert::{sector_index, sector_numbers_per_sector};
+use core::fmt;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt::Formatter;
+
+/// A rule of the specification that a compound file breaks, found by [`Cfb::validate`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Violation {
+    /// The mini FAT has fewer entries than there are mini sectors in the mini stream declared by the
+    /// root storage.
+    MiniFatTooShort {
+        entries: usize,
+        mini_sectors: u64,
+    },
+    /// The chain of the mini FAT sectors differs in length from the count in the header.
+    MiniFatSectorCountMismatch {
+        declared: u32,
+        chain: usize,
+    },
+    /// The chain of the mini stream holds fewer bytes than the size declared by the root storage.
+    MiniStreamChainTooShort {
+        declared: u64,
+        capacity: u64,
+    },
+    /// The mini FAT allocates mini sectors beyond the size of the mini stream declared by the root
+    /// storage.
+    MiniFatBeyondMiniStream {
+        last_allocated: usize,
+        declared: u64,
+    },
+    /// The number of FAT sectors listed in the DIFAT differs from the count in the header.
+    FatSectorCountMismatch {
+        declared: u32,
+        listed: usize,
+    },
+    /// A FAT sector listed in the DIFAT is not marked FATSECT in the FAT.
+    FatSectorNotMarked {
+        sector: SectorNumber,
+        entry: SectorNumber,
+    },
+    /// A sector marked FATSECT in the FAT is not listed in the DIFAT.
+    UnlistedFatSector {
+        sector: SectorNumber,
+    },
+    /// A sector, or a mini sector, is claimed by two owners, such as two streams sharing a sector or
+    /// a stream running through the directory.
+    SharedSector(Overlap),
+    /// The color flag of a directory entry, by its path, is neither red nor black. An empty path
+    /// is the root storage.
+    InvalidColorFlag {
+        path: String,
+        value: u8,
+    },
+    /// A field of a directory entry, by its path and the name of its getter, is not all zeroes
+    /// although the specification says it must, or should, be for the type of the entry, such as
+    /// the object class GUID of a stream object. An empty path is the root storage.
+    NonZeroField {
+        path: String,
+        object_type: ObjectType,
+        field: &'static str,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MiniFatTooShort { entries, mini_sectors } =>
+                write!(f, "the mini FAT has {} entries for {} mini sectors of the mini stream", entries, mini_sectors),
+            Self::MiniFatSectorCountMismatch { declared, chain } =>
+                write!(f, "the header declares {} mini FAT sectors but their chain has {}", declared, chain),
+            Self::MiniStreamChainTooShort { declared, capacity } =>
+                write!(f, "the chain of the mini stream holds {} bytes for its {} declared bytes", capacity, declared),
+            Self::MiniFatBeyondMiniStream { last_allocated, declared } =>
+                write!(f, "the mini FAT allocates mini sector {} beyond the {} bytes of the mini stream", last_allocated, declared),
+            Self::FatSectorCountMismatch { declared, listed } =>
+                write!(f, "the header declares {} FAT sectors but the DIFAT lists {}", declared, listed),
+            Self::FatSectorNotMarked { sector, entry } =>
+                write!(f, "FAT sector {} is marked {} instead of FATSECT", sector, entry),
+            Self::UnlistedFatSector { sector } =>
+                write!(f, "sector {} is marked FATSECT but not listed in the DIFAT", sector),
+            Self::SharedSector(overlap) => write!(f, "{}", overlap),
+            Self::InvalidColorFlag { path, value } =>
+                write!(f, "the color flag of `{}` is 0x{:02X} instead of red or black", path, value),
+            Self::NonZeroField { path, object_type, field } =>
+                write!(f, "the {} of `{}` is not all zeroes as it should be for a {:?} entry", field, path, object_type),
+        }
+    }
+}
+
+impl Cfb {
+    /// Checks the structures of the compound file against the rules of the specification, returning
+    /// every violation found. Unlike reading, which tolerates whatever it can, validating is meant
+    /// for tools flagging corrupted or tampered files.
+    pub fn validate(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        check_fat_sectors(self, &mut violations);
+        check_mini_fat(self, &mut violations);
+        check_shared_sectors(self, &mut violations);
+        check_color_flags(self, &mut violations);
+        check_zero_fields(self, &mut violations);
+        violations
+    }
+}
+
+/// Checks that the size of the mini stream declared by the root storage agrees with its chain and
+/// the mini FAT, which must describe every mini sector of the mini stream and allocate none beyond.
+fn check_mini_fat(cfb: &Cfb, violations: &mut Vec<Violation>) {
+    let (start, declared) = match cfb.root_storage() {
+        Some(root_storage) => (root_storage.starting_sector_location(), root_storage.stream_size().0),
+        None => return,
+    };
+
+    // a chain beyond the limits can't be checked
+    let (chain, table) = match (cfb.sector_chain(start), cfb.mini_fat_table()) {
+        (Ok(chain), Ok(table)) => (chain, table),
+        _ => return,
+    };
+
+    let capacity = chain.len() as u64 * cfb.sector_size as u64;
+    if capacity < declared {
+        violations.push(Violation::MiniStreamChainTooShort { declared, capacity });
+    }
+
+    let declared_mini_fat_sectors = cfb.header().no_of_mini_fat_sectors().0;
+    if table.sector_locations().len() != declared_mini_fat_sectors as usize {
+        violations.push(Violation::MiniFatSectorCountMismatch {
+            declared: declared_mini_fat_sectors,
+            chain: table.sector_locations().len(),
+        });
+    }
+
+    let mini_sectors = declared.div_ceil(MINI_SECTOR_SIZE);
+    if (table.len() as u64) < mini_sectors {
+        violations.push(Violation::MiniFatTooShort { entries: table.len(), mini_sectors });
+    }
+    if let Some(last_allocated) = table.last_allocated() {
+        if (last_allocated as u64 + 1) * MINI_SECTOR_SIZE > declared {
+            violations.push(Violation::MiniFatBeyondMiniStream { last_allocated, declared });
+        }
+    }
+}
+
+/// Checks that the header, the DIFAT and the FAT agree on which sectors hold the FAT.
+fn check_fat_sectors(cfb: &Cfb, violations: &mut Vec<Violation>) {
+    let declared = cfb.header().no_of_fat_sectors().0;
+    let listed: Vec<SectorNumber> = match cfb.fat_sector_locations().collect() {
+        Ok(listed) => listed,
+        Err(_) => return,
+    };
+    if listed.len() != declared as usize {
+        violations.push(Violation::FatSectorCountMismatch { declared, listed: listed.len() });
+    }
+
+    // the entry of a sector lies in the FAT sector listed at its index divided by the entries per
+    // FAT sector
+    let entries_per_sector = sector_index(sector_numbers_per_sector(cfb.sector_size));
+    let fats: Vec<_> = listed.iter()
+        .map(|sector_no| if sector_no.is_other() { cfb.fat(*sector_no).ok() } else { None })
+        .collect();
+    let entry_of = |sector_no: SectorNumber| {
+        let fat = fats.get(sector_index(sector_no.0) / entries_per_sector)?.as_ref()?;
+        fat.get(sector_index(sector_no.0) % entries_per_sector)
+    };
+
+    for sector in &listed {
+        match entry_of(*sector) {
+            Some(entry) if entry.is_fat() => {}
+            Some(entry) => violations.push(Violation::FatSectorNotMarked { sector: *sector, entry }),
+            None => violations.push(Violation::FatSectorNotMarked { sector: *sector, entry: SectorNumber::FREESECT }),
+        }
+    }
+
+    let listed: HashSet<SectorNumber> = listed.into_iter().collect();
+    for (index, fat) in fats.iter().enumerate() {
+        let marked = fat.iter()
+            .flat_map(|fat| fat.iter().enumerate())
+            .filter(|(_, entry)| entry.is_fat())
+            // a FAT listed beyond the sector numbers can't mark a sector
+            .filter_map(|(offset, _)| u32::try_from(index * entries_per_sector + offset).ok())
+            .map(SectorNumber);
+        for sector in marked {
+            if !listed.contains(&sector) {
+                violations.push(Violation::UnlistedFatSector { sector });
+            }
+        }
+    }
+}
+
+/// Checks that no sector, or mini sector, is claimed by two owners, as mapped by the sector map.
+fn check_shared_sectors(cfb: &Cfb, violations: &mut Vec<Violation>) {
+    // a chain beyond the limits can't be checked
+    if let Ok(map) = cfb.sector_map() {
+        violations.extend(map.overlaps().iter().cloned().map(Violation::SharedSector));
+    }
+}
+
+/// Checks that every directory entry reachable from the root storage is either red or black.
+fn check_color_flags(cfb: &Cfb, violations: &mut Vec<Violation>) {
+    let root_storage = match cfb.root_storage() {
+        Some(root_storage) => root_storage,
+        None => return,
+    };
+
+    let mut check = |path: &str, entry: &Entry| {
+        if let ColorFlag::Unknown(value) = entry.color_flag() {
+            violations.push(Violation::InvalidColorFlag { path: path.to_string(), value });
+        }
+    };
+    let root_storage = Entry::RootStorage(root_storage);
+    check("", &root_storage);
+    // a corrupted tree is reported by reading
+    let _ = walk_unchecked(cfb, root_storage, &mut |path, entry| {
+        check(path, entry);
+        Ok(())
+    });
+}
+
+/// Checks that the fields of every directory entry reachable from the root storage which the
+/// specification wants zeroed for its type are all zeroes.
+fn check_zero_fields(cfb: &Cfb, violations: &mut Vec<Violation>) {
+    let root_storage = match cfb.root_storage() {
+        Some(root_storage) => root_storage,
+        None => return,
+    };
+
+    let mut check = |path: &str, entry: &Entry| {
+        let (object_type, fields) = match entry {
+            Entry::RootStorage(root_storage) => (ObjectType::RootStorage, vec![
+                ("creation_time", root_storage.creation_filetime() != 0),
+            ]),
+            Entry::Storage(storage) => (ObjectType::Storage, vec![
+                ("starting_sector_location", storage.starting_sector_location().0 != 0),
+                ("stream_size", storage.stream_size().0 != 0),
+            ]),
+            Entry::Stream(stream) => (ObjectType::Stream, vec![
+                ("cls_id", stream.cls_id() != [0; 16]),
+                ("state_bits", !stream.state_bits().is_empty()),
+                ("creation_time", stream.creation_filetime() != 0),
+                ("modified_time", stream.modified_filetime() != 0),
+            ]),
+            Entry::Unknown | Entry::Malformed { .. } => return,
+        };
+        for (field, non_zero) in fields {
+            if non_zero {
+                violations.push(Violation::NonZeroField { path: path.to_string(), object_type, field });
+            }
+        }
+    };
+    let root_storage = Entry::RootStorage(root_storage);
+    check("", &root_storage);
+    // a corrupted tree is reported by reading
+    let _ = walk_unchecked(cfb, root_storage, &mut |path, entry| {
+        check(path, entry);
+        Ok(())
+    });
+}
@@ -0,0 +1,131 @@
+use alloc::string::String;
+use core::fmt;
+use core::fmt::Formatter;
+#[cfg(feature = "std")]
+use std::sync::{Arc, Mutex};
+
+/// A rule of the specification which a compound file breaks, but which reading tolerates.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Rule {
+    /// The minor version isn't 0x003E.
+    MinorVersion,
+    /// The byte order mark isn't 0xFFFE. The integers are read as little-endian regardless.
+    ByteOrder,
+    /// The sector shift doesn't match the major version, such as one of 10 or 11. The sector shift is
+    /// used regardless.
+    SectorShift,
+    /// The mini sector shift isn't 0x0006. Mini sectors are read as 64 bytes regardless.
+    MiniSectorShift,
+    /// The mini stream cutoff size isn't 4,096 bytes. The cutoff is clamped to 4,096 bytes.
+    MiniStreamCutoff,
+    /// The most significant 32 bits of the size of a stream in a version 3 compound file aren't
+    /// zero. They are masked off.
+    StreamSizeHighBits,
+    /// The color flag of a directory entry is neither red nor black. It is ignored, as only writing
+    /// balances the sibling trees by color.
+    ColorFlag,
+    /// The object type of a directory entry is none of the valid values. The slot is yielded as
+    /// [malformed](crate::cfb::Entry::Malformed) and left out of the sibling trees.
+    ObjectType,
+    /// The name length of a directory entry is odd, beyond the 64 bytes of the name field, or
+    /// doesn't point right after the terminating null character. The name is read up to the first
+    /// null character of the field.
+    NameLength,
+    /// A sibling or child ID of a directory entry is above MAXREGSID without being NOSTREAM, or
+    /// beyond the directory entry slots. The link is taken as absent.
+    StreamIdLink,
+    /// A FAT, DIFAT, mini FAT or directory sector lies partly or wholly past the end of the file.
+    /// The bytes missing are read as zeroes.
+    TruncatedSector,
+}
+
+impl Rule {
+    /// Returns the identifier of the rule, such as `header.byte_order`
+    pub fn id(&self) -> &'static str {
+        match self {
+            Self::MinorVersion => "header.minor_version",
+            Self::ByteOrder => "header.byte_order",
+            Self::SectorShift => "header.sector_shift",
+            Self::MiniSectorShift => "header.mini_sector_shift",
+            Self::MiniStreamCutoff => "header.mini_stream_cutoff",
+            Self::StreamSizeHighBits => "entry.stream_size",
+            Self::ColorFlag => "entry.color_flag",
+            Self::ObjectType => "entry.object_type",
+            Self::NameLength => "entry.name_length",
+            Self::StreamIdLink => "entry.stream_id_link",
+            Self::TruncatedSector => "sector.truncated",
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Where in a compound file a [`Warning`] was raised.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Location {
+    /// A field of the header, at its byte offset.
+    Header { offset: u64 },
+    /// A directory entry, at its byte offset, with its name.
+    Entry { offset: u64, name: String },
+    /// A directory entry slot too malformed to be named, at its byte offset, with its stream ID.
+    Slot { id: u32, offset: u64 },
+    /// A sector, by its sector number, at its byte offset.
+    Sector { sector: u32, offset: u64 },
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Header { offset } => write!(f, "header offset {}", offset),
+            Self::Entry { offset, name } => write!(f, "entry `{}` at offset {}", name, offset),
+            Self::Slot { id, offset } => write!(f, "stream ID {} at offset {}", id, offset),
+            Self::Sector { sector, offset } => write!(f, "sector {} at offset {}", sector, offset),
+        }
+    }
+}
+
+/// A deviation from the specification tolerated while reading a compound file, returned by
+/// [`Cfb::warnings`].
+///
+/// [`Cfb::warnings`]: crate::cfb::Cfb::warnings
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Warning {
+    pub rule: Rule,
+    pub location: Location,
+    /// The value found, as it would be reported by the [`Error`] of a strict reading.
+    ///
+    /// [`Error`]: crate::cfb::Error
+    pub value: u64,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}: 0x{:X}", self.rule, self.location, self.value)
+    }
+}
+
+/// The warnings of a compound file, shared by its clones, each raised once however many times the
+/// deviation is read.
+#[cfg(feature = "std")]
+#[derive(Clone, Default)]
+pub(crate) struct Warnings(Arc<Mutex<Vec<Warning>>>);
+
+#[cfg(feature = "std")]
+impl Warnings {
+    pub(crate) fn push(&self, rule: Rule, location: Location, value: u64) {
+        let mut warnings = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !warnings.iter().any(|warning| warning.rule == rule && warning.location == location) {
+            warnings.push(Warning { rule, location, value });
+        }
+    }
+
+    pub(crate) fn to_vec(&self) -> Vec<Warning> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone()
+    }
+}
@@ -1,15 +1,23 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
 pub mod cfb;
 
+#[cfg(feature = "alloc")]
 macro_rules! impl_for_hex_debug {
     ($type:ident, $hex_mask:literal) => {
         impl fmt::Debug for $type {
-            fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-                f.write_str(&format!(concat!(stringify!($type), "(0x{:0", $hex_mask, "X})") , self.0))
+            fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+                write!(f, concat!(stringify!($type), "(0x{:0", $hex_mask, "X})") , self.0)
             }
         }
     };
 }
 
+#[cfg(feature = "alloc")]
 pub(crate) use impl_for_hex_debug;
 
 /// Adds entries to a debug map formatter, with the getter method names being the keys.
@@ -19,7 +27,7 @@ pub(crate) use impl_for_hex_debug;
 ///
 /// # Examples
 ///
-/// ```
+/// ```ignore
 /// impl fmt::Debug for Cfb {
 ///     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
 ///         let mut fmt = f.debug_map();
@@ -28,6 +36,7 @@ pub(crate) use impl_for_hex_debug;
 ///     }
 /// }
 /// ```
+#[cfg(feature = "alloc")]
 macro_rules! debug_map_method_reflection {
     ($fmt:tt, $receiver:tt, $first:ident, $($methods:tt)*) => {
         $fmt.entry(&stringify!($first), &$receiver.$first());
@@ -39,9 +48,10 @@ macro_rules! debug_map_method_reflection {
     }
 }
 
+#[cfg(feature = "alloc")]
 pub(crate) use debug_map_method_reflection;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
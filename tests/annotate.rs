@@ -0,0 +1,46 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{annotations_to_json, annotations_to_tsv, Cfb};
+    use crate::common::CfbBuilder;
+
+    #[test]
+    fn word_document_annotations() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let annotations = cfb.annotations().unwrap();
+        let golden = std::fs::read_to_string("tests_rsc/testing.doc.annotations.tsv").unwrap();
+        assert_eq!(annotations_to_tsv(&annotations), golden);
+
+        let json = annotations_to_json(&annotations);
+        assert!(json.starts_with("[\n  {\"offset\": 0, \"length\": 8, \"label\": \"header.signature\"},\n"));
+        assert!(json.contains("{\"offset\": 13504, \"length\": 64, \"label\": \"stream `\\\\u{5}SummaryInformation` mini sector 0\"},"));
+        assert!(json.ends_with("{\"offset\": 14848, \"length\": 512, \"label\": \"FAT sector 0\"}\n]\n"));
+    }
+
+    #[test]
+    fn annotations_cover_the_file() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Big", &[1; 5000]);
+        builder.stream(0, "Small", &[2; 100]);
+        let mut bytes = builder.sector_shift(12).build();
+        bytes.extend_from_slice(&[0; 10]);
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap();
+
+        let annotations = cfb.annotations().unwrap();
+        assert_eq!(annotations[18].range, 512..4096);
+        assert_eq!(annotations[18].label, "header.padding");
+        let mut offset = 0;
+        for annotation in &annotations {
+            assert_eq!(annotation.range.start, offset, "{}", annotation.label);
+            offset = annotation.range.end;
+        }
+        assert_eq!(offset, bytes.len() as u64);
+
+        let labels: Vec<&str> = annotations.iter().map(|annotation| annotation.label.as_str()).collect();
+        assert!(labels.contains(&"stream `Big` sector 1"));
+        assert!(labels.contains(&"stream `Small` mini sector 1"));
+        assert_eq!(labels.last(), Some(&"trailing bytes"));
+    }
+}
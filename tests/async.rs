@@ -0,0 +1,50 @@
+#[cfg(all(test, feature = "async"))]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::r#async::AsyncCfb;
+    use ole_kit::cfb::{Cfb, Error};
+
+    const WORD_STREAMS: [&str; 5] = ["Data", "1Table", "WordDocument", "\u{5}SummaryInformation",
+                                     "\u{5}DocumentSummaryInformation"];
+
+    #[tokio::test]
+    async fn reads_like_the_synchronous_api() {
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let cfb = AsyncCfb::open("tests_rsc/testing.doc").await.unwrap();
+        assert_eq!(format!("{:?}", cfb.header()), format!("{:?}", expected.header()));
+        for name in WORD_STREAMS {
+            let entry = cfb.directory_entry(name).unwrap();
+            assert_eq!(format!("{:?}", entry), format!("{:?}", expected.directory_entry(name).unwrap()));
+            assert_eq!(cfb.stream_bytes(name).await.unwrap(), expected.stream_bytes(name).unwrap(), "{}", name);
+        }
+        assert_eq!(cfb.mini_stream_bytes().await.unwrap(), expected.mini_stream_bytes().unwrap());
+        assert!(matches!(cfb.stream_bytes("Missing").await, Err(Error::NotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn reads_from_any_async_reader() {
+        let bytes = std::fs::read("tests_rsc/hwp5.0.hwp").unwrap();
+        let expected = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        let cfb = AsyncCfb::new(Cursor::new(bytes.clone())).await.unwrap();
+        for name in ["FileHeader", "Section0", "Section3", "PrvText"] {
+            assert_eq!(cfb.stream_bytes(name).await.unwrap(), expected.stream_bytes(name).unwrap(), "{}", name);
+            assert_eq!(cfb.directory_entry(name).unwrap().offset(), expected.directory_entry(name).unwrap().offset());
+        }
+        assert!(matches!(AsyncCfb::new(Cursor::new(b"plain text".to_vec())).await, Err(Error::NotCfb { .. })));
+    }
+
+    #[tokio::test]
+    async fn resolves_the_chain_before_reading() {
+        // WordDocument starting in sector 0x100, past the end of the reader
+        let mut bytes = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let directory = Cfb::from_path("tests_rsc/testing.doc").unwrap().header().first_directory_sector_location();
+        let offset = (directory.0 as usize + 1) * 512 + 2 * 128 + 116;
+        bytes[offset..offset + 4].copy_from_slice(&0x100u32.to_le_bytes());
+
+        // the synchronous read fails at the first sector, the asynchronous one at the chain
+        let cfb = Cfb::from_bytes(bytes.clone()).unwrap();
+        assert!(matches!(cfb.stream_bytes("WordDocument"), Err(Error::Truncated { size: 4096, available: 0 })));
+        let cfb = AsyncCfb::new(Cursor::new(bytes)).await.unwrap();
+        assert!(matches!(cfb.stream_bytes("WordDocument").await, Err(Error::TruncatedChain { size: 4096, available: 512 })));
+    }
+}
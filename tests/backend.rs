@@ -0,0 +1,178 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, Entry, Error, ReadAt};
+
+    const KEY: u8 = 0x5A;
+
+    /// A container holding its content XOR-ed with a key, deciphered on every read
+    struct XorSource(Vec<u8>);
+
+    impl ReadAt for XorSource {
+        fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+            let read = self.0.read_at(buf, offset)?;
+            buf[..read].iter_mut().for_each(|byte| *byte ^= KEY);
+            Ok(read)
+        }
+
+        fn size(&self) -> Option<u64> {
+            Some(self.0.len() as u64)
+        }
+    }
+
+    fn names(cfb: &Cfb) -> Vec<String> {
+        cfb.open_storage("").unwrap().children().unwrap().iter().map(Entry::name).collect()
+    }
+
+    #[test]
+    fn reads_through_custom_source() {
+        let plain = std::fs::read("tests_rsc/hwp5.0.hwp").unwrap();
+        let ciphered: Vec<u8> = plain.iter().map(|byte| byte ^ KEY).collect();
+
+        let expected = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        let cfb = Cfb::from_read_at(XorSource(ciphered), 0, None).unwrap();
+        assert_eq!(cfb.limits(), expected.limits());
+        assert_eq!(names(&cfb), names(&expected));
+
+        let mut section = Vec::new();
+        cfb.open_stream("Section3").unwrap().read_to_end(&mut section).unwrap();
+        assert_eq!(section.len(), 174052);
+        assert_eq!(section, expected.stream_bytes("Section3").unwrap());
+
+        // the stream readers share the source across threads
+        let reader = cfb.open_stream("FileHeader").unwrap();
+        let header = std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            { reader }.read_to_end(&mut bytes).unwrap();
+            bytes
+        }).join().unwrap();
+        assert_eq!(header, expected.stream_bytes("FileHeader").unwrap());
+    }
+
+    #[test]
+    fn reads_from_cursor() {
+        let bytes = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let cfb = Cfb::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(names(&cfb), names(&expected));
+        assert_eq!(cfb.stream_bytes("WordDocument").unwrap(), expected.stream_bytes("WordDocument").unwrap());
+        assert_eq!(cfb.mini_stream_bytes().unwrap(), expected.mini_stream_bytes().unwrap());
+    }
+
+    #[test]
+    fn reads_from_owned_bytes() {
+        let bytes = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let cfb = Cfb::from_bytes(bytes.clone()).unwrap();
+        assert_eq!(format!("{:?}", cfb.header()), format!("{:?}", expected.header()));
+        assert_eq!(cfb.limits(), expected.limits());
+        assert_eq!(names(&cfb), names(&expected));
+        assert_eq!(cfb.directory_entry("1Table").unwrap().offset(), expected.directory_entry("1Table").unwrap().offset());
+        for name in ["Data", "1Table", "WordDocument", "\u{5}SummaryInformation", "\u{5}DocumentSummaryInformation"] {
+            assert_eq!(cfb.stream_bytes(name).unwrap(), expected.stream_bytes(name).unwrap(), "{}", name);
+        }
+        assert_eq!(cfb.mini_stream_bytes().unwrap(), expected.mini_stream_bytes().unwrap());
+
+        // WordDocument starting in sector 0x100, beyond the end of the bytes
+        let mut beyond = bytes.clone();
+        let offset = (cfb.header().first_directory_sector_location().0 as usize + 1) * 512 + 2 * 128 + 116;
+        beyond[offset..offset + 4].copy_from_slice(&0x100u32.to_le_bytes());
+        let cfb = CfbOpenOptions::new().open_bytes(beyond).unwrap();
+        assert!(matches!(cfb.stream_bytes("WordDocument"), Err(Error::Truncated { size: 4096, available: 0 })));
+    }
+
+    #[test]
+    fn reads_from_open_file() {
+        use std::io::{Seek, SeekFrom};
+
+        // the cursor left mid-file doesn't move the reads, which are all positioned
+        let mut file = std::fs::File::open("tests_rsc/hwp5.0.hwp").unwrap();
+        file.seek(SeekFrom::Start(3000)).unwrap();
+        let expected = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        let cfb = Cfb::from_file(file).unwrap();
+        assert_eq!(cfb.limits(), expected.limits());
+        assert_eq!(names(&cfb), names(&expected));
+        assert_eq!(cfb.stream_bytes("Section3").unwrap(), expected.stream_bytes("Section3").unwrap());
+
+        let cfb = CfbOpenOptions::new().open_file(std::fs::File::open("tests_rsc/testing.doc").unwrap()).unwrap();
+        assert_eq!(cfb.stream_bytes("WordDocument").unwrap().len(), 4096);
+        assert!(matches!(Cfb::from_file(std::fs::File::open("Cargo.toml").unwrap()), Err(Error::NotCfb { .. })));
+    }
+
+    /// A reader which can't seek, as a pipe, counting the bytes it gave
+    struct Pipe<R>(R, usize);
+
+    impl<R: Read> Read for Pipe<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let read = self.0.read(buf)?;
+            self.1 += read;
+            Ok(read)
+        }
+    }
+
+    #[test]
+    fn reads_from_pipe() {
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let mut pipe = Pipe(std::fs::File::open("tests_rsc/testing.doc").unwrap(), 0);
+        let cfb = Cfb::read_from(&mut pipe).unwrap();
+        assert_eq!(pipe.1, 15360);
+        assert_eq!(names(&cfb), names(&expected));
+        assert_eq!(cfb.stream_bytes("WordDocument").unwrap(), expected.stream_bytes("WordDocument").unwrap());
+        assert_eq!(cfb.mini_stream_bytes().unwrap(), expected.mini_stream_bytes().unwrap());
+
+        // anything else fails at the header, without draining the pipe
+        let mut pipe = Pipe(std::io::repeat(b'x'), 0);
+        assert!(matches!(Cfb::read_from(&mut pipe), Err(Error::NotCfb { .. })));
+        assert_eq!(pipe.1, 512);
+    }
+
+    #[test]
+    fn reads_from_static_slice() {
+        static DOC: &[u8] = include_bytes!("../tests_rsc/testing.doc");
+
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let cfb = CfbOpenOptions::new().max_tree_depth(8).open_read_at(DOC, 0, None).unwrap();
+        assert_eq!(names(&cfb), names(&expected));
+        assert_eq!(cfb.stream_bytes("WordDocument").unwrap(), expected.stream_bytes("WordDocument").unwrap());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn reads_from_memory_map() {
+        let file = std::fs::File::open("tests_rsc/testing.doc").unwrap();
+        let map = std::sync::Arc::new(unsafe { memmap2::Mmap::map(&file) }.unwrap());
+
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let cfb = Cfb::from_read_at(map, 0, None).unwrap();
+        assert_eq!(names(&cfb), names(&expected));
+        assert_eq!(cfb.stream_bytes("WordDocument").unwrap(), expected.stream_bytes("WordDocument").unwrap());
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn maps_by_path() {
+        for path in ["tests_rsc/testing.doc", "tests_rsc/hwp5.0.hwp"] {
+            let expected = Cfb::from_path(path).unwrap();
+            let cfb = unsafe { Cfb::from_path_mmap(path) }.unwrap();
+            assert_eq!(cfb.limits(), expected.limits());
+            for stream in expected.fragmentation_report().unwrap() {
+                let bytes = |cfb: &Cfb| match cfb.entry_by_path(&stream.path).unwrap() {
+                    Some(Entry::Stream(stream)) => stream.bytes().unwrap(),
+                    entry => panic!("unexpected entry {:?}", entry),
+                };
+                assert_eq!(bytes(&cfb), bytes(&expected), "{} in {}", stream.path, path);
+            }
+        }
+
+        // a map fails to read past its end, here with WordDocument starting in sector 0x100
+        let mut bytes = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let directory = Cfb::from_path("tests_rsc/testing.doc").unwrap().header().first_directory_sector_location();
+        let offset = (directory.0 as usize + 1) * 512 + 2 * 128 + 116;
+        bytes[offset..offset + 4].copy_from_slice(&0x100u32.to_le_bytes());
+        let path = std::env::temp_dir().join(format!("ole_kit_mmap_{}.doc", std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        let cfb = unsafe { CfbOpenOptions::new().open_mmap(path.to_str().unwrap()) };
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(cfb.unwrap().stream_bytes("WordDocument"), Err(Error::Truncated { size: 4096, available: 0 })));
+    }
+}
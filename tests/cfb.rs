@@ -1,12 +1,1119 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
-    use ole_kit::cfb::Cfb;
+    use std::io::{Read, Seek, SeekFrom};
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, ColorFlag, CommonProps, Entry, Error, ObjectType, SectorNumber, StreamID, StreamSize};
+    use crate::common::{CfbBuilder, entry_offset, put_u32};
 
     #[test]
     fn read_stream_bytes() {
         let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
-        let word_document_bytes = cfb.stream_bytes("WordDocument");
-        assert!(word_document_bytes.is_some());
-        assert_eq!(word_document_bytes.as_ref().map(Vec::len), Some(4096));
+        let word_document_bytes = cfb.stream_bytes("WordDocument").unwrap();
+        assert_eq!(word_document_bytes.len(), 4096);
+    }
+
+    #[test]
+    fn copy_stream_to_writer() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let mut sink = Vec::new();
+        assert_eq!(cfb.copy_stream_to("WordDocument", &mut sink).unwrap(), 4096);
+        assert_eq!(sink, cfb.stream_bytes("WordDocument").unwrap());
+
+        // a small stream is copied from the mini stream
+        let stream = match cfb.entry_by_path("\u{5}SummaryInformation").unwrap() {
+            Some(Entry::Stream(stream)) => stream,
+            entry => panic!("{:?}", entry),
+        };
+        assert!(stream.stream_size().0 < 4096);
+        let mut sink = Cursor::new(Vec::new());
+        assert_eq!(stream.copy_to(&mut sink).unwrap(), stream.stream_size().0);
+        assert_eq!(sink.into_inner(), stream.bytes().unwrap());
+
+        assert!(matches!(cfb.copy_stream_to("Nothing", &mut Vec::new()), Err(Error::NotFound { .. })));
+    }
+
+    #[test]
+    fn stream_bytes_have_the_declared_size() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Sectors", &[1; 4000]);
+        builder.stream(0, "MiniSectors", &[2; 100]);
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        // no slack from the last sector or mini sector of the chain
+        assert_eq!(cfb.stream_bytes("Sectors").unwrap(), vec![1; 4000]);
+        assert_eq!(cfb.stream_bytes("MiniSectors").unwrap(), vec![2; 100]);
+        match cfb.entry_by_path("").unwrap() {
+            Some(Entry::RootStorage(root_storage)) =>
+                assert_eq!(cfb.mini_stream_bytes().unwrap().len() as u64, root_storage.stream_size().0),
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+    }
+
+    #[test]
+    fn for_each_chunk_of_stream() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        for name in ["WordDocument", "\u{5}SummaryInformation"] {
+            let mut chunks = Vec::new();
+            let passed = cfb.for_each_chunk(name, |chunk| chunks.push(chunk.to_vec())).unwrap();
+            assert!(chunks.iter().all(|chunk| chunk.len() <= 512));
+            assert_eq!(chunks.concat(), cfb.stream_bytes(name).unwrap());
+            assert_eq!(passed, chunks.concat().len() as u64);
+        }
+
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Empty", b"");
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+        assert_eq!(cfb.for_each_chunk("Empty", |_| panic!("an empty stream has no chunk")).unwrap(), 0);
+    }
+
+    #[test]
+    fn find_needles_across_sector_boundaries() {
+        let mut large = vec![0u8; 5000];
+        large[510..515].copy_from_slice(b"MAGIC");
+        large[4000..4005].copy_from_slice(b"MAGIC");
+        let mut small = vec![0u8; 200];
+        small[62..67].copy_from_slice(b"MAGIC");
+        // beyond a megabyte, the stream is read in several runs
+        let mut huge = vec![0u8; (1 << 20) + 512];
+        huge[(1 << 20) - 2..(1 << 20) + 3].copy_from_slice(b"MAGIC");
+
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Large", &large);
+        builder.stream(0, "Small", &small);
+        builder.stream(0, "Huge", &huge);
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        // straddling the first two 512-byte sectors
+        assert_eq!(cfb.stream_find("Large", b"MAGIC").unwrap(), Some(510));
+        assert_eq!(cfb.stream_find_all("Large", b"MAGIC").unwrap(), [510, 4000]);
+        // straddling the first two 64-byte mini sectors
+        assert_eq!(cfb.stream_find("Small", b"MAGIC").unwrap(), Some(62));
+        assert_eq!(cfb.stream_find_all("Huge", b"MAGIC").unwrap(), [(1 << 20) - 2]);
+
+        assert_eq!(cfb.stream_find("Small", b"MAGICK").unwrap(), None);
+        assert_eq!(cfb.stream_find_all("Small", b"\0\0\0").unwrap().len(), 200 - 5 - 2 * 2);
+        assert_eq!(cfb.stream_find("Small", b"").unwrap(), Some(0));
+        // the zeroes past the stream size in its last mini sector aren't searched
+        assert_eq!(cfb.stream_find("Small", &[0; 134]).unwrap(), None);
+        assert_eq!(cfb.stream_find_all("Small", &[0; 133]).unwrap(), [67]);
+        assert!(matches!(cfb.stream_find("Missing", b"MAGIC"), Err(Error::NotFound { .. })));
+    }
+
+    #[test]
+    fn stream_sector_chains() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let word_document = match cfb.entry_by_path("WordDocument").unwrap() {
+            Some(Entry::Stream(stream)) => stream,
+            entry => panic!("{:?}", entry),
+        };
+        let chain = word_document.sector_chain().unwrap();
+        let size = word_document.stream_size().0;
+        assert!(chain.len() as u64 * 512 >= size);
+        assert!((chain.len() as u64 - 1) * 512 < size);
+        assert_eq!(chain[0], word_document.starting_sector_location());
+        assert_eq!(cfb.byte_offset_of_sector(chain[0]).unwrap(), (chain[0].0 as u64 + 1) * 512);
+        assert!(matches!(cfb.byte_offset_of_sector(SectorNumber::FREESECT), Err(Error::InvalidSectorNumber(_))));
+
+        let root_storage = match cfb.entry_by_path("").unwrap() {
+            Some(Entry::RootStorage(root_storage)) => root_storage,
+            entry => panic!("{:?}", entry),
+        };
+        assert_eq!(root_storage.mini_stream_chain().unwrap(), root_storage.mini_stream_sectors().unwrap());
+    }
+
+    #[test]
+    fn broken_sector_chains() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Large", &[1; 5000]);
+        let bytes = builder.build();
+        let chain = |bytes: Vec<u8>| {
+            let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+            match cfb.entry_by_path("Large").unwrap() {
+                Some(Entry::Stream(stream)) => (stream.starting_sector_location(), stream.sector_chain()),
+                entry => panic!("{:?}", entry),
+            }
+        };
+        let (start, sectors) = chain(bytes.clone());
+        assert_eq!(sectors.unwrap().len(), 10);
+
+        // the FAT starts at sector 0, right after the header
+        let fat_entry = |sector: u32| 512 + 4 * sector as usize;
+        let fifth = start.0 + 4;
+        for (next, expected) in [
+            (SectorNumber::FREESECT, Some(SectorNumber::FREESECT)),
+            (SectorNumber::FATSECT, Some(SectorNumber::FATSECT)),
+            (SectorNumber::DIFSECT, Some(SectorNumber::DIFSECT)),
+            (start, Some(start)),
+            (SectorNumber(100_000), None),
+        ] {
+            let mut bytes = bytes.clone();
+            put_u32(&mut bytes, fat_entry(fifth), next.0);
+            let (_, sectors) = chain(bytes);
+            match sectors {
+                Err(Error::BrokenChain { sector, next }) => {
+                    let broken_at = if expected.is_some() { SectorNumber(fifth) } else { SectorNumber(100_000) };
+                    assert_eq!((sector, next), (broken_at, expected));
+                }
+                sectors => panic!("{:?}", sectors),
+            }
+        }
+    }
+
+    #[test]
+    fn locate_mini_sectors() {
+        let cfb = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        let bytes = std::fs::read("tests_rsc/hwp5.0.hwp").unwrap();
+        let root_storage = match cfb.entry_by_path("").unwrap() {
+            Some(Entry::RootStorage(root_storage)) => root_storage,
+            entry => panic!("{:?}", entry),
+        };
+
+        let mut highest = 0;
+        for entry in cfb.entries() {
+            let stream = match entry.unwrap() {
+                Entry::Stream(stream) if (1..4096).contains(&stream.stream_size().0) => stream,
+                _ => continue,
+            };
+            let chain = stream.mini_sector_chain().unwrap();
+            assert_eq!(chain, stream.sector_chain().unwrap());
+            highest = highest.max(chain.iter().map(|mini_sector| mini_sector.0).max().unwrap());
+
+            // the data read from where the mini sectors lie is the stream
+            let mut data = Vec::new();
+            for (sector, offset) in root_storage.mini_sector_locations(&chain).unwrap() {
+                assert!(offset < 512 && offset % 64 == 0);
+                let start = (cfb.byte_offset_of_sector(sector).unwrap() + offset) as usize;
+                data.extend_from_slice(&bytes[start..start + 64]);
+            }
+            data.truncate(stream.stream_size().0 as usize);
+            assert_eq!(data, stream.bytes().unwrap(), "{}", stream.name());
+        }
+        // the chains reach into the last of the four mini FAT sectors, of 128 entries each
+        assert!(highest >= 384, "{}", highest);
+
+        assert!(matches!(root_storage.mini_sector_locations(&[SectorNumber(1 << 24)]), Err(Error::TruncatedChain { .. })));
+        assert!(matches!(root_storage.mini_sector_locations(&[SectorNumber::ENDOFCHAIN]), Err(Error::InvalidSectorNumber(_))));
+    }
+
+    #[test]
+    fn seek_within_stream() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let word_document_bytes = cfb.stream_bytes("WordDocument").unwrap();
+
+        let mut reader = cfb.open_stream("WordDocument").unwrap();
+        assert_eq!(reader.len(), 4096);
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, word_document_bytes);
+
+        let mut buf = [0u8; 600];
+        reader.seek(SeekFrom::Start(300)).unwrap();
+        reader.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf[..], &word_document_bytes[300..900]);
+
+        reader.seek(SeekFrom::Current(-100)).unwrap();
+        reader.read_exact(&mut buf[..10]).unwrap();
+        assert_eq!(&buf[..10], &word_document_bytes[800..810]);
+
+        assert_eq!(reader.seek(SeekFrom::End(8)).unwrap(), 4104);
+        assert_eq!(reader.read(&mut buf).unwrap(), 0);
+        assert!(reader.seek(SeekFrom::Current(-5000)).is_err());
+    }
+
+    #[test]
+    fn read_stream_in_chunks() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let word_document_bytes = cfb.stream_bytes("WordDocument").unwrap();
+        let mut reader = cfb.open_stream("WordDocument").unwrap();
+
+        // chunks straddling the sector boundaries
+        let mut bytes = Vec::new();
+        let mut chunk = [0u8; 100];
+        loop {
+            let read = reader.read(&mut chunk).unwrap();
+            if read == 0 {
+                break;
+            }
+            bytes.extend_from_slice(&chunk[..read]);
+        }
+        assert_eq!(bytes, word_document_bytes);
+
+        // seeking backwards rereads the end of the stream
+        assert_eq!(reader.seek(SeekFrom::Current(-1000)).unwrap(), 3096);
+        bytes.clear();
+        reader.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes, word_document_bytes[3096..]);
+    }
+
+    #[test]
+    fn iterate_stream_sectors() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        for (name, unit_size) in [("WordDocument", 512), ("\u{5}SummaryInformation", 64)] {
+            let stream = match cfb.entry_by_path(name).unwrap() {
+                Some(Entry::Stream(stream)) => stream,
+                entry => panic!("unexpected entry {:?}", entry),
+            };
+            let sectors: Vec<_> = stream.sectors().collect::<Result<_, _>>().unwrap();
+            assert!(sectors.iter().rev().skip(1).all(|sector| sector.len() == unit_size));
+            assert_eq!(sectors.concat(), cfb.stream_bytes(name).unwrap());
+        }
+
+        // the chain of a 5,000-byte stream reaches a FREESECT after its first sector
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Sectors", &[1; 5000]);
+        let mut bytes = builder.build();
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap();
+        let start = match cfb.entry_by_path("Sectors").unwrap() {
+            Some(Entry::Stream(stream)) => stream.starting_sector_location(),
+            entry => panic!("unexpected entry {:?}", entry),
+        };
+        put_u32(&mut bytes, 512 + start.0 as usize * 4, SectorNumber::FREESECT.0);
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        let mut sectors = match cfb.entry_by_path("Sectors").unwrap() {
+            Some(Entry::Stream(stream)) => stream.sectors(),
+            entry => panic!("unexpected entry {:?}", entry),
+        };
+        assert_eq!(sectors.next().unwrap().unwrap(), vec![1; 512]);
+        assert!(matches!(sectors.next(), Some(Err(Error::TruncatedChain { size: 5000, available: 512 }))));
+        assert!(sectors.next().is_none());
+    }
+
+    #[test]
+    fn read_mini_stream_resident_stream() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let mut reader = cfb.open_stream("\u{5}SummaryInformation").unwrap();
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 72);
+        // the byte order mark of a property set stream
+        assert_eq!(&bytes[..2], &[0xFE, 0xFF]);
+
+        assert!(cfb.open_stream("Root Entry").is_none());
+        assert!(cfb.open_stream("NoSuchStream").is_none());
+    }
+
+    #[test]
+    fn open_at_offset() {
+        let doc = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let mut wrapped = vec![0x5Au8; 4096];
+        wrapped.extend_from_slice(&doc);
+        wrapped.extend_from_slice(b"trailing bytes of the container");
+
+        let path = std::env::temp_dir().join("ole_kit_open_at_offset.bin");
+        std::fs::write(&path, &wrapped).unwrap();
+
+        let cfb = Cfb::from_path_at(path.to_str().unwrap(), 4096, None).unwrap();
+        assert_eq!(cfb.stream_bytes("WordDocument").unwrap().len(), 4096);
+        std::fs::remove_file(&path).unwrap();
+
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap()
+            .stream_bytes("WordDocument")
+            .unwrap();
+        let cfb = Cfb::from_reader_at(std::io::Cursor::new(wrapped), 4096, Some(doc.len() as u64))
+            .unwrap();
+        assert_eq!(cfb.stream_bytes("WordDocument").unwrap(), expected);
+        assert_eq!(cfb.header().first_directory_sector_location().0, 24);
+
+        assert!(Cfb::from_reader_at(std::io::Cursor::new(doc), 1, None).is_err());
+    }
+
+    #[test]
+    fn opens_any_path() {
+        let path = std::path::PathBuf::from("tests_rsc").join("testing.doc");
+        let cfb = Cfb::from_path(&path).unwrap();
+        assert_eq!(cfb.path(), Some(path.as_path()));
+        assert!(format!("{:?}", cfb).starts_with(r#"{"path": "tests_rsc/testing.doc", "sector_size": 512"#));
+        assert_eq!(Cfb::from_bytes(std::fs::read(&path).unwrap()).unwrap().path(), None);
+
+        // the error names the file which failed to open
+        let err = CfbOpenOptions::new().open("tests_rsc/missing.doc").unwrap_err();
+        assert!(matches!(&err, Error::Io(err) if err.kind() == std::io::ErrorKind::NotFound));
+        assert!(err.to_string().contains("tests_rsc/missing.doc"), "{}", err);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let path = std::env::temp_dir().join(std::ffi::OsStr::from_bytes(b"ole_kit_\xFF.doc"));
+            std::fs::copy("tests_rsc/testing.doc", &path).unwrap();
+            let cfb = Cfb::from_path(&path);
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(cfb.unwrap().stream_bytes("WordDocument").unwrap().len(), 4096);
+        }
+    }
+
+    #[test]
+    fn entries_read_through_their_compound_file() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+
+        let root_storage = match cfb.directory_entry("Root Entry") {
+            Ok(Entry::RootStorage(root_storage)) => root_storage,
+            _ => panic!("the root storage is missing"),
+        };
+        let names: Vec<String> = root_storage.children().unwrap()
+            .iter()
+            .map(|entry| entry.name())
+            .collect();
+        assert_eq!(names, ["Data", "1Table", "WordDocument", "\u{5}SummaryInformation",
+            "\u{5}DocumentSummaryInformation"]);
+
+        match cfb.directory_entry("WordDocument") {
+            Ok(Entry::Stream(stream)) => {
+                assert_eq!(stream.bytes().unwrap(), cfb.stream_bytes("WordDocument").unwrap());
+                assert_eq!(stream.reader().len(), 4096);
+            }
+            _ => panic!("the WordDocument stream is missing"),
+        }
+    }
+
+    #[test]
+    fn list_allocated_entries() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let names: Vec<String> = cfb.allocated_entries()
+            .map(|entry| entry.unwrap().name())
+            .collect();
+        assert_eq!(names.len(), 6);
+        assert!(names.iter().any(|name| name == "Root Entry"));
+        assert!(names.iter().any(|name| name == "WordDocument"));
+
+        let slots = cfb.entries().count();
+        let unallocated = cfb.entries().filter(|entry| matches!(entry, Ok(Entry::Unknown))).count();
+        assert_eq!(slots - unallocated, names.len());
+    }
+
+    #[test]
+    fn storage_children_across_directory_sectors() {
+        let cfb = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+
+        let view_text = match cfb.directory_entry("ViewText") {
+            Ok(Entry::Storage(storage)) => storage,
+            _ => panic!("the ViewText storage is missing"),
+        };
+        let children = view_text.children().unwrap();
+        let names: Vec<String> = children.iter().map(|entry| entry.name()).collect();
+        assert_eq!(names, ["Section0", "Section1", "Section2", "Section3", "Section4", "Section5"]);
+
+        match &children[3] {
+            Entry::Stream(stream) => assert_eq!(stream.bytes().unwrap().len(), 174052),
+            _ => panic!("Section3 is not a stream"),
+        }
+    }
+
+    #[test]
+    fn stream_reader_outlives_cfb() {
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap()
+            .stream_bytes("WordDocument")
+            .unwrap();
+
+        let mut reader = {
+            let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+            cfb.open_stream("WordDocument").unwrap()
+        };
+
+        let bytes = std::thread::spawn(move || {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).map(|_| bytes)
+        }).join().unwrap().unwrap();
+        assert_eq!(bytes, expected);
+    }
+
+    #[test]
+    fn owned_entries_outlive_borrow() {
+        fn root_children(path: &str) -> Vec<ole_kit::cfb::OwnedEntry> {
+            let cfb = Cfb::from_path(path).unwrap();
+            let root_storage = match cfb.directory_entry("Root Entry") {
+                Ok(Entry::RootStorage(root_storage)) => root_storage,
+                _ => panic!("the root storage is missing"),
+            };
+            root_storage.children().unwrap()
+                .iter()
+                .filter_map(Entry::to_owned)
+                .collect()
+        }
+
+        let entries = root_children("tests_rsc/testing.doc");
+        assert_eq!(entries.len(), 5);
+
+        let sizes = std::thread::spawn(move || {
+            entries.iter()
+                .map(|owned| match owned.entry().unwrap() {
+                    Entry::Stream(stream) => (stream.name(), stream.bytes().unwrap().len()),
+                    _ => panic!("the root storage has only streams"),
+                })
+                .collect::<Vec<_>>()
+        }).join().unwrap();
+
+        assert_eq!(sizes, [("Data".to_string(), 4096), ("1Table".to_string(), 4096),
+            ("WordDocument".to_string(), 4096), ("\u{5}SummaryInformation".to_string(), 72),
+            ("\u{5}DocumentSummaryInformation".to_string(), 144)]);
+
+        // the getters of the borrowed entry read the same through the owned one
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        for entry in cfb.entries().map(Result::unwrap).filter(|entry| !matches!(entry, Entry::Unknown)) {
+            let owned = entry.to_owned().unwrap();
+            assert_eq!(owned.name().unwrap(), entry.name());
+            match &entry {
+                Entry::Stream(stream) => {
+                    assert_eq!(owned.object_type(), ObjectType::Stream);
+                    assert_eq!(owned.stream_size().unwrap(), stream.stream_size());
+                    assert_eq!(owned.starting_sector_location().unwrap(), stream.starting_sector_location());
+                    assert_eq!(owned.stream_bytes().unwrap(), stream.bytes().unwrap());
+                }
+                Entry::RootStorage(root_storage) => {
+                    assert_eq!(owned.stream_size().unwrap(), root_storage.stream_size());
+                    assert!(matches!(owned.stream_bytes(), Err(Error::NotAStream { object_type: ObjectType::RootStorage, .. })));
+                }
+                _ => panic!("testing.doc has no storage"),
+            }
+        }
+    }
+
+    #[test]
+    fn navigate_nested_storages() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "__substg1.0_0037001F", "Subject".as_bytes());
+        let recipient = builder.storage(0, "__recip_version1.0_#00000000");
+        builder.stream(recipient, "__substg1.0_3001001F", "Recipient".as_bytes());
+        let attachment_id = builder.storage(0, "__attach_version1.0_#00000000");
+        builder.stream(attachment_id, "__substg1.0_3707001F", "report.msg".as_bytes());
+        let embedded = builder.storage(attachment_id, "__substg1.0_3701000D");
+        builder.stream(embedded, "__substg1.0_0037001F", "Embedded subject".as_bytes());
+        let body_id = builder.stream(embedded, "__substg1.0_1000001F", &[0x42; 5000]);
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        let root = cfb.open_storage("").unwrap();
+        let names: Vec<_> = root.children().unwrap().iter().map(Entry::name).collect();
+        assert_eq!(names, ["__substg1.0_0037001F", "__recip_version1.0_#00000000", "__attach_version1.0_#00000000"]);
+
+        let mut name = String::new();
+        root.open_storage("__recip_version1.0_#00000000").unwrap()
+            .open_stream("__substg1.0_3001001F").unwrap()
+            .read_to_string(&mut name).unwrap();
+        assert_eq!(name, "Recipient");
+
+        let attachment = cfb.open_storage("__attach_version1.0_#00000000").unwrap();
+        let embedded = attachment.open_storage("__substg1.0_3701000D").unwrap();
+        assert_eq!(embedded.children().unwrap().len(), 2);
+        let mut subject = String::new();
+        embedded.open_stream("__substg1.0_0037001F").unwrap().read_to_string(&mut subject).unwrap();
+        assert_eq!(subject, "Embedded subject");
+
+        let mut body_bytes = Vec::new();
+        cfb.open_storage("/__attach_version1.0_#00000000/__substg1.0_3701000D").unwrap()
+            .open_stream("__substg1.0_1000001F").unwrap()
+            .read_to_end(&mut body_bytes).unwrap();
+        assert_eq!(body_bytes, [0x42; 5000]);
+        let mut by_id = Vec::new();
+        cfb.open_stream_at_id(StreamID(body_id as u32)).unwrap().read_to_end(&mut by_id).unwrap();
+        assert_eq!(by_id, body_bytes);
+
+        assert!(attachment.open_storage("__substg1.0_3707001F").is_none());
+        assert!(attachment.open_stream("__substg1.0_3701000D").is_none());
+        assert!(cfb.open_storage("__attach_version1.0_#00000001").is_none());
+        assert!(cfb.open_stream_at_id(StreamID(attachment_id as u32)).is_none());
+    }
+
+    #[test]
+    fn clones_read_identical_data() {
+        fn read_section(cfb: Cfb) -> Vec<u8> {
+            let mut bytes = Vec::new();
+            cfb.open_storage("ViewText").unwrap()
+                .open_stream("Section3").unwrap()
+                .read_to_end(&mut bytes).unwrap();
+            bytes
+        }
+
+        let from_path = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        let file = std::fs::read("tests_rsc/hwp5.0.hwp").unwrap();
+        let from_reader = Cfb::from_reader_at(Cursor::new(file), 0, None).unwrap();
+
+        for cfb in [from_path, from_reader] {
+            let clone = cfb.try_clone().unwrap();
+            assert_eq!(clone.sector_size(), cfb.sector_size());
+
+            let workers = [cfb, clone].map(|cfb| std::thread::spawn(move || read_section(cfb)));
+            let [original, clone] = workers.map(|worker| worker.join().unwrap());
+            assert_eq!(original.len(), 174052);
+            assert_eq!(original, clone);
+        }
+    }
+
+    #[test]
+    fn reads_streams_on_many_threads() {
+        fn assert_shareable<T: Send + Sync>(_: &T) {}
+
+        let names = ["Data", "1Table", "WordDocument", "\u{5}SummaryInformation"];
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        assert_shareable(&cfb);
+        let expected: Vec<_> = names.iter().map(|name| Cfb::from_path("tests_rsc/testing.doc").unwrap().stream_bytes(name).unwrap()).collect();
+
+        let cfb = &cfb;
+        let streams: Vec<_> = std::thread::scope(|scope| {
+            let threads: Vec<_> = names.iter().map(|name| scope.spawn(move || cfb.stream_bytes(name).unwrap())).collect();
+            threads.into_iter().map(|thread| thread.join().unwrap()).collect()
+        });
+        assert_eq!(streams, expected);
+    }
+
+    #[test]
+    fn read_at_within_stream() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        for (name, offset, len) in [("WordDocument", 500, 100), ("\u{5}SummaryInformation", 60, 10)] {
+            let stream = match cfb.directory_entry(name) {
+                Ok(Entry::Stream(stream)) => stream,
+                _ => panic!("{} is missing", name),
+            };
+            let bytes = stream.bytes().unwrap();
+
+            let mut buf = vec![0u8; len];
+            assert_eq!(stream.read_at(offset as u64, &mut buf).unwrap(), len);
+            assert_eq!(buf, bytes[offset..][..len]);
+            assert_eq!(cfb.stream_bytes_range(name, offset as u64, len), Some(buf));
+
+            let mut tail = [0u8; 16];
+            assert_eq!(stream.read_at(bytes.len() as u64 - 4, &mut tail).unwrap(), 4);
+            assert_eq!(tail[..4], bytes[bytes.len() - 4..]);
+            assert_eq!(stream.read_at(bytes.len() as u64 + 1, &mut tail).unwrap(), 0);
+        }
+    }
+
+    #[test]
+    fn mini_fat_table_across_sectors() {
+        let mut builder = CfbBuilder::new();
+        for name in ["A", "B", "C", "D", "E"] {
+            builder.stream(0, name, &[0; 4000]);
+        }
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        let table = cfb.mini_fat_table().unwrap();
+        assert_eq!(table.sector_locations(), [SectorNumber(3), SectorNumber(4), SectorNumber(5)]);
+        assert_eq!(table.len(), 384);
+        assert_eq!(table.get(0), Some(SectorNumber(1)));
+        assert_eq!(table.get(62), Some(SectorNumber::ENDOFCHAIN));
+        assert_eq!(table.get(63), Some(SectorNumber(64)));
+        assert_eq!(table.get(200), Some(SectorNumber(201)));
+        assert_eq!(table.get(314), Some(SectorNumber::ENDOFCHAIN));
+        assert_eq!(table.get(315), Some(SectorNumber::FREESECT));
+        assert_eq!(table.get(384), None);
+        assert_eq!(table.last_allocated(), Some(314));
+        assert_eq!(table.iter().filter(|sector_no| sector_no.is_end_of_chain()).count(), 5);
+    }
+
+    #[test]
+    fn metadata_types_are_nameable() {
+        fn summary<'a, E: CommonProps<'a>>(entry: &E) -> (String, ColorFlag, bool) {
+            (entry.name(), entry.color_flag(), entry.child_id().is_some())
+        }
+
+        let cfb = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        let body_text = cfb.entry_by_path("BodyText").unwrap().unwrap();
+        assert_eq!(summary(&body_text), ("BodyText".to_string(), ColorFlag::Black, true));
+
+        let types: Vec<ObjectType> = ["", "BodyText", "BodyText/Section0"].iter()
+            .filter_map(|path| cfb.entry_type(path).unwrap())
+            .collect();
+        assert_eq!(types, [ObjectType::RootStorage, ObjectType::Storage, ObjectType::Stream]);
+
+        let sizes: Vec<StreamSize> = ["FileHeader", "BodyText"].iter()
+            .map(|path| cfb.stream_size(path).unwrap().unwrap_or_default())
+            .collect();
+        assert_eq!(sizes, [StreamSize(256), StreamSize(0)]);
+    }
+
+    #[test]
+    fn fragmented_directory_chain() {
+        let mut builder = CfbBuilder::new();
+        builder.fragment_directory();
+        builder.stream(0, "Large", &[7; 6000]);
+        let storage = builder.storage(0, "Storage");
+        let mut last_id = 0;
+        for i in 0..10 {
+            last_id = builder.stream(storage, &format!("Stream{}", i), format!("content {}", i).as_bytes());
+        }
+        let bytes = builder.build();
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+
+        let names: Vec<_> = cfb.open_storage("Storage").unwrap()
+            .children().unwrap()
+            .iter()
+            .map(Entry::name)
+            .collect();
+        assert_eq!(names.len(), 10);
+
+        let mut content = String::new();
+        cfb.open_storage("Storage").unwrap()
+            .open_stream("Stream9").unwrap()
+            .read_to_string(&mut content).unwrap();
+        assert_eq!(content, "content 9");
+
+        assert_eq!(cfb.stream_bytes("Stream8").unwrap(), b"content 8".to_vec());
+        let mut by_id = String::new();
+        cfb.open_stream_at_id(StreamID(last_id as u32)).unwrap().read_to_string(&mut by_id).unwrap();
+        assert_eq!(by_id, "content 9");
+        assert_eq!(cfb.stream_bytes("Large").unwrap(), vec![7; 6000]);
+    }
+
+    #[test]
+    fn walk_storage_hierarchy() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let paths: Vec<_> = cfb.walk().map(|item| item.unwrap().0).collect();
+        assert_eq!(paths, [["Data"], ["1Table"], ["WordDocument"], ["\u{5}SummaryInformation"],
+            ["\u{5}DocumentSummaryInformation"]]);
+
+        let mut builder = CfbBuilder::new();
+        let object_pool = builder.storage(0, "ObjectPool");
+        let object = builder.storage(object_pool, "_123");
+        builder.stream(object, "\u{1}Ole", b"ole");
+        builder.stream(object, "Contents", b"contents");
+        let book = builder.stream(0, "Book", b"book");
+        let bytes = builder.build();
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap();
+        let walked: Vec<_> = cfb.walk()
+            .map(|item| item.map(|(path, entry)| (path.join("/"), matches!(entry, Entry::Stream(_)))))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        // each storage right before its children, which are ordered by length first
+        assert_eq!(walked, [
+            ("Book".to_string(), true),
+            ("ObjectPool".to_string(), false),
+            ("ObjectPool/_123".to_string(), false),
+            ("ObjectPool/_123/\u{1}Ole".to_string(), true),
+            ("ObjectPool/_123/Contents".to_string(), true),
+        ]);
+
+        // a storage holding itself ends the walk once its own subtree comes up
+        let mut looped = bytes.clone();
+        put_u32(&mut looped, entry_offset(&bytes, object) + 76, object as u32);
+        let cfb = Cfb::from_reader_at(Cursor::new(looped), 0, None).unwrap();
+        let items: Vec<_> = cfb.walk().collect();
+        assert!(matches!(items.last(), Some(Err(Error::InvalidDirectory(reason))) if reason.contains("reached twice")));
+        assert_eq!(items.iter().filter(|item| item.is_err()).count(), 1);
+
+        // so does a sibling linking to itself
+        let mut looped = bytes.clone();
+        put_u32(&mut looped, entry_offset(&bytes, book) + 68, book as u32);
+        let cfb = Cfb::from_reader_at(Cursor::new(looped), 0, None).unwrap();
+        let mut walk = cfb.walk();
+        let err = walk.find_map(Result::err);
+        assert!(matches!(err, Some(Error::InvalidDirectory(reason)) if reason.contains("loops")));
+        assert!(walk.next().is_none());
+    }
+
+    #[test]
+    fn stream_ids_across_fragmented_directory() {
+        let mut builder = CfbBuilder::new();
+        builder.fragment_directory();
+        builder.stream(0, "Large", &[7; 6000]);
+        let ids: Vec<_> = (0..10).map(|i| builder.stream(0, &format!("Stream{}", i), b"content")).collect();
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        // stream IDs 3 and 4 are the last slot of the first directory sector and the first of the
+        // second, which is not the sector after it
+        let offsets: Vec<_> = ids.iter().map(|id| cfb.entry_by_id(StreamID(*id as u32)).unwrap().offset()).collect();
+        assert_ne!(offsets[2] - offsets[1], 128);
+        for (i, id) in ids.iter().enumerate() {
+            let entry = cfb.entry_by_id(StreamID(*id as u32)).unwrap();
+            assert_eq!(entry.name(), format!("Stream{}", i));
+            assert_eq!(entry.offset(), cfb.entries().nth(*id).unwrap().unwrap().offset());
+        }
+        // every sibling link resolves through the chain
+        let mut linked = 0;
+        for entry in cfb.allocated_entries().map(Result::unwrap) {
+            for id in [entry.left_sibling_id(), entry.right_sibling_id(), entry.child_id()].iter().flatten() {
+                assert!(!matches!(cfb.entry_by_id(*id).unwrap(), Entry::Unknown));
+                linked += 1;
+            }
+        }
+        assert_eq!(linked, 11);
+    }
+
+    #[test]
+    fn reserved_fat_location_is_an_error() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Stream", &[1; 5000]);
+        let mut bytes = builder.build();
+        // the header lists ENDOFCHAIN as the first FAT sector
+        put_u32(&mut bytes, 76, 0xFFFFFFFE);
+
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        let mut buf = Vec::new();
+        let err = Error::from(cfb.open_stream("Stream").unwrap().read_to_end(&mut buf).unwrap_err());
+        assert!(matches!(err, Error::InvalidSectorNumber(SectorNumber::ENDOFCHAIN)), "{:?}", err);
+        assert!(cfb.fat_sector(0).is_none());
+    }
+
+    #[test]
+    fn fat_sector_locations_follow_difat_chain() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Stream", &[1; 5000]);
+        let mut bytes = builder.build();
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap();
+        assert_eq!(cfb.fat_sector_locations().map(Result::unwrap).collect::<Vec<_>>(), [SectorNumber(0)]);
+
+        // the FAT sector is listed by a DIFAT sector appended to the file instead of the header
+        let difat_sector = (bytes.len() / 512 - 1) as u32;
+        let mut difat = vec![0xFF; 512];
+        difat[..4].copy_from_slice(&0u32.to_le_bytes());
+        difat[508..].copy_from_slice(&0xFFFFFFFEu32.to_le_bytes());
+        bytes.extend_from_slice(&difat);
+        put_u32(&mut bytes, 76, 0xFFFFFFFF);
+        put_u32(&mut bytes, 68, difat_sector);
+        put_u32(&mut bytes, 72, 1);
+
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        assert_eq!(cfb.fat_sector_locations().map(Result::unwrap).collect::<Vec<_>>(), [SectorNumber(0)]);
+        assert_eq!(cfb.fat_sector(0).unwrap().location(), SectorNumber(0));
+        assert_eq!(cfb.stream_bytes("Stream").unwrap(), vec![1; 5000]);
+    }
+
+    #[test]
+    fn reserved_stream_id_link_is_malformed() {
+        let mut builder = CfbBuilder::new();
+        let stream = builder.stream(0, "Stream", b"content");
+        let bytes = builder.build();
+
+        for (id, field) in [(0, 76), (stream, 68), (stream, 72)] {
+            for reserved in 0xFFFFFFFBu32..=0xFFFFFFFE {
+                let mut bytes = bytes.clone();
+                let offset = entry_offset(&bytes, id) + field;
+                put_u32(&mut bytes, offset, reserved);
+
+                let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+                let err = cfb.entry_by_path("Stream").unwrap_err();
+                assert!(matches!(&err, Error::InvalidDirectory(reason) if reason.contains("malformed")), "{:?}", err);
+                assert!(cfb.open_storage("").unwrap().children().is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn stream_id_link_beyond_directory_is_malformed() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Stream", b"content");
+        let mut bytes = builder.build();
+        let root_offset = entry_offset(&bytes, 0);
+
+        // the first ID past the single directory sector, and a huge one with no limit to stop it
+        for child in [4, 0x00FFFFFF] {
+            put_u32(&mut bytes, root_offset + 76, child);
+            let cfb = CfbOpenOptions::new()
+                .max_directory_entries(u64::MAX)
+                .open_reader_at(Cursor::new(bytes.clone()), 0, None)
+                .unwrap();
+            let err = cfb.entry_by_path("Stream").unwrap_err();
+            assert!(matches!(&err, Error::InvalidDirectory(reason) if reason.contains("beyond the 4 directory entry slots")),
+                    "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn look_up_entries_by_stream_id() {
+        for (shift, slots) in [(9, 8), (12, 32)] {
+            let mut builder = CfbBuilder::new();
+            builder.sector_shift(shift);
+            for i in 1..=6u8 {
+                builder.stream(0, &format!("S{}", i), &vec![i; 100 * i as usize]);
+            }
+            let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+            let root_storage = cfb.entry_by_id(StreamID(0)).unwrap();
+            assert!(matches!(root_storage, Entry::RootStorage(_)));
+            assert!(cfb.entry_by_id(root_storage.child_id().unwrap()).is_ok());
+            for i in 1..=6u8 {
+                assert_eq!(cfb.entry_by_id(StreamID(i as u32)).unwrap().name(), format!("S{}", i));
+                assert_eq!(cfb.stream_bytes_by_id(StreamID(i as u32)).unwrap(), vec![i; 100 * i as usize]);
+            }
+
+            assert!(matches!(cfb.entry_by_id(StreamID(7)), Ok(Entry::Unknown)));
+            assert!(matches!(cfb.stream_bytes_by_id(StreamID(7)), Err(Error::NotAStream { object_type: ObjectType::Unknown, .. })));
+            assert!(matches!(cfb.stream_bytes_by_id(StreamID(0)), Err(Error::NotAStream { object_type: ObjectType::RootStorage, .. })));
+            for id in [StreamID(slots), StreamID::NOSTREAM] {
+                assert!(matches!(cfb.entry_by_id(id), Err(Error::NoSuchStreamID(missing)) if missing == id));
+                assert!(matches!(cfb.stream_bytes_by_id(id), Err(Error::NoSuchStreamID(_))));
+            }
+        }
+    }
+
+    #[test]
+    fn stream_bytes_at_path_components() {
+        let mut builder = CfbBuilder::new();
+        let object_pool = builder.storage(0, "ObjectPool");
+        for (name, data) in [("_123", b"first"), ("_456", b"other")] {
+            let object = builder.storage(object_pool, name);
+            builder.stream(object, "\u{1}Ole", data);
+        }
+        builder.stream(0, "a/b", b"slash");
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        assert_eq!(cfb.stream_bytes_at(&["ObjectPool", "_123", "\u{1}Ole"]).unwrap(), b"first");
+        assert_eq!(cfb.stream_bytes_at(&["ObjectPool", "_456", "\u{1}Ole"]).unwrap(), b"other");
+        // compared under the uppercase mapping, and never split
+        assert_eq!(cfb.stream_bytes_at(&["OBJECTPOOL", "_456", "\u{1}ole"]).unwrap(), b"other");
+        assert_eq!(cfb.stream_bytes_at(&["A/B"]).unwrap(), b"slash");
+        assert!(matches!(cfb.entry_at(&[]), Ok(Some(Entry::RootStorage(_)))));
+
+        assert!(matches!(cfb.stream_bytes_at(&["ObjectPool", "_789", "\u{1}Ole"]),
+                         Err(Error::NotFound { name }) if name == "ObjectPool/_789/\u{1}Ole"));
+        assert!(matches!(cfb.stream_bytes_at(&["ObjectPool", "_123"]),
+                         Err(Error::NotAStream { object_type: ObjectType::Storage, .. })));
+        assert!(matches!(cfb.entry_at(&["a/b", "c"]), Ok(None)));
+    }
+
+    #[test]
+    fn contains_streams_and_storages() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        assert!(cfb.contains_stream("WordDocument"));
+        assert!(cfb.contains_stream("worddocument"));
+        assert!(!cfb.contains_stream("Workbook"));
+        assert!(!cfb.contains_storage("WordDocument"));
+        assert!(!cfb.contains_storage("Root Entry"));
+
+        let mut builder = CfbBuilder::new();
+        let macros = builder.storage(0, "Macros");
+        builder.stream(macros, "VBA", b"project");
+        builder.stream(0, "Vba", b"stream");
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+        assert!(cfb.contains_storage("MACROS"));
+        assert!(!cfb.contains_stream("Macros"));
+        assert!(cfb.contains_stream("vba"));
+        assert!(!cfb.contains_storage("VBA"));
+    }
+
+    #[test]
+    fn names_compared_under_uppercase_mapping() {
+        let word_document = Cfb::from_path("tests_rsc/testing.doc").unwrap().stream_bytes("WordDocument").unwrap();
+        for name_index in [false, true] {
+            let cfb = CfbOpenOptions::new().name_index(name_index).open("tests_rsc/testing.doc").unwrap();
+            assert_eq!(cfb.directory_entry("worddocument").unwrap().name(), "WordDocument");
+            assert_eq!(cfb.stream_bytes("worddocument").unwrap(), word_document);
+            assert_eq!(cfb.entry_by_path("/WORDDOCUMENT").unwrap().unwrap().name(), "WordDocument");
+        }
+
+        let mut builder = CfbBuilder::new();
+        let storage = builder.storage(0, "Équipe");
+        builder.stream(storage, "straße", b"street");
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+        // é maps to É, but ß has no single code unit uppercase
+        assert_eq!(cfb.stream_bytes_at(&["éQUIPE", "STRAßE"]).unwrap(), b"street");
+        assert!(matches!(cfb.entry_by_path("ÉQUIPE/STRASSE"), Ok(None)));
+    }
+
+    #[test]
+    fn exact_names_reject_other_case() {
+        for name_index in [false, true] {
+            let cfb = CfbOpenOptions::new().name_index(name_index).exact_names(true).open("tests_rsc/testing.doc").unwrap();
+            assert!(matches!(cfb.directory_entry("worddocument"), Err(Error::NotFound { name }) if name == "worddocument"));
+            assert!(matches!(cfb.stream_bytes("worddocument"), Err(Error::NotFound { .. })));
+            assert!(matches!(cfb.entry_by_path("WORDDOCUMENT"), Ok(None)));
+            assert!(!cfb.contains_stream("worddocument"));
+            assert!(cfb.find_entries(&["worddocument"]).is_empty());
+            assert_eq!(cfb.directory_entry("WordDocument").unwrap().name(), "WordDocument");
+        }
+    }
+
+    #[test]
+    fn find_all_entries_of_duplicate_names() {
+        let mut builder = CfbBuilder::new();
+        for name in ["_1", "_2"] {
+            let object = builder.storage(0, name);
+            builder.stream(object, "\u{1}Ole", name.as_bytes());
+        }
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        let found = cfb.find_all_entries(&["\u{1}OLE", "\u{1}Ole"]);
+        for name in ["\u{1}OLE", "\u{1}Ole"] {
+            let bytes: Vec<_> = found[name].iter().map(|entry| match entry {
+                Entry::Stream(stream) => stream.bytes().unwrap(),
+                entry => panic!("unexpected entry {:?}", entry),
+            }).collect();
+            assert_eq!(bytes, [b"_1", b"_2"]);
+        }
+        assert!(matches!(&cfb.find_entries(&["\u{1}Ole"])["\u{1}Ole"], Entry::Stream(stream) if stream.bytes().unwrap() == b"_1"));
+    }
+
+    #[test]
+    fn find_entries_in_tree_order() {
+        let mut builder = CfbBuilder::new();
+        // "_22" takes the lower stream IDs, but "_1", shorter, comes first in its sibling tree
+        for name in ["_22", "_1"] {
+            let object = builder.storage(0, name);
+            builder.stream(object, "\u{1}Ole", name.as_bytes());
+        }
+        let bytes = builder.build();
+
+        for name_index in [false, true] {
+            let cfb = CfbOpenOptions::new().name_index(name_index).open_bytes(bytes.clone()).unwrap();
+            assert_eq!(cfb.directory_entry("\u{1}Ole").unwrap().offset(), entry_offset(&bytes, 2) as u64);
+            let found = cfb.find_all_entries(&["\u{1}ole"]);
+            let offsets: Vec<_> = found["\u{1}ole"].iter().map(|entry| entry.offset()).collect();
+            assert_eq!(offsets, [entry_offset(&bytes, 4) as u64, entry_offset(&bytes, 2) as u64]);
+            assert!(matches!(&cfb.find_entries(&["\u{1}Ole"])["\u{1}Ole"], Entry::Stream(stream) if stream.bytes().unwrap() == b"_1"));
+        }
+    }
+
+    #[test]
+    fn entry_counts() {
+        fn count_beneath(storage: &ole_kit::cfb::StorageHandle, counts: &mut (u64, u64)) {
+            for child in storage.children().unwrap() {
+                match child {
+                    Entry::Stream(_) => counts.0 += 1,
+                    _ => {
+                        counts.1 += 1;
+                        count_beneath(&storage.open_storage(&child.name()).unwrap(), counts);
+                    }
+                }
+            }
+        }
+
+        let mut builder = CfbBuilder::new();
+        let storage = builder.storage(0, "Storage");
+        let nested = builder.storage(storage, "Nested");
+        for i in 0..7 {
+            builder.stream(nested, &format!("Stream{}", i), &[i; 100]);
+        }
+        builder.stream(0, "Large", &[1; 5000]);
+        let mut bytes = builder.build();
+
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap();
+        let mut counts = (0, 0);
+        count_beneath(&cfb.open_storage("").unwrap(), &mut counts);
+        assert_eq!(counts, (8, 2));
+        assert_eq!((cfb.stream_count().unwrap(), cfb.storage_count().unwrap()), counts);
+        assert_eq!(cfb.entry_count().unwrap(), 11);
+
+        // an allocated stream which no storage links to is counted, though the tree doesn't reach it
+        let orphan = entry_offset(&bytes, 11);
+        bytes[orphan + 66] = 2;
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        let mut counts = (0, 0);
+        count_beneath(&cfb.open_storage("").unwrap(), &mut counts);
+        assert_eq!(counts, (8, 2));
+        assert_eq!(cfb.stream_count().unwrap(), 9);
+        assert_eq!(cfb.entry_count().unwrap(), 12);
+    }
+
+    #[test]
+    fn mini_stream_sectors() {
+        let mut builder = CfbBuilder::new();
+        for name in ["A", "B", "C"] {
+            builder.stream(0, name, &[name.as_bytes()[0]; 1000]);
+        }
+        let mut bytes = builder.build();
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap();
+        let root_storage = match cfb.entry_by_path("").unwrap() {
+            Some(Entry::RootStorage(root_storage)) => root_storage,
+            entry => panic!("unexpected entry {:?}", entry),
+        };
+
+        // FAT, directory, mini FAT, then the 6 sectors of the 48 mini sectors of the mini stream
+        let sectors = root_storage.mini_stream_sectors().unwrap();
+        assert_eq!(sectors, (3..9).map(SectorNumber).collect::<Vec<_>>());
+        let from_sectors: Vec<u8> = sectors.iter()
+            .flat_map(|sector| bytes[(sector.0 as usize + 1) * 512..][..512].to_vec())
+            .collect();
+        assert_eq!(root_storage.mini_stream().unwrap(), from_sectors);
+        assert_eq!(cfb.mini_stream_bytes().unwrap(), from_sectors);
+
+        // the chain loops back from its last sector to its first
+        put_u32(&mut bytes, 512 + 8 * 4, 3);
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap();
+        match cfb.entry_by_path("").unwrap() {
+            Some(Entry::RootStorage(root_storage)) =>
+                assert_eq!(root_storage.mini_stream_sectors().unwrap(), (3..9).map(SectorNumber).collect::<Vec<_>>()),
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+
+        // the file ends within the last sector of the mini stream
+        bytes.truncate(9 * 512 + 100);
+        put_u32(&mut bytes, 512 + 8 * 4, 0xFFFFFFFE);
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        assert!(matches!(cfb.mini_stream_bytes(), Err(Error::Truncated { size: 3072, available: 2660 })),
+                "{:?}", cfb.mini_stream_bytes());
+    }
+
+    #[test]
+    fn first_entry_not_root_storage() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Small", &[1; 1000]);
+        builder.stream(0, "Large", &[2; 5000]);
+        let mut bytes = builder.build();
+        let root = entry_offset(&bytes, 0);
+        bytes[root + 66] = ObjectType::Storage as u8;
+
+        // reading fails with an error rather than a panic
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        assert!(matches!(cfb.mini_stream_bytes(), Err(Error::InvalidDirectory(_))), "{:?}", cfb.mini_stream_bytes());
+        // a small stream has no mini stream to be read from, while a large one is still read
+        assert!(matches!(cfb.stream_bytes("Small"), Err(Error::TruncatedChain { size: 1000, available: 0 })));
+        assert_eq!(cfb.stream_bytes("Large").unwrap(), [2; 5000]);
+        assert!(matches!(cfb.stream_bytes("Missing"), Err(Error::NotFound { .. })));
+    }
+
+    #[test]
+    fn large_version_4_directory() {
+        // 101 entries over 4 directory sectors of 32 slots
+        let mut builder = CfbBuilder::new();
+        builder.sector_shift(12);
+        for i in 1..=100u32 {
+            builder.stream(0, &format!("S{}", i), &i.to_le_bytes());
+        }
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+        assert_eq!(cfb.header().major_version().0, 4);
+
+        let entries: Vec<_> = cfb.entries().map(Result::unwrap).collect();
+        assert_eq!(entries.len(), 128);
+        for i in 1..=100u32 {
+            assert_eq!(entries[i as usize].name(), format!("S{}", i));
+            let mut bytes = Vec::new();
+            cfb.open_stream_at_id(StreamID(i)).unwrap().read_to_end(&mut bytes).unwrap();
+            assert_eq!(bytes, i.to_le_bytes());
+        }
+        assert!(entries[101..].iter().all(|entry| matches!(entry, Entry::Unknown)));
+    }
+
+    #[test]
+    fn hostile_stream_ids() {
+        let mut builder = CfbBuilder::new();
+        builder.sector_shift(12);
+        builder.stream(0, "A", b"a");
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        // IDs whose byte offsets wrap around in 32 bits to those of the root storage or of A
+        for id in [0x0200_0000, 0x0200_0001, 0x8000_0001, StreamID::MAXREGSID.0, StreamID::NOSTREAM.0] {
+            assert!(cfb.open_stream_at_id(StreamID(id)).is_none(), "stream ID 0x{:08X}", id);
+        }
+        assert!(cfb.open_stream_at_id(StreamID(1)).is_some());
+    }
+
+    #[test]
+    fn stream_named_like_the_root_storage() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Root Entry", b"user data");
+        builder.storage(0, "Storage");
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        assert_eq!(cfb.stream_bytes("Root Entry").unwrap(), b"user data".to_vec());
+        assert_eq!(cfb.stream_bytes_range("Root Entry", 5, 10), Some(b"data".to_vec()));
+        assert!(cfb.open_stream("Root Entry").is_some());
+        assert!(matches!(cfb.stream_bytes("Storage"),
+                         Err(Error::NotAStream { name, object_type: ObjectType::Storage }) if name == "Storage"));
+        assert!(matches!(cfb.try_open_stream("Storage"), Err(Error::NotAStream { object_type: ObjectType::Storage, .. })));
+        assert!(matches!(cfb.try_stream_bytes_range("Storage", 0, 1),
+                         Err(Error::NotAStream { object_type: ObjectType::Storage, .. })));
+        // the mini stream, a whole mini sector, is only read explicitly
+        assert_eq!(cfb.root_mini_stream().unwrap()[..9], *b"user data");
+        assert_eq!(cfb.root_mini_stream().unwrap(), cfb.mini_stream_bytes().unwrap());
+        assert_eq!(cfb.root_mini_stream().unwrap().len(), 64);
+
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        assert!(matches!(cfb.stream_bytes("Root Entry"),
+                         Err(Error::NotAStream { object_type: ObjectType::RootStorage, .. })));
+        assert!(matches!(cfb.try_open_stream("Root Entry"),
+                         Err(Error::NotAStream { object_type: ObjectType::RootStorage, .. })));
+        assert!(matches!(cfb.try_stream_bytes_range("Missing", 0, 1), Err(Error::NotFound { .. })));
+        assert!(matches!(cfb.stream_bytes("Missing"), Err(Error::NotFound { name }) if name == "Missing"));
     }
-}
\ No newline at end of file
+}
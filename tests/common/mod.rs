@@ -0,0 +1,290 @@
+//! Builds small version 3 compound files in memory, with 512-byte sectors unless told otherwise, so that tests can cover layouts that none of
+//! the fixtures in `tests_rsc` have.
+#![allow(dead_code)]
+
+use ole_kit::cfb::name::cmp_names_str;
+
+const MINI_SECTOR_SIZE: usize = 64;
+const MINI_STREAM_CUTOFF: usize = 4096;
+const ENTRY_LENGTH: usize = 128;
+
+const FATSECT: u32 = 0xFFFFFFFD;
+const ENDOFCHAIN: u32 = 0xFFFFFFFE;
+const FREESECT: u32 = 0xFFFFFFFF;
+const NOSTREAM: u32 = 0xFFFFFFFF;
+
+enum Kind {
+    RootStorage,
+    Storage,
+    Stream(Vec<u8>),
+}
+
+struct Node {
+    name: String,
+    kind: Kind,
+    children: Vec<usize>,
+    clsid: [u8; 16],
+}
+
+/// A tree of storages and streams, laid out by [`CfbBuilder::build`] as FAT sectors, directory
+/// sectors, mini FAT sectors, the mini stream and then the regular streams, each chain being
+/// contiguous.
+pub struct CfbBuilder {
+    nodes: Vec<Node>,
+    fragmented_directory: bool,
+    sector_shift: u16,
+}
+
+impl CfbBuilder {
+    /// Creates a builder holding only the root storage, whose stream ID is 0
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![Node { name: "Root Entry".to_string(), kind: Kind::RootStorage, children: Vec::new(), clsid: [0; 16] }],
+            fragmented_directory: false,
+            sector_shift: 9,
+        }
+    }
+
+    /// Adds a storage to a parent storage, returning the stream ID of the storage
+    pub fn storage(&mut self, parent: usize, name: &str) -> usize {
+        self.add(parent, name, Kind::Storage)
+    }
+
+    /// Adds a stream to a parent storage, returning the stream ID of the stream
+    pub fn stream(&mut self, parent: usize, name: &str, data: &[u8]) -> usize {
+        self.add(parent, name, Kind::Stream(data.to_vec()))
+    }
+
+    /// Sets the CLSID of a storage
+    pub fn clsid(&mut self, id: usize, clsid: [u8; 16]) -> &mut Self {
+        self.nodes[id].clsid = clsid;
+        self
+    }
+
+    /// Moves every directory sector but the first after the stream data, as when the directory
+    /// grows after the streams are written
+    pub fn fragment_directory(&mut self) -> &mut Self {
+        self.fragmented_directory = true;
+        self
+    }
+
+    /// Sets the sector shift. A shift of 12 lays out a version 4 file, while a version 3 file only
+    /// allows 9, so that other sector sizes are only read leniently
+    pub fn sector_shift(&mut self, shift: u16) -> &mut Self {
+        self.sector_shift = shift;
+        self
+    }
+
+    fn add(&mut self, parent: usize, name: &str, kind: Kind) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(Node { name: name.to_string(), kind, children: Vec::new(), clsid: [0; 16] });
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    /// Lays out the compound file
+    pub fn build(&self) -> Vec<u8> {
+        let sector_size = 1usize << self.sector_shift;
+        let dir_sectors = self.nodes.len().div_ceil(sector_size / ENTRY_LENGTH);
+
+        // (starting sector, size) of each stream, in mini sectors for streams below the cutoff
+        let mut locations = vec![(ENDOFCHAIN, 0u64); self.nodes.len()];
+        let mut mini_sectors = Vec::new();
+        let mut regular_sectors = Vec::new();
+        for (id, node) in self.nodes.iter().enumerate() {
+            if let Kind::Stream(data) = &node.kind {
+                if data.is_empty() {
+                    continue;
+                }
+                let (sectors, unit) = if data.len() < MINI_STREAM_CUTOFF {
+                    (&mut mini_sectors, MINI_SECTOR_SIZE)
+                } else {
+                    (&mut regular_sectors, sector_size)
+                };
+                let start: usize = sectors.iter().sum();
+                locations[id] = (start as u32, data.len() as u64);
+                sectors.push(data.len().div_ceil(unit));
+            }
+        }
+
+        let mini_sector_count: usize = mini_sectors.iter().sum();
+        let mini_fat_sectors = mini_sector_count.div_ceil(sector_size / 4);
+        let mini_stream_sectors = (mini_sector_count * MINI_SECTOR_SIZE).div_ceil(sector_size);
+        let regular_sector_count: usize = regular_sectors.iter().sum();
+        let rest = dir_sectors + mini_fat_sectors + mini_stream_sectors + regular_sector_count;
+        let mut fat_sectors = 1;
+        while fat_sectors * sector_size / 4 < fat_sectors + rest {
+            fat_sectors += 1;
+        }
+        assert!(fat_sectors <= 109, "the builder doesn't write DIFAT sectors");
+
+        let first_dir_sector = fat_sectors;
+        let leading_dir_sectors = if self.fragmented_directory { 1 } else { dir_sectors };
+        let first_mini_fat_sector = first_dir_sector + leading_dir_sectors;
+        let first_mini_stream_sector = first_mini_fat_sector + mini_fat_sectors;
+        let first_regular_sector = first_mini_stream_sector + mini_stream_sectors;
+        let trailing_dir_sector = first_regular_sector + regular_sector_count;
+        let sector_count = trailing_dir_sector + dir_sectors - leading_dir_sectors;
+        let dir_locations: Vec<usize> = (first_dir_sector..first_dir_sector + leading_dir_sectors)
+            .chain(trailing_dir_sector..sector_count)
+            .collect();
+
+        // FAT and mini FAT
+        let mut fat = vec![FREESECT; fat_sectors * sector_size / 4];
+        fat[..fat_sectors].fill(FATSECT);
+        for (location, next) in dir_locations.iter().zip(dir_locations.iter().skip(1)) {
+            fat[*location] = *next as u32;
+        }
+        fat[dir_locations[dir_sectors - 1]] = ENDOFCHAIN;
+        chain(&mut fat, first_mini_fat_sector, mini_fat_sectors);
+        chain(&mut fat, first_mini_stream_sector, mini_stream_sectors);
+        let mut mini_fat = vec![FREESECT; mini_fat_sectors * sector_size / 4];
+        let mut mini_start = 0;
+        for len in &mini_sectors {
+            chain(&mut mini_fat, mini_start, *len);
+            mini_start += len;
+        }
+        let mut regular_start = first_regular_sector;
+        for len in &regular_sectors {
+            chain(&mut fat, regular_start, *len);
+            regular_start += len;
+        }
+
+        let mut bytes = vec![0u8; (sector_count + 1) * sector_size];
+
+        // header
+        bytes[0..8].copy_from_slice(&0xE11AB1A1E011CFD0u64.to_le_bytes());
+        put_u16(&mut bytes, 24, 0x003E);
+        put_u16(&mut bytes, 26, if self.sector_shift == 12 { 4 } else { 3 });
+        put_u16(&mut bytes, 28, 0xFFFE);
+        put_u16(&mut bytes, 30, self.sector_shift);
+        put_u16(&mut bytes, 32, 6);
+        put_u32(&mut bytes, 44, fat_sectors as u32);
+        put_u32(&mut bytes, 48, first_dir_sector as u32);
+        put_u32(&mut bytes, 56, MINI_STREAM_CUTOFF as u32);
+        put_u32(&mut bytes, 60, if mini_fat_sectors > 0 { first_mini_fat_sector as u32 } else { ENDOFCHAIN });
+        put_u32(&mut bytes, 64, mini_fat_sectors as u32);
+        put_u32(&mut bytes, 68, ENDOFCHAIN);
+        for i in 0..109 {
+            put_u32(&mut bytes, 76 + i * 4, if i < fat_sectors { i as u32 } else { FREESECT });
+        }
+
+        let sector_offset = |sector: usize| (sector + 1) * sector_size;
+        for (i, next) in fat.iter().enumerate() {
+            put_u32(&mut bytes, sector_offset(0) + i * 4, *next);
+        }
+        for (i, next) in mini_fat.iter().enumerate() {
+            put_u32(&mut bytes, sector_offset(first_mini_fat_sector) + i * 4, *next);
+        }
+
+        // directory entries, unallocated ones only linking to NOSTREAM
+        let entries_per_sector = sector_size / ENTRY_LENGTH;
+        let entry_offset = |id: usize|
+            sector_offset(dir_locations[id / entries_per_sector]) + id % entries_per_sector * ENTRY_LENGTH;
+        for slot in 0..dir_sectors * entries_per_sector {
+            let offset = entry_offset(slot);
+            for field in [68, 72, 76] {
+                put_u32(&mut bytes, offset + field, NOSTREAM);
+            }
+        }
+        let mut siblings = vec![(NOSTREAM, NOSTREAM); self.nodes.len()];
+        let mut child_ids = vec![NOSTREAM; self.nodes.len()];
+        for (id, node) in self.nodes.iter().enumerate() {
+            let mut children = node.children.clone();
+            children.sort_by(|a, b| {
+                cmp_names_str(&self.nodes[*a].name, &self.nodes[*b].name)
+            });
+            child_ids[id] = link(&children, &mut siblings);
+        }
+        for (id, node) in self.nodes.iter().enumerate() {
+            let offset = entry_offset(id);
+            let name: Vec<u16> = node.name.encode_utf16().collect();
+            assert!(name.len() < 32, "the name {} is too long", node.name);
+            for (i, unit) in name.iter().enumerate() {
+                put_u16(&mut bytes, offset + i * 2, *unit);
+            }
+            put_u16(&mut bytes, offset + 64, ((name.len() + 1) * 2) as u16);
+            bytes[offset + 66] = match node.kind {
+                Kind::RootStorage => 5,
+                Kind::Storage => 1,
+                Kind::Stream(_) => 2,
+            };
+            bytes[offset + 67] = 1;
+            put_u32(&mut bytes, offset + 68, siblings[id].0);
+            put_u32(&mut bytes, offset + 72, siblings[id].1);
+            put_u32(&mut bytes, offset + 76, child_ids[id]);
+            bytes[offset + 80..offset + 96].copy_from_slice(&node.clsid);
+
+            let (start, size) = match node.kind {
+                Kind::RootStorage if mini_sector_count > 0 =>
+                    (first_mini_stream_sector as u32, (mini_sector_count * MINI_SECTOR_SIZE) as u64),
+                Kind::RootStorage => (ENDOFCHAIN, 0),
+                Kind::Storage => (0, 0),
+                Kind::Stream(ref data) if data.len() >= MINI_STREAM_CUTOFF =>
+                    (first_regular_sector as u32 + locations[id].0, locations[id].1),
+                Kind::Stream(_) => locations[id],
+            };
+            put_u32(&mut bytes, offset + 116, start);
+            bytes[offset + 120..offset + 128].copy_from_slice(&size.to_le_bytes());
+        }
+
+        // stream data
+        let mut mini_start = 0;
+        let mut regular_start = first_regular_sector;
+        for node in &self.nodes {
+            if let Kind::Stream(data) = &node.kind {
+                if data.is_empty() {
+                    continue;
+                }
+                let offset = if data.len() < MINI_STREAM_CUTOFF {
+                    let offset = sector_offset(first_mini_stream_sector) + mini_start * MINI_SECTOR_SIZE;
+                    mini_start += data.len().div_ceil(MINI_SECTOR_SIZE);
+                    offset
+                } else {
+                    let offset = sector_offset(regular_start);
+                    regular_start += data.len().div_ceil(sector_size);
+                    offset
+                };
+                bytes[offset..offset + data.len()].copy_from_slice(data);
+            }
+        }
+
+        bytes
+    }
+}
+
+/// Chains `len` contiguous sectors starting at `start` in a FAT.
+fn chain(fat: &mut [u32], start: usize, len: usize) {
+    for (sector, next) in fat.iter_mut().enumerate().skip(start).take(len) {
+        *next = if sector + 1 < start + len { sector as u32 + 1 } else { ENDOFCHAIN };
+    }
+}
+
+/// Links sorted siblings into a balanced binary search tree, returning the stream ID of its root.
+fn link(sorted: &[usize], siblings: &mut [(u32, u32)]) -> u32 {
+    if sorted.is_empty() {
+        return NOSTREAM;
+    }
+
+    let middle = sorted.len() / 2;
+    let left = link(&sorted[..middle], siblings);
+    let right = link(&sorted[middle + 1..], siblings);
+    siblings[sorted[middle]] = (left, right);
+    sorted[middle] as u32
+}
+
+pub fn put_u16(bytes: &mut [u8], offset: usize, value: u16) {
+    bytes[offset..offset + 2].copy_from_slice(&value.to_le_bytes());
+}
+
+pub fn put_u32(bytes: &mut [u8], offset: usize, value: u32) {
+    bytes[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+}
+
+/// Returns the offset of a directory entry in a compound file laid out by [`CfbBuilder::build`],
+/// whose directory sectors are contiguous unless fragmented.
+pub fn entry_offset(bytes: &[u8], id: usize) -> usize {
+    let sector_size = 1usize << u16::from_le_bytes([bytes[30], bytes[31]]);
+    let first_dir_sector = u32::from_le_bytes([bytes[48], bytes[49], bytes[50], bytes[51]]) as usize;
+    (first_dir_sector + 1) * sector_size + id * ENTRY_LENGTH
+}
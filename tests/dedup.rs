@@ -0,0 +1,47 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, DuplicateGroup};
+    use sha2::{Digest, Sha256};
+    use crate::common::CfbBuilder;
+
+    #[test]
+    fn groups_identical_streams() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "A", &[1; 100]);
+        builder.stream(0, "Big", &[1; 5000]);
+        builder.stream(0, "C", &[2; 100]);
+        builder.stream(0, "Odd", &[3; 7]);
+        builder.stream(0, "Empty", &[]);
+        let storage = builder.storage(0, "S");
+        builder.stream(storage, "B", &[1; 100]);
+        builder.stream(storage, "Big2", &[1; 5000]);
+        builder.stream(storage, "Empty", &[]);
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        // the children of a storage are walked in the order of their sibling tree, shorter names first
+        let digest = |bytes: &[u8]| -> [u8; 32] { Sha256::digest(bytes).into() };
+        assert_eq!(cfb.duplicate_streams().unwrap(), [
+            DuplicateGroup { size: 100, digest: digest(&[1; 100]), paths: vec!["A".to_string(), "S/B".to_string()] },
+            DuplicateGroup { size: 5000, digest: digest(&[1; 5000]), paths: vec!["S/Big2".to_string(), "Big".to_string()] },
+        ]);
+    }
+
+    #[test]
+    fn fixtures_have_no_duplicates() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        assert_eq!(cfb.duplicate_streams().unwrap(), []);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn groups_round_trip_through_serde() {
+        let group = DuplicateGroup { size: 100, digest: [7; 32], paths: vec!["A".to_string(), "S/B".to_string()] };
+        let json = serde_json::to_string(&group).unwrap();
+        assert!(json.starts_with(r#"{"size":100,"digest":[7,"#), "{}", json);
+        assert!(json.ends_with(r#""paths":["A","S/B"]}"#), "{}", json);
+        assert_eq!(serde_json::from_str::<DuplicateGroup>(&json).unwrap(), group);
+    }
+}
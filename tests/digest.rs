@@ -0,0 +1,27 @@
+mod common;
+
+#[cfg(all(test, feature = "digest"))]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::Cfb;
+    use sha2::{Digest, Sha256};
+    use crate::common::CfbBuilder;
+
+    #[test]
+    fn digest_equals_the_digest_of_the_bytes() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        for name in ["WordDocument", "1Table", "\u{5}SummaryInformation"] {
+            let expected = Sha256::digest(cfb.stream_bytes(name).unwrap());
+            assert_eq!(cfb.stream_digest::<Sha256>(name).unwrap(), expected);
+        }
+        assert!(cfb.stream_digest::<Sha256>("Nothing").is_err());
+    }
+
+    #[test]
+    fn empty_stream_has_the_digest_of_no_input() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Empty", b"");
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+        assert_eq!(cfb.stream_digest::<Sha256>("Empty").unwrap(), Sha256::digest(b""));
+    }
+}
@@ -0,0 +1,58 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, Entry, Error, SectorNumber};
+    use crate::common::CfbBuilder;
+
+    fn cfb() -> Cfb {
+        let small: Vec<u8> = (0..100).collect();
+        let large: Vec<u8> = (0..5000).map(|i| b"Hello, world!\n"[i % 14]).collect();
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Small", &small);
+        builder.stream(0, "Large", &large);
+        Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap()
+    }
+
+    #[test]
+    fn dump_stream_regions() {
+        let cfb = cfb();
+
+        // the range crosses from the first mini sector into the second, and is cut at the stream size
+        let dump = cfb.dump_stream("Small", 60..200).unwrap();
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines, [
+            "0000003c  3c 3d 3e 3f 40 41 42 43  44 45 46 47 48 49 4a 4b  |<=>?@ABCDEFGHIJK|",
+            "0000004c  4c 4d 4e 4f 50 51 52 53  54 55 56 57 58 59 5a 5b  |LMNOPQRSTUVWXYZ[|",
+            "0000005c  5c 5d 5e 5f 60 61 62 63                           |\\]^_`abc|",
+        ]);
+        assert_eq!(cfb.dump_stream("Small", 100..200).unwrap(), "");
+
+        // across the first sector boundary
+        let dump = cfb.dump_stream("Large", 504..520).unwrap();
+        assert_eq!(dump, "000001f8  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 48 65  |Hello, world!.He|\n");
+
+        assert!(matches!(cfb.dump_stream("Missing", 0..16), Err(Error::NotFound { .. })));
+    }
+
+    #[test]
+    fn dump_sectors() {
+        let cfb = cfb();
+        let start = match cfb.entry_by_path("Large").unwrap() {
+            Some(Entry::Stream(stream)) => stream.starting_sector_location(),
+            entry => panic!("{:?}", entry),
+        };
+
+        // the offsets are within the file, past the header
+        let dump = cfb.dump_sector(start).unwrap();
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines.len(), 32);
+        let offset = (start.0 as u64 + 1) * 512;
+        assert_eq!(lines[0], format!("{:08x}  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0a 48 65  |Hello, world!.He|", offset));
+        assert!(lines[31].starts_with(&format!("{:08x} ", offset + 496)));
+
+        assert_eq!(cfb.dump_sector(SectorNumber(1000)).unwrap(), "");
+        assert!(matches!(cfb.dump_sector(SectorNumber::ENDOFCHAIN), Err(Error::InvalidSectorNumber(_))));
+    }
+}
@@ -0,0 +1,145 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use chrono::NaiveDate;
+    use ole_kit::cfb::{Cfb, Entry, Error, StateBits, UnflushedPolicy};
+    use crate::common::{CfbBuilder, entry_offset};
+
+    /// Writes a file holding a storage with a stream to a temporary path
+    fn temp_file(name: &str) -> (std::path::PathBuf, Vec<u8>) {
+        let mut builder = CfbBuilder::new();
+        let storage = builder.storage(0, "Storage");
+        builder.stream(storage, "Stream", b"content");
+        let bytes = builder.build();
+
+        let path = std::env::temp_dir().join(format!("ole_kit_edit_{}_{}.cfb", std::process::id(), name));
+        std::fs::write(&path, &bytes).unwrap();
+        (path, bytes)
+    }
+
+    #[test]
+    fn edits_storage_metadata_in_place() {
+        let (path, original) = temp_file("storage");
+        let creation = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let modified = NaiveDate::from_ymd_opt(2021, 6, 15).unwrap().and_hms_nano_opt(12, 30, 45, 123_456_700).unwrap();
+
+        let editor = Cfb::open_for_metadata(path.to_str().unwrap()).unwrap();
+        editor.set_entry_times("Storage", Some(creation), Some(modified)).unwrap();
+        editor.set_cls_id("Storage", [0xAB; 16]).unwrap();
+        editor.set_state_bits("Storage", StateBits::from_bits_retain(5)).unwrap();
+        editor.set_cls_id("", [0xCD; 16]).unwrap();
+        editor.flush().unwrap();
+        drop(editor);
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let offset = entry_offset(&bytes, 1);
+        assert_eq!(bytes[offset + 100..offset + 108], 132223104000000000u64.to_le_bytes());
+
+        // nothing but the edited fields changed
+        let root = entry_offset(&bytes, 0);
+        let changed: Vec<_> = (0..bytes.len()).filter(|&i| bytes[i] != original[i]).collect();
+        assert!(changed.iter().all(|&i| (offset + 80..offset + 116).contains(&i) || (root + 80..root + 96).contains(&i)));
+
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        match cfb.entry_by_path("Storage").unwrap() {
+            Some(Entry::Storage(storage)) => {
+                assert_eq!(storage.creation_time(), Some(creation));
+                assert_eq!(storage.modified_time(), Some(modified));
+                assert_eq!(storage.cls_id(), [0xAB; 16]);
+                assert_eq!(storage.state_bits(), StateBits::from_bits_retain(5));
+            }
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+        assert_eq!(cfb.stream_bytes("Stream").unwrap(), b"content".to_vec());
+    }
+
+    #[test]
+    fn refuses_fields_which_must_be_zero() {
+        let (path, original) = temp_file("refused");
+        let time = NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+
+        let editor = Cfb::open_for_metadata(path.to_str().unwrap()).unwrap();
+        let refused = [
+            editor.set_cls_id("Storage/Stream", [1; 16]),
+            editor.set_state_bits("Storage/Stream", StateBits::from_bits_retain(1)),
+            editor.set_entry_times("Storage/Stream", None, Some(time)),
+            editor.set_entry_times("", Some(time), None),
+            editor.set_header_clsid([1; 16]),
+            editor.set_cls_id("Missing", [1; 16]),
+        ];
+        for result in refused {
+            assert!(matches!(result, Err(Error::InvalidEdit(_))), "{:?}", result);
+        }
+        editor.set_entry_times("", None, Some(time)).unwrap();
+        editor.set_header_clsid([0; 16]).unwrap();
+        editor.flush().unwrap();
+        drop(editor);
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let root = entry_offset(&bytes, 0);
+        let changed: Vec<_> = (0..bytes.len()).filter(|&i| bytes[i] != original[i]).collect();
+        assert!(changed.iter().all(|&i| (root + 108..root + 116).contains(&i)), "{:?}", changed);
+    }
+
+    #[test]
+    fn edits_are_pending_until_flushed() {
+        let (path, original) = temp_file("pending");
+
+        let editor = Cfb::open_for_metadata(path.to_str().unwrap()).unwrap();
+        assert!(!editor.is_dirty());
+        editor.set_cls_id("Storage", [1; 16]).unwrap();
+        editor.set_cls_id("Storage", [2; 16]).unwrap();
+        editor.set_state_bits("", StateBits::from_bits_retain(3)).unwrap();
+        assert!(editor.is_dirty());
+        assert_eq!(editor.pending_changes(), 2);
+        assert_eq!(std::fs::read(&path).unwrap(), original);
+
+        editor.discard();
+        assert!(!editor.is_dirty());
+        editor.set_header_clsid([0; 16]).unwrap();
+        editor.set_cls_id("Storage", [3; 16]).unwrap();
+        editor.flush().unwrap();
+        assert!(!editor.is_dirty());
+        match editor.cfb().entry_by_path("Storage").unwrap() {
+            Some(Entry::Storage(storage)) => assert_eq!(storage.cls_id(), [3; 16]),
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+
+        // an edit made after the flush is lost with the editor
+        editor.set_cls_id("Storage", [4; 16]).unwrap();
+        drop(editor);
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let offset = entry_offset(&bytes, 1);
+        assert_eq!(bytes[offset + 80..offset + 96], [3; 16]);
+    }
+
+    #[test]
+    fn unflushed_edits_leave_the_file_intact() {
+        for policy in [UnflushedPolicy::Discard, UnflushedPolicy::Warn, UnflushedPolicy::Panic] {
+            let (path, original) = temp_file(&format!("{:?}", policy));
+
+            let discarded = Arc::new(AtomicUsize::new(0));
+            let result = std::panic::catch_unwind(|| {
+                let mut editor = Cfb::open_for_metadata(path.to_str().unwrap()).unwrap();
+                let warned = discarded.clone();
+                editor.set_unflushed_policy(policy).set_unflushed_warning(move |count| warned.store(count, Ordering::Relaxed));
+                editor.set_cls_id("Storage", [1; 16]).unwrap();
+                editor.set_entry_times("", None, None).unwrap();
+            });
+            assert_eq!(result.is_err(), policy == UnflushedPolicy::Panic);
+            // only the warning policy reports the discarded changes
+            assert_eq!(discarded.load(Ordering::Relaxed), if policy == UnflushedPolicy::Warn { 2 } else { 0 });
+
+            let bytes = std::fs::read(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(bytes, original);
+        }
+    }
+}
@@ -0,0 +1,166 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, CompObj, Error, Limit, ObjectType, Ole10Native, Package};
+    use crate::common::CfbBuilder;
+
+    const WORD_CLSID: [u8; 16] = [
+        0x06, 0x09, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+    ];
+
+    /// Appends a string prefixed by its length, including its null terminator
+    fn put_string(bytes: &mut Vec<u8>, string: &str) {
+        bytes.extend_from_slice(&(string.len() as u32 + 1).to_le_bytes());
+        bytes.extend_from_slice(string.as_bytes());
+        bytes.push(0);
+    }
+
+    /// Crafts a `\x01CompObj` stream with a registered clipboard format
+    fn comp_obj(user_type: &str, clipboard_format: &str, prog_id: &str) -> Vec<u8> {
+        let mut bytes = vec![0u8; 28];
+        put_string(&mut bytes, user_type);
+        put_string(&mut bytes, clipboard_format);
+        put_string(&mut bytes, prog_id);
+        bytes
+    }
+
+    /// Crafts a `\x01Ole10Native` stream wrapping a file in a package
+    fn packager(label: &str, source_path: &str, temp_path: &str, data: &[u8]) -> Vec<u8> {
+        let mut native = 2u16.to_le_bytes().to_vec();
+        for string in [label, source_path] {
+            native.extend_from_slice(string.as_bytes());
+            native.push(0);
+        }
+        native.extend_from_slice(&0x00030000u32.to_le_bytes());
+        put_string(&mut native, temp_path);
+        native.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        native.extend_from_slice(data);
+
+        let mut bytes = (native.len() as u32).to_le_bytes().to_vec();
+        bytes.extend_from_slice(&native);
+        bytes
+    }
+
+    fn document() -> Vec<u8> {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "WordDocument", &[0; 64]);
+        let pool = builder.storage(0, "ObjectPool");
+
+        let word = builder.storage(pool, "_1234567890");
+        builder.clsid(word, WORD_CLSID);
+        builder.stream(word, "\u{1}Ole", &[0; 20]);
+        builder.stream(word, "\u{1}CompObj", &comp_obj("Microsoft Word Document", "MSWordDoc", "Word.Document.8"));
+        builder.stream(word, "\u{3}ObjInfo", &[0; 6]);
+        builder.stream(word, "WordDocument", &[0; 128]);
+
+        let package = builder.storage(pool, "_987");
+        builder.stream(package, "\u{1}Ole10Native",
+                       &packager("notes.txt", "C:\\notes.txt", "C:\\Temp\\notes.txt", b"hello"));
+        builder.stream(package, "\u{3}ObjInfo", &[0; 6]);
+
+        builder.stream(pool, "stray", &[0; 4]);
+        builder.build()
+    }
+
+    #[test]
+    fn embedded_objects() {
+        let cfb = Cfb::from_reader_at(Cursor::new(document()), 0, None).unwrap();
+        let objects = cfb.embedded_objects().unwrap();
+        assert_eq!(objects.len(), 2);
+
+        let package = &objects[0];
+        assert_eq!(package.path, "ObjectPool/_987");
+        assert_eq!(package.object_id, Some(987));
+        assert_eq!(package.cls_id, [0; 16]);
+        assert_eq!(package.comp_obj, None);
+        assert_eq!(package.ole10_native, Some(Ole10Native {
+            size: 60,
+            package: Some(Package {
+                label: "notes.txt".to_string(),
+                source_path: "C:\\notes.txt".to_string(),
+                temp_path: "C:\\Temp\\notes.txt".to_string(),
+                data_size: 5,
+            }),
+        }));
+        assert_eq!(package.streams, ["\u{3}ObjInfo", "\u{1}Ole10Native"]);
+
+        let word = &objects[1];
+        assert_eq!(word.path, "ObjectPool/_1234567890");
+        assert_eq!(word.object_id, Some(1234567890));
+        assert_eq!(word.cls_id, WORD_CLSID);
+        assert_eq!(word.comp_obj, Some(CompObj {
+            user_type: "Microsoft Word Document".to_string(),
+            prog_id: Some("Word.Document.8".to_string()),
+        }));
+        assert_eq!(word.ole10_native, None);
+        assert_eq!(word.streams, ["\u{1}Ole", "\u{1}CompObj", "\u{3}ObjInfo", "WordDocument"]);
+    }
+
+    #[test]
+    fn no_object_pool() {
+        let cfb = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        assert_eq!(cfb.embedded_objects().unwrap(), []);
+    }
+
+    #[test]
+    fn malformed_streams_are_skipped() {
+        assert_eq!(CompObj::parse(&[0; 20]), None);
+        assert_eq!(CompObj::parse(&comp_obj("Picture", "", "")[..40]).map(|comp_obj| comp_obj.prog_id), Some(None));
+        assert_eq!(Ole10Native::parse(&[7, 0, 0, 0, 1, 0]), Some(Ole10Native { size: 7, package: None }));
+    }
+
+    #[test]
+    fn nested_compound_files() {
+        let doc = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let mut middle = CfbBuilder::new();
+        let object = middle.storage(0, "ObjectPool");
+        middle.stream(object, "Document", &doc);
+        middle.stream(0, "Text", b"not a compound file");
+        let mut outer = CfbBuilder::new();
+        outer.stream(0, "Embedded", &middle.build());
+        let outer = Cfb::from_reader_at(Cursor::new(outer.build()), 0, None).unwrap();
+
+        let middle = outer.open_nested("Embedded").unwrap();
+        let nested = middle.open_nested("ObjectPool/Document").unwrap();
+        let original = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        assert_eq!(nested.entry_names().unwrap(), original.entry_names().unwrap());
+        assert_eq!(nested.stream_bytes("WordDocument").unwrap(), original.stream_bytes("WordDocument").unwrap());
+
+        assert!(matches!(middle.open_nested("Text"), Err(Error::NotCfb { detected: None, .. })));
+        assert!(matches!(middle.open_nested("ObjectPool"), Err(Error::NotAStream { object_type: ObjectType::Storage, .. })));
+        assert!(matches!(outer.open_nested("Missing"), Err(Error::NotFound { name }) if name == "Missing"));
+    }
+
+    #[test]
+    fn walk_descends_into_nested_files() {
+        let doc = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let mut middle = CfbBuilder::new();
+        let object = middle.storage(0, "ObjectPool");
+        middle.stream(object, "Document", &doc);
+        let mut outer = CfbBuilder::new();
+        outer.stream(0, "Embedded", &middle.build());
+        outer.stream(0, "Text", b"not a compound file");
+        let bytes = outer.build();
+        let outer = Cfb::from_bytes(bytes.clone()).unwrap();
+
+        let mut paths = Vec::new();
+        outer.walk_nested(|path, _| {
+            paths.push(path.join("/"));
+            Ok(())
+        }).unwrap();
+
+        let original = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let mut expected = vec!["Text".to_string(), "Embedded".to_string(), "Embedded/ObjectPool".to_string(), "Embedded/ObjectPool/Document".to_string()];
+        for item in original.walk() {
+            expected.push(format!("Embedded/ObjectPool/Document/{}", item.unwrap().0.join("/")));
+        }
+        assert_eq!(paths, expected);
+
+        // each file is shallow enough, but not the nesting of the three
+        let outer = CfbOpenOptions::new().max_tree_depth(3).open_bytes(bytes).unwrap();
+        assert!(outer.walk().all(|item| item.is_ok()));
+        assert!(matches!(outer.walk_nested(|_, _| Ok(())), Err(Error::LimitExceeded { which: Limit::TreeDepth, .. })));
+    }
+}
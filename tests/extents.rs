@@ -0,0 +1,65 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, Entry, Error, ObjectType};
+    use crate::common::CfbBuilder;
+
+    /// Reads the extents of a stream from the bytes of the file, one after the other
+    fn read_extents(bytes: &[u8], extents: &[(u64, u64)]) -> Vec<u8> {
+        extents.iter().flat_map(|(offset, len)| bytes[*offset as usize..(offset + len) as usize].iter().copied()).collect()
+    }
+
+    #[test]
+    fn extents_read_back_every_stream() {
+        for path in ["tests_rsc/testing.doc", "tests_rsc/hwp5.0.hwp"] {
+            let bytes = std::fs::read(path).unwrap();
+            let cfb = Cfb::from_path(path).unwrap();
+            let report = cfb.fragmentation_report().unwrap();
+            assert!(report.iter().any(|stream| stream.mini) && report.iter().any(|stream| !stream.mini));
+            for stream in report {
+                let extents = cfb.stream_extents(&stream.path).unwrap();
+                let expected = match cfb.entry_by_path(&stream.path).unwrap() {
+                    Some(Entry::Stream(entry)) => entry.bytes().unwrap(),
+                    entry => panic!("unexpected entry {:?}", entry),
+                };
+                assert_eq!(read_extents(&bytes, &extents), expected, "{} in {}", stream.path, path);
+                // adjacent sectors are coalesced
+                assert!(extents.windows(2).all(|pair| pair[0].0 + pair[0].1 != pair[1].0), "{}", stream.path);
+                if !stream.mini {
+                    assert_eq!(extents.len() as u64, stream.stats.runs, "{}", stream.path);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn word_document_extents() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        // the 4,096 bytes of WordDocument run in two parts, around two sectors of 1Table
+        assert_eq!(cfb.stream_extents("WordDocument").unwrap(), [(512, 2048), (3584, 2048)]);
+        // two adjacent mini sectors, trimmed to the 72 bytes of the stream
+        assert_eq!(cfb.stream_extents("\u{5}SummaryInformation").unwrap(), [(13504, 72)]);
+        assert!(matches!(cfb.stream_extents("Missing"), Err(Error::NotFound { .. })));
+        assert!(matches!(cfb.stream_extents(""), Err(Error::NotAStream { object_type: ObjectType::RootStorage, .. })));
+    }
+
+    #[test]
+    fn truncated_chain() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Stream", &[1; 5000]);
+        let bytes = builder.build();
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes.clone()), 0, None).unwrap();
+        let extents = cfb.stream_extents("Stream").unwrap();
+        assert_eq!(extents.iter().map(|(_, len)| len).sum::<u64>(), 5000);
+        assert_eq!(extents.last().unwrap().1 % 512, 5000 % 512);
+
+        // the stream declares twice its size, beyond its chain
+        let mut bytes = bytes;
+        let entry = cfb.entry_by_path("Stream").unwrap().unwrap().offset() as usize;
+        bytes[entry + 120..entry + 128].copy_from_slice(&10_000u64.to_le_bytes());
+        let cfb = Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        assert!(matches!(cfb.stream_extents("Stream"), Err(Error::TruncatedChain { size: 10_000, .. })));
+    }
+}
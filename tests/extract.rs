@@ -0,0 +1,69 @@
+mod common;
+
+#[cfg(all(test, feature = "std-fs"))]
+mod tests {
+    use std::io::Cursor;
+    use std::path::PathBuf;
+    use ole_kit::cfb::Cfb;
+    use crate::common::CfbBuilder;
+
+    fn dest(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ole_kit_extract_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&path);
+        path
+    }
+
+    #[test]
+    fn extracts_every_stream() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let dest = dest("doc");
+        let report = cfb.extract_to(&dest).unwrap();
+
+        let word_document = report.streams.iter().find(|stream| stream.names == ["WordDocument"]).unwrap();
+        assert_eq!(word_document.path, dest.join("WordDocument"));
+        assert_eq!(word_document.size, 4096);
+        assert_eq!(std::fs::read(&word_document.path).unwrap(), cfb.stream_bytes("WordDocument").unwrap());
+
+        let summary = report.streams.iter().find(|stream| stream.names == ["\u{5}SummaryInformation"]).unwrap();
+        assert_eq!(summary.path, dest.join("%05SummaryInformation"));
+        for stream in &report.streams {
+            assert_eq!(std::fs::read(&stream.path).unwrap().len() as u64, stream.size);
+        }
+        std::fs::remove_dir_all(&dest).unwrap();
+    }
+
+    #[test]
+    fn escapes_names_and_disambiguates_collisions() {
+        let mut builder = CfbBuilder::new();
+        let object_pool = builder.storage(0, "ObjectPool");
+        let object = builder.storage(object_pool, "_123");
+        builder.stream(object, "\u{1}Ole", b"ole");
+        builder.stream(object, "a/b:c", b"escaped");
+        builder.stream(object, "50%", b"percent");
+        builder.stream(object, "..", b"dots");
+        builder.stream(object, "Name", b"first");
+        builder.stream(object, "NAME", b"second");
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+        let dest = dest("escape");
+        let report = cfb.extract_to(&dest).unwrap();
+
+        let dir = dest.join("ObjectPool").join("_123");
+        assert_eq!(report.storages, [dest.join("ObjectPool"), dir.clone()]);
+        let file = |name: &str| std::fs::read(dir.join(name)).unwrap();
+        assert_eq!(file("%01Ole"), b"ole");
+        assert_eq!(file("a%2Fb%3Ac"), b"escaped");
+        assert_eq!(file("50%25"), b"percent");
+        assert_eq!(file("%2E%2E"), b"dots");
+
+        // names differing in case only share a file name on most file systems
+        let named: Vec<_> = report.streams.iter()
+            .filter(|stream| stream.names[2].eq_ignore_ascii_case("name"))
+            .collect();
+        assert_eq!(named.len(), 2);
+        assert_eq!(named[1].path, dir.join(format!("{}~1", named[1].names[2])));
+        let contents: Vec<_> = named.iter().map(|stream| std::fs::read(&stream.path).unwrap()).collect();
+        assert_ne!(contents[0], contents[1]);
+        assert_eq!(report.streams.iter().map(|stream| stream.size).sum::<u64>(), 3 + 7 + 7 + 4 + 5 + 6);
+        std::fs::remove_dir_all(&dest).unwrap();
+    }
+}
@@ -0,0 +1,72 @@
+#[cfg(all(test, feature = "ffi"))]
+mod tests {
+    use ole_kit::cfb::ffi::*;
+    use ole_kit::cfb::name::display_name;
+    use ole_kit::cfb::Cfb;
+    use std::ffi::{c_void, CStr, CString};
+    use std::ptr;
+
+    extern "C" fn collect(entry: *const OlekitEntry, user_data: *mut c_void) {
+        let (entry, entries) = unsafe { (&*entry, &mut *(user_data as *mut Vec<(String, String, u8, u64)>)) };
+        let string = |string| unsafe { CStr::from_ptr(string) }.to_str().unwrap().to_string();
+        entries.push((string(entry.path), string(entry.name), entry.object_type, entry.size));
+    }
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(olekit_last_error_message()) }.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn opens_lists_reads_and_closes() {
+        let expected = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let path = CString::new("tests_rsc/testing.doc").unwrap();
+        unsafe {
+            let handle = olekit_open(path.as_ptr());
+            assert!(!handle.is_null());
+
+            let mut entries: Vec<(String, String, u8, u64)> = Vec::new();
+            assert_eq!(olekit_list_entries(handle, collect, &mut entries as *mut _ as *mut c_void), 0);
+            let streams: Vec<_> = entries.iter().filter(|entry| entry.2 == 2).map(|entry| (display_name(&entry.0), entry.3)).collect();
+            assert_eq!(streams, expected.stream_names_with_sizes().unwrap());
+
+            for (_, name, _, size) in &entries {
+                let name = CString::new(name.as_str()).unwrap();
+                assert_eq!(olekit_stream_size(handle, name.as_ptr()), *size as i64);
+                let mut buf = vec![0u8; *size as usize];
+                assert_eq!(olekit_stream_read(handle, name.as_ptr(), buf.as_mut_ptr(), buf.len()), *size as i64);
+                assert_eq!(buf, expected.stream_bytes(name.to_str().unwrap()).unwrap());
+
+                // a smaller buffer reads the start of the stream
+                let mut start = [0; 16];
+                assert_eq!(olekit_stream_read(handle, name.as_ptr(), start.as_mut_ptr(), start.len()), 16);
+                assert_eq!(start, buf[..16]);
+            }
+
+            olekit_close(handle);
+        }
+    }
+
+    #[test]
+    fn failures_return_codes_and_messages() {
+        let path = CString::new("tests_rsc/testing.doc").unwrap();
+        let missing = CString::new("missing.doc").unwrap();
+        let name = CString::new("Nothing").unwrap();
+        unsafe {
+            assert!(olekit_open(missing.as_ptr()).is_null());
+            assert!(last_error().contains("missing.doc"), "{}", last_error());
+            assert!(olekit_open(ptr::null()).is_null());
+            assert_eq!(last_error(), "path is null");
+
+            let handle = olekit_open(path.as_ptr());
+            assert_eq!(olekit_stream_size(handle, name.as_ptr()), OLEKIT_ERR_NOT_FOUND);
+            assert_eq!(last_error(), "no entry is named `Nothing`");
+            assert_eq!(olekit_stream_read(handle, name.as_ptr(), ptr::null_mut(), 0), OLEKIT_ERR_NOT_FOUND);
+            let word_document = CString::new("WordDocument").unwrap();
+            assert_eq!(olekit_stream_read(handle, word_document.as_ptr(), ptr::null_mut(), 1), OLEKIT_ERR_INVALID_ARGUMENT);
+            olekit_close(handle);
+
+            assert_eq!(olekit_stream_size(ptr::null(), name.as_ptr()), OLEKIT_ERR_INVALID_ARGUMENT);
+            olekit_close(ptr::null_mut());
+        }
+    }
+}
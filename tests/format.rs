@@ -0,0 +1,105 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, DetectedFormat};
+    use crate::common::CfbBuilder;
+
+    const WORD_CLSID: [u8; 16] = [0x06, 0x09, 0x02, 0, 0, 0, 0, 0, 0xC0, 0, 0, 0, 0, 0, 0, 0x46];
+    const MSI_CLSID: [u8; 16] = [0x84, 0x10, 0x0C, 0, 0, 0, 0, 0, 0xC0, 0, 0, 0, 0, 0, 0, 0x46];
+
+    fn open(builder: &CfbBuilder) -> Cfb {
+        Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap()
+    }
+
+    fn comp_obj(prog_id: &str) -> Vec<u8> {
+        let mut bytes = vec![0u8; 28];
+        for string in ["Microsoft Excel 97-2003 Worksheet", "Biff8", prog_id] {
+            bytes.extend(((string.len() + 1) as u32).to_le_bytes());
+            bytes.extend(string.as_bytes());
+            bytes.push(0);
+        }
+        bytes
+    }
+
+    #[test]
+    fn detect_word_document() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        assert_eq!(cfb.detect_format(), DetectedFormat::WordDoc);
+    }
+
+    #[test]
+    fn detect_hwp_document() {
+        let cfb = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        let classification = cfb.classify();
+        assert_eq!(classification.format, DetectedFormat::Hwp);
+        assert_eq!(classification.confidence, 100);
+    }
+
+    #[test]
+    fn detect_excel_workbook() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Workbook", &[0; 5000]);
+        builder.stream(0, "\u{1}CompObj", &comp_obj("Excel.Sheet.8"));
+        let classification = open(&builder).classify();
+        assert_eq!(classification.format, DetectedFormat::Excel97);
+        assert_eq!(classification.confidence, 90);
+    }
+
+    #[test]
+    fn detect_powerpoint_presentation() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "PowerPoint Document", &[0; 100]);
+        builder.stream(0, "Current User", &[0; 20]);
+        assert_eq!(open(&builder).detect_format(), DetectedFormat::PowerPoint97);
+    }
+
+    #[test]
+    fn detect_installer_package() {
+        let mut builder = CfbBuilder::new();
+        builder.clsid(0, MSI_CLSID);
+        builder.stream(0, "\u{4840}\u{3f3f}\u{4577}\u{446c}", &[0; 16]);
+        builder.stream(0, "\u{5}SummaryInformation", &[0; 16]);
+        assert_eq!(open(&builder).detect_format(), DetectedFormat::Msi);
+    }
+
+    #[test]
+    fn detect_outlook_message() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "__properties_version1.0", &[0; 32]);
+        builder.stream(0, "__substg1.0_0037001F", b"S\0u\0b\0");
+        builder.storage(0, "__recip_version1.0_#00000000");
+        assert_eq!(open(&builder).detect_format(), DetectedFormat::OutlookMsg);
+    }
+
+    #[test]
+    fn encrypted_package_beats_clsid() {
+        let mut builder = CfbBuilder::new();
+        builder.clsid(0, WORD_CLSID);
+        builder.stream(0, "EncryptionInfo", &[4, 0, 4, 0]);
+        builder.stream(0, "EncryptedPackage", &[0; 4200]);
+        let classification = open(&builder).classify();
+        assert_eq!(classification.format, DetectedFormat::EncryptedOoxml);
+        assert_eq!(classification.confidence, 100);
+    }
+
+    #[test]
+    fn conflicting_signals_lower_confidence() {
+        let mut builder = CfbBuilder::new();
+        builder.clsid(0, WORD_CLSID);
+        builder.stream(0, "Workbook", &[0; 64]);
+        let classification = open(&builder).classify();
+        assert_eq!(classification.format, DetectedFormat::Excel97);
+        assert_eq!(classification.confidence, 40);
+    }
+
+    #[test]
+    fn detect_unknown_format() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Contents", &[0; 10]);
+        let classification = open(&builder).classify();
+        assert_eq!(classification.format, DetectedFormat::Unknown);
+        assert_eq!(classification.confidence, 0);
+    }
+}
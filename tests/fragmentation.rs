@@ -0,0 +1,61 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, ChainStats, StreamFragmentation};
+    use crate::common::CfbBuilder;
+
+    fn stats(length: u64, runs: u64, longest_run: u64) -> ChainStats {
+        ChainStats { length, runs, longest_run }
+    }
+
+    fn stream(path: &str, mini: bool, stats: ChainStats) -> StreamFragmentation {
+        StreamFragmentation { path: path.to_string(), mini, stats }
+    }
+
+    #[test]
+    fn word_document_chains() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        assert_eq!(cfb.chain_stats(cfb.header().first_directory_sector_location()).unwrap(), stats(2, 2, 1));
+
+        let report = cfb.fragmentation_report().unwrap();
+        assert_eq!(report, [
+            stream("Data", false, stats(8, 1, 8)),
+            stream("1Table", false, stats(8, 2, 6)),
+            stream("WordDocument", false, stats(8, 2, 4)),
+            stream("\u{5}SummaryInformation", true, stats(2, 1, 2)),
+            stream("\u{5}DocumentSummaryInformation", true, stats(3, 1, 3)),
+        ]);
+        assert_eq!(report[1].stats.fragmentation(), 1.0 / 7.0);
+    }
+
+    #[test]
+    fn hwp_chains() {
+        let cfb = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        assert_eq!(cfb.chain_stats(cfb.header().first_directory_sector_location()).unwrap(), stats(15, 3, 11));
+
+        // every stream was written in one go
+        let report = cfb.fragmentation_report().unwrap();
+        assert_eq!(report.len(), 53);
+        assert!(report.iter().all(|stream| stream.stats.runs == 1 && stream.stats.fragmentation() == 0.0));
+        let section3 = report.iter().find(|stream| stream.path == "ViewText/Section3").unwrap();
+        assert_eq!(*section3, stream("ViewText/Section3", false, stats(340, 1, 340)));
+    }
+
+    #[test]
+    fn fragmented_directory() {
+        let mut builder = CfbBuilder::new();
+        for i in 0..10 {
+            builder.stream(0, &format!("S{}", i), &[i; 5000]);
+        }
+        builder.fragment_directory();
+        let cfb = Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap();
+
+        // the first of the 3 directory sectors stays ahead of the stream data
+        let directory = cfb.chain_stats(cfb.header().first_directory_sector_location()).unwrap();
+        assert_eq!(directory, stats(3, 2, 2));
+        assert_eq!(directory.fragmentation(), 0.5);
+        assert!(cfb.fragmentation_report().unwrap().iter().all(|stream| stream.stats == stats(10, 1, 10)));
+    }
+}
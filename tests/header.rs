@@ -1,7 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use super::*;
     use std::fs::File;
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, Error, ForeignFormat};
     use ole_kit::cfb::header::Header;
 
     #[test]
@@ -24,4 +25,26 @@ mod tests {
         assert_eq!(header.first_difat_sector_location().0, 0xFFFF_FFFE);
         assert_eq!(header.no_of_difat_sectors().0, 0x00000000);
     }
-}
\ No newline at end of file
+
+    fn open(bytes: &[u8]) -> Error {
+        Cfb::from_reader_at(Cursor::new(bytes.to_vec()), 0, None).unwrap_err()
+    }
+
+    #[test]
+    fn near_miss_formats() {
+        // the local file header of a ZIP archive, as an Office Open XML document begins
+        let zip = b"PK\x03\x04\x14\x00\x06\x00\x08\x00[Content_Types].xml";
+        let err = open(zip);
+        assert!(matches!(err, Error::NotCfb { detected: Some(ForeignFormat::Zip), .. }), "{:?}", err);
+        assert!(err.to_string().contains("Office Open XML"), "{}", err);
+
+        assert!(matches!(open(b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n"), Error::NotCfb { detected: Some(ForeignFormat::Pdf), .. }));
+        assert!(matches!(open(b"\xEF\xBB\xBF<?xml version=\"1.0\"?>"), Error::NotCfb { detected: Some(ForeignFormat::Xml), .. }));
+        assert!(matches!(open(b"\xDB\xA5\x2D\x00\x00\x00\x09\x04"), Error::NotCfb { detected: Some(ForeignFormat::Word2), .. }));
+
+        let err = open(b"plain text");
+        assert!(matches!(err, Error::NotCfb { signature: 0x6574_206E_6961_6C70, detected: None }), "{:?}", err);
+        assert_eq!(err.to_string(), "invalid compound file signature 0x6574206E69616C70");
+        assert!(matches!(open(b"PK"), Error::NotCfb { detected: None, .. }));
+    }
+}
@@ -0,0 +1,141 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use std::sync::Arc;
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, Entry, Error, SectorReadCounter};
+    use crate::common::{CfbBuilder, entry_offset, put_u32};
+
+    /// Collects the path of every entry beneath a storage, in the order of the sibling trees
+    fn paths(cfb: &Cfb, storage: &str, paths: &mut Vec<String>) {
+        for child in cfb.open_storage(storage).unwrap().children().unwrap() {
+            let path = if storage.is_empty() { child.name() } else { format!("{}/{}", storage, child.name()) };
+            paths.push(path.clone());
+            if !matches!(child, Entry::Stream(_)) {
+                self::paths(cfb, &path, paths);
+            }
+        }
+    }
+
+    /// Describes the result of a lookup by the offset of the entry found
+    fn found(entry: Result<Option<Entry>, Error>) -> Result<Option<u64>, String> {
+        entry.map(|entry| entry.map(|entry| entry.offset())).map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn indexed_lookups_agree() {
+        for fixture in ["tests_rsc/hwp5.0.hwp", "tests_rsc/testing.doc"] {
+            let cfb = Cfb::from_path(fixture).unwrap();
+            let indexed = Cfb::from_path(fixture).unwrap();
+            assert!(indexed.index_stats().is_none());
+            indexed.build_index().unwrap();
+
+            let mut all = Vec::new();
+            paths(&cfb, "", &mut all);
+            let stats = indexed.index_stats().unwrap();
+            assert_eq!(stats.paths, all.len());
+            assert!(stats.slots > all.len());
+            assert!(stats.bytes > 0);
+
+            let misses = ["Missing", "/", "", "BodyText/Missing", "FileHeader/Section0", "bodytext"];
+            for path in all.iter().map(String::as_str).chain(misses) {
+                assert_eq!(found(indexed.entry_by_path(path)), found(cfb.entry_by_path(path)), "{}", path);
+                let name = path.rsplit('/').next().unwrap();
+                assert_eq!(indexed.directory_entry(name).ok().map(|entry| entry.offset()),
+                           cfb.directory_entry(name).ok().map(|entry| entry.offset()), "{}", name);
+                assert_eq!(indexed.contains_stream(name), cfb.contains_stream(name), "{}", name);
+                assert_eq!(indexed.contains_storage(name), cfb.contains_storage(name), "{}", name);
+            }
+
+            for storage in all.iter().filter(|path| cfb.open_storage(path).is_some()) {
+                let handle = indexed.open_storage(storage).unwrap();
+                let expected = cfb.open_storage(storage).unwrap();
+                for child in expected.children().unwrap() {
+                    assert_eq!(handle.entry_by_path(&child.name()).map(|entry| entry.offset()), Some(child.offset()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn index_is_built_on_first_lookup() {
+        let mut builder = CfbBuilder::new();
+        let storage = builder.storage(0, "Storage");
+        builder.stream(storage, "Stream", b"content");
+        let bytes = builder.build();
+
+        let cfb = CfbOpenOptions::new().name_index(true).open_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        assert!(cfb.index_stats().is_none());
+        assert!(cfb.entry_by_path("Storage/Stream").unwrap().is_some());
+        assert_eq!(cfb.index_stats().unwrap().paths, 2);
+
+        // the clones share the index, while a duplicate builds its own
+        assert!(cfb.clone().index_stats().is_some());
+        let duplicate = cfb.try_clone().unwrap();
+        assert!(duplicate.index_stats().is_none());
+        assert!(duplicate.entry_by_path("Storage/Stream").unwrap().is_some());
+        assert_eq!(duplicate.index_stats().unwrap().paths, 2);
+    }
+
+    #[test]
+    fn lookups_by_components_use_the_index() {
+        let mut all = Vec::new();
+        paths(&Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap(), "", &mut all);
+        // counts the reads of looking every path up by its components
+        let lookups = |name_index: bool| {
+            let counter = Arc::new(SectorReadCounter::new());
+            let cfb = CfbOpenOptions::new().name_index(name_index).instrument(counter.clone())
+                .open("tests_rsc/hwp5.0.hwp").unwrap();
+            if name_index {
+                cfb.build_index().unwrap();
+            }
+            counter.take_reads();
+            let found: Vec<_> = all.iter()
+                .map(|path| found(cfb.entry_at(&path.split('/').collect::<Vec<_>>())))
+                .collect();
+            (found, counter.read_count())
+        };
+
+        let (expected, walked) = lookups(false);
+        let (found, indexed) = lookups(true);
+        assert_eq!(found, expected);
+        assert!(indexed * 2 < walked, "{} reads through the index, {} without", indexed, walked);
+    }
+
+    #[test]
+    fn contains_answers_from_the_index() {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().name_index(true).instrument(counter.clone())
+            .open("tests_rsc/hwp5.0.hwp").unwrap();
+        cfb.build_index().unwrap();
+        counter.take_reads();
+
+        // a name missing from the index reads nothing
+        assert!(!cfb.contains_stream("Missing"));
+        assert!(!cfb.contains_storage("Missing"));
+        assert_eq!(counter.read_count(), 0);
+        assert!(cfb.contains_stream("section0"));
+        assert!(!cfb.contains_storage("Section0"));
+        assert!(cfb.contains_storage("bodytext"));
+    }
+
+    #[test]
+    fn corrupted_tree_isnt_indexed() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "A", b"a");
+        builder.stream(0, "B", b"b");
+        let mut bytes = builder.build();
+        // the children link to each other as their right siblings
+        for (id, sibling) in [(1, 2), (2, 1)] {
+            let offset = entry_offset(&bytes, id);
+            put_u32(&mut bytes, offset + 72, sibling);
+        }
+
+        let cfb = CfbOpenOptions::new().name_index(true).open_reader_at(Cursor::new(bytes), 0, None).unwrap();
+        let err = cfb.entry_by_path("A").unwrap_err();
+        assert!(matches!(err, Error::InvalidDirectory(_)), "{:?}", err);
+        assert!(cfb.index_stats().is_none());
+        assert!(cfb.build_index().is_err());
+    }
+}
@@ -0,0 +1,310 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, ColorFlag, Entry, Error, Location, Rule, StreamID, StreamSize, Violation, Warning};
+    use crate::common::{CfbBuilder, entry_offset, put_u16, put_u32};
+
+    fn build() -> Vec<u8> {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Small", b"small");
+        builder.stream(0, "Large", &[7; 5000]);
+        builder.build()
+    }
+
+    fn open(bytes: Vec<u8>, lenient: bool) -> Result<Cfb, Error> {
+        CfbOpenOptions::new().lenient(lenient).open_reader_at(Cursor::new(bytes), 0, None)
+    }
+
+    fn header_warning(rule: Rule, offset: u64, value: u64) -> Warning {
+        Warning { rule, location: Location::Header { offset }, value }
+    }
+
+    #[test]
+    fn well_formed_files_have_no_warnings() {
+        for fixture in ["tests_rsc/hwp5.0.hwp", "tests_rsc/testing.doc"] {
+            let cfb = CfbOpenOptions::new().lenient(true).open(fixture).unwrap();
+            cfb.stream_names_with_sizes().unwrap();
+            assert_eq!(cfb.warnings(), []);
+        }
+    }
+
+    #[test]
+    fn header_deviations() {
+        let mut bytes = build();
+        put_u16(&mut bytes, 24, 0x0021);
+        put_u16(&mut bytes, 28, 0xFEFF);
+        put_u16(&mut bytes, 32, 0x0007);
+        put_u32(&mut bytes, 56, 0x2000);
+
+        let err = open(bytes.clone(), false).unwrap_err();
+        assert!(matches!(err, Error::InvalidHeader { field: "byte_order", value: 0xFEFF }), "{:?}", err);
+
+        let cfb = open(bytes, true).unwrap();
+        assert_eq!(cfb.warnings(), [
+            header_warning(Rule::MinorVersion, 24, 0x21),
+            header_warning(Rule::ByteOrder, 28, 0xFEFF),
+            header_warning(Rule::MiniSectorShift, 32, 7),
+            header_warning(Rule::MiniStreamCutoff, 56, 0x2000),
+        ]);
+        assert_eq!(cfb.warnings()[1].to_string(), "header.byte_order at header offset 28: 0xFEFF");
+
+        // the clamped cutoff keeps the large stream in regular sectors
+        assert_eq!(cfb.stream_bytes("Large").unwrap(), vec![7; 5000]);
+        assert_eq!(cfb.stream_bytes("Small").unwrap(), b"small".to_vec());
+    }
+
+    #[test]
+    fn minor_version_is_warned_of_when_strict() {
+        let mut bytes = build();
+        put_u16(&mut bytes, 24, 0x003B);
+
+        let cfb = open(bytes, false).unwrap();
+        assert_eq!(cfb.warnings(), [header_warning(Rule::MinorVersion, 24, 0x3B)]);
+    }
+
+    #[test]
+    fn sector_shift_of_other_version() {
+        let mut bytes = build();
+        put_u16(&mut bytes, 26, 4);
+
+        let err = open(bytes.clone(), false).unwrap_err();
+        assert!(matches!(err, Error::InvalidHeader { field: "sector_shift", value: 9 }), "{:?}", err);
+
+        let cfb = open(bytes, true).unwrap();
+        assert_eq!(cfb.warnings(), [header_warning(Rule::SectorShift, 30, 9)]);
+        assert_eq!(cfb.sector_size(), 512);
+    }
+
+    #[test]
+    fn stream_size_high_bits_are_masked() {
+        let mut bytes = build();
+        let offset = entry_offset(&bytes, 2);
+        put_u32(&mut bytes, offset + 124, 1);
+
+        let cfb = open(bytes.clone(), false).unwrap();
+        assert_eq!(cfb.stream_size("Large").unwrap(), Some(StreamSize((1 << 32) + 5000)));
+        assert_eq!(cfb.warnings(), []);
+
+        let cfb = open(bytes, true).unwrap();
+        assert_eq!(cfb.warnings(), []);
+        assert_eq!(cfb.stream_size("Large").unwrap(), Some(StreamSize(5000)));
+        assert_eq!(cfb.stream_bytes("Large").unwrap(), vec![7; 5000]);
+        assert_eq!(cfb.clone().warnings(), [Warning {
+            rule: Rule::StreamSizeHighBits,
+            location: Location::Entry { offset: offset as u64, name: "Large".to_string() },
+            value: (1 << 32) + 5000,
+        }]);
+    }
+
+    /// Builds a file of several directory sectors, and two FAT sectors for 1,024-byte sectors, with
+    /// streams in the mini stream, in regular sectors and in a storage
+    fn build_with_shift(shift: u16) -> Vec<u8> {
+        let mut builder = CfbBuilder::new();
+        builder.sector_shift(shift);
+        builder.stream(0, "Small", b"small");
+        builder.stream(0, "Large", &(0..300_000u32).map(|i| i as u8).collect::<Vec<_>>());
+        let storage = builder.storage(0, "Storage");
+        for i in 0..20u8 {
+            builder.stream(storage, &format!("Stream{}", i), &vec![i; 100 + 400 * i as usize]);
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn uncommon_sector_shifts() {
+        for shift in [10, 11] {
+            let bytes = build_with_shift(shift);
+            let err = open(bytes.clone(), false).unwrap_err();
+            assert!(matches!(err, Error::InvalidHeader { field: "sector_shift", value } if value == shift as u32), "{:?}", err);
+
+            let cfb = open(bytes, true).unwrap();
+            assert_eq!(cfb.sector_size(), 1 << shift);
+            assert_eq!(cfb.warnings(), [header_warning(Rule::SectorShift, 30, shift as u64)]);
+            assert_eq!(cfb.header().no_of_fat_sectors().0, if shift == 10 { 2 } else { 1 });
+            assert_eq!(cfb.validate(), []);
+            assert_eq!(cfb.entry_count().unwrap(), 24);
+
+            assert_eq!(cfb.stream_bytes("Small").unwrap(), b"small".to_vec());
+            let large: Vec<u8> = (0..300_000u32).map(|i| i as u8).collect();
+            assert_eq!(cfb.stream_bytes("Large").unwrap(), large);
+            for i in 0..20u8 {
+                let path = format!("Storage/Stream{}", i);
+                let stream = match cfb.entry_by_path(&path).unwrap() {
+                    Some(Entry::Stream(stream)) => stream,
+                    _ => panic!("{} is not a stream", path),
+                };
+                assert_eq!(stream.bytes().unwrap(), vec![i; 100 + 400 * i as usize], "{}", path);
+            }
+
+            // the large stream and the streams of the storage from 4,096 bytes on take whole sectors
+            let sector_size = 1u64 << shift;
+            let regular: u64 = (10..20).map(|i| (100 + 400 * i as u64).div_ceil(sector_size) * sector_size).sum();
+            let mini: u64 = (0..10).map(|i| (100 + 400 * i as u64).div_ceil(64) * 64).sum();
+            let report = cfb.storage_size("Storage").unwrap().unwrap();
+            assert_eq!(report.totals.allocated, regular + mini);
+            assert_eq!(cfb.storage_size("").unwrap().unwrap().children[0].totals.allocated,
+                       300_000u64.div_ceil(sector_size) * sector_size);
+        }
+    }
+
+    #[test]
+    fn insane_sector_shifts_are_refused() {
+        for shift in [8, 17] {
+            let mut bytes = build();
+            put_u16(&mut bytes, 30, shift);
+            let err = open(bytes, true).unwrap_err();
+            assert!(matches!(err, Error::InvalidHeader { field: "sector_shift", value } if value == shift as u32), "{:?}", err);
+        }
+    }
+
+    #[test]
+    fn invalid_color_flags_are_tolerated() {
+        let mut bytes = build();
+        let offset = entry_offset(&bytes, 1);
+        bytes[offset + 67] = 0x02;
+        // garbage in the unallocated slot
+        let unallocated = entry_offset(&bytes, 3);
+        bytes[unallocated + 67] = 0x7F;
+
+        for lenient in [false, true] {
+            let cfb = open(bytes.clone(), lenient).unwrap();
+            assert_eq!(cfb.stream_names().unwrap(), ["Large", "Small"]);
+            assert_eq!(cfb.entry_count().unwrap(), 3);
+            assert_eq!(cfb.stream_bytes("Small").unwrap(), b"small".to_vec());
+            assert_eq!(cfb.warnings(), []);
+
+            let small = cfb.entry_by_path("Small").unwrap().unwrap();
+            assert_eq!(small.color_flag(), ColorFlag::Unknown(0x02));
+            assert_eq!(cfb.warnings(), [Warning {
+                rule: Rule::ColorFlag,
+                location: Location::Entry { offset: offset as u64, name: "Small".to_string() },
+                value: 0x02,
+            }]);
+            assert_eq!(cfb.validate(), [Violation::InvalidColorFlag { path: "Small".to_string(), value: 0x02 }]);
+        }
+    }
+
+    #[test]
+    fn invalid_object_types() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "A", b"a");
+        builder.stream(0, "B", b"b");
+        builder.stream(0, "C", b"c");
+        let mut bytes = builder.build();
+        // C is the right sibling of B, the root of the sibling tree, and the last slot of the sector
+        let offset = entry_offset(&bytes, 3);
+        bytes[offset + 66] = 0x07;
+
+        let cfb = open(bytes.clone(), false).unwrap();
+        let entries: Vec<_> = cfb.entries().collect();
+        assert_eq!(entries.len(), 4);
+        assert!(entries[..3].iter().all(Result::is_ok));
+        assert!(matches!(&entries[3], Err(Error::InvalidDirectory(reason)) if reason.contains("stream ID 3")));
+        assert!(matches!(cfb.stream_names(), Err(Error::InvalidDirectory(reason)) if reason.contains("stream ID 3")));
+
+        let cfb = open(bytes, true).unwrap();
+        let entries: Vec<_> = cfb.entries().map(Result::unwrap).collect();
+        assert!(matches!(entries[..], [Entry::RootStorage(_), Entry::Stream(_), Entry::Stream(_),
+                                       Entry::Malformed { id: StreamID(3), raw_type: 0x07 }]));
+        assert_eq!(cfb.stream_names().unwrap(), ["A", "B"]);
+        assert_eq!(cfb.stream_bytes("B").unwrap(), b"b".to_vec());
+        assert_eq!(cfb.warnings(), [Warning {
+            rule: Rule::ObjectType,
+            location: Location::Slot { id: 3, offset: offset as u64 },
+            value: 0x07,
+        }]);
+    }
+
+    #[test]
+    fn pathological_name_lengths() {
+        let bytes = build();
+        let small = entry_offset(&bytes, 1);
+        let large = entry_offset(&bytes, 2);
+        for (name_length, offset, name) in [(0xFFFF, small, "Small"), (0, small, "Small"), (11, small, "Small"),
+                                            (4, large, "Large")] {
+            let mut bytes = bytes.clone();
+            put_u16(&mut bytes, offset + 64, name_length);
+
+            let cfb = open(bytes.clone(), false).unwrap();
+            assert!(matches!(cfb.stream_names(), Err(Error::InvalidDirectory(reason)) if reason.contains("name length")),
+                    "{}", name_length);
+
+            let cfb = open(bytes, true).unwrap();
+            assert_eq!(cfb.stream_names().unwrap(), ["Large", "Small"]);
+            assert_eq!(cfb.stream_bytes("Small").unwrap(), b"small".to_vec());
+            let warning = Warning {
+                rule: Rule::NameLength,
+                location: Location::Entry { offset: offset as u64, name: name.to_string() },
+                value: name_length as u64,
+            };
+            assert_eq!(cfb.warnings(), [warning], "{}", name_length);
+        }
+    }
+
+    #[test]
+    fn malformed_stream_id_links() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "A", b"a");
+        builder.stream(0, "B", b"b");
+        builder.stream(0, "C", b"c");
+        let bytes = builder.build();
+        // B is the root of the sibling tree, C its right sibling
+        let offset = entry_offset(&bytes, 2);
+
+        // a reserved stream ID, and the first one past the single directory sector
+        for link in [0xFFFFFFFC, 0xFFFFFFFB, 4] {
+            let mut bytes = bytes.clone();
+            put_u32(&mut bytes, offset + 72, link);
+
+            let cfb = open(bytes.clone(), false).unwrap();
+            assert!(matches!(cfb.stream_names(), Err(Error::InvalidDirectory(reason)) if reason.contains("malformed link")),
+                    "0x{:08X}", link);
+
+            let cfb = open(bytes, true).unwrap();
+            assert_eq!(cfb.stream_names().unwrap(), ["A", "B"]);
+            assert_eq!(cfb.stream_bytes("A").unwrap(), b"a".to_vec());
+            assert!(cfb.entry_by_path("C").unwrap().is_none());
+            assert_eq!(cfb.warnings(), [Warning {
+                rule: Rule::StreamIdLink,
+                location: Location::Entry { offset: offset as u64, name: "B".to_string() },
+                value: link as u64,
+            }], "0x{:08X}", link);
+            if link != 4 {
+                assert_eq!(cfb.entry_by_path("B").unwrap().unwrap().right_sibling_id(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn sectors_past_the_end() {
+        let bytes = build();
+        // the only FAT sector is sector 0, moved to the sector past the last
+        let past_end = (bytes.len() / 512 - 1) as u32;
+        let mut moved = bytes.clone();
+        put_u32(&mut moved, 76, past_end);
+        let err = CfbOpenOptions::new().open_bytes(moved.clone()).and_then(|cfb| cfb.stream_bytes("Small")).unwrap_err();
+        assert!(matches!(err, Error::Truncated { size: 512, available: 0 }), "{:?}", err);
+
+        let cfb = CfbOpenOptions::new().lenient(true).open_bytes(moved).unwrap();
+        cfb.stream_names().ok();
+        assert!(cfb.warnings().contains(&Warning {
+            rule: Rule::TruncatedSector,
+            location: Location::Sector { sector: past_end, offset: 512 * (past_end as u64 + 1) },
+            value: 0,
+        }), "{:?}", cfb.warnings());
+
+        // the directory sector cut short after 100 bytes
+        let cut = bytes[..2 * 512 + 100].to_vec();
+        let err = CfbOpenOptions::new().open_bytes(cut.clone()).and_then(|cfb| cfb.stream_names()).unwrap_err();
+        assert!(matches!(err, Error::Truncated { size: 512, available: 100 }), "{:?}", err);
+        let cfb = open(cut, true).unwrap();
+        cfb.stream_names().ok();
+        assert_eq!(cfb.warnings()[0], Warning {
+            rule: Rule::TruncatedSector,
+            location: Location::Sector { sector: 1, offset: 1024 },
+            value: 100,
+        });
+    }
+}
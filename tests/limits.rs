@@ -0,0 +1,135 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, Entry, Error, Limit, Limits};
+    use crate::common::{CfbBuilder, entry_offset, put_u32};
+
+    fn open(bytes: Vec<u8>, options: &CfbOpenOptions) -> Cfb {
+        options.open_reader_at(Cursor::new(bytes), 0, None).unwrap()
+    }
+
+    fn stream_bytes(cfb: &Cfb, name: &str) -> Result<Vec<u8>, Error> {
+        match cfb.directory_entry(name) {
+            Ok(Entry::Stream(stream)) => stream.bytes(),
+            entry => panic!("{} is not a stream: {:?}", name, entry),
+        }
+    }
+
+    /// Builds a file holding a stream of 10 sectors, returning it with the offset of the entry
+    fn ten_sector_stream() -> (Vec<u8>, usize) {
+        let mut builder = CfbBuilder::new();
+        let id = builder.stream(0, "Stream", &[7; 5000]);
+        let bytes = builder.build();
+        let offset = entry_offset(&bytes, id);
+        (bytes, offset)
+    }
+
+    #[test]
+    fn defaults_derive_from_file_size() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        assert_eq!(cfb.limits(), Limits::for_size(Some(15360)));
+        assert_eq!(cfb.limits().max_bytes_read, 15360);
+        assert_eq!(cfb.limits().max_chain_length, 15360 / 64);
+        assert_eq!(cfb.limits().max_directory_entries, 15360 / 128);
+    }
+
+    #[test]
+    fn bytes_read_limit() {
+        let (mut bytes, offset) = ten_sector_stream();
+        assert_eq!(stream_bytes(&open(bytes.clone(), &CfbOpenOptions::new()), "Stream").unwrap(), [7; 5000]);
+
+        let cfb = open(bytes.clone(), CfbOpenOptions::new().max_bytes_read(1000));
+        let mut buf = Vec::new();
+        let err = Error::from(cfb.open_stream("Stream").unwrap().read_to_end(&mut buf).unwrap_err());
+        assert!(matches!(err, Error::LimitExceeded { which: Limit::BytesRead, limit: 1000, .. }), "{:?}", err);
+
+        // a stream declaring a terabyte fails before anything is read
+        bytes[offset + 120..offset + 128].copy_from_slice(&(1u64 << 40).to_le_bytes());
+        let len = bytes.len() as u64;
+        let err = stream_bytes(&open(bytes, &CfbOpenOptions::new()), "Stream").unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { which: Limit::BytesRead, limit, observed }
+            if limit == len && observed == 1 << 40), "{:?}", err);
+    }
+
+    #[test]
+    fn declared_size_beyond_file() {
+        let (mut bytes, offset) = ten_sector_stream();
+        bytes[offset + 120..offset + 128].copy_from_slice(&u64::MAX.to_le_bytes());
+        let sectors = bytes.len() as u64 - 512;
+
+        // without the limit of bytes read, a strict reading still refuses the size before reading
+        let cfb = open(bytes.clone(), CfbOpenOptions::new().max_bytes_read(u64::MAX));
+        let err = stream_bytes(&cfb, "Stream").unwrap_err();
+        assert!(matches!(&err, Error::InvalidDirectory(reason)
+            if reason.contains(&u64::MAX.to_string()) && reason.contains(&sectors.to_string())), "{:?}", err);
+        assert!(matches!(cfb.copy_stream_to("Stream", &mut std::io::sink()), Err(Error::InvalidDirectory(_))));
+
+        // a lenient reading, which takes the size as 4 GiB in a version 3 file, reads the chain and
+        // fails where it ends, never allocating the size
+        let cfb = open(bytes, CfbOpenOptions::new().max_bytes_read(u64::MAX).lenient(true));
+        let err = stream_bytes(&cfb, "Stream").unwrap_err();
+        assert!(matches!(err, Error::TruncatedChain { size: 0xFFFFFFFF, available: 5120 }), "{:?}", err);
+    }
+
+    #[test]
+    fn chain_length_limit() {
+        let (mut bytes, offset) = ten_sector_stream();
+
+        // the last sector of the stream links back to its first, and the stream declares a gigabyte
+        let start = u32::from_le_bytes([bytes[offset + 116], bytes[offset + 117], bytes[offset + 118], bytes[offset + 119]]);
+        put_u32(&mut bytes, 512 + (start as usize + 9) * 4, start);
+        bytes[offset + 120..offset + 128].copy_from_slice(&(1u64 << 30).to_le_bytes());
+
+        // read through a reader, as reading the stream whole fails first for its size, beyond the file
+        let max_chain_length = bytes.len() as u64 / 64;
+        let cfb = open(bytes, CfbOpenOptions::new().max_bytes_read(u64::MAX));
+        let mut buf = Vec::new();
+        let err = Error::from(cfb.open_stream("Stream").unwrap().read_to_end(&mut buf).unwrap_err());
+        assert!(matches!(err, Error::LimitExceeded { which: Limit::ChainLength, limit, observed }
+            if limit == max_chain_length && observed == limit + 1), "{:?}", err);
+    }
+
+    #[test]
+    fn directory_entries_limit() {
+        let mut builder = CfbBuilder::new();
+        for name in ["A", "B", "C", "D", "E", "F", "G", "H"] {
+            builder.stream(0, name, &[0; 8]);
+        }
+        let mut bytes = builder.build();
+        assert!(open(bytes.clone(), &CfbOpenOptions::new()).entry_by_path("H").unwrap().is_some());
+
+        let cfb = open(bytes.clone(), CfbOpenOptions::new().max_directory_entries(4));
+        let err = cfb.entry_by_path("H").unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { which: Limit::DirectoryEntries, limit: 4, .. }), "{:?}", err);
+        assert!(matches!(cfb.directory_entry("H"), Err(Error::NotFound { .. })));
+
+        // a child ID far beyond the directory fails without walking the directory chain
+        let root_offset = entry_offset(&bytes, 0);
+        put_u32(&mut bytes, root_offset + 76, 0xFFFFFFF0);
+        let err = open(bytes, &CfbOpenOptions::new()).entry_by_path("H").unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { which: Limit::DirectoryEntries, observed: 0xFFFFFFF1, .. }),
+                "{:?}", err);
+    }
+
+    #[test]
+    fn tree_depth_limit() {
+        let mut builder = CfbBuilder::new();
+        let mut parent = 0;
+        for _ in 0..6 {
+            parent = builder.storage(parent, "Nested");
+        }
+        builder.stream(parent, "Leaf", b"leaf");
+        let bytes = builder.build();
+        let path = "Nested/Nested/Nested/Nested/Nested/Nested/Leaf";
+
+        let cfb = open(bytes.clone(), &CfbOpenOptions::new());
+        assert!(matches!(cfb.entry_by_path(path).unwrap(), Some(Entry::Stream(_))));
+
+        let cfb = open(bytes, CfbOpenOptions::new().max_tree_depth(4));
+        assert!(cfb.entry_by_path("Nested/Nested/Nested/Nested").unwrap().is_some());
+        let err = cfb.entry_by_path(path).unwrap_err();
+        assert!(matches!(err, Error::LimitExceeded { which: Limit::TreeDepth, limit: 4, observed: 5 }), "{:?}", err);
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use ole_kit::cfb::Cfb;
+
+    const HWP_STREAMS: [&str; 53] = [
+        "BinData/BIN0001.png", "BinData/BIN0002.png", "BinData/BIN0003.bmp", "BinData/BIN0004.png",
+        "BinData/BIN0005.bmp", "BinData/BIN0006.bmp", "BinData/BIN0007.bmp", "BinData/BIN0008.bmp",
+        "BinData/BIN0009.bmp", "BinData/BIN000A.bmp", "BinData/BIN000B.bmp", "BinData/BIN000C.bmp",
+        "BinData/BIN000D.bmp", "BinData/BIN000E.bmp", "BinData/BIN000F.bmp", "BinData/BIN0010.bmp",
+        "BinData/BIN0011.WMF", "BinData/BIN0012.WMF", "BinData/BIN0013.WMF", "BinData/BIN0014.png",
+        "BinData/BIN0015.png", "BinData/BIN0016.png", "BinData/BIN0017.png", "BinData/BIN0018.png",
+        "BinData/BIN0019.png", "BinData/BIN001A.png", "BinData/BIN001B.png", "BinData/BIN001C.png",
+        "BinData/BIN001D.png", "BinData/BIN001E.png", "BinData/BIN001F.png", "BinData/BIN0020.png",
+        "BinData/BIN0021.png", "BinData/BIN0022.png", "BinData/BIN0023.png", "BinData/BIN0024.png",
+        "BinData/BIN0025.WMF", "BinData/BIN0026.WMF", "DocInfo", "PrvText",
+        "Scripts/DefaultJScript", "Scripts/JScriptVersion", "BodyText/Section0", "PrvImage",
+        "ViewText/Section0", "ViewText/Section1", "ViewText/Section2", "ViewText/Section3",
+        "ViewText/Section4", "ViewText/Section5", "DocOptions/_LinkDoc", "FileHeader",
+        "\\u{5}HwpSummaryInformation"
+    ];
+
+    #[test]
+    fn hwp_names() {
+        let cfb = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+
+        let mut entries: Vec<&str> = HWP_STREAMS.to_vec();
+        for (storage, before) in [("BinData", "BinData/BIN0001.png"), ("Scripts", "Scripts/DefaultJScript"),
+                                  ("BodyText", "BodyText/Section0"), ("ViewText", "ViewText/Section0"),
+                                  ("DocOptions", "DocOptions/_LinkDoc")] {
+            let position = entries.iter().position(|path| *path == before).unwrap();
+            entries.insert(position, storage);
+        }
+        assert_eq!(cfb.entry_names().unwrap(), entries);
+        assert_eq!(cfb.stream_names().unwrap(), HWP_STREAMS);
+
+        let sizes = cfb.stream_names_with_sizes().unwrap();
+        assert_eq!(sizes.len(), HWP_STREAMS.len());
+        assert_eq!(sizes[0], ("BinData/BIN0001.png".to_string(), 12153));
+        assert_eq!(sizes[47], ("ViewText/Section3".to_string(), 174052));
+        assert_eq!(sizes[52], ("\\u{5}HwpSummaryInformation".to_string(), 485));
+        assert_eq!(sizes.iter().map(|(_, size)| size).sum::<u64>(), 324604);
+    }
+
+    #[test]
+    fn doc_names() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let streams = ["Data", "1Table", "WordDocument", "\\u{5}SummaryInformation", "\\u{5}DocumentSummaryInformation"];
+
+        assert_eq!(cfb.entry_names().unwrap(), streams);
+        assert_eq!(cfb.stream_names().unwrap(), streams);
+        assert_eq!(cfb.stream_names_with_sizes().unwrap(),
+                   streams.iter().map(|path| path.to_string()).zip([4096, 4096, 4096, 72, 144]).collect::<Vec<_>>());
+    }
+}
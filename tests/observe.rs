@@ -0,0 +1,164 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::CfbBuilder;
+    use std::collections::HashSet;
+    use std::io::{Read, Seek, SeekFrom};
+    use std::sync::Arc;
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, Entry, SectorNumber, SectorOwner, SectorReadCounter};
+
+    #[test]
+    fn rereading_a_stream_only_reads_its_data() {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().instrument(counter.clone()).open("tests_rsc/testing.doc").unwrap();
+        let map = cfb.sector_map().unwrap();
+
+        let mut reader = cfb.open_stream("WordDocument").unwrap();
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).unwrap();
+        assert_eq!(bytes.len(), 4096);
+        counter.take_reads();
+
+        // the sectors located by the first pass are remembered, so the FAT isn't read again
+        reader.seek(SeekFrom::Start(0)).unwrap();
+        reader.read_to_end(&mut bytes).unwrap();
+        let sectors = counter.sectors(cfb.sector_size());
+        assert_eq!(sectors.len(), 8);
+        assert!(sectors.iter().all(|sector| map.owner(*sector) == Some(&SectorOwner::Stream("WordDocument".to_string()))));
+        assert_eq!(counter.bytes_read(), 4096);
+    }
+
+    #[test]
+    fn partial_reads_only_read_overlapping_sectors() {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().instrument(counter.clone()).open("tests_rsc/testing.doc").unwrap();
+        let map = cfb.sector_map().unwrap();
+        let stream = match cfb.entry_by_path("WordDocument").unwrap() {
+            Some(Entry::Stream(stream)) => stream,
+            entry => panic!("{:?}", entry),
+        };
+        counter.take_reads();
+
+        // the FIB at the start of the stream lies in its first sector
+        let mut fib = [0u8; 68];
+        assert_eq!(stream.read_at(0, &mut fib).unwrap(), 68);
+        let is_data = |sector: &SectorNumber| map.owner(*sector) == Some(&SectorOwner::Stream("WordDocument".to_string()));
+        let data_sectors: Vec<_> = counter.sectors(cfb.sector_size()).into_iter().filter(is_data).collect();
+        assert_eq!(data_sectors, [stream.starting_sector_location()]);
+        counter.take_reads();
+
+        // a read crossing a sector boundary reads both sectors, and nothing else
+        let mut buf = [0u8; 100];
+        assert_eq!(stream.read_at(1000, &mut buf).unwrap(), 100);
+        let sectors = counter.sectors(cfb.sector_size());
+        assert_eq!(sectors.len(), 2);
+        assert!(sectors.iter().all(is_data));
+    }
+
+    #[test]
+    fn stream_len_only_reads_the_directory() {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().instrument(counter.clone()).open("tests_rsc/testing.doc").unwrap();
+        let map = Cfb::from_path("tests_rsc/testing.doc").unwrap().sector_map().unwrap();
+        counter.take_reads();
+
+        assert_eq!(cfb.stream_len("WordDocument"), Some(4096));
+        assert_eq!(cfb.stream_is_in_mini_stream("WordDocument"), Some(false));
+        assert_eq!(cfb.stream_len("\u{5}SummaryInformation"), Some(72));
+        assert_eq!(cfb.stream_is_in_mini_stream("\u{5}SummaryInformation"), Some(true));
+        assert_eq!(cfb.stream_len("Root Entry"), None);
+        assert_eq!(cfb.stream_is_in_mini_stream("Nothing"), None);
+
+        let sectors = counter.sectors(cfb.sector_size());
+        assert!(sectors.iter().any(|sector| map.owner(*sector) == Some(&SectorOwner::Directory)));
+        assert!(sectors.iter().all(|sector| matches!(map.owner(*sector), Some(SectorOwner::Directory | SectorOwner::Fat))),
+                "{:?}", sectors);
+    }
+
+    #[test]
+    fn find_entries_in_one_pass() {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().instrument(counter.clone()).open("tests_rsc/testing.doc").unwrap();
+        let map = Cfb::from_path("tests_rsc/testing.doc").unwrap().sector_map().unwrap();
+        counter.take_reads();
+
+        let found = cfb.find_entries(&["WordDocument", "root entry", "1TABLE", "Missing"]);
+        assert_eq!(found.len(), 3);
+        assert!(matches!(found["WordDocument"], Entry::Stream(_)));
+        assert!(matches!(found["root entry"], Entry::RootStorage(_)));
+        assert_eq!(found["1TABLE"].name(), "1Table");
+
+        // every directory sector is read once at most
+        let reads = counter.take_reads();
+        let directory_reads: Vec<_> = reads.iter()
+            .filter(|read| read.offset >= 512 && map.owner(SectorNumber((read.offset / 512 - 1) as u32)) == Some(&SectorOwner::Directory))
+            .map(|read| read.offset)
+            .collect();
+        let distinct: HashSet<_> = directory_reads.iter().collect();
+        assert!(!directory_reads.is_empty());
+        assert_eq!(distinct.len(), directory_reads.len());
+    }
+
+    #[test]
+    fn clones_share_parsed_fat_sectors() {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().instrument(counter.clone()).open("tests_rsc/hwp5.0.hwp").unwrap();
+        let map = cfb.sector_map().unwrap();
+        let clone = cfb.clone();
+        let expected = std::thread::spawn(move || clone.stream_bytes("Section3").unwrap()).join().unwrap();
+        counter.take_reads();
+
+        // the FAT sectors the clone parsed aren't read again, only the stream data is
+        assert_eq!(cfb.stream_bytes("Section3").unwrap(), expected);
+        let sectors = counter.sectors(cfb.sector_size());
+        assert!(!sectors.is_empty());
+        assert!(sectors.iter().all(|sector| map.owner(*sector) != Some(&SectorOwner::Fat)));
+    }
+
+    #[test]
+    fn clones_report_to_the_same_observer() {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().instrument(counter.clone()).open("tests_rsc/testing.doc").unwrap();
+        assert!(counter.read_count() > 0);
+        counter.take_reads();
+
+        let clone = cfb.try_clone().unwrap();
+        assert_eq!(clone.stream_bytes("WordDocument").unwrap().len(), 4096);
+        let reads = counter.take_reads();
+        assert!(!reads.is_empty());
+        assert!(reads.iter().all(|read| read.read == Some(read.len)));
+    }
+
+    #[test]
+    fn contiguous_sectors_are_read_at_once() {
+        let data: Vec<u8> = (0..64 * 512).map(|i| (i % 251) as u8).collect();
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Contents", &data);
+        builder.stream(0, "Small", &data[..3000]);
+        let bytes = builder.build();
+
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().instrument(counter.clone()).open_bytes(bytes).unwrap();
+        counter.take_reads();
+
+        // the 64 sectors of the stream follow one another, so a single read fetches them, along
+        // with the few reads locating them through the FAT
+        assert_eq!(cfb.stream_bytes("Contents").unwrap(), data);
+        let reads = counter.take_reads();
+        assert!(reads.len() <= 4, "{:?}", reads);
+        assert!(reads.iter().any(|read| read.len == data.len()), "{:?}", reads);
+
+        let mut copied = Vec::new();
+        assert_eq!(cfb.copy_stream_to("Contents", &mut copied).unwrap(), data.len() as u64);
+        assert_eq!(copied, data);
+        assert!(counter.take_reads().len() <= 4);
+
+        // the sectors of the mini stream too
+        assert_eq!(cfb.stream_bytes("Small").unwrap(), &data[..3000]);
+        counter.take_reads();
+        let mini_stream = cfb.mini_stream_bytes().unwrap();
+        assert_eq!(&mini_stream[..3000], &data[..3000]);
+        assert!(counter.take_reads().len() <= 4);
+    }
+}
@@ -0,0 +1,90 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, Presence, QuickLook, SectorReadCounter};
+    use crate::common::CfbBuilder;
+
+    /// Takes a quick look at a file, returning it with the reads it made
+    fn quick_look(path: &str) -> (QuickLook, usize) {
+        let counter = Arc::new(SectorReadCounter::new());
+        let cfb = CfbOpenOptions::new().instrument(counter.clone()).open(path).unwrap();
+        counter.take_reads();
+        let quick_look = cfb.quick_look(10).unwrap();
+        (quick_look, counter.read_count())
+    }
+
+    #[test]
+    fn looks_within_the_budget() {
+        // the fourth object of the root storage lies in the second directory sector
+        let (doc, reads) = quick_look("tests_rsc/testing.doc");
+        assert_eq!(doc, QuickLook {
+            major_version: 3,
+            minor_version: 0x3E,
+            sector_size: 512,
+            fat_sectors: 1,
+            directory_sectors: None,
+            mini_fat_sectors: 1,
+            difat_sectors: 0,
+            root_clsid: Some([0; 16]),
+            root_entries: vec!["Data".to_string(), "1Table".to_string(), "WordDocument".to_string()],
+            root_entries_complete: false,
+            macros: Presence::Unknown,
+            encrypted_package: Presence::Unknown,
+            reads: 2,
+            bytes_read: 1024,
+        });
+        assert_eq!(reads, 2);
+
+        // the child of the root storage lies beyond the first directory sector
+        let (hwp, reads) = quick_look("tests_rsc/hwp5.0.hwp");
+        assert_eq!(hwp, QuickLook {
+            fat_sectors: 6,
+            mini_fat_sectors: 4,
+            root_entries: Vec::new(),
+            ..doc
+        });
+        assert_eq!(reads, 2);
+    }
+
+    #[test]
+    fn answers_what_the_first_directory_sector_holds() {
+        let mut builder = CfbBuilder::new();
+        builder.sector_shift(12);
+        builder.stream(0, "WordDocument", b"text");
+        let macros = builder.storage(0, "Macros");
+        builder.stream(macros, "PROJECT", b"ID=");
+        let bytes = builder.build();
+
+        let quick_look = Cfb::from_bytes(bytes.clone()).unwrap().quick_look(10).unwrap();
+        assert_eq!(quick_look.major_version, 4);
+        assert_eq!(quick_look.root_entries, ["Macros", "WordDocument"]);
+        assert!(quick_look.root_entries_complete);
+        assert_eq!(quick_look.macros, Presence::Present);
+        assert_eq!(quick_look.encrypted_package, Presence::Absent);
+
+        let quick_look = Cfb::from_bytes(bytes).unwrap().quick_look(1).unwrap();
+        assert_eq!(quick_look.root_entries.len(), 1);
+        assert!(!quick_look.root_entries_complete);
+        assert_eq!(quick_look.macros, Presence::Present);
+    }
+
+    #[test]
+    fn degrades_on_truncated_files() {
+        // cut within the first directory sector, after the root storage and 1Table
+        let bytes = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let cfb = Cfb::from_bytes(bytes[..12800 + 256].to_vec()).unwrap();
+        let quick_look = cfb.quick_look(10).unwrap();
+        assert_eq!(quick_look.root_clsid, Some([0; 16]));
+        assert_eq!(quick_look.root_entries, ["1Table"]);
+        assert_eq!(quick_look.macros, Presence::Unknown);
+        assert_eq!(quick_look.bytes_read, 512 + 256);
+
+        // cut before it
+        let cfb = Cfb::from_bytes(bytes[..12800].to_vec()).unwrap();
+        let quick_look = cfb.quick_look(10).unwrap();
+        assert_eq!((quick_look.root_clsid, quick_look.root_entries.len()), (None, 0));
+        assert_eq!((quick_look.reads, quick_look.bytes_read), (2, 512));
+    }
+}
@@ -0,0 +1,65 @@
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb;
+
+    /// Fills a buffer with deterministic pseudo-random bytes.
+    fn junk(len: usize) -> Vec<u8> {
+        let mut state = 0x2545F491u32;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn recover_embedded_doc() {
+        let doc = std::fs::read("tests_rsc/testing.doc").unwrap();
+
+        let mut blob = junk(12345);
+        // a bare signature without a valid header must be rejected
+        blob[100..108].copy_from_slice(&doc[..8]);
+        blob.extend_from_slice(&doc);
+        blob.extend(junk(777));
+
+        let found = cfb::scan(Cursor::new(blob.clone()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].offset(), 12345);
+        assert_eq!(found[0].size(), doc.len() as u64);
+
+        let cfb = found[0].open(Cursor::new(blob)).unwrap();
+        let word_document_bytes = cfb.stream_bytes("WordDocument").unwrap();
+        assert_eq!(word_document_bytes.len(), 4096);
+    }
+
+    #[test]
+    fn scan_without_compound_file() {
+        assert!(cfb::scan(Cursor::new(junk(200_000))).next().is_none());
+    }
+
+    #[test]
+    fn self_linked_difat_is_bounded() {
+        // a header declaring 2,000,000 FAT sectors, listed by a DIFAT sector linking to itself
+        let doc = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let mut bytes = vec![0u8; 1536];
+        bytes[..76].copy_from_slice(&doc[..76]);
+        bytes[44..48].copy_from_slice(&2_000_000u32.to_le_bytes());
+        bytes[68..72].copy_from_slice(&0u32.to_le_bytes());
+        bytes[72..76].copy_from_slice(&2_000_000u32.to_le_bytes());
+        for index in 0..109 {
+            bytes[76 + index * 4..][..4].copy_from_slice(&1u32.to_le_bytes());
+        }
+        bytes[512 + 508..1024].copy_from_slice(&0u32.to_le_bytes());
+
+        let found = cfb::scan(Cursor::new(bytes)).next().unwrap().unwrap();
+        assert_eq!(found.offset(), 0);
+        assert!(found.size() <= 1536, "{}", found.size());
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod tests {
+    use ole_kit::cfb::{Cfb, Entry, Error, ObjectType, SliceCfb, StreamID};
+
+    #[test]
+    fn enumerates_directory_entries() {
+        let bytes = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let cfb = SliceCfb::new(&bytes).unwrap();
+        assert_eq!(cfb.sector_size(), 512);
+
+        let names: Vec<_> = cfb.entries()
+            .filter(|entry| matches!(entry.object_type(), Some(object_type) if object_type != ObjectType::Unknown))
+            .map(|entry| entry.name())
+            .collect();
+        for name in ["Root Entry", "1Table", "WordDocument", "Data", "\u{5}SummaryInformation", "\u{5}DocumentSummaryInformation"] {
+            assert!(names.iter().any(|found| found == name), "{} not in {:?}", name, names);
+        }
+        assert_eq!(cfb.entry(StreamID(0)).unwrap().object_type(), Some(ObjectType::RootStorage));
+    }
+
+    #[test]
+    fn agrees_with_cfb() {
+        let bytes = std::fs::read("tests_rsc/testing.doc").unwrap();
+        let slice_cfb = SliceCfb::new(&bytes).unwrap();
+        let cfb = Cfb::from_bytes(bytes.clone()).unwrap();
+
+        let locations: Vec<_> = cfb.fat_sector_locations().collect::<Result<_, _>>().unwrap();
+        assert_eq!(slice_cfb.fat_sector_locations(), locations);
+
+        let entries: Vec<_> = cfb.entries().collect::<Result<_, _>>().unwrap();
+        let raw_entries: Vec<_> = slice_cfb.entries().collect();
+        assert_eq!(raw_entries.len(), entries.len());
+        for (raw_entry, entry) in raw_entries.iter().zip(&entries) {
+            match entry {
+                Entry::RootStorage(_) | Entry::Storage(_) | Entry::Stream(_) => {
+                    assert_eq!(raw_entry.name(), entry.name());
+                    assert_eq!(raw_entry.left_sibling_id(), entry.left_sibling_id());
+                    assert_eq!(raw_entry.right_sibling_id(), entry.right_sibling_id());
+                    assert_eq!(raw_entry.child_id(), entry.child_id());
+                }
+                _ => assert_eq!(raw_entry.object_type(), Some(ObjectType::Unknown)),
+            }
+        }
+        let word_document = raw_entries.iter().find(|entry| entry.name() == "WordDocument").unwrap();
+        assert_eq!(word_document.stream_size().0, 4096);
+        assert_eq!(slice_cfb.sector_chain(word_document.starting_sector_location()).len(), 8);
+    }
+
+    #[test]
+    fn rejects_other_bytes() {
+        assert!(matches!(SliceCfb::new(b"not a compound file"), Err(Error::NotCfb { .. })));
+        assert!(matches!(SliceCfb::new(&[]), Err(Error::NotCfb { .. })));
+    }
+}
@@ -0,0 +1,73 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+    use ole_kit::cfb::{Cfb, Error, TruncatedEntry};
+    use crate::common::CfbBuilder;
+
+    /// Builds a file laid out as the FAT in sector 0, the directory in sector 1, the mini FAT in
+    /// sector 2, the mini stream in sector 3 and the large stream in sectors 4 to 13
+    fn build() -> Vec<u8> {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Small", &[1; 100]);
+        builder.stream(0, "Large", &[2; 5000]);
+        builder.build()
+    }
+
+    fn open(mut bytes: Vec<u8>, len: usize) -> Cfb {
+        bytes.truncate(len);
+        Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap()
+    }
+
+    fn entry(path: &str, declared: u64, available: u64) -> TruncatedEntry {
+        TruncatedEntry { path: path.to_string(), declared, available }
+    }
+
+    #[test]
+    fn intact_file() {
+        let bytes = build();
+        let len = bytes.len();
+        let report = open(bytes, len).truncation_report().unwrap();
+        assert_eq!(report.file_len, 15 * 512);
+        assert!(!report.is_truncated(), "{:?}", report);
+    }
+
+    #[test]
+    fn cut_within_large_stream() {
+        let cfb = open(build(), 15 * 512 - 300);
+        let report = cfb.truncation_report().unwrap();
+        assert_eq!(report.entries, [entry("Large", 5000, 4820)]);
+        assert!(!report.fat_truncated && !report.directory_truncated && !report.mini_fat_truncated);
+
+        let mut bytes = Vec::new();
+        let err = Error::from(cfb.open_stream("Large").unwrap().read_to_end(&mut bytes).unwrap_err());
+        assert!(matches!(err, Error::Truncated { size: 5000, available: 4820 }), "{:?}", err);
+        assert_eq!(bytes.len(), 4820);
+        assert_eq!(cfb.stream_bytes("Small").unwrap(), vec![1; 100]);
+    }
+
+    #[test]
+    fn cut_within_mini_stream() {
+        let cfb = open(build(), 4 * 512 + 74);
+        let report = cfb.truncation_report().unwrap();
+        assert_eq!(report.entries, [entry("", 128, 74), entry("Large", 5000, 0), entry("Small", 100, 74)]);
+        assert!(!report.fat_truncated && !report.directory_truncated && !report.mini_fat_truncated);
+
+        let mut bytes = Vec::new();
+        let err = Error::from(cfb.open_stream("Small").unwrap().read_to_end(&mut bytes).unwrap_err());
+        assert!(matches!(err, Error::Truncated { size: 100, available: 74 }), "{:?}", err);
+    }
+
+    #[test]
+    fn cut_within_metadata() {
+        let report = open(build(), 2 * 512 + 200).truncation_report().unwrap();
+        assert!(!report.fat_truncated);
+        assert!(report.directory_truncated);
+        assert!(report.mini_fat_truncated);
+
+        let report = open(build(), 512 + 100).truncation_report().unwrap();
+        assert!(report.fat_truncated);
+        assert!(report.directory_truncated);
+    }
+}
@@ -0,0 +1,75 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, ChildSize, ObjectType, SizeTotals};
+    use crate::common::CfbBuilder;
+
+    fn totals(declared: u64, allocated: u64, entries: u64) -> SizeTotals {
+        SizeTotals { declared, allocated, entries }
+    }
+
+    fn child(name: &str, object_type: ObjectType, totals: SizeTotals) -> ChildSize {
+        ChildSize { name: name.to_string(), object_type, totals }
+    }
+
+    /// Root: Big (5000 bytes, 10 sectors), Small (100 bytes, 2 mini sectors), and Sub holding A (64
+    /// bytes, 1 mini sector), B (empty), and Deep holding C (4096 bytes at the cutoff, 8 sectors)
+    /// and D (1 byte, 1 mini sector)
+    fn fixture() -> Cfb {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Big", &[1; 5000]);
+        builder.stream(0, "Small", &[2; 100]);
+        let sub = builder.storage(0, "Sub");
+        builder.stream(sub, "A", &[3; 64]);
+        builder.stream(sub, "B", &[]);
+        let deep = builder.storage(sub, "Deep");
+        builder.stream(deep, "C", &[4; 4096]);
+        builder.stream(deep, "D", &[5]);
+        Cfb::from_reader_at(Cursor::new(builder.build()), 0, None).unwrap()
+    }
+
+    #[test]
+    fn root_storage_size() {
+        let report = fixture().storage_size("").unwrap().unwrap();
+
+        // Sub: 64 + 0 + 4096 + 1 declared, 64 + 0 + 4096 + 64 allocated, 6 entries with itself
+        assert_eq!(report.children, [
+            child("Big", ObjectType::Stream, totals(5000, 5120, 1)),
+            child("Sub", ObjectType::Storage, totals(4161, 4224, 6)),
+            child("Small", ObjectType::Stream, totals(100, 128, 1)),
+        ]);
+        assert_eq!(report.totals, totals(9261, 9472, 8));
+    }
+
+    #[test]
+    fn nested_storage_size() {
+        let cfb = fixture();
+
+        let report = cfb.storage_size("Sub").unwrap().unwrap();
+        assert_eq!(report.children, [
+            child("A", ObjectType::Stream, totals(64, 64, 1)),
+            child("B", ObjectType::Stream, totals(0, 0, 1)),
+            child("Deep", ObjectType::Storage, totals(4097, 4160, 3)),
+        ]);
+        assert_eq!(report.totals, totals(4161, 4224, 5));
+
+        assert_eq!(cfb.storage_size("Sub/Deep").unwrap().unwrap().totals, totals(4097, 4160, 2));
+        assert_eq!(cfb.storage_size("Big").unwrap(), None);
+        assert_eq!(cfb.storage_size("Missing").unwrap(), None);
+    }
+
+    #[test]
+    fn fixture_totals_agree_with_listing() {
+        for fixture in ["tests_rsc/hwp5.0.hwp", "tests_rsc/testing.doc"] {
+            let cfb = Cfb::from_path(fixture).unwrap();
+            let report = cfb.storage_size("").unwrap().unwrap();
+            let sizes = cfb.stream_names_with_sizes().unwrap();
+
+            assert_eq!(report.totals.declared, sizes.iter().map(|(_, size)| size).sum::<u64>());
+            assert_eq!(report.totals.entries, cfb.entry_names().unwrap().len() as u64);
+            assert!(report.totals.allocated >= report.totals.declared);
+        }
+    }
+}
@@ -0,0 +1,257 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+    use ole_kit::cfb::{Cfb, CfbOpenOptions, Entry, Error, ObjectType, Overlap, SectorNumber, SectorOwner, Violation};
+    use crate::common::{CfbBuilder, entry_offset, put_u32};
+
+    fn open(bytes: Vec<u8>) -> Cfb {
+        Cfb::from_reader_at(Cursor::new(bytes), 0, None).unwrap()
+    }
+
+    fn non_zero(path: &str, object_type: ObjectType, field: &'static str) -> Violation {
+        Violation::NonZeroField { path: path.to_string(), object_type, field }
+    }
+
+    #[test]
+    fn fixtures_are_valid() {
+        let cfb = Cfb::from_path("tests_rsc/hwp5.0.hwp").unwrap();
+        assert_eq!(cfb.validate(), []);
+
+        // Word stamps the root storage and every stream object with times, which only the zeroed
+        // fields break
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let mut expected = vec![non_zero("", ObjectType::RootStorage, "creation_time")];
+        for path in ["Data", "1Table", "WordDocument", "\u{5}SummaryInformation", "\u{5}DocumentSummaryInformation"] {
+            expected.push(non_zero(path, ObjectType::Stream, "creation_time"));
+            expected.push(non_zero(path, ObjectType::Stream, "modified_time"));
+        }
+        assert_eq!(cfb.validate(), expected);
+    }
+
+    #[test]
+    fn mini_fat_shorter_than_mini_stream() {
+        let mut builder = CfbBuilder::new();
+        for name in ["A", "B", "C", "D", "E"] {
+            builder.stream(0, name, &[0; 4000]);
+        }
+        let mut bytes = builder.build();
+        assert_eq!(open(bytes.clone()).validate(), []);
+
+        let size_offset = entry_offset(&bytes, 0) + 120;
+        bytes[size_offset..size_offset + 8].copy_from_slice(&(400u64 * 64).to_le_bytes());
+        assert_eq!(open(bytes).validate(), [
+            Violation::MiniStreamChainTooShort { declared: 400 * 64, capacity: 40 * 512 },
+            Violation::MiniFatTooShort { entries: 384, mini_sectors: 400 },
+        ]);
+    }
+
+    #[test]
+    fn fat_sectors_disagree_with_difat() {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "Stream", &[1; 5000]);
+        let bytes = builder.build();
+        assert_eq!(open(bytes.clone()).validate(), []);
+
+        // the header counts a FAT sector which the DIFAT doesn't list
+        let mut miscounted = bytes.clone();
+        put_u32(&mut miscounted, 44, 2);
+        assert_eq!(open(miscounted).validate(), [Violation::FatSectorCountMismatch { declared: 2, listed: 1 }]);
+
+        // the DIFAT lists the directory sector as a FAT sector
+        let mut listed_directory = bytes.clone();
+        put_u32(&mut listed_directory, 44, 2);
+        put_u32(&mut listed_directory, 80, 1);
+        assert_eq!(open(listed_directory).validate(), [
+            Violation::FatSectorNotMarked { sector: SectorNumber(1), entry: SectorNumber::ENDOFCHAIN },
+            Violation::SharedSector(Overlap {
+                sector: SectorNumber(1),
+                mini: false,
+                first: SectorOwner::Fat,
+                second: SectorOwner::Directory,
+            }),
+        ]);
+
+        // the FAT marks a free sector as a FAT sector
+        let mut unlisted = bytes;
+        put_u32(&mut unlisted, 512 + 100 * 4, 0xFFFFFFFD);
+        assert_eq!(open(unlisted).validate(), [Violation::UnlistedFatSector { sector: SectorNumber(100) }]);
+    }
+
+    #[test]
+    fn mini_stream_disagrees_with_root_entry() {
+        let mut builder = CfbBuilder::new();
+        for name in ["A", "B", "C", "D", "E"] {
+            builder.stream(0, name, &[0; 4000]);
+        }
+        let bytes = builder.build();
+
+        // the mini FAT allocates 315 mini sectors, beyond the declared size of 300 mini sectors
+        let mut shrunk = bytes.clone();
+        let size_offset = entry_offset(&shrunk, 0) + 120;
+        shrunk[size_offset..size_offset + 8].copy_from_slice(&(300u64 * 64).to_le_bytes());
+        assert_eq!(open(shrunk).validate(),
+                   [Violation::MiniFatBeyondMiniStream { last_allocated: 314, declared: 300 * 64 }]);
+
+        // the chain of the mini stream, from sector 6, ends at sector 10
+        let mut truncated = bytes;
+        put_u32(&mut truncated, 512 + 10 * 4, 0xFFFFFFFE);
+        let cfb = open(truncated);
+        assert_eq!(cfb.validate(), [Violation::MiniStreamChainTooShort { declared: 315 * 64, capacity: 5 * 512 }]);
+        assert!(matches!(cfb.mini_stream_bytes(),
+                         Err(Error::TruncatedChain { size: 20160, available: 2560 })));
+        let stream = match cfb.entry_by_path("E").unwrap() {
+            Some(Entry::Stream(stream)) => stream,
+            _ => panic!("E is missing"),
+        };
+        assert!(matches!(stream.bytes(), Err(Error::TruncatedChain { size: 4000, .. })));
+    }
+
+    #[test]
+    fn mini_fat_chain_disagrees_with_header() {
+        let mut builder = CfbBuilder::new();
+        for name in ["A", "B", "C", "D", "E"] {
+            builder.stream(0, name, &[5; 4000]);
+        }
+        let bytes = builder.build();
+        let read = |cfb: &Cfb, path: &str| match cfb.entry_by_path(path).unwrap() {
+            Some(Entry::Stream(stream)) => stream.bytes(),
+            _ => panic!("{} is missing", path),
+        };
+
+        // the chain of the mini FAT sectors 3, 4 and 5 is trusted over a count of 2
+        let mut too_long = bytes.clone();
+        put_u32(&mut too_long, 64, 2);
+        let cfb = open(too_long);
+        assert_eq!(cfb.validate(), [Violation::MiniFatSectorCountMismatch { declared: 2, chain: 3 }]);
+        assert_eq!(read(&cfb, "E").unwrap(), [5; 4000]);
+
+        // the chain ends at sector 4, so the mini sectors of E described by sector 5 are lost
+        let mut too_short = bytes;
+        put_u32(&mut too_short, 512 + 4 * 4, 0xFFFFFFFE);
+        let cfb = open(too_short);
+        assert_eq!(cfb.validate(), [
+            Violation::MiniFatSectorCountMismatch { declared: 3, chain: 2 },
+            Violation::MiniFatTooShort { entries: 256, mini_sectors: 315 },
+        ]);
+        assert_eq!(read(&cfb, "A").unwrap(), [5; 4000]);
+        assert!(matches!(read(&cfb, "E"), Err(Error::TruncatedChain { size: 4000, .. })));
+    }
+
+    /// Builds two streams of 9 sectors each, A and B, returning the file with the starting sectors
+    fn two_streams() -> (Vec<u8>, u32, u32) {
+        let mut builder = CfbBuilder::new();
+        builder.stream(0, "A", &[1; 4608]);
+        builder.stream(0, "B", &[2; 4608]);
+        let bytes = builder.build();
+
+        let cfb = open(bytes.clone());
+        let start = |path: &str| match cfb.entry_by_path(path).unwrap() {
+            Some(Entry::Stream(stream)) => stream.starting_sector_location().0,
+            _ => unreachable!(),
+        };
+        let (a, b) = (start("A"), start("B"));
+        (bytes, a, b)
+    }
+
+    #[test]
+    fn streams_share_a_tail_sector() {
+        let (mut bytes, a, b) = two_streams();
+        assert_eq!(open(bytes.clone()).validate(), []);
+
+        // the chain of A runs into the last sector of B instead of its own
+        put_u32(&mut bytes, 512 + (a as usize + 7) * 4, b + 8);
+        let shared = Overlap {
+            sector: SectorNumber(b + 8),
+            mini: false,
+            first: SectorOwner::Stream("A".to_string()),
+            second: SectorOwner::Stream("B".to_string()),
+        };
+
+        let cfb = open(bytes.clone());
+        assert_eq!(cfb.validate(), [Violation::SharedSector(shared.clone())]);
+        let map = cfb.sector_map().unwrap();
+        assert_eq!(map.owner(SectorNumber(b + 8)), Some(&SectorOwner::Stream("A".to_string())));
+        assert_eq!(map.owner(SectorNumber(a + 8)), None);
+        assert_eq!(map.owner(SectorNumber(0)), Some(&SectorOwner::Fat));
+
+        // a strict reading refuses to extract or walk the file
+        let stream = match cfb.entry_by_path("B").unwrap() {
+            Some(Entry::Stream(stream)) => stream,
+            _ => unreachable!(),
+        };
+        assert!(matches!(stream.bytes(), Err(Error::SharedSector(overlap)) if overlap == shared));
+        assert!(matches!(cfb.stream_names(), Err(Error::SharedSector(_))));
+        let walked: Vec<_> = cfb.walk().collect();
+        assert!(matches!(walked[..], [Err(Error::SharedSector(ref overlap))] if *overlap == shared));
+
+        // a lenient one reads what the chains say
+        let cfb = CfbOpenOptions::new().lenient(true).open_read_at(bytes, 0, None).unwrap();
+        let a = match cfb.entry_by_path("A").unwrap() {
+            Some(Entry::Stream(stream)) => stream.bytes().unwrap(),
+            _ => unreachable!(),
+        };
+        assert_eq!(a[..4096], [1; 4096]);
+        assert_eq!(a[4096..], [2; 512]);
+        assert_eq!(cfb.walk().filter_map(Result::ok).count(), 2);
+    }
+
+    #[test]
+    fn stream_runs_through_directory() {
+        let (mut bytes, a, _) = two_streams();
+        let directory = open(bytes.clone()).header().first_directory_sector_location();
+
+        put_u32(&mut bytes, 512 + (a as usize + 7) * 4, directory.0);
+        assert_eq!(open(bytes).validate(), [Violation::SharedSector(Overlap {
+            sector: directory,
+            mini: false,
+            first: SectorOwner::Directory,
+            second: SectorOwner::Stream("A".to_string()),
+        })]);
+    }
+
+    #[test]
+    fn fields_zeroed_for_the_type() {
+        let mut builder = CfbBuilder::new();
+        let storage = builder.storage(0, "Storage");
+        let stream = builder.stream(storage, "Stream", &[1; 10]);
+        builder.clsid(stream, [7; 16]);
+        let mut bytes = builder.build();
+        let storage_offset = entry_offset(&bytes, storage);
+        put_u32(&mut bytes, storage_offset + 116, 3);
+        let stream_offset = entry_offset(&bytes, stream);
+        put_u32(&mut bytes, stream_offset + 96, 1);
+
+        let cfb = open(bytes);
+        assert_eq!(cfb.validate(), [
+            non_zero("Storage", ObjectType::Storage, "starting_sector_location"),
+            non_zero("Storage/Stream", ObjectType::Stream, "cls_id"),
+            non_zero("Storage/Stream", ObjectType::Stream, "state_bits"),
+        ]);
+
+        // every type of entry reads and formats every field alike
+        match cfb.directory_entry("Stream").unwrap() {
+            Entry::Stream(stream) => {
+                assert_eq!(stream.cls_id(), [7; 16]);
+                assert_eq!(stream.state_bits().bits(), 1);
+                assert_eq!(stream.creation_time(), None);
+            }
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+        match cfb.directory_entry("Storage").unwrap() {
+            Entry::Storage(storage) => assert_eq!(storage.starting_sector_location(), SectorNumber(3)),
+            entry => panic!("unexpected entry {:?}", entry),
+        }
+        // the fields after the name, which is formatted first
+        let keys = |entry: Entry| -> Vec<String> {
+            format!("{:?}", entry).split(", \"").skip(1)
+                .map(|field| field.split('"').next().unwrap().to_string())
+                .collect()
+        };
+        let root_storage = keys(cfb.entries().next().unwrap().unwrap());
+        assert_eq!(root_storage.len(), 11);
+        assert_eq!(root_storage, keys(cfb.directory_entry("Storage").unwrap()));
+        assert_eq!(root_storage, keys(cfb.directory_entry("Stream").unwrap()));
+    }
+}
@@ -0,0 +1,22 @@
+#[cfg(all(test, feature = "alloc-backend"))]
+mod tests {
+    use ole_kit::cfb::{Cfb, Entry};
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    /// A document dropped into a page, which reaches the parser as bytes rather than as a file
+    const DOC: &[u8] = include_bytes!("../tests_rsc/testing.doc");
+
+    #[test]
+    fn lists_entries_in_memory() {
+        let cfb = Cfb::from_bytes(DOC.to_vec()).unwrap();
+        let names: Vec<String> = cfb.entries()
+            .map(Result::unwrap)
+            .filter(|entry| !matches!(entry, Entry::Unknown))
+            .map(|entry| entry.name())
+            .collect();
+        assert_eq!(names, ["Root Entry", "1Table", "WordDocument", "Data", "\u{5}SummaryInformation",
+                           "\u{5}DocumentSummaryInformation"]);
+        assert_eq!(cfb.stream_bytes("WordDocument").unwrap().len(), 4096);
+    }
+}
@@ -0,0 +1,25 @@
+#[cfg(all(test, feature = "zip"))]
+mod tests {
+    use ole_kit::cfb::Cfb;
+    use std::io::{Cursor, Read};
+    use zip::ZipArchive;
+
+    #[test]
+    fn export_streams_into_zip() {
+        let cfb = Cfb::from_path("tests_rsc/testing.doc").unwrap();
+        let mut bytes = Cursor::new(Vec::new());
+        cfb.export_zip(&mut bytes).unwrap();
+
+        let mut archive = ZipArchive::new(bytes).unwrap();
+        let mut word_document = archive.by_name("WordDocument").unwrap();
+        assert_eq!(word_document.size(), 4096);
+        let mut contents = Vec::new();
+        word_document.read_to_end(&mut contents).unwrap();
+        assert_eq!(contents, cfb.stream_bytes("WordDocument").unwrap());
+        drop(word_document);
+
+        // names are escaped as file names are when extracting
+        assert!(archive.file_names().any(|name| name == "%05SummaryInformation"));
+        assert!(archive.file_names().all(|name| !name.contains('\u{5}')));
+    }
+}